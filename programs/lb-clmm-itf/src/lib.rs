@@ -61,6 +61,33 @@ pub struct LbPair {
     pub _reserved: [u8; 64],
 }
 
+/// Maximum number of bins in a bin array
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+/// Number of bin arrays on each side of 0 tracked by [`LbPair::bin_array_bitmap`]
+pub const BIN_ARRAY_BITMAP_SIZE: i32 = 512;
+
+/// Values of [`LbPair::status`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PairStatus {
+    Enabled = 0,
+    Disabled = 1,
+}
+
+impl LbPair {
+    /// Whether the bin array containing the active bin is initialized, according to the bitmap of
+    /// the pair. Returns `None` if the bin array is outside of the bitmap (tracked by the bitmap
+    /// extension account).
+    pub fn is_active_bin_array_initialized(&self) -> Option<bool> {
+        let bin_array_index = self.active_id.div_euclid(MAX_BIN_PER_ARRAY);
+        if !(-BIN_ARRAY_BITMAP_SIZE..BIN_ARRAY_BITMAP_SIZE).contains(&bin_array_index) {
+            return None;
+        }
+        let offset = usize::try_from(bin_array_index + BIN_ARRAY_BITMAP_SIZE).ok()?;
+        Some(self.bin_array_bitmap[offset / 64] & (1 << (offset % 64)) != 0)
+    }
+}
+
 /// Calculate price based on the given bin id. Eg: 1.0001 ^ 5555. The returned value is in Q64.64
 pub fn get_x64_price_from_id(active_id: i32, bin_step: u16) -> Option<U128> {
     // bin_step is in bps, convert to a fraction scaled by 64 bits (Q64x64).
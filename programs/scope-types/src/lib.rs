@@ -1,6 +1,8 @@
 #![allow(clippy::result_large_err)] //Needed because we can't change Anchor result type
 
+pub mod price_feed;
 pub mod program_id;
+pub mod twap;
 
 // Reexports to deal with eventual conflicts
 // Local use
@@ -42,20 +44,39 @@ pub struct DatedPrice {
     pub price: Price,
     pub last_updated_slot: u64,
     pub unix_timestamp: u64,
-    pub _reserved: [u64; 2],
-    pub _reserved2: [u16; 3],
+    /// 1h EMA of the entry at the time of the refresh, zero if the entry has no TWAP enabled
+    pub ema_1h: Price,
+    /// Confidence interval reported by the oracle, in bps of the price (saturated at `u16::MAX`),
+    /// 0 if the oracle does not report one
+    pub confidence_bps: u16,
+    /// Flags qualifying the price, see the `DatedPrice::STATUS_*` constants
+    pub status: u16,
+    /// Source that produced the price: 0 for the entry own oracle, `n` for its n-th fallback
+    pub price_source: u16,
     // Current index of the dated price.
     pub index: u16,
 }
 
+impl DatedPrice {
+    /// The price (or its TWAP) comes from an admin provided seed and not from an oracle
+    pub const STATUS_BOOTSTRAP_SEED: u16 = 1 << 0;
+    /// The entry own oracle failed too many consecutive refreshes, kept until the admin
+    /// acknowledges it
+    pub const STATUS_DEGRADED: u16 = 1 << 1;
+    /// The entry is suspended by the admin, the price is frozen until the entry is resumed
+    pub const STATUS_SUSPENDED: u16 = 1 << 2;
+}
+
 impl Default for DatedPrice {
     fn default() -> Self {
         Self {
             price: Default::default(),
             last_updated_slot: Default::default(),
             unix_timestamp: Default::default(),
-            _reserved: Default::default(),
-            _reserved2: Default::default(),
+            ema_1h: Default::default(),
+            confidence_bps: 0,
+            status: 0,
+            price_source: 0,
             index: MAX_ENTRIES_U16,
         }
     }
@@ -83,8 +104,21 @@ pub struct EmaTwap {
     pub last_update_unix_timestamp: u64,
 
     pub current_ema_1h: u128,
-
-    pub padding: [u128; 40],
+    /// Samples tracker of the 1h EMA, see [`twap`]
+    pub updates_tracker_1h: u64,
+    pub padding_0: u64,
+    pub current_ema_24h: u128,
+    pub current_ema_7d: u128,
+    pub updates_tracker_24h: u64,
+    pub updates_tracker_7d: u64,
+    /// Slower EMAs skip the samples too close to their previous one, so they track their own last sample
+    pub last_sample_unix_timestamp_24h: u64,
+    pub last_sample_unix_timestamp_7d: u64,
+    /// Number of refreshes left before the EMA seeded by a bootstrap is validated as usual
+    pub bootstrap_remaining_refreshes: u64,
+    pub padding_2: u64,
+
+    pub padding: [u128; 34],
 }
 
 impl Default for EmaTwap {
@@ -93,11 +127,74 @@ impl Default for EmaTwap {
             current_ema_1h: 0,
             last_update_slot: 0,
             last_update_unix_timestamp: 0,
-            padding: [0_u128; 40],
+            updates_tracker_1h: 0,
+            padding_0: 0,
+            current_ema_24h: 0,
+            current_ema_7d: 0,
+            updates_tracker_24h: 0,
+            updates_tracker_7d: 0,
+            last_sample_unix_timestamp_24h: 0,
+            last_sample_unix_timestamp_7d: 0,
+            bootstrap_remaining_refreshes: 0,
+            padding_2: 0,
+            padding: [0_u128; 34],
+        }
+    }
+}
+
+/// Scale of [`EmaTwap::current_ema_1h`] (18 decimals)
+pub const EMA_SCALE: u128 = 1_000_000_000_000_000_000;
+
+impl EmaTwap {
+    /// Absolute deviation of `price` versus the 1h EMA, in bps of the EMA.
+    ///
+    /// Returns `None` if the EMA has never been computed. Saturates at `u64::MAX`.
+    pub fn divergence_bps(&self, price: &Price) -> Option<u64> {
+        if self.current_ema_1h == 0 {
+            return None;
         }
+        let exp = u32::try_from(price.exp).ok()?;
+        let price_scaled = u128::from(price.value)
+            .checked_mul(EMA_SCALE)?
+            .checked_div(10_u128.checked_pow(exp)?)?;
+        let diff = price_scaled.abs_diff(self.current_ema_1h);
+        let diff_bps = diff
+            .checked_mul(10_000)
+            .map_or(u128::MAX, |d| d / self.current_ema_1h);
+        Some(u64::try_from(diff_bps).unwrap_or(u64::MAX))
     }
 }
 
+/// Spot price of an entry along with its divergence versus the entry own 1h EMA
+#[derive(Debug, Eq, PartialEq)]
+pub struct SpotPriceWithDivergence {
+    pub price: DatedPrice,
+    /// `None` if the TWAP is not enabled for the entry or has not been computed yet
+    pub twap_divergence_bps: Option<u64>,
+}
+
+/// Read the spot price of `entry_id` and its divergence versus the entry own 1h EMA.
+///
+/// Meant for consumers reading the scope accounts directly, so they can apply haircuts
+/// on prices far from their trailing average.
+pub fn get_spot_price_with_twap_divergence(
+    oracle_prices: &OraclePrices,
+    oracle_mappings: &OracleMappings,
+    oracle_twaps: &OracleTwaps,
+    entry_id: usize,
+) -> Option<SpotPriceWithDivergence> {
+    let price = *oracle_prices.prices.get(entry_id)?;
+    let twap_divergence_bps = if oracle_mappings.is_twap_enabled(entry_id) {
+        oracle_twaps.twaps[entry_id].divergence_bps(&price.price)
+    } else {
+        None
+    };
+    Some(SpotPriceWithDivergence {
+        price,
+        twap_divergence_bps,
+    })
+}
+
 // Accounts holding source of prices
 #[account(zero_copy)]
 pub struct OracleMappings {
@@ -144,12 +241,48 @@ pub struct TokenMetadata {
     pub _reserved: [u64; 15],
 }
 
+/// Reason for which [`get_validated_price`] or a [`price_feed::PriceFeed`] rejected a price
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PriceValidationError {
+    BadTokenNb,
+    PriceNotSet,
+    StalePrice { age_slots: u64, max_age_slots: u64 },
+    MathOverflow,
+}
+
+/// Read the price of `entry_id`, only if it is not older than the `max_age_price_slots` of the
+/// entry metadata.
+pub fn get_validated_price(
+    oracle_prices: &OraclePrices,
+    tokens_metadata: &TokensMetadata,
+    entry_id: usize,
+    current_slot: u64,
+) -> std::result::Result<DatedPrice, PriceValidationError> {
+    let price = oracle_prices
+        .prices
+        .get(entry_id)
+        .ok_or(PriceValidationError::BadTokenNb)?;
+    if price.price.value == 0 {
+        return Err(PriceValidationError::PriceNotSet);
+    }
+    let max_age_slots = tokens_metadata.metadatas_array[entry_id].max_age_price_slots;
+    let age_slots = current_slot.saturating_sub(price.last_updated_slot);
+    if age_slots > max_age_slots {
+        return Err(PriceValidationError::StalePrice {
+            age_slots,
+            max_age_slots,
+        });
+    }
+    Ok(*price)
+}
+
 #[derive(TryFromPrimitive, PartialEq, Eq, Clone, Copy, Debug)]
 #[repr(u64)]
 pub enum UpdateTokenMetadataMode {
     Name = 0,
     MaxPriceAgeSlots = 1,
     GroupIds = 2,
+    MinRefreshIntervalSlots = 3,
 }
 
 #[error_code]
@@ -215,3 +348,93 @@ impl From<TryFromIntError> for ScopeError {
         ScopeError::OutOfRangeIntegralConversion
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+
+    use super::*;
+
+    fn ema_twap(current_ema_1h: u128) -> EmaTwap {
+        EmaTwap {
+            current_ema_1h,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_divergence_bps() {
+        let twap = ema_twap(EMA_SCALE);
+        assert_eq!(twap.divergence_bps(&Price { value: 1, exp: 0 }), Some(0));
+        assert_eq!(
+            twap.divergence_bps(&Price { value: 102, exp: 2 }),
+            Some(200)
+        );
+        assert_eq!(twap.divergence_bps(&Price { value: 98, exp: 2 }), Some(200));
+        assert_eq!(
+            twap.divergence_bps(&Price {
+                value: 3_000_000,
+                exp: 6
+            }),
+            Some(20_000)
+        );
+
+        // Not computed yet
+        assert_eq!(
+            ema_twap(0).divergence_bps(&Price { value: 1, exp: 0 }),
+            None
+        );
+        // Exponent out of range
+        assert_eq!(twap.divergence_bps(&Price { value: 1, exp: 40 }), None);
+        // Saturates
+        assert_eq!(
+            ema_twap(1).divergence_bps(&Price {
+                value: u64::MAX,
+                exp: 0
+            }),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_get_spot_price_with_twap_divergence() {
+        let mut oracle_prices: Box<OraclePrices> = Box::new(Zeroable::zeroed());
+        let mut oracle_mappings: Box<OracleMappings> = Box::new(Zeroable::zeroed());
+        let mut oracle_twaps: Box<OracleTwaps> = Box::new(Zeroable::zeroed());
+        let price = DatedPrice {
+            price: Price { value: 101, exp: 2 },
+            last_updated_slot: 10,
+            unix_timestamp: 100,
+            ..Default::default()
+        };
+        oracle_prices.prices[3] = price;
+        oracle_twaps.twaps[3] = ema_twap(EMA_SCALE);
+
+        // TWAP disabled: no divergence even if an EMA is stored
+        let spot =
+            get_spot_price_with_twap_divergence(&oracle_prices, &oracle_mappings, &oracle_twaps, 3)
+                .unwrap();
+        assert_eq!(
+            spot,
+            SpotPriceWithDivergence {
+                price,
+                twap_divergence_bps: None,
+            }
+        );
+
+        oracle_mappings.twap_enabled[3] = 1;
+        let spot =
+            get_spot_price_with_twap_divergence(&oracle_prices, &oracle_mappings, &oracle_twaps, 3)
+                .unwrap();
+        assert_eq!(spot.price, price);
+        assert_eq!(spot.twap_divergence_bps, Some(100));
+
+        assert!(get_spot_price_with_twap_divergence(
+            &oracle_prices,
+            &oracle_mappings,
+            &oracle_twaps,
+            MAX_ENTRIES
+        )
+        .is_none());
+    }
+}
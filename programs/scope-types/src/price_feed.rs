@@ -0,0 +1,117 @@
+//! Read-only access to the prices of a scope feed for integrating programs
+//!
+//! All the checks a consumer is expected to do (price set, staleness) and the handling of the
+//! price exponents are done here, so that integrators only need this crate to consume scope.
+
+use anchor_lang::prelude::Clock;
+use decimal_wad::{common::TryDiv, decimal::Decimal};
+
+use crate::{DatedPrice, OraclePrices, Price, PriceValidationError};
+
+/// Wrapper around a (zero-copy) [`OraclePrices`] account
+#[derive(Clone, Copy)]
+pub struct PriceFeed<'a> {
+    oracle_prices: &'a OraclePrices,
+}
+
+impl<'a> PriceFeed<'a> {
+    pub fn new(oracle_prices: &'a OraclePrices) -> Self {
+        Self { oracle_prices }
+    }
+
+    /// Price of `index`, only if it is set and not older than `max_age_slots`
+    pub fn get_price(
+        &self,
+        index: usize,
+        clock: &Clock,
+        max_age_slots: u64,
+    ) -> Result<DatedPrice, PriceValidationError> {
+        let price = self
+            .oracle_prices
+            .prices
+            .get(index)
+            .ok_or(PriceValidationError::BadTokenNb)?;
+        if price.price.value == 0 {
+            return Err(PriceValidationError::PriceNotSet);
+        }
+        let age_slots = clock.slot.saturating_sub(price.last_updated_slot);
+        if age_slots > max_age_slots {
+            return Err(PriceValidationError::StalePrice {
+                age_slots,
+                max_age_slots,
+            });
+        }
+        Ok(*price)
+    }
+
+    /// Price of `index_a` expressed in `index_b` (e.g. SOL/USD and USDC/USD give SOL/USDC),
+    /// as old as the oldest of the two prices.
+    ///
+    /// The confidence of the cross price is only known if both prices report one.
+    ///
+    /// Both prices must be set and not older than `max_age_slots`.
+    pub fn get_price_in(
+        &self,
+        index_a: usize,
+        index_b: usize,
+        clock: &Clock,
+        max_age_slots: u64,
+    ) -> Result<DatedPrice, PriceValidationError> {
+        let price_a = self.get_price(index_a, clock, max_age_slots)?;
+        let price_b = self.get_price(index_b, clock, max_age_slots)?;
+        let cross_price = Decimal::from(price_a.price)
+            .try_div(Decimal::from(price_b.price))
+            .map_err(|_| PriceValidationError::MathOverflow)?;
+        Ok(DatedPrice {
+            price: Price::try_from(cross_price)?,
+            last_updated_slot: price_a.last_updated_slot.min(price_b.last_updated_slot),
+            unix_timestamp: price_a.unix_timestamp.min(price_b.unix_timestamp),
+            // Relative uncertainties add up through a division (first order)
+            confidence_bps: if price_a.confidence_bps == 0 || price_b.confidence_bps == 0 {
+                0
+            } else {
+                price_a
+                    .confidence_bps
+                    .saturating_add(price_b.confidence_bps)
+            },
+            ..Default::default()
+        })
+    }
+
+    /// Same as [`PriceFeed::get_price`], as a [`Decimal`]
+    pub fn get_price_decimal(
+        &self,
+        index: usize,
+        clock: &Clock,
+        max_age_slots: u64,
+    ) -> Result<Decimal, PriceValidationError> {
+        self.get_price(index, clock, max_age_slots)
+            .map(|price| price.price.into())
+    }
+}
+
+impl From<Price> for Decimal {
+    fn from(val: Price) -> Self {
+        Decimal::from(val.value) / 10u128.pow(val.exp as u32)
+    }
+}
+
+/// Conversion keeping as many decimals as possible (up to 18) in the `u64` value
+impl TryFrom<Decimal> for Price {
+    type Error = PriceValidationError;
+
+    fn try_from(decimal: Decimal) -> Result<Self, Self::Error> {
+        let integer_part = decimal
+            .try_round::<u64>()
+            .map_err(|_| PriceValidationError::MathOverflow)?;
+        let integer_digits = integer_part.checked_ilog10().map_or(0, |log| log + 1);
+        let exp = 18_u32.saturating_sub(integer_digits);
+        let value = (decimal * 10_u64.pow(exp))
+            .try_round::<u64>()
+            .map_err(|_| PriceValidationError::MathOverflow)?;
+        Ok(Price {
+            value,
+            exp: u64::from(exp),
+        })
+    }
+}
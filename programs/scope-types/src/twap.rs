@@ -0,0 +1,195 @@
+//! Decoding of the TWAP samples trackers, mirroring the on-chain validation of the EMAs
+//!
+//! The tracker of an EMA is a 64 bit number where each bit represents a point in time: one point
+//! per `ema_period / 64` window, set if a sample was added in that window.
+//! These are pure functions so that off-chain monitoring and integrating programs can check the
+//! validity of a TWAP with the exact rules of the program.
+
+use crate::EmaTwap;
+
+/// Minimum number of samples in the last EMA period for the EMA to be valid
+pub const MIN_SAMPLES_IN_PERIOD: u32 = 10;
+/// Number of sub-periods the last EMA period is split into to check the samples distribution
+pub const NUM_SUB_PERIODS: usize = 3;
+/// Minimum number of samples in the oldest and in the newest sub-period
+pub const MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD: u32 = 1;
+
+const NB_POINTS: u64 = u64::BITS as u64;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EmaType {
+    Ema1h,
+    Ema24h,
+    Ema7d,
+}
+
+impl EmaType {
+    pub const fn period_seconds(&self) -> u64 {
+        match self {
+            EmaType::Ema1h => 60 * 60,
+            EmaType::Ema24h => 24 * 60 * 60,
+            EmaType::Ema7d => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Reason for which [`validate_ema`] rejected an EMA
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TwapValidationError {
+    /// Less than [`MIN_SAMPLES_IN_PERIOD`] samples in the last period
+    NotEnoughSamplesInPeriod { samples_count: u32 },
+    /// Less than [`MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD`] samples in the oldest or the newest
+    /// sub-period
+    NotEnoughSamplesInSubPeriods {
+        samples_count_per_subperiods: [u32; NUM_SUB_PERIODS],
+    },
+}
+
+/// Point of the tracker of `ts`
+pub const fn ts_to_point(ts: u64, ema_period: u64) -> u64 {
+    (ts * NB_POINTS / ema_period) % NB_POINTS
+}
+
+/// Mask of the points from `first` to `last` (included), wrapping around the tracker
+fn points_mask(first: u64, last: u64) -> u64 {
+    let range_mask =
+        |first: u64, last: u64| (u64::MAX >> (NB_POINTS - 1 - last)) & (u64::MAX << first);
+    if first <= last {
+        range_mask(first, last)
+    } else {
+        range_mask(first, NB_POINTS - 1) | range_mask(0, last)
+    }
+}
+
+/// Tracker as seen at `current_ts`: the points older than `ema_period` are erased.
+///
+/// `last_sample_ts` is the timestamp of the last sample added to the tracker, a `current_ts`
+/// before it is considered equal to it.
+pub fn erase_old_samples(
+    tracker: u64,
+    ema_period: u64,
+    current_ts: u64,
+    last_sample_ts: u64,
+) -> u64 {
+    let current_ts = current_ts.max(last_sample_ts);
+    let points_since_last_sample =
+        current_ts * NB_POINTS / ema_period - last_sample_ts * NB_POINTS / ema_period;
+    if points_since_last_sample >= NB_POINTS {
+        return 0;
+    }
+    let last_sample_point = ts_to_point(last_sample_ts, ema_period);
+    let current_point = ts_to_point(current_ts, ema_period);
+    if last_sample_point == current_point {
+        return tracker;
+    }
+    tracker & !points_mask((last_sample_point + 1) % NB_POINTS, current_point)
+}
+
+/// Number of samples in the tracker
+pub fn samples_count(tracker: u64) -> u32 {
+    tracker.count_ones()
+}
+
+/// Number of samples in each of the `N` sub-periods of the period ending at `current_ts`, from the
+/// oldest to the newest. The last sub-period may be larger than the others.
+pub fn samples_count_per_subperiods<const N: usize>(
+    tracker: u64,
+    ema_period: u64,
+    current_ts: u64,
+) -> [u32; N] {
+    // Rotate the points so that the oldest one is the first one
+    let pivot_point = (ts_to_point(current_ts, ema_period) + 1) % NB_POINTS;
+    let sorted_points = tracker.rotate_right(pivot_point as u32);
+
+    let count_in_period = |start_point: u64, end_point: u64| -> u32 {
+        let len = end_point - start_point;
+        let mask = if len >= NB_POINTS {
+            u64::MAX
+        } else {
+            (1 << len) - 1
+        };
+        ((sorted_points >> start_point) & mask).count_ones()
+    };
+
+    let sub_period_size = NB_POINTS / N as u64;
+    let mut counts = [0; N];
+    let mut start_period_point = 0;
+    for count in counts.iter_mut().take(N - 1) {
+        let end_period_point = start_period_point + sub_period_size;
+        *count = count_in_period(start_period_point, end_period_point);
+        start_period_point = end_period_point;
+    }
+    counts[N - 1] = count_in_period(start_period_point, NB_POINTS);
+
+    counts
+}
+
+/// Check that enough samples, spread over the period, were added to an EMA
+///
+/// As on-chain, the sub-periods are the ones of the period ending at the last sample.
+pub fn validate_ema(
+    tracker: u64,
+    ema_period: u64,
+    current_ts: u64,
+    last_sample_ts: u64,
+) -> Result<(), TwapValidationError> {
+    let tracker = erase_old_samples(tracker, ema_period, current_ts, last_sample_ts);
+
+    let samples_count = samples_count(tracker);
+    if samples_count < MIN_SAMPLES_IN_PERIOD {
+        return Err(TwapValidationError::NotEnoughSamplesInPeriod { samples_count });
+    }
+
+    let samples_count_per_subperiods =
+        samples_count_per_subperiods::<NUM_SUB_PERIODS>(tracker, ema_period, last_sample_ts);
+    if samples_count_per_subperiods[0] < MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD
+        || samples_count_per_subperiods[NUM_SUB_PERIODS - 1] < MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD
+    {
+        return Err(TwapValidationError::NotEnoughSamplesInSubPeriods {
+            samples_count_per_subperiods,
+        });
+    }
+
+    Ok(())
+}
+
+impl EmaTwap {
+    /// Samples tracker and last sample timestamp of the given EMA
+    pub fn get_tracker(&self, ema_type: EmaType) -> (u64, u64) {
+        match ema_type {
+            EmaType::Ema1h => (self.updates_tracker_1h, self.last_update_unix_timestamp),
+            EmaType::Ema24h => (
+                self.updates_tracker_24h,
+                self.last_sample_unix_timestamp_24h,
+            ),
+            EmaType::Ema7d => (self.updates_tracker_7d, self.last_sample_unix_timestamp_7d),
+        }
+    }
+
+    /// Number of samples of the given EMA in the period ending at `current_ts`
+    pub fn samples_count(&self, ema_type: EmaType, current_ts: u64) -> u32 {
+        let (tracker, last_sample_ts) = self.get_tracker(ema_type);
+        samples_count(erase_old_samples(
+            tracker,
+            ema_type.period_seconds(),
+            current_ts,
+            last_sample_ts,
+        ))
+    }
+
+    /// Whether a scope TWAP entry reading the given EMA would be accepted at `current_ts`.
+    ///
+    /// A bootstrapped EMA is accepted until its bootstrap expires.
+    pub fn validate(&self, ema_type: EmaType, current_ts: u64) -> Result<(), TwapValidationError> {
+        if self.bootstrap_remaining_refreshes > 0 {
+            return Ok(());
+        }
+        let (tracker, last_sample_ts) = self.get_tracker(ema_type);
+        validate_ema(
+            tracker,
+            ema_type.period_seconds(),
+            current_ts,
+            last_sample_ts,
+        )
+    }
+}
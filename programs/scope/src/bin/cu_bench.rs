@@ -0,0 +1,365 @@
+//! Measure the compute units consumed by the scope program and compare them to baselines.
+//!
+//! The accounts of a price feed are cloned from an RPC endpoint into a `solana-program-test` bank
+//! running the locally built program (`scope.so`, looked up in `BPF_OUT_DIR` or `target/deploy`).
+//! Each entry is then refreshed alone with `refresh_price_list` and the consumed compute units are
+//! recorded per oracle type (worst entry of the type), as well as per handler.
+//!
+//! The measures are compared to the baselines file (`cu_baselines.json` by default): the run
+//! fails if any measure exceeds its baseline by more than the tolerance. A measure without
+//! baseline is only reported as new.
+//!
+//! The baselines file only holds measured values: it is written by a run with
+//! `--update-baselines` (see the README), never by hand.
+//!
+//! Limitations:
+//! - Both the binary and `scope.so` must be built for the same cluster (program id).
+//! - The refreshes start from the on-chain state at the time of the fetch, the cost of a refresh
+//!   may differ depending on whether the price actually moved.
+//! - Oracles requiring extra accounts need them to be provided with `--extra`.
+
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use scope::{
+    anchor_lang::{
+        prelude::{AccountMeta, Clock, Pubkey},
+        solana_program::{instruction::Instruction, sysvar},
+        InstructionData, ToAccountMetas,
+    },
+    oracles::OracleType,
+    utils::{
+        consts::{MAX_TX_COMPUTE_UNITS, MAX_VALIDATED_PRICES},
+        pdas::config_pubkey,
+        zero_copy_deserialize,
+    },
+    Configuration, OracleMappings, MAX_ENTRIES,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{from_account, Account},
+    account_info::IntoAccountInfo,
+    compute_budget::ComputeBudgetInstruction,
+    signer::Signer,
+    transaction::Transaction,
+};
+
+#[derive(Parser, Debug)]
+#[command(about = "Measure the compute units consumed by scope against recorded baselines")]
+struct Args {
+    /// RPC endpoint to fetch the accounts from
+    #[arg(
+        long,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    rpc_url: String,
+    /// Name of the price feed
+    #[arg(long, default_value = "hubble")]
+    feed_name: String,
+    /// Only measure the given entries (all mapped entries by default)
+    #[arg(long, value_delimiter = ',')]
+    entries: Vec<u16>,
+    /// Extra accounts of an entry, as `<entry>:<pubkey>[,<pubkey>...]` (can be repeated)
+    #[arg(long = "extra", value_parser = parse_extra_accounts)]
+    extra_accounts: Vec<(u16, Vec<Pubkey>)>,
+    /// Baselines file (JSON object of measure name to compute units)
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/cu_baselines.json"))]
+    baselines: PathBuf,
+    /// Increase over the baseline (in percent) tolerated before a measure is a regression
+    #[arg(long, default_value_t = 2.0)]
+    tolerance_pct: f64,
+    /// Record the measures as the new baselines instead of checking them
+    #[arg(long)]
+    update_baselines: bool,
+}
+
+fn parse_extra_accounts(arg: &str) -> Result<(u16, Vec<Pubkey>)> {
+    let (entry, pubkeys) = arg
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected <entry>:<pubkey>[,<pubkey>...], got {arg}"))?;
+    let pubkeys = pubkeys
+        .split(',')
+        .map(Pubkey::from_str)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok((entry.parse()?, pubkeys))
+}
+
+fn fetch_accounts(
+    rpc: &RpcClient,
+    pubkeys: impl IntoIterator<Item = Pubkey>,
+) -> Result<BTreeMap<Pubkey, Account>> {
+    let mut pubkeys: Vec<Pubkey> = pubkeys.into_iter().collect();
+    pubkeys.sort();
+    pubkeys.dedup();
+    let mut accounts = BTreeMap::new();
+    // RPC nodes limit the number of accounts per request
+    for chunk in pubkeys.chunks(100) {
+        let fetched = rpc
+            .get_multiple_accounts(chunk)
+            .context("Failed to fetch accounts")?;
+        for (pubkey, account) in chunk.iter().zip(fetched) {
+            match account {
+                Some(account) => {
+                    accounts.insert(*pubkey, account);
+                }
+                None => eprintln!("Account {pubkey} not found"),
+            }
+        }
+    }
+    Ok(accounts)
+}
+
+/// Simulate the instruction (after a compute budget instruction, as cranks do) and return the
+/// consumed compute units
+async fn measure(ctx: &mut ProgramTestContext, ix: Instruction) -> Result<u64> {
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_TX_COMPUTE_UNITS),
+            ix,
+        ],
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.last_blockhash,
+    );
+    let simulation = ctx.banks_client.simulate_transaction(tx).await?;
+    let details = simulation
+        .simulation_details
+        .ok_or_else(|| anyhow!("No simulation details"))?;
+    if let Some(Err(e)) = simulation.result {
+        let logs = details.logs.join("\n  ");
+        bail!("Transaction failed: {e}\n  {logs}");
+    }
+    Ok(details.units_consumed)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let rpc = RpcClient::new(args.rpc_url.clone());
+
+    let (configuration_pk, _) = config_pubkey(&args.feed_name);
+    let configuration_acc = rpc
+        .get_account(&configuration_pk)
+        .with_context(|| format!("Failed to fetch configuration of feed {}", args.feed_name))?;
+    // Skip the account discriminator
+    let configuration: Configuration = bytemuck::pod_read_unaligned(
+        &configuration_acc.data[8..][..std::mem::size_of::<Configuration>()],
+    );
+
+    let mappings_acc = rpc
+        .get_account(&configuration.oracle_mappings)
+        .context("Failed to fetch oracle mappings")?;
+    let price_types_and_accounts: Vec<(u8, Pubkey)> = {
+        let mut mappings_acc = mappings_acc.clone();
+        let mappings_info = (&configuration.oracle_mappings, &mut mappings_acc).into_account_info();
+        let oracle_mappings = zero_copy_deserialize::<OracleMappings>(&mappings_info)
+            .map_err(|e| anyhow!("Failed to deserialize oracle mappings: {e:?}"))?;
+        (0..MAX_ENTRIES)
+            .map(|entry| {
+                (
+                    oracle_mappings.price_types[entry],
+                    oracle_mappings.price_info_accounts[entry],
+                )
+            })
+            .collect()
+    };
+
+    let entries: Vec<u16> = if args.entries.is_empty() {
+        (0..MAX_ENTRIES)
+            .filter(|&entry| price_types_and_accounts[entry].1 != Pubkey::default())
+            .map(|entry| u16::try_from(entry).unwrap())
+            .collect()
+    } else {
+        args.entries.clone()
+    };
+    if let Some(entry) = entries
+        .iter()
+        .find(|&&entry| usize::from(entry) >= MAX_ENTRIES)
+    {
+        bail!("Entry {entry} is out of range");
+    }
+    let extra_accounts: BTreeMap<u16, Vec<Pubkey>> = args.extra_accounts.into_iter().collect();
+
+    let fetched = fetch_accounts(
+        &rpc,
+        [
+            configuration.oracle_prices,
+            configuration.oracle_twaps,
+            configuration.tokens_metadata,
+            sysvar::clock::ID,
+        ]
+        .into_iter()
+        .chain(
+            entries
+                .iter()
+                .map(|&entry| price_types_and_accounts[usize::from(entry)].1),
+        )
+        .chain(extra_accounts.values().flatten().copied()),
+    )?;
+    let clock: Clock = from_account(
+        fetched
+            .get(&sysvar::clock::ID)
+            .ok_or_else(|| anyhow!("Clock sysvar not found"))?,
+    )
+    .ok_or_else(|| anyhow!("Failed to deserialize the clock sysvar"))?;
+
+    let mut program_test = ProgramTest::new("scope", scope::ID, None);
+    program_test.prefer_bpf(true);
+    program_test.add_account(configuration_pk, configuration_acc);
+    program_test.add_account(configuration.oracle_mappings, mappings_acc);
+    for (pubkey, account) in fetched
+        .into_iter()
+        .filter(|(pubkey, _)| *pubkey != sysvar::clock::ID)
+    {
+        program_test.add_account(pubkey, account);
+    }
+    let mut ctx = program_test.start_with_context().await;
+    // Oracles check the age of their prices against the clock of the fetch
+    ctx.set_sysvar(&clock);
+
+    println!(
+        "Measuring feed {} ({} entries) at slot {}",
+        args.feed_name,
+        entries.len(),
+        clock.slot
+    );
+
+    let refresh_accounts = scope::accounts::RefreshList {
+        oracle_prices: configuration.oracle_prices,
+        oracle_mappings: configuration.oracle_mappings,
+        oracle_twaps: configuration.oracle_twaps,
+        instruction_sysvar_account_info: sysvar::instructions::ID,
+        configuration: configuration_pk,
+    };
+
+    let mut measures: BTreeMap<String, u64> = BTreeMap::new();
+    let mut failures = 0;
+    for &entry in &entries {
+        let (price_type, price_account) = price_types_and_accounts[usize::from(entry)];
+        let price_type = match OracleType::try_from(price_type) {
+            Ok(price_type) => price_type,
+            Err(_) => {
+                println!("{entry:>3} | invalid oracle type");
+                failures += 1;
+                continue;
+            }
+        };
+        let mut accounts = refresh_accounts.to_account_metas(None);
+        accounts.push(AccountMeta::new_readonly(price_account, false));
+        accounts.extend(
+            extra_accounts
+                .get(&entry)
+                .into_iter()
+                .flatten()
+                .map(|&pubkey| AccountMeta::new_readonly(pubkey, false)),
+        );
+        let ix = Instruction {
+            program_id: scope::ID,
+            accounts,
+            data: scope::instruction::RefreshPriceList {
+                tokens: vec![entry],
+            }
+            .data(),
+        };
+        match measure(&mut ctx, ix).await {
+            Ok(units) => {
+                println!("{entry:>3} | {price_type:?} | {units} CU");
+                let measure = measures
+                    .entry(format!("refresh_price_list/{price_type:?}"))
+                    .or_default();
+                *measure = (*measure).max(units);
+            }
+            Err(e) => {
+                println!("{entry:>3} | {price_type:?} | {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    let validated_entries: Vec<u16> = entries.iter().copied().take(MAX_VALIDATED_PRICES).collect();
+    if !validated_entries.is_empty() {
+        let ix = Instruction {
+            program_id: scope::ID,
+            accounts: scope::accounts::GetValidatedPrices {
+                oracle_prices: configuration.oracle_prices,
+                tokens_metadata: configuration.tokens_metadata,
+                configuration: configuration_pk,
+                oracle_mappings: Some(configuration.oracle_mappings),
+            }
+            .to_account_metas(None),
+            data: scope::instruction::GetValidatedPrices {
+                tokens: validated_entries.clone(),
+            }
+            .data(),
+        };
+        match measure(&mut ctx, ix).await {
+            Ok(units) => {
+                println!(
+                    "get_validated_prices ({} entries) | {units} CU",
+                    validated_entries.len()
+                );
+                measures.insert(
+                    format!("get_validated_prices/{}", validated_entries.len()),
+                    units,
+                );
+            }
+            Err(e) => {
+                println!("get_validated_prices | {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    let mut baselines: BTreeMap<String, u64> = match std::fs::read_to_string(&args.baselines) {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baselines file {}", args.baselines.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    if args.update_baselines {
+        baselines.extend(measures);
+        std::fs::write(
+            &args.baselines,
+            serde_json::to_string_pretty(&baselines)? + "\n",
+        )?;
+        println!("Baselines written to {}", args.baselines.display());
+        return Ok(());
+    }
+
+    let mut regressions = 0;
+    println!(
+        "{:<50} | {:>9} | {:>9} | {:>8}",
+        "measure", "CU", "baseline", "delta"
+    );
+    for (name, &units) in &measures {
+        let Some(&baseline) = baselines.get(name) else {
+            println!("{name:<50} | {units:>9} | {:>9} | {:>8}", "-", "new");
+            continue;
+        };
+        let delta_pct = (units as f64 - baseline as f64) * 100.0 / (baseline.max(1) as f64);
+        let regression = delta_pct > args.tolerance_pct;
+        if regression {
+            regressions += 1;
+        }
+        println!(
+            "{name:<50} | {units:>9} | {baseline:>9} | {delta_pct:>+7.2}%{}",
+            if regression { " REGRESSION" } else { "" }
+        );
+    }
+
+    println!(
+        "{} measures, {regressions} regressions, {failures} entries failed",
+        measures.len()
+    );
+    if regressions > 0 {
+        bail!(
+            "{regressions} measures exceed their baseline by more than {}%",
+            args.tolerance_pct
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+//! Replay the refresh of a price feed locally.
+//!
+//! Fetches the accounts of a price feed from an RPC endpoint and runs the on-chain oracle code
+//! paths natively for every mapped entry, printing the `DatedPrice` each entry would be refreshed
+//! with. Nothing is written on-chain.
+//!
+//! Limitations:
+//! - The binary must be built for the cluster of the feed (program id is selected at build time).
+//! - Oracles requiring extra accounts need them to be provided with `--extra`.
+//! - Derived entries (TWAP, capped/floored, median...) are computed from the on-chain prices,
+//!   not from the replayed ones.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use scope::{
+    anchor_lang::{
+        prelude::{AccountInfo, AccountLoader, Clock, Pubkey},
+        solana_program::{account_info::IntoAccountInfo, sysvar},
+    },
+    oracles::{get_non_zero_price, OracleType},
+    utils::{pdas::config_pubkey, zero_copy_deserialize},
+    Configuration, OracleMappings, OraclePrices, OracleTwaps, MAX_ENTRIES,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::{from_account, Account};
+
+#[derive(Parser, Debug)]
+#[command(about = "Replay the refresh of a scope price feed locally")]
+struct Args {
+    /// RPC endpoint to fetch the accounts from
+    #[arg(
+        long,
+        env = "RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    rpc_url: String,
+    /// Name of the price feed
+    #[arg(long, default_value = "hubble")]
+    feed_name: String,
+    /// Only replay the given entries (all mapped entries by default)
+    #[arg(long, value_delimiter = ',')]
+    entries: Vec<u16>,
+    /// Extra accounts of an entry, as `<entry>:<pubkey>[,<pubkey>...]` (can be repeated)
+    #[arg(long = "extra", value_parser = parse_extra_accounts)]
+    extra_accounts: Vec<(u16, Vec<Pubkey>)>,
+}
+
+fn parse_extra_accounts(arg: &str) -> Result<(u16, Vec<Pubkey>)> {
+    let (entry, pubkeys) = arg
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected <entry>:<pubkey>[,<pubkey>...], got {arg}"))?;
+    let pubkeys = pubkeys
+        .split(',')
+        .map(Pubkey::from_str)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok((entry.parse()?, pubkeys))
+}
+
+/// Accounts fetched from the RPC, kept alive to build `AccountInfo`s from
+struct FetchedAccounts(BTreeMap<Pubkey, Account>);
+
+impl FetchedAccounts {
+    fn fetch(rpc: &RpcClient, pubkeys: impl IntoIterator<Item = Pubkey>) -> Result<Self> {
+        let mut pubkeys: Vec<Pubkey> = pubkeys.into_iter().collect();
+        pubkeys.sort();
+        pubkeys.dedup();
+        let mut accounts = BTreeMap::new();
+        // RPC nodes limit the number of accounts per request
+        for chunk in pubkeys.chunks(100) {
+            let fetched = rpc
+                .get_multiple_accounts(chunk)
+                .context("Failed to fetch accounts")?;
+            for (pubkey, account) in chunk.iter().zip(fetched) {
+                match account {
+                    Some(account) => {
+                        accounts.insert(*pubkey, account);
+                    }
+                    None => eprintln!("Account {pubkey} not found"),
+                }
+            }
+        }
+        Ok(Self(accounts))
+    }
+
+    fn get(&self, pubkey: &Pubkey) -> Result<&Account> {
+        self.0
+            .get(pubkey)
+            .ok_or_else(|| anyhow!("Account {pubkey} not found"))
+    }
+
+    /// Build fresh `AccountInfo`s (the oracle code paths only read them).
+    fn account_infos(&mut self) -> BTreeMap<Pubkey, AccountInfo<'_>> {
+        self.0
+            .iter_mut()
+            .map(|(pubkey, account)| (*pubkey, (pubkey, account).into_account_info()))
+            .collect()
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let rpc = RpcClient::new(args.rpc_url.clone());
+
+    let (configuration_pk, _) = config_pubkey(&args.feed_name);
+    let configuration_acc = rpc
+        .get_account(&configuration_pk)
+        .with_context(|| format!("Failed to fetch configuration of feed {}", args.feed_name))?;
+    // Skip the account discriminator
+    let configuration: Configuration = bytemuck::pod_read_unaligned(
+        &configuration_acc.data[8..][..std::mem::size_of::<Configuration>()],
+    );
+
+    let mut mappings_acc = rpc
+        .get_account(&configuration.oracle_mappings)
+        .context("Failed to fetch oracle mappings")?;
+    let mappings_info = (&configuration.oracle_mappings, &mut mappings_acc).into_account_info();
+    let oracle_mappings = zero_copy_deserialize::<OracleMappings>(&mappings_info)
+        .map_err(|e| anyhow!("Failed to deserialize oracle mappings: {e:?}"))?;
+
+    let entries: Vec<u16> = if args.entries.is_empty() {
+        (0..MAX_ENTRIES)
+            .filter(|&entry| oracle_mappings.price_info_accounts[entry] != Pubkey::default())
+            .map(|entry| u16::try_from(entry).unwrap())
+            .collect()
+    } else {
+        args.entries.clone()
+    };
+    let extra_accounts: BTreeMap<u16, Vec<Pubkey>> = args.extra_accounts.into_iter().collect();
+
+    let mut fetched = FetchedAccounts::fetch(
+        &rpc,
+        [
+            configuration.oracle_prices,
+            configuration.oracle_twaps,
+            sysvar::clock::ID,
+        ]
+        .into_iter()
+        .chain(
+            entries
+                .iter()
+                .map(|&entry| oracle_mappings.price_info_accounts[usize::from(entry)]),
+        )
+        .chain(extra_accounts.values().flatten().copied()),
+    )?;
+
+    let clock: Clock = from_account(fetched.get(&sysvar::clock::ID)?)
+        .ok_or_else(|| anyhow!("Failed to deserialize the clock sysvar"))?;
+    println!(
+        "Replaying feed {} ({} entries) at slot {}",
+        args.feed_name,
+        entries.len(),
+        clock.slot
+    );
+
+    let infos = fetched.account_infos();
+    let prices_info = infos
+        .get(&configuration.oracle_prices)
+        .ok_or_else(|| anyhow!("Oracle prices account not found"))?;
+    let oracle_prices = AccountLoader::<OraclePrices>::try_from(prices_info)
+        .map_err(|e| anyhow!("Invalid oracle prices account: {e:?}"))?;
+    let twaps_info = infos
+        .get(&configuration.oracle_twaps)
+        .ok_or_else(|| anyhow!("Oracle twaps account not found"))?;
+    let twaps_loader = AccountLoader::<OracleTwaps>::try_from(twaps_info)
+        .map_err(|e| anyhow!("Invalid oracle twaps account: {e:?}"))?;
+    let oracle_twaps = twaps_loader
+        .load()
+        .map_err(|e| anyhow!("Failed to load oracle twaps: {e:?}"))?;
+
+    let mut failures = 0;
+    for &entry in &entries {
+        let entry_idx = usize::from(entry);
+        if entry_idx >= MAX_ENTRIES {
+            bail!("Entry {entry} is out of range");
+        }
+        let price_type = match OracleType::try_from(oracle_mappings.price_types[entry_idx]) {
+            Ok(price_type) => price_type,
+            Err(_) => {
+                println!("{entry:>3} | invalid oracle type");
+                failures += 1;
+                continue;
+            }
+        };
+        let Some(base_account) = infos.get(&oracle_mappings.price_info_accounts[entry_idx]) else {
+            println!("{entry:>3} | {price_type:?} | price account not found");
+            failures += 1;
+            continue;
+        };
+        let entry_extra_accounts: Vec<&AccountInfo> = extra_accounts
+            .get(&entry)
+            .map(|pubkeys| pubkeys.iter().filter_map(|pk| infos.get(pk)).collect())
+            .unwrap_or_default();
+
+        let res = get_non_zero_price(
+            price_type,
+            base_account,
+            &mut entry_extra_accounts.into_iter(),
+            &clock,
+            &oracle_twaps,
+            &oracle_mappings,
+            &oracle_prices,
+            entry_idx,
+        );
+        let onchain_price = oracle_prices
+            .load()
+            .map_err(|e| anyhow!("Failed to load oracle prices: {e:?}"))?
+            .prices[entry_idx];
+        match res {
+            Ok(price) => println!(
+                "{entry:>3} | {price_type:?} | {:?} (on-chain: {:?})",
+                price, onchain_price
+            ),
+            Err(e) => {
+                failures += 1;
+                println!(
+                    "{entry:>3} | {price_type:?} | error: {e:?} (on-chain: {onchain_price:?})"
+                );
+            }
+        }
+    }
+
+    println!("{} entries replayed, {failures} failed", entries.len());
+    Ok(())
+}
@@ -102,6 +102,198 @@ pub enum ScopeError {
 
     #[msg("Confidence interval check failed")]
     ConfidenceIntervalCheckFailed,
+
+    #[msg("Switchboard On Demand feed hash does not match the configured one")]
+    SwitchboardOnDemandFeedHashMismatch,
+
+    #[msg("Too many TWAP samples to inject in one instruction")]
+    TwapTooManyInjectedSamples,
+
+    #[msg("Injected TWAP samples must be strictly more recent than the last TWAP update")]
+    TwapInjectedSampleOutOfOrder,
+
+    #[msg("Group ID is out of range or defined more than once")]
+    InvalidGroupId,
+
+    #[msg("Invalid fallback sources configuration")]
+    InvalidFallbackSources,
+
+    #[msg("Invalid capped/floored oracle configuration")]
+    CappedFlooredInvalid,
+
+    #[msg("Invalid confidence factor configuration")]
+    InvalidConfidenceFactor,
+
+    #[msg("Invalid median oracle configuration")]
+    MedianOfInvalid,
+
+    #[msg("The same account is provided under several roles")]
+    DuplicateAccount,
+
+    #[msg("An instruction account is also provided as a remaining account")]
+    InstructionAccountInRemainingAccounts,
+
+    #[msg("Invalid EMA type for the TWAP entry")]
+    InvalidEmaType,
+
+    #[msg("Price refreshes of this feed are paused")]
+    FeedPaused,
+
+    #[msg("Invalid SOL quote publication configuration")]
+    InvalidSolQuotePublication,
+
+    #[msg("Price is older than the maximum age of the entry")]
+    StalePrice,
+
+    #[msg("Price deviates too much from the previous stored price")]
+    PriceDeviationTooLarge,
+
+    #[msg("Account version is more recent than the one supported by the program")]
+    UnsupportedAccountVersion,
+
+    #[msg("Account must be migrated to the current layout version")]
+    AccountMigrationRequired,
+
+    #[msg("Invalid Sanctum INF configuration")]
+    UnexpectedSanctumInfConfiguration,
+
+    #[msg("Invalid quote entry")]
+    InvalidQuoteEntry,
+
+    #[msg("Unexpected Meteora vault configuration")]
+    UnexpectedMeteoraVaultConfiguration,
+
+    #[msg("Invalid Orca whirlpool position configuration")]
+    OrcaWhirlpoolPositionInvalid,
+
+    #[msg("Invalid Raydium CP-Swap configuration")]
+    RaydiumCpmmInvalid,
+
+    #[msg("A price chain has a missing entry")]
+    ScopeChainMissingEntry,
+
+    #[msg("A price chain contains a zero price")]
+    ScopeChainZeroPrice,
+
+    #[msg("Twap bootstrap is only allowed on a new twap entry")]
+    TwapBootstrapNotAllowed,
+
+    #[msg("No close of the feed has been proposed")]
+    FeedCloseNotProposed,
+
+    #[msg("The timelock of the feed close proposal has not elapsed")]
+    FeedCloseTimelockNotElapsed,
+
+    #[msg("The entry is still referenced by other entries or scope chains")]
+    EntryStillReferenced,
+
+    #[msg("Invalid staking rate configuration")]
+    StakingRateInvalid,
+
+    #[msg("The entry is not degraded")]
+    EntryNotDegraded,
+
+    #[msg("Invalid most recent oracle configuration")]
+    MostRecentOfInvalid,
+
+    #[msg("Not enough fresh sources for the most recent oracle")]
+    MostRecentOfStaleSources,
+
+    #[msg("A fresh source diverges too much from the most recent price")]
+    MostRecentOfDivergence,
+
+    #[msg("Invalid discount to maturity oracle configuration")]
+    DiscountToMaturityInvalid,
+
+    #[msg("Invalid fixed rate accrual oracle configuration")]
+    FixedRateAccrualInvalid,
+
+    #[msg("Invalid Jito restaking configuration")]
+    JitoRestakingInvalid,
+
+    #[msg("Invalid fixed multiplier oracle configuration")]
+    FixedMultiplierInvalid,
+
+    #[msg("Invalid SPL stake pool oracle configuration")]
+    SplStakeInvalid,
+
+    #[msg("Invalid mSOL oracle configuration")]
+    MsolStakeInvalid,
+
+    #[msg("Invalid list of programs allowed to refresh through CPI")]
+    InvalidRefreshCpiCallers,
+
+    #[msg("Signer does not have the admin role required by the instruction")]
+    InvalidAdminRole,
+
+    #[msg("The entry is suspended")]
+    EntrySuspended,
+
+    #[msg("Invalid cToken oracle configuration")]
+    CTokenInvalid,
+
+    #[msg("cToken rate grew faster than the configured maximum")]
+    CTokenRateGrowthExceeded,
+
+    #[msg("Invalid degraded mode configuration")]
+    InvalidDegradedModeConfig,
+
+    #[msg("The slot production was observed too recently")]
+    SlotObservationTooRecent,
+
+    #[msg("Invalid mint map update")]
+    InvalidMintMapUpdate,
+
+    #[msg("Invalid annualized growth rate oracle configuration")]
+    AnnualizedGrowthRateInvalid,
+
+    #[msg("The price history has no price at the annualized growth rate lookback")]
+    AnnualizedGrowthRateNoReference,
+
+    #[msg("A price chain refers to an entry that is not mapped")]
+    ScopeChainUnmappedEntry,
+
+    #[msg("A price chain uses the same entry several times")]
+    ScopeChainDuplicateEntry,
+
+    #[msg("Invalid pool liquidity guard configuration")]
+    PoolLiquidityGuardInvalid,
+
+    #[msg("The liquidity of the pool is below the configured minimum")]
+    PoolLiquidityTooLow,
+
+    #[msg("The decimals of a kToken strategy do not match its mints")]
+    KTokenDecimalsMismatch,
+
+    #[msg("The pyth price is computed from fewer publishers than required")]
+    PythNotEnoughPublishers,
+
+    #[msg("Invalid Raydium TWAP configuration")]
+    RaydiumTwapInvalid,
+
+    #[msg("The Raydium pool observations do not cover the TWAP window")]
+    RaydiumTwapWindowNotCovered,
+
+    #[msg("This instruction is disabled on the feed")]
+    InstructionDisabled,
+
+    #[msg("Unknown instruction flags")]
+    InvalidInstructionFlags,
+
+    #[msg("The Meteora DLMM pair is not active")]
+    MeteoraPairNotActive,
+
+    #[msg("Invalid confidence policy")]
+    InvalidConfidencePolicy,
+
+    #[msg("Invalid generic vault share configuration")]
+    GenericVaultShareInvalid,
+
+    #[msg("Invalid Switchboard On-Demand entry configuration")]
+    SwitchboardOnDemandInvalidConfig,
+
+    #[msg("Twap is not enabled for the entry")]
+    TwapNotEnabled,
 }
 
 impl<T> From<TryFromPrimitiveError<T>> for ScopeError
@@ -0,0 +1,41 @@
+use std::cell::RefMut;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{account_header::load_versioned_mut, pdas::seeds},
+    InstructionFlag, OracleMappings, ScopeError,
+};
+
+/// Accounts shared by the instructions updating a single setting of an entry
+/// (unit scale, quote entry, fallback sources...), restricted to the mapping admin.
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct UpdateEntryConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        constraint = configuration.load()?.is_mapping_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    /// CHECK: checked above + on deserialize
+    #[account(mut, owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+}
+
+impl UpdateEntryConfig<'_> {
+    /// Run the checks shared by the entry setting updates and load the oracle mappings to update
+    pub fn load_mappings<T>(&self, ctx: &Context<T>) -> Result<RefMut<'_, OracleMappings>> {
+        check_context(ctx)?;
+        self.configuration
+            .load()?
+            .check_instruction_enabled(InstructionFlag::MappingUpdates)?;
+
+        let oracle_mappings = load_versioned_mut::<OracleMappings>(&self.oracle_mappings)?;
+        Ok(oracle_mappings)
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::{account_header::LoadVersioned, pdas::seeds},
+    DatedPrice, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct AcknowledgeDegradedEntry<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(mut)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(mut, has_one = oracle_prices)]
+    pub entry_stats: AccountLoader<'info, crate::OracleEntryStats>,
+}
+
+/// Clear the degraded status of an entry (and its failure count) once the admin has looked into
+/// its oracle. The next failures are counted from zero.
+pub fn process(ctx: Context<AcknowledgeDegradedEntry>, entry_id: usize, _: String) -> Result<()> {
+    msg!("AcknowledgeDegradedEntry, token: {entry_id}");
+
+    let mut entry_stats = ctx.accounts.entry_stats.load_mut()?;
+    if entry_id >= crate::MAX_ENTRIES {
+        return err!(ScopeError::BadTokenNb);
+    }
+    if !entry_stats.is_degraded(entry_id) {
+        return err!(ScopeError::EntryNotDegraded);
+    }
+    entry_stats.acknowledge(entry_id);
+    ctx.accounts.oracle_prices.load_versioned_mut()?.prices[entry_id].status &=
+        !DatedPrice::STATUS_DEGRADED;
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use solana_program::system_program;
+
+use crate::{
+    oracles::{
+        check_remaining_accounts_exclude, is_mapped_account_validated, validate_oracle_cfg,
+        OracleType,
+    },
+    utils::account_header::load_versioned,
+    OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+pub struct AuditMappings<'info> {
+    /// CHECK: Checked on deserialize
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, has_one = oracle_mappings)]
+    pub report: AccountLoader<'info, crate::MappingsAuditReport>,
+    // Note: use remaining accounts as mapped accounts, one per token
+}
+
+/// Check that the accounts mapped to the given entries still exist, are not owned by the
+/// system program (closed) and still deserialize as expected by their oracle type.
+///
+/// The result of each audited entry is written in the report account. The entries whose oracle type
+/// does not validate its mapped account are only checked for existence: they are reported as not
+/// audited unless their account does not exist.
+pub fn process(ctx: Context<AuditMappings>, tokens: &[u16]) -> Result<()> {
+    if tokens.is_empty() {
+        return err!(ScopeError::EmptyTokenList);
+    }
+    require_eq!(
+        tokens.len(),
+        ctx.remaining_accounts.len(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    check_remaining_accounts_exclude(
+        ctx.remaining_accounts,
+        &[
+            ctx.accounts.oracle_mappings.key(),
+            ctx.accounts.report.key(),
+        ],
+    )?;
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let mut report = ctx.accounts.report.load_mut()?;
+
+    let zero_pk = Pubkey::default();
+    let mut failed_count = 0;
+    for (&token_nb, mapped_account) in tokens.iter().zip(ctx.remaining_accounts.iter()) {
+        let token_idx: usize = token_nb.into();
+        let mapping = oracle_mappings
+            .price_info_accounts
+            .get(token_idx)
+            .ok_or(ScopeError::BadTokenNb)?;
+        // Entries without mapping or without external account have nothing to audit
+        if *mapping == zero_pk || *mapping == crate::ID {
+            report.set_entry_result(token_idx, false, false);
+            continue;
+        }
+        require_keys_eq!(
+            *mapping,
+            mapped_account.key(),
+            ScopeError::UnexpectedAccount
+        );
+
+        let price_type: OracleType = oracle_mappings.price_types[token_idx]
+            .try_into()
+            .map_err(|_| ScopeError::BadTokenType)?;
+
+        let failed = if mapped_account.lamports() == 0 || mapped_account.data_is_empty() {
+            msg!("tk {token_idx}, {price_type:?}: account {mapping} does not exist");
+            true
+        } else if system_program::check_id(mapped_account.owner) {
+            msg!(
+                "tk {token_idx}, {price_type:?}: account {mapping} is owned by the system program"
+            );
+            true
+        } else if !is_mapped_account_validated(price_type) {
+            msg!(
+                "tk {token_idx}, {price_type:?}: account {mapping} cannot be validated, unchecked"
+            );
+            report.set_entry_result(token_idx, false, false);
+            continue;
+        } else if validate_oracle_cfg(
+            price_type,
+            &Some(mapped_account.clone()),
+            oracle_mappings.twap_source[token_idx],
+            &oracle_mappings.generic[token_idx],
+        )
+        .is_err()
+        {
+            msg!("tk {token_idx}, {price_type:?}: account {mapping} is not valid for its oracle type");
+            true
+        } else {
+            false
+        };
+        if failed {
+            failed_count += 1;
+        }
+        report.set_entry_result(token_idx, true, failed);
+    }
+
+    report.last_audit_slot = Clock::get()?.slot;
+    msg!("Audited {} entries, {} failed", tokens.len(), failed_count);
+
+    Ok(())
+}
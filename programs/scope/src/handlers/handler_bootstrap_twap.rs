@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        pdas::seeds,
+    },
+    DatedPrice, OracleMappings, Price, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(token: u64, feed_name: String)]
+pub struct BootstrapTwap<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump,
+        has_one = admin,
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    /// CHECK: checked above + on deserialize
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, has_one = oracle_prices, has_one = oracle_mappings)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+}
+
+/// Seed the price and the EMA of a newly listed entry so that its TWAP consumers are not blocked
+/// while the EMA warms up. Both are flagged with [`DatedPrice::STATUS_BOOTSTRAP_SEED`]: the price
+/// until the first refresh, the EMA until `num_refreshes` refreshes added a sample.
+pub fn process(
+    ctx: Context<BootstrapTwap>,
+    token: usize,
+    seed_price: Price,
+    num_refreshes: u64,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::TwapOverrides)?;
+
+    msg!(
+        "BootstrapTwap, token: {}, seed_price: {:?}, num_refreshes: {}",
+        token,
+        seed_price,
+        num_refreshes
+    );
+
+    if seed_price.value == 0 || num_refreshes == 0 {
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    if token >= crate::MAX_ENTRIES || !oracle_mappings.is_twap_enabled(token) {
+        msg!("Twap is not enabled for token {token}");
+        return err!(ScopeError::TwapBootstrapNotAllowed);
+    }
+
+    let mut oracle_prices = ctx.accounts.oracle_prices.load_versioned_mut()?;
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
+
+    let dated_price = &mut oracle_prices.prices[token];
+    if dated_price.last_updated_slot != 0 {
+        msg!(
+            "Token {token} already has a price (last update slot {})",
+            dated_price.last_updated_slot
+        );
+        return err!(ScopeError::TwapBootstrapNotAllowed);
+    }
+
+    let clock = Clock::get()?;
+    let current_ts: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .map_err(|_| ScopeError::BadTimestamp)?;
+
+    crate::oracles::twap::bootstrap_twap(
+        &mut oracle_twaps,
+        token,
+        seed_price,
+        current_ts,
+        clock.slot,
+        num_refreshes,
+    )?;
+
+    *dated_price = DatedPrice {
+        price: seed_price,
+        last_updated_slot: clock.slot,
+        unix_timestamp: current_ts,
+        status: DatedPrice::STATUS_BOOTSTRAP_SEED,
+        index: u16::try_from(token).map_err(|_| ScopeError::BadTokenNb)?,
+        ..Default::default()
+    };
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{
+        account_header::{load_versioned, load_versioned_mut, LoadVersioned},
+        pdas::seeds,
+    },
+    DatedPrice, EmaTwap, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(start_entry: u16, end_entry: u16, feed_name: String)]
+pub struct CloneMappings<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(has_one = admin)]
+    pub src_configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: checked above + on deserialize
+    #[account(owner = crate::ID, address = src_configuration.load()?.oracle_mappings)]
+    pub src_oracle_mappings: AccountInfo<'info>,
+    #[account(address = src_configuration.load()?.tokens_metadata)]
+    pub src_tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = admin,
+        constraint = dst_configuration.key() != src_configuration.key() @ ScopeError::UnexpectedAccount,
+    )]
+    pub dst_configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: checked above + on deserialize
+    #[account(mut, owner = crate::ID, address = dst_configuration.load()?.oracle_mappings)]
+    pub dst_oracle_mappings: AccountInfo<'info>,
+    #[account(mut, address = dst_configuration.load()?.tokens_metadata)]
+    pub dst_tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+    #[account(mut, address = dst_configuration.load()?.oracle_prices)]
+    pub dst_oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(mut, address = dst_configuration.load()?.oracle_twaps)]
+    pub dst_oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+}
+
+/// Copy the mappings and the token metadata of the entries `start_entry..end_entry` of a feed into
+/// the feed `feed_name`, both feeds having the same admin, e.g. to stand up a mirror of a feed.
+///
+/// The prices and TWAPs of the copied entries are reset in the destination feed. The references
+/// of the copied entries to entries outside of the range are logged, they are copied as is.
+pub fn process(
+    ctx: Context<CloneMappings>,
+    start_entry: u16,
+    end_entry: u16,
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+    ctx.accounts
+        .dst_configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MappingUpdates)?;
+
+    msg!(
+        "CloneMappings, from: {}, to feed: {}, entries: {}..{}",
+        ctx.accounts.src_configuration.key(),
+        feed_name,
+        start_entry,
+        end_entry
+    );
+
+    let entries = usize::from(start_entry)..usize::from(end_entry);
+    if entries.is_empty() || entries.end > crate::MAX_ENTRIES {
+        return err!(ScopeError::BadTokenNb);
+    }
+
+    let src_oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.src_oracle_mappings)?;
+    let src_tokens_metadata = ctx.accounts.src_tokens_metadata.load()?;
+    let mut dst_oracle_mappings =
+        load_versioned_mut::<OracleMappings>(&ctx.accounts.dst_oracle_mappings)?;
+    let mut dst_tokens_metadata = ctx.accounts.dst_tokens_metadata.load_mut()?;
+    let mut dst_oracle_prices = ctx.accounts.dst_oracle_prices.load_versioned_mut()?;
+    let mut dst_oracle_twaps = ctx.accounts.dst_oracle_twaps.load_versioned_mut()?;
+
+    for entry_id in entries.clone() {
+        for (dependency, kind) in src_oracle_mappings.get_dependencies(entry_id) {
+            if !entries.contains(&dependency) {
+                msg!("Entry {entry_id} reads entry {dependency} ({kind}) outside of the cloned range");
+            }
+        }
+        dst_oracle_mappings.copy_entry_from(&src_oracle_mappings, entry_id);
+        dst_tokens_metadata.metadatas_array[entry_id] =
+            src_tokens_metadata.metadatas_array[entry_id];
+        dst_oracle_prices.prices[entry_id] = DatedPrice::default();
+        dst_oracle_twaps.twaps[entry_id] = EmaTwap::default();
+    }
+
+    Ok(())
+}
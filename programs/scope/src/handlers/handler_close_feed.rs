@@ -0,0 +1,83 @@
+use anchor_lang::{prelude::*, system_program};
+
+use crate::{
+    oracles::check_context,
+    utils::{consts::FEED_CLOSE_TIMELOCK_SECONDS, pdas::seeds},
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct CloseFeed<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump,
+        close = destination,
+        has_one = admin,
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(mut, close = destination)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    /// CHECK: checked above, closed manually as it is not deserialized with an `AccountLoader`
+    #[account(mut, owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, close = destination)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+    #[account(mut, close = destination)]
+    pub tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+
+    /// CHECK: destination of the pending close proposal
+    #[account(mut, address = configuration.load()?.close_destination)]
+    pub destination: AccountInfo<'info>,
+}
+
+/// Close all the accounts of the feed once the timelock of the close proposal has elapsed.
+///
+/// The accounts derived from the feed (mint maps, group definitions, group digests, audit reports)
+/// are not closed by this instruction.
+pub fn process(ctx: Context<CloseFeed>, feed_name: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    {
+        let configuration = ctx.accounts.configuration.load()?;
+        if !configuration.is_close_proposed() {
+            return err!(ScopeError::FeedCloseNotProposed);
+        }
+        let current_ts: u64 = Clock::get()?
+            .unix_timestamp
+            .try_into()
+            .map_err(|_| ScopeError::BadTimestamp)?;
+        let executable_at = configuration
+            .close_proposed_at
+            .saturating_add(FEED_CLOSE_TIMELOCK_SECONDS);
+        if current_ts < executable_at {
+            msg!("Feed {feed_name} can be closed from ts {executable_at} (now {current_ts})");
+            return err!(ScopeError::FeedCloseTimelockNotElapsed);
+        }
+    }
+
+    msg!(
+        "Closing feed {} to {}",
+        feed_name,
+        ctx.accounts.destination.key()
+    );
+
+    // The other accounts are closed (and zeroed) by anchor on exit
+    let oracle_mappings = &ctx.accounts.oracle_mappings;
+    let destination = &ctx.accounts.destination;
+    oracle_mappings.try_borrow_mut_data()?.fill(0);
+    let lamports = oracle_mappings.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ScopeError::MathOverflow)?;
+    **oracle_mappings.try_borrow_mut_lamports()? = 0;
+    oracle_mappings.assign(&system_program::ID);
+    oracle_mappings.realloc(0, false)?;
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, GroupDefinitions};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct CloseGroupDefinitions<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(mut, close = admin, has_one = configuration)]
+    pub group_definitions: Account<'info, GroupDefinitions>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(_ctx: Context<CloseGroupDefinitions>, _: String) -> Result<()> {
+    Ok(())
+}
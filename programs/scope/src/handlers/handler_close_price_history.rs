@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::pdas::seeds;
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct ClosePriceHistory<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(mut, close = admin, has_one = oracle_prices)]
+    pub price_history: AccountLoader<'info, crate::OraclePriceHistory>,
+}
+
+pub fn process(_ctx: Context<ClosePriceHistory>, _: String) -> Result<()> {
+    Ok(())
+}
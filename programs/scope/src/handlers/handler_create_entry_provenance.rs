@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, EntryProvenanceInfo, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct CreateEntryProvenance<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(
+        init,
+        seeds = [seeds::ENTRY_PROVENANCE, oracle_prices.key().as_ref(), &token.to_le_bytes()],
+        bump,
+        space = 8 + std::mem::size_of::<crate::EntryProvenance>(),
+        payer = admin,
+    )]
+    pub entry_provenance: AccountLoader<'info, crate::EntryProvenance>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(
+    ctx: Context<CreateEntryProvenance>,
+    token: u16,
+    mut info: EntryProvenanceInfo,
+    _: String,
+) -> Result<()> {
+    require_gt!(crate::MAX_ENTRIES_U16, token, ScopeError::BadTokenNb);
+
+    let slot = Clock::get()?.slot;
+    if info.onboarding_slot == 0 {
+        info.onboarding_slot = slot;
+    }
+
+    let mut entry_provenance = ctx.accounts.entry_provenance.load_init()?;
+    entry_provenance.oracle_prices = ctx.accounts.oracle_prices.key();
+    entry_provenance.entry_id = token;
+    entry_provenance.info = info;
+    entry_provenance.last_update_slot = slot;
+
+    Ok(())
+}
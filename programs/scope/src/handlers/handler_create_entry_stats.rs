@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::pdas::seeds;
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct CreateEntryStats<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(
+        init,
+        seeds = [seeds::ENTRY_STATS, oracle_prices.key().as_ref()],
+        bump,
+        space = 8 + std::mem::size_of::<crate::OracleEntryStats>(),
+        payer = admin,
+    )]
+    pub entry_stats: AccountLoader<'info, crate::OracleEntryStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(
+    ctx: Context<CreateEntryStats>,
+    max_consecutive_failures: u16,
+    _: String,
+) -> Result<()> {
+    let mut entry_stats = ctx.accounts.entry_stats.load_init()?;
+    entry_stats.oracle_prices = ctx.accounts.oracle_prices.key();
+    entry_stats.max_consecutive_failures = max_consecutive_failures;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, GroupDefinition, GroupDefinitions, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String, groups: Vec<GroupDefinition>)]
+pub struct CreateGroupDefinitions<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(
+        init,
+        seeds = [seeds::GROUP_DEFINITIONS, configuration.key().as_ref()],
+        bump,
+        space = 8 + GroupDefinitions::size_from_groups(&groups),
+        payer = admin,
+    )]
+    pub group_definitions: Account<'info, GroupDefinitions>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(
+    ctx: Context<CreateGroupDefinitions>,
+    groups: Vec<GroupDefinition>,
+    _: String,
+) -> Result<()> {
+    let mut defined_groups: u64 = 0;
+    for group in groups.iter() {
+        require_gt!(
+            u64::BITS,
+            u32::from(group.group_id),
+            ScopeError::InvalidGroupId
+        );
+        if defined_groups & (1 << group.group_id) != 0 {
+            msg!("Group {} is defined more than once", group.group_id);
+            return err!(ScopeError::InvalidGroupId);
+        }
+        defined_groups |= 1 << group.group_id;
+    }
+
+    let group_definitions = GroupDefinitions {
+        configuration: ctx.accounts.configuration.key(),
+        bump: *ctx.bumps.get("group_definitions").unwrap(),
+        groups,
+    };
+    // Fail early on out of range entries
+    let _ = group_definitions.compute_group_ids_bitsets()?;
+
+    ctx.accounts.group_definitions.set_inner(group_definitions);
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::pdas::seeds;
+
+#[derive(Accounts)]
+pub struct CreateMappingsAuditReport<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Only used as seed, checked on deserialize during the audit
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [seeds::MAPPINGS_AUDIT_REPORT, oracle_mappings.key().as_ref()],
+        bump,
+        space = 8 + std::mem::size_of::<crate::MappingsAuditReport>(),
+        payer = payer,
+    )]
+    pub report: AccountLoader<'info, crate::MappingsAuditReport>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(ctx: Context<CreateMappingsAuditReport>) -> Result<()> {
+    let mut report = ctx.accounts.report.load_init()?;
+    report.oracle_mappings = ctx.accounts.oracle_mappings.key();
+    Ok(())
+}
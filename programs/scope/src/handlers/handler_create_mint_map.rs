@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
 
-use crate::{utils::pdas::seeds, MintToScopeChain, MintsToScopeChains};
+use crate::{
+    oracles::check_remaining_accounts_exclude,
+    utils::{account_header::load_versioned, pdas::seeds, scope_chain, unpack_mint},
+    MintToScopeChain, MintsToScopeChains, OracleMappings, ScopeError,
+};
 
 #[derive(Accounts)]
 #[instruction(
@@ -13,8 +16,11 @@ use crate::{utils::pdas::seeds, MintToScopeChain, MintsToScopeChains};
 pub struct CreateMintMap<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(has_one = admin)]
+    #[account(has_one = admin, has_one = oracle_mappings)]
     pub configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: Checked above
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
     #[account(
         init,
         seeds = [seeds::MINTS_TO_SCOPE_CHAINS, configuration.load()?.oracle_prices.as_ref(), seed_pk.as_ref(), &seed_id.to_le_bytes()],
@@ -35,8 +41,22 @@ pub fn process(
     bump: u8,
     scope_chains: Vec<[u16; 4]>,
 ) -> Result<()> {
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MintMaps)?;
+
     require_eq!(ctx.remaining_accounts.len(), scope_chains.len());
+    check_remaining_accounts_exclude(
+        ctx.remaining_accounts,
+        &[
+            ctx.accounts.configuration.key(),
+            ctx.accounts.oracle_mappings.key(),
+            ctx.accounts.mappings.key(),
+        ],
+    )?;
 
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
     ctx.accounts.mappings.set_inner(MintsToScopeChains {
         seed_pk,
         seed_id,
@@ -46,8 +66,11 @@ pub fn process(
             .iter()
             .zip(ctx.remaining_accounts.iter())
             .map(|(chain, mint)| {
-                let mint_data = mint.data.borrow();
-                let _: Mint = Mint::try_deserialize_unchecked(&mut mint_data.as_ref())?;
+                scope_chain::validate_chain(&oracle_mappings, chain).map_err(|e| {
+                    msg!("Invalid scope chain {chain:?} for mint {}: {e:?}", mint.key);
+                    ScopeError::from(e)
+                })?;
+                unpack_mint(mint)?;
                 Ok(MintToScopeChain {
                     mint: *mint.key,
                     scope_chain: *chain,
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(group_id: u8, feed_name: String)]
+pub struct CreatePriceGroupDigest<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(
+        init,
+        seeds = [seeds::PRICE_GROUP_DIGEST, oracle_prices.key().as_ref(), &[group_id]],
+        bump,
+        space = 8 + std::mem::size_of::<crate::PriceGroupDigest>(),
+        payer = admin,
+    )]
+    pub group_digest: AccountLoader<'info, crate::PriceGroupDigest>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(ctx: Context<CreatePriceGroupDigest>, group_id: u8, _: String) -> Result<()> {
+    require_gt!(u64::BITS, u32::from(group_id), ScopeError::InvalidGroupId);
+
+    let mut group_digest = ctx.accounts.group_digest.load_init()?;
+    group_digest.oracle_prices = ctx.accounts.oracle_prices.key();
+    group_digest.group_id = group_id;
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct CreatePriceHistory<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(
+        init,
+        seeds = [seeds::PRICE_HISTORY, oracle_prices.key().as_ref(), &token.to_le_bytes()],
+        bump,
+        space = 8 + std::mem::size_of::<crate::OraclePriceHistory>(),
+        payer = admin,
+    )]
+    pub price_history: AccountLoader<'info, crate::OraclePriceHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(ctx: Context<CreatePriceHistory>, token: u16, _: String) -> Result<()> {
+    require_gt!(crate::MAX_ENTRIES_U16, token, ScopeError::BadTokenNb);
+
+    let mut price_history = ctx.accounts.price_history.load_init()?;
+    price_history.oracle_prices = ctx.accounts.oracle_prices.key();
+    price_history.entry_id = token;
+
+    Ok(())
+}
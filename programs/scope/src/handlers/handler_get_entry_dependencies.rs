@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context, utils::account_header::load_versioned, OracleMappings, ScopeError,
+};
+
+/// Maximum number of entries that fit in the instruction return data (1024 bytes)
+pub const MAX_RETURNED_DEPENDENCIES: usize = 510;
+
+#[derive(Accounts)]
+pub struct GetEntryDependencies<'info> {
+    /// CHECK: checked on deserialize
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+}
+
+/// Return the entries whose price is read, directly or transitively, to compute the price of
+/// `token` (TWAP sources, ref prices, fallbacks, quote entries and the entries read through the
+/// generic data), in increasing order. The direct dependencies of each entry are logged.
+///
+/// These are the entries whose failure or bad price can affect `token`.
+pub fn process(ctx: Context<GetEntryDependencies>, token: u16) -> Result<Vec<u16>> {
+    check_context(&ctx)?;
+
+    let token_idx = usize::from(token);
+    require_gt!(crate::MAX_ENTRIES, token_idx, ScopeError::BadTokenNb);
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+
+    let dependencies = oracle_mappings.get_transitive_dependencies(token_idx);
+    for &entry_id in std::iter::once(&token_idx).chain(dependencies.iter()) {
+        let direct_dependencies = oracle_mappings.get_dependencies(entry_id);
+        if !direct_dependencies.is_empty() {
+            msg!("Entry {entry_id} depends on {direct_dependencies:?}");
+        }
+    }
+    require_gte!(
+        MAX_RETURNED_DEPENDENCIES,
+        dependencies.len(),
+        ScopeError::BadTokenNb
+    );
+
+    Ok(dependencies
+        .into_iter()
+        .map(|entry_id| entry_id as u16)
+        .collect())
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::{check_context, OracleType},
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        consts::MAX_VALIDATED_PRICES,
+    },
+    DatedPrice, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+pub struct GetValidatedPrices<'info> {
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    pub tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+    #[account(has_one = oracle_prices, has_one = tokens_metadata)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    /// If provided, the entries without a max age in their metadata get the default of their
+    /// oracle type
+    /// CHECK: checked above + on deserialize
+    #[account(owner = crate::ID, address = configuration.load()?.oracle_mappings)]
+    pub oracle_mappings: Option<AccountInfo<'info>>,
+}
+
+/// Return the prices of the given tokens, failing if any of them is older than the
+/// `max_age_price_slots` of its entry metadata (scaled while the feed is in degraded mode).
+///
+/// If the oracle mappings are provided, the max age of an entry falls back to the defaults of its
+/// oracle type (see [`crate::Configuration::max_age_slots`]) when its metadata has none.
+pub fn process(ctx: Context<GetValidatedPrices>, tokens: &[u16]) -> Result<Vec<DatedPrice>> {
+    check_context(&ctx)?;
+
+    if tokens.is_empty() {
+        return err!(ScopeError::EmptyTokenList);
+    }
+    require_gte!(MAX_VALIDATED_PRICES, tokens.len(), ScopeError::BadTokenNb);
+
+    let oracle_prices = ctx.accounts.oracle_prices.load_versioned()?;
+    let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+    let configuration = ctx.accounts.configuration.load()?;
+    if configuration.is_degraded() {
+        msg!(
+            "Feed degraded, max ages scaled by {}",
+            configuration.degraded_max_age_factor
+        );
+    }
+    let oracle_mappings = ctx
+        .accounts
+        .oracle_mappings
+        .as_ref()
+        .map(load_versioned::<OracleMappings>)
+        .transpose()?;
+    let current_slot = Clock::get()?.slot;
+
+    tokens
+        .iter()
+        .map(|&token| {
+            let token_idx = usize::from(token);
+            let price = oracle_prices
+                .prices
+                .get(token_idx)
+                .ok_or(ScopeError::BadTokenNb)?;
+            if price.price.value == 0 {
+                msg!("Token {} has no price", token_idx);
+                return err!(ScopeError::PriceNotValid);
+            }
+            let entry_max_age_slots =
+                tokens_metadata.metadatas_array[token_idx].max_age_price_slots;
+            let max_age_price_slots = match &oracle_mappings {
+                Some(oracle_mappings) => {
+                    let price_type: OracleType = oracle_mappings.price_types[token_idx]
+                        .try_into()
+                        .map_err(|_| ScopeError::BadTokenType)?;
+                    match configuration.max_age_slots(price_type, entry_max_age_slots) {
+                        Some(max_age_slots) => max_age_slots,
+                        None => return Ok(*price),
+                    }
+                }
+                None => entry_max_age_slots,
+            };
+            let max_age_price_slots = configuration.effective_max_age_slots(max_age_price_slots);
+            let age_slots = current_slot.saturating_sub(price.last_updated_slot);
+            if age_slots > max_age_price_slots {
+                msg!(
+                    "Price of token {} is {} slots old, max age is {} slots",
+                    token_idx,
+                    age_slots,
+                    max_age_price_slots
+                );
+                return err!(ScopeError::StalePrice);
+            }
+            Ok(*price)
+        })
+        .collect()
+}
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 
-use crate::utils::pdas::seeds;
+use crate::{
+    oracles::check_unique_accounts,
+    utils::{account_header::init_account_header, pdas::seeds},
+    OracleMappings, OraclePrices, OracleTwaps,
+};
 
 #[derive(Accounts)]
 #[instruction(feed_name: String)]
@@ -32,6 +36,19 @@ pub struct Initialize<'info> {
 }
 
 pub fn process(ctx: Context<Initialize>, _: String) -> Result<()> {
+    // `zero` accounts have no discriminator yet, the same account could be passed under several roles
+    check_unique_accounts(&[
+        ctx.accounts.token_metadatas.key(),
+        ctx.accounts.oracle_twaps.key(),
+        ctx.accounts.oracle_prices.key(),
+        ctx.accounts.oracle_mappings.key(),
+    ])?;
+
+    // Accounts allocated with room for the header start at the current layout version
+    init_account_header::<OracleMappings>(ctx.accounts.oracle_mappings.as_ref())?;
+    init_account_header::<OraclePrices>(ctx.accounts.oracle_prices.as_ref())?;
+    init_account_header::<OracleTwaps>(ctx.accounts.oracle_twaps.as_ref())?;
+
     let _ = ctx.accounts.oracle_mappings.load_init()?;
     let _ = ctx.accounts.token_metadatas.load_init()?;
     let mut oracle_prices = ctx.accounts.oracle_prices.load_init()?;
@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        pdas::seeds,
+    },
+    OracleMappings, Price, ScopeError,
+};
+
+/// Maximum number of samples that can be injected in one instruction
+/// (the TWAP tracker holds at most 64 points per period).
+pub const MAX_INJECTED_TWAP_SAMPLES: usize = 64;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy)]
+pub struct TwapSample {
+    pub price: Price,
+    pub unix_timestamp: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct TwapSampleInjected {
+    pub entry_id: u16,
+    pub price: Price,
+    pub unix_timestamp: u64,
+    pub slot: u64,
+    pub ema_1h: u128,
+    pub ema_24h: u128,
+    pub ema_7d: u128,
+}
+
+#[derive(Accounts)]
+#[instruction(token: u64, feed_name: String)]
+pub struct InjectTwapSamples<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump,
+        has_one = admin,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: checked above + on deserialize
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+}
+
+pub fn process(
+    ctx: Context<InjectTwapSamples>,
+    token: usize,
+    samples: &[TwapSample],
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::TwapOverrides)?;
+
+    if samples.is_empty() {
+        return Err(ProgramError::InvalidArgument.into());
+    }
+    require_gte!(
+        MAX_INJECTED_TWAP_SAMPLES,
+        samples.len(),
+        ScopeError::TwapTooManyInjectedSamples
+    );
+
+    if token >= crate::MAX_ENTRIES {
+        return err!(ScopeError::BadTokenNb);
+    }
+    let entry_id = u16::try_from(token).map_err(|_| ScopeError::BadTokenNb)?;
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    if !oracle_mappings.is_twap_enabled(token) {
+        msg!("Twap is not enabled for token {token}");
+        return err!(ScopeError::TwapNotEnabled);
+    }
+
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
+    let clock = Clock::get()?;
+    let current_ts: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .map_err(|_| ScopeError::BadTimestamp)?;
+
+    for sample in samples {
+        // Injected samples must be historical observations
+        if sample.unix_timestamp >= current_ts || sample.slot >= clock.slot {
+            msg!(
+                "Injected sample is not in the past: ts {} (now {}), slot {} (now {})",
+                sample.unix_timestamp,
+                current_ts,
+                sample.slot,
+                clock.slot
+            );
+            return err!(ScopeError::BadTimestamp);
+        }
+
+        let twap = crate::oracles::twap::inject_twap_sample(
+            &mut oracle_twaps,
+            token,
+            sample.price,
+            sample.unix_timestamp,
+            sample.slot,
+        )?;
+
+        emit!(TwapSampleInjected {
+            entry_id,
+            price: sample.price,
+            unix_timestamp: sample.unix_timestamp,
+            slot: sample.slot,
+            ema_1h: twap.current_ema_1h,
+            ema_24h: twap.current_ema_24h,
+            ema_7d: twap.current_ema_7d,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::{account_header::migrate_account, pdas::seeds},
+    OracleMappings, OraclePrices, OracleTwaps, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: one of the prices, mappings or TWAPs accounts of the feed, checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub account: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Migrate the prices, mappings or TWAPs account of the feed to the current layout version.
+///
+/// A call grows the account by at most 10KiB, it has to be repeated until the migration is logged
+/// as complete. See [`crate::utils::account_header`] for the upgrade path.
+pub fn process(ctx: Context<MigrateAccount>, _: String) -> Result<()> {
+    let account = &ctx.accounts.account;
+    let payer = ctx.accounts.admin.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let (oracle_prices, oracle_mappings, oracle_twaps) = {
+        let configuration = ctx.accounts.configuration.load()?;
+        (
+            configuration.oracle_prices,
+            configuration.oracle_mappings,
+            configuration.oracle_twaps,
+        )
+    };
+
+    let key = account.key();
+    if key == oracle_prices {
+        migrate_account::<OraclePrices>(account, &payer, &system_program)?;
+    } else if key == oracle_mappings {
+        migrate_account::<OracleMappings>(account, &payer, &system_program)?;
+    } else if key == oracle_twaps {
+        migrate_account::<OracleTwaps>(account, &payer, &system_program)?;
+    } else {
+        msg!("Account {key} is not a versioned account of the feed");
+        return err!(ScopeError::UnexpectedAccount);
+    }
+
+    Ok(())
+}
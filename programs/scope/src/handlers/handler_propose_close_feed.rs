@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct ProposeCloseFeed<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Propose to close the feed, sending the rent of its accounts to `destination` once the
+/// timelock has elapsed. The feed is paused until the close is executed.
+///
+/// Proposing with the default pubkey as `destination` cancels the pending proposal (the feed
+/// stays paused), as does unpausing the feed.
+pub fn process(
+    ctx: Context<ProposeCloseFeed>,
+    destination: Pubkey,
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+
+    if destination == Pubkey::default() {
+        if !configuration.is_close_proposed() {
+            return err!(ScopeError::FeedCloseNotProposed);
+        }
+        msg!("Cancelling the close of feed {}", feed_name);
+        configuration.close_proposed_at = 0;
+        configuration.close_destination = Pubkey::default();
+        return Ok(());
+    }
+
+    let clock = Clock::get()?;
+    let current_ts: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .map_err(|_| ScopeError::BadTimestamp)?;
+
+    msg!(
+        "Proposing to close feed {} to {} at ts {}",
+        feed_name,
+        destination,
+        current_ts
+    );
+
+    configuration.close_proposed_at = current_ts;
+    configuration.close_destination = destination;
+    configuration.paused = 1;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, GroupDefinitions};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct RecomputeGroupIds<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = tokens_metadata)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    #[account(has_one = configuration)]
+    pub group_definitions: Account<'info, GroupDefinitions>,
+    #[account(mut)]
+    pub tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+}
+
+pub fn process(ctx: Context<RecomputeGroupIds>, _: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    let bitsets = ctx.accounts.group_definitions.compute_group_ids_bitsets()?;
+
+    let mut tokens_metadata = ctx.accounts.tokens_metadata.load_mut()?;
+    let mut updated_entries = 0;
+    for (token_metadata, bitset) in tokens_metadata.metadatas_array.iter_mut().zip(bitsets) {
+        if token_metadata.group_ids_bitset != bitset {
+            token_metadata.group_ids_bitset = bitset;
+            updated_entries += 1;
+        }
+    }
+    msg!(
+        "Recomputed group IDs from {} group definitions, {} entries updated",
+        ctx.accounts.group_definitions.groups.len(),
+        updated_entries
+    );
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use std::convert::TryInto;
 
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program};
+use decimal_wad::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+};
 use solana_program::{
     instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
     pubkey,
@@ -10,13 +14,56 @@ use solana_program::{
 };
 
 use crate::{
-    oracles::{get_non_zero_price, OracleType},
-    utils::{price_impl::check_ref_price_difference, zero_copy_deserialize},
-    OracleMappings, ScopeError,
+    oracles::{check_remaining_accounts_exclude, get_non_zero_price, OracleType},
+    scope_log,
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        logs::LogCode,
+        pdas::seeds,
+        price_impl::{check_price_deviation_bps, check_ref_price_difference},
+    },
+    Configuration, DatedPrice, OracleEntryStats, OracleMappings, OraclePriceHistory, OraclePrices,
+    OracleTwaps, Price, ScopeError, SmoothingMode, TokenMetadatas,
 };
 
 const COMPUTE_BUDGET_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
 
+#[event]
+pub struct RefreshSkippedMinInterval {
+    pub entry_id: u16,
+    pub last_updated_slot: u64,
+    pub new_slot: u64,
+    pub min_refresh_interval_slots: u64,
+}
+
+#[event]
+pub struct RefreshRewardPaid {
+    pub cranker: Pubkey,
+    pub rewarded_entries: u16,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct RefreshRejectedRefPrice {
+    pub entry_id: u16,
+    pub price: Price,
+    pub ref_entry_id: u16,
+    pub ref_price: Price,
+    pub tolerance_bps: u16,
+}
+
+#[event]
+pub struct EntryDegraded {
+    pub entry_id: u16,
+    pub consecutive_failures: u16,
+    pub slot: u64,
+}
+
+/// Accounts of the refresh of a list of entries of a feed.
+///
+/// `configuration` is required since the feed-level pause: the cranks built against the previous
+/// accounts list (without it) must append the configuration of the feed after the instruction
+/// sysvar. It is not optional, as a refresh without it would bypass the pause of the feed.
 #[derive(Accounts)]
 pub struct RefreshList<'info> {
     #[account(mut, has_one = oracle_mappings)]
@@ -29,29 +76,233 @@ pub struct RefreshList<'info> {
     /// CHECK: Sysvar fixed address
     #[account(address = SYSVAR_INSTRUCTIONS_ID)]
     pub instruction_sysvar_account_info: AccountInfo<'info>,
+    #[account(has_one = oracle_prices, has_one = oracle_mappings, has_one = oracle_twaps)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
     // Note: use remaining accounts as price accounts
 }
 
+#[derive(Accounts)]
+pub struct RefreshListRewarded<'info> {
+    pub refresh: RefreshList<'info>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    #[account(mut, seeds = [seeds::REFRESH_REWARD_VAULT, refresh.configuration.key().as_ref()], bump)]
+    pub reward_vault: SystemAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshTwoFeeds<'info> {
+    pub first: RefreshList<'info>,
+    #[account(constraint = second.configuration.key() != first.configuration.key() @ ScopeError::UnexpectedAccount)]
+    pub second: RefreshList<'info>,
+}
+
 pub fn refresh_price_list<'info>(
     ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
     tokens: &[u16],
 ) -> Result<()> {
-    check_execution_ctx(&ctx.accounts.instruction_sysvar_account_info)?;
+    refresh_list(ctx.accounts, ctx.remaining_accounts, tokens, &[])?;
+    Ok(())
+}
+
+/// Same as [`refresh_price_list`], the cranker is paid from the feed reward vault for each
+/// refreshed entry whose previous price was stale enough (see [`crate::RefreshRewardConfig`]).
+pub fn refresh_price_list_rewarded<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshListRewarded<'info>>,
+    tokens: &[u16],
+) -> Result<()> {
+    let stale_slots = refresh_list(
+        &ctx.accounts.refresh,
+        ctx.remaining_accounts,
+        tokens,
+        &[
+            ctx.accounts.cranker.key(),
+            ctx.accounts.reward_vault.key(),
+            ctx.accounts.system_program.key(),
+        ],
+    )?;
+
+    let reward_config = ctx.accounts.refresh.configuration.load()?.refresh_reward;
+    if !reward_config.is_enabled() {
+        return Ok(());
+    }
+    let rewarded_entries = stale_slots
+        .iter()
+        .filter(|&&slots| slots >= reward_config.min_stale_slots)
+        .count();
+    let reward_vault = &ctx.accounts.reward_vault;
+    let available_lamports = reward_vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    let lamports = reward_config
+        .lamports_per_entry
+        .saturating_mul(u64::try_from(rewarded_entries).unwrap())
+        .min(reward_config.max_lamports_per_refresh)
+        .min(available_lamports);
+    if lamports == 0 {
+        msg!("No refresh reward paid ({rewarded_entries} rewarded entries)");
+        return Ok(());
+    }
+
+    let configuration_key = ctx.accounts.refresh.configuration.key();
+    let vault_seeds: &[&[u8]] = &[
+        seeds::REFRESH_REWARD_VAULT,
+        configuration_key.as_ref(),
+        &[*ctx.bumps.get("reward_vault").unwrap()],
+    ];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: reward_vault.to_account_info(),
+                to: ctx.accounts.cranker.to_account_info(),
+            },
+            &[vault_seeds],
+        ),
+        lamports,
+    )?;
+
+    emit!(RefreshRewardPaid {
+        cranker: ctx.accounts.cranker.key(),
+        rewarded_entries: u16::try_from(rewarded_entries).unwrap(),
+        lamports,
+    });
 
-    let oracle_mappings = &zero_copy_deserialize::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
-    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
+    Ok(())
+}
+
+/// Refresh lists of tokens of two feeds in the same instruction, so that the entries of both
+/// feeds are updated in the same slot (and with the same clock) or none is.
+///
+/// The first `first_feed_accounts` remaining accounts are the accounts of the refresh of the first
+/// feed (price accounts, their extra accounts and leftover accounts), the others the accounts of
+/// the refresh of the second feed.
+pub fn refresh_price_list_two_feeds<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshTwoFeeds<'info>>,
+    first_tokens: &[u16],
+    second_tokens: &[u16],
+    first_feed_accounts: usize,
+) -> Result<()> {
+    if first_feed_accounts > ctx.remaining_accounts.len() {
+        return err!(ScopeError::AccountsAndTokenMismatch);
+    }
+    let (first_accounts, second_accounts) = ctx.remaining_accounts.split_at(first_feed_accounts);
+    let first = &ctx.accounts.first;
+    let second = &ctx.accounts.second;
+    let feed_accounts = |refresh: &RefreshList| {
+        [
+            refresh.oracle_mappings.key(),
+            refresh.oracle_twaps.key(),
+            refresh.configuration.key(),
+        ]
+    };
+
+    refresh_list(first, first_accounts, first_tokens, &feed_accounts(second))?;
+    refresh_list(
+        second,
+        second_accounts,
+        second_tokens,
+        &feed_accounts(first),
+    )?;
+    Ok(())
+}
+
+/// Refresh a list of tokens, returns the staleness of the entries actually updated (see
+/// [`refresh_tokens`]).
+///
+/// `other_accounts` are the accounts of the instruction not part of [`RefreshList`], they must
+/// not be provided as remaining accounts either.
+///
+/// The prices account of a feed can be provided as remaining account: the kToken oracles read the
+/// prices of the feed of their strategy (`scope_prices`), which is usually the refreshed feed.
+fn refresh_list<'info>(
+    accounts: &RefreshList<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    tokens: &[u16],
+    other_accounts: &[Pubkey],
+) -> Result<Vec<u64>> {
+    check_execution_ctx(
+        &accounts.instruction_sysvar_account_info,
+        &accounts.configuration.load()?.refresh_cpi_callers,
+    )?;
+    let mut instruction_accounts = vec![
+        accounts.oracle_mappings.key(),
+        accounts.oracle_twaps.key(),
+        accounts.configuration.key(),
+    ];
+    instruction_accounts.extend_from_slice(other_accounts);
+    check_remaining_accounts_exclude(remaining_accounts, &instruction_accounts)?;
+
+    if accounts.configuration.load()?.is_paused() {
+        return err!(ScopeError::FeedPaused);
+    }
+
+    let oracle_mappings = &load_versioned::<OracleMappings>(&accounts.oracle_mappings)?;
+    let mut oracle_twaps = accounts.oracle_twaps.load_versioned_mut()?;
 
     // No token to refresh
     if tokens.is_empty() {
         return err!(ScopeError::EmptyTokenList);
     }
 
+    // The list refresh has no tokens metadata account, the max age of the entries is the default of
+    // their oracle type
+    let stale_slots = refresh_tokens(
+        &accounts.oracle_prices,
+        oracle_mappings,
+        &mut oracle_twaps,
+        &accounts.configuration.load()?,
+        None,
+        remaining_accounts,
+        tokens,
+    )?;
+
+    publish_sol_quotes(
+        &accounts.configuration.load()?,
+        &mut accounts.oracle_prices.load_versioned_mut()?,
+        tokens,
+    );
+
+    Ok(stale_slots)
+}
+
+/// Refresh the given tokens, the price accounts (and their extra accounts) are expected in
+/// `remaining_accounts` in the same order as the tokens.
+///
+/// The prices refreshed before the end of the minimum refresh interval of their entry (see
+/// [`Configuration::min_refresh_intervals_slots`]) are skipped and an event is emitted.
+///
+/// If the staleness policy of the feed is enforced on refresh, the prices older than the max age
+/// of their entry are rejected (see [`check_refreshed_price_age`]).
+///
+/// The accounts left after the price accounts can be price histories and the entry stats of the
+/// feed, see [`update_leftover_accounts`].
+///
+/// Returns, for each entry whose price was advanced, the number of slots elapsed since its
+/// previous price.
+pub(super) fn refresh_tokens<'info>(
+    oracle_prices_loader: &AccountLoader<'info, crate::OraclePrices>,
+    oracle_mappings: &OracleMappings,
+    oracle_twaps: &mut OracleTwaps,
+    configuration: &Configuration,
+    tokens_metadata: Option<&TokenMetadatas>,
+    remaining_accounts: &[AccountInfo<'info>],
+    tokens: &[u16],
+) -> Result<Vec<u64>> {
     // Check that the received token list is not too long
     if tokens.len() > crate::MAX_ENTRIES {
         return Err(ProgramError::InvalidArgument.into());
     }
-    // Check the received token list is at least as long as the number of provided accounts
-    if tokens.len() > ctx.remaining_accounts.len() {
+    // Check that enough accounts are provided for the whole list before consuming any
+    let expected_accounts = get_min_refresh_accounts(oracle_mappings, tokens)?;
+    if expected_accounts > remaining_accounts.len() {
+        msg!(
+            "Not enough accounts to refresh the {} tokens: expected at least {}, received {}",
+            tokens.len(),
+            expected_accounts,
+            remaining_accounts.len()
+        );
         return err!(ScopeError::AccountsAndTokenMismatch);
     }
 
@@ -60,7 +311,10 @@ pub fn refresh_price_list<'info>(
 
     let zero_pk: Pubkey = Pubkey::default();
 
-    let mut accounts_iter = ctx.remaining_accounts.iter();
+    let mut accounts_iter = remaining_accounts.iter();
+    let mut stale_slots = Vec::with_capacity(tokens.len());
+    // Whether the own oracle of each refreshed entry failed validation
+    let mut refresh_outcomes: Vec<(usize, bool)> = Vec::with_capacity(tokens.len());
 
     for &token_nb in tokens.iter() {
         let token_idx: usize = token_nb.into();
@@ -76,7 +330,12 @@ pub fn refresh_price_list<'info>(
             .ok_or(ScopeError::AccountsAndTokenMismatch)?;
         // Ignore unset mapping accounts
         if zero_pk == *oracle_mapping {
-            msg!("Skipping token {} as no mapping is set", token_idx);
+            scope_log!(
+                LogCode::RefreshNoMapping,
+                [token_idx],
+                "Skipping token {} as no mapping is set",
+                token_idx
+            );
             continue;
         }
         // Check that the provided oracle accounts are the one referenced in oracleMapping
@@ -94,56 +353,207 @@ pub fn refresh_price_list<'info>(
             received_account,
             &mut accounts_iter,
             &clock,
-            &oracle_twaps,
+            oracle_twaps,
             oracle_mappings,
-            &ctx.accounts.oracle_prices,
+            oracle_prices_loader,
             token_idx,
-        );
-        let price = if fail_tx_on_error {
-            price_res?
-        } else {
-            match price_res {
-                Ok(price) => price,
-                Err(_) => {
-                    msg!(
-                        "Price skipped as validation failed (token {token_idx}, type {price_type:?})",
-                    );
-                    continue;
+        )
+        .and_then(|price| apply_unit_scale(oracle_mappings, token_idx, price))
+        .and_then(|price| {
+            apply_quote_entry(
+                oracle_mappings,
+                &oracle_prices_loader.load_versioned()?,
+                token_idx,
+                price,
+            )
+        })
+        .and_then(|price| {
+            check_refreshed_price_age(
+                configuration,
+                tokens_metadata,
+                price_type,
+                token_idx,
+                clock.slot,
+                price,
+            )
+        });
+        let own_source_failed = price_res.is_err();
+        let price = match price_res {
+            Ok(price) => price,
+            Err(e) => {
+                let fallback_price = get_fallback_price(
+                    oracle_mappings,
+                    &oracle_prices_loader.load_versioned()?,
+                    token_idx,
+                );
+                match fallback_price {
+                    Some(price) => {
+                        scope_log!(
+                            LogCode::RefreshFallbackPrice,
+                            [token_idx, price_type as u8, price.price_source],
+                            "Price of token {token_idx} (type {price_type:?}) taken from fallback source {}",
+                            price.price_source
+                        );
+                        price
+                    }
+                    None if fail_tx_on_error => return Err(e),
+                    None => {
+                        scope_log!(
+                            LogCode::RefreshSkippedValidation,
+                            [token_idx, price_type as u8],
+                            "Price skipped as validation failed (token {token_idx}, type {price_type:?})",
+                        );
+                        refresh_outcomes.push((token_idx, true));
+                        continue;
+                    }
                 }
             }
         };
 
-        if oracle_mappings.is_twap_enabled(token_idx) {
-            let _ = crate::oracles::twap::update_twap(&mut oracle_twaps, token_idx, &price)
-                .map_err(|_| msg!("Twap not found for token {}", token_idx));
-        };
-
-        // Only temporary load as mut to allow prices to be computed based on a scope chain
-        // from the price feed that is currently updated
-
-        let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
-
-        // check that the price is close enough to the ref price is there is a ref price
-        if oracle_mappings.ref_price[token_idx] != u16::MAX {
-            let ref_price =
-                oracle_prices.prices[usize::from(oracle_mappings.ref_price[token_idx])].price;
-            if let Err(diff_err) = check_ref_price_difference(price.price, ref_price) {
-                if fail_tx_on_error {
-                    return Err(diff_err);
-                } else {
-                    msg!(
-                    "Price skipped as ref price check failed (token {token_idx}, type {price_type:?})",
+        // The price is computed before these checks so that extra accounts are consumed
+        if oracle_mappings.is_suspended(token_idx) {
+            if fail_tx_on_error {
+                return err!(ScopeError::EntrySuspended);
+            }
+            msg!("Price skipped as the entry is suspended (token {token_idx})");
+            continue;
+        }
+        let min_refresh_interval_slots =
+            u64::from(configuration.min_refresh_intervals_slots[token_idx]);
+        if min_refresh_interval_slots != 0 {
+            let last_updated_slot =
+                oracle_prices_loader.load_versioned()?.prices[token_idx].last_updated_slot;
+            if price.last_updated_slot
+                < last_updated_slot.saturating_add(min_refresh_interval_slots)
+            {
+                scope_log!(
+                    LogCode::RefreshSkippedMinInterval,
+                    [token_idx],
+                    "Price skipped as min refresh interval is not elapsed (token {token_idx})"
                 );
-                    continue;
+                emit!(RefreshSkippedMinInterval {
+                    entry_id: token_nb,
+                    last_updated_slot,
+                    new_slot: price.last_updated_slot,
+                    min_refresh_interval_slots,
+                });
+                continue;
+            }
+        }
+
+        let mut price = price;
+        price.unix_timestamp = clamp_unix_timestamp(
+            &price,
+            &oracle_prices_loader.load_versioned()?.prices[token_idx],
+        );
+        // The checks run before the smoothing and twap samples so that a rejected price is not
+        // recorded
+        {
+            let oracle_prices = oracle_prices_loader.load_versioned()?;
+            // check that the price is close enough to the ref price is there is a ref price
+            if let Some(ref_idx) = oracle_mappings.get_ref_price(token_idx) {
+                let ref_price = oracle_prices.prices[ref_idx].price;
+                let tolerance_bps = oracle_mappings.get_ref_price_tolerance_bps(token_idx);
+                if let Err(diff_err) =
+                    check_ref_price_difference(price.price, ref_price, tolerance_bps)
+                {
+                    emit!(RefreshRejectedRefPrice {
+                        entry_id: token_nb,
+                        price: price.price,
+                        ref_entry_id: oracle_mappings.ref_price[token_idx],
+                        ref_price,
+                        tolerance_bps,
+                    });
+                    if fail_tx_on_error {
+                        return Err(diff_err);
+                    } else {
+                        scope_log!(
+                            LogCode::RefreshSkippedRefPrice,
+                            [token_idx, price_type as u8],
+                            "Price skipped as ref price check failed (token {token_idx}, type {price_type:?})",
+                        );
+                        refresh_outcomes.push((token_idx, true));
+                        continue;
+                    }
+                }
+            }
+            // check that the price did not move too much since the previous stored price
+            let deviation_check = &oracle_mappings.deviation_checks[token_idx];
+            let previous_price = &oracle_prices.prices[token_idx];
+            if deviation_check.max_deviation_bps != 0
+                && previous_price.price.value != 0
+                && price.last_updated_slot
+                    <= previous_price
+                        .last_updated_slot
+                        .saturating_add(deviation_check.window_slots.into())
+            {
+                if let Err(deviation_err) = check_price_deviation_bps(
+                    price.price,
+                    previous_price.price,
+                    deviation_check.max_deviation_bps,
+                ) {
+                    if fail_tx_on_error {
+                        return Err(deviation_err);
+                    } else {
+                        scope_log!(
+                            LogCode::RefreshSkippedDeviation,
+                            [token_idx, price_type as u8],
+                            "Price skipped as deviation check failed (token {token_idx}, type {price_type:?})",
+                        );
+                        refresh_outcomes.push((token_idx, true));
+                        continue;
+                    }
+                }
+            }
+        }
+        // Only the prices of the entry own oracle are smoothed, not the fallback ones
+        if price.price_source == 0
+            && oracle_mappings.get_smoothing_mode(token_idx) == SmoothingMode::MedianOf3
+        {
+            let twap = oracle_twaps
+                .twaps
+                .get_mut(token_idx)
+                .ok_or(ScopeError::BadTokenNb)?;
+            price.price = twap.record_and_median_of_3(&price);
+        }
+        if oracle_mappings.is_twap_sampled_on_refresh(token_idx) {
+            match crate::oracles::twap::update_twap(oracle_twaps, token_idx, &price) {
+                // Publish the EMA along the spot price so that consumers can read both at once
+                Ok(()) => {
+                    price.ema_1h =
+                        Decimal::from_scaled_val(oracle_twaps.twaps[token_idx].current_ema_1h)
+                            .into()
                 }
+                Err(_) => scope_log!(
+                    LogCode::RefreshTwapNotFound,
+                    [token_idx],
+                    "Twap not found for token {}",
+                    token_idx
+                ),
             }
+        } else if oracle_mappings.is_twap_enabled(token_idx) {
+            // Sampled by `update_twap_only`, still publish its latest EMA
+            price.ema_1h =
+                Decimal::from_scaled_val(oracle_twaps.twaps[token_idx].current_ema_1h).into();
         }
+
+        // Only temporary load as mut to allow prices to be computed based on a scope chain
+        // from the price feed that is currently updated
+        let mut oracle_prices = oracle_prices_loader.load_versioned_mut()?;
+
         let to_update = oracle_prices
             .prices
             .get_mut(token_idx)
             .ok_or(ScopeError::BadTokenNb)?;
 
-        msg!(
+        scope_log!(
+            LogCode::RefreshPrice,
+            [
+                token_idx,
+                price_type as u8,
+                price.price.value,
+                price.last_updated_slot
+            ],
             "tk {}, {:?}: {:?} to {:?} | prev_slot: {:?}, new_slot: {:?}, crt_slot: {:?}",
             token_idx,
             price_type,
@@ -154,30 +564,307 @@ pub fn refresh_price_list<'info>(
             clock.slot,
         );
 
+        if price.last_updated_slot > to_update.last_updated_slot {
+            stale_slots.push(clock.slot.saturating_sub(to_update.last_updated_slot));
+        }
+        // The degraded flag is only cleared by an acknowledgement of the admin
+        let degraded_flag = to_update.status & DatedPrice::STATUS_DEGRADED;
         *to_update = price;
+        to_update.status = (price.status & !DatedPrice::STATUS_DEGRADED) | degraded_flag;
         to_update.index = token_nb;
+        refresh_outcomes.push((token_idx, own_source_failed));
+    }
+
+    update_leftover_accounts(oracle_prices_loader, accounts_iter, &refresh_outcomes)?;
+
+    Ok(stale_slots)
+}
+
+/// Minimum number of remaining accounts of the refresh of `tokens`: the price account of each
+/// token (even unmapped) and the extra accounts of its oracle type, see
+/// [`OracleType::min_extra_accounts`].
+fn get_min_refresh_accounts(oracle_mappings: &OracleMappings, tokens: &[u16]) -> Result<usize> {
+    let mut accounts_count = 0;
+    for &token_nb in tokens {
+        let token_idx = usize::from(token_nb);
+        let oracle_mapping = oracle_mappings
+            .price_info_accounts
+            .get(token_idx)
+            .ok_or(ScopeError::BadTokenNb)?;
+        accounts_count += 1;
+        if *oracle_mapping == Pubkey::default() {
+            continue;
+        }
+        let price_type: OracleType = oracle_mappings.price_types[token_idx]
+            .try_into()
+            .map_err(|_| ScopeError::BadTokenType)?;
+        accounts_count += price_type.min_extra_accounts(&oracle_mappings.generic[token_idx]);
+    }
+    Ok(accounts_count)
+}
+
+/// Re-express in SOL the configured USD entries into their companion entries.
+///
+/// Only the publications whose USD entry or the SOL/USD entry are in `refreshed_tokens` are
+/// updated, using the prices currently stored.
+pub(super) fn publish_sol_quotes(
+    configuration: &Configuration,
+    oracle_prices: &mut OraclePrices,
+    refreshed_tokens: &[u16],
+) {
+    let sol_usd_entry = configuration.sol_usd_entry;
+    let sol_usd_refreshed = refreshed_tokens.contains(&sol_usd_entry);
+    let sol_usd_price = oracle_prices.prices[usize::from(sol_usd_entry)];
+    if sol_usd_price.price.value == 0 {
+        return;
     }
+    let sol_usd_decimal = Decimal::from(sol_usd_price.price);
 
+    for publication in configuration.sol_quote_publications() {
+        if !sol_usd_refreshed && !refreshed_tokens.contains(&publication.usd_entry) {
+            continue;
+        }
+        let usd_price = oracle_prices.prices[usize::from(publication.usd_entry)];
+        if usd_price.price.value == 0 {
+            continue;
+        }
+        let Some(price) = Decimal::from(usd_price.price)
+            .try_div(sol_usd_decimal)
+            .ok()
+            .and_then(|sol_quote| Price::try_from_decimal(sol_quote).ok())
+        else {
+            msg!(
+                "SOL quote of token {} is out of range, not published",
+                publication.usd_entry
+            );
+            continue;
+        };
+        oracle_prices.prices[usize::from(publication.sol_entry)] = DatedPrice {
+            price,
+            last_updated_slot: usd_price
+                .last_updated_slot
+                .min(sol_usd_price.last_updated_slot),
+            unix_timestamp: usd_price.unix_timestamp.min(sol_usd_price.unix_timestamp),
+            index: publication.sol_entry,
+            ..Default::default()
+        };
+    }
+}
+
+/// Multiply the price given by the oracle of `token_idx` by the stored price of its quote entry,
+/// if any. The resulting price is as old as the oldest of the two.
+pub(super) fn apply_quote_entry(
+    oracle_mappings: &OracleMappings,
+    oracle_prices: &OraclePrices,
+    token_idx: usize,
+    price: DatedPrice,
+) -> Result<DatedPrice> {
+    let Some(quote_idx) = oracle_mappings.quote_entries[token_idx].get() else {
+        return Ok(price);
+    };
+    let quote_price = oracle_prices
+        .prices
+        .get(quote_idx)
+        .ok_or(ScopeError::BadTokenNb)?;
+    if quote_price.price.value == 0 {
+        msg!("Quote entry {quote_idx} of token {token_idx} has no price");
+        return err!(ScopeError::PriceNotValid);
+    }
+    let quoted_price = Decimal::from(price.price)
+        .try_mul(Decimal::from(quote_price.price))
+        .map_err(|_| error!(ScopeError::MathOverflow))?;
+    let quoted_price = Price::try_from_decimal(quoted_price).map_err(|e| {
+        msg!("Price of token {token_idx} quoted by entry {quote_idx} is out of range");
+        e
+    })?;
+    Ok(DatedPrice {
+        price: quoted_price,
+        last_updated_slot: price.last_updated_slot.min(quote_price.last_updated_slot),
+        unix_timestamp: price.unix_timestamp.min(quote_price.unix_timestamp),
+        ..price
+    })
+}
+
+/// Timestamp to store with the refreshed `price` of an entry whose stored price is `previous`.
+///
+/// The cluster unix timestamp can briefly go backwards between slots (and the timestamps published
+/// by some oracles are ahead of it): a price of a newer slot keeps at least the timestamp of the
+/// previous one, so that the timestamps of an entry never decrease.
+pub(super) fn clamp_unix_timestamp(price: &DatedPrice, previous: &DatedPrice) -> u64 {
+    if price.last_updated_slot > previous.last_updated_slot {
+        price.unix_timestamp.max(previous.unix_timestamp)
+    } else {
+        price.unix_timestamp
+    }
+}
+
+/// Reject a refreshed price older than the max age of its entry (see
+/// [`Configuration::max_age_slots`]) if the staleness policy of the feed is enforced on refresh.
+///
+/// Without `tokens_metadata`, the max age of the entry metadata is not known and the default of
+/// the oracle type applies.
+fn check_refreshed_price_age(
+    configuration: &Configuration,
+    tokens_metadata: Option<&TokenMetadatas>,
+    price_type: OracleType,
+    token_idx: usize,
+    current_slot: u64,
+    price: DatedPrice,
+) -> Result<DatedPrice> {
+    if !configuration.staleness_policy.is_enforced_on_refresh() {
+        return Ok(price);
+    }
+    let entry_max_age_slots = tokens_metadata.map_or(0, |metadatas| {
+        metadatas.metadatas_array[token_idx].max_age_price_slots
+    });
+    let Some(max_age_slots) = configuration.max_age_slots(price_type, entry_max_age_slots) else {
+        return Ok(price);
+    };
+    let max_age_slots = configuration.effective_max_age_slots(max_age_slots);
+    let age_slots = current_slot.saturating_sub(price.last_updated_slot);
+    if age_slots > max_age_slots {
+        msg!(
+            "Refreshed price of token {token_idx} is {age_slots} slots old, max age is {max_age_slots} slots"
+        );
+        return err!(ScopeError::StalePrice);
+    }
+    Ok(price)
+}
+
+/// Express the price of the oracle of `token_idx` per whole token, see
+/// [`OracleMappings::unit_scales`]
+pub(super) fn apply_unit_scale(
+    oracle_mappings: &OracleMappings,
+    token_idx: usize,
+    price: DatedPrice,
+) -> Result<DatedPrice> {
+    let unit_scale = oracle_mappings.unit_scales[token_idx];
+    if unit_scale == 0 {
+        return Ok(price);
+    }
+    let scaled_price = price.price.scale_units(unit_scale)?;
+    if scaled_price.value == 0 {
+        msg!("Price of token {token_idx} is 0 once scaled by 10^-{unit_scale}");
+        return err!(ScopeError::PriceNotValid);
+    }
+    Ok(DatedPrice {
+        price: scaled_price,
+        ..price
+    })
+}
+
+/// Update the accounts left after the price accounts of a refresh:
+/// - the price histories record the new price of their entry,
+/// - the entry stats of the feed record the outcome of the refresh of each entry (a refresh that
+///   failed the whole transaction is not recorded) and mark the entries failing too often as
+///   degraded,
+/// - the other accounts are ignored.
+fn update_leftover_accounts<'a, 'info: 'a>(
+    oracle_prices_loader: &AccountLoader<'info, crate::OraclePrices>,
+    accounts: impl Iterator<Item = &'a AccountInfo<'info>>,
+    refresh_outcomes: &[(usize, bool)],
+) -> Result<()> {
+    for account in accounts {
+        if *account.owner != crate::ID {
+            continue;
+        }
+        if let Ok(stats_loader) = AccountLoader::<OracleEntryStats>::try_from(account) {
+            record_entry_stats(oracle_prices_loader, &stats_loader, refresh_outcomes)?;
+            continue;
+        }
+        let Ok(history_loader) = AccountLoader::<OraclePriceHistory>::try_from(account) else {
+            continue;
+        };
+        let mut history = history_loader.load_mut()?;
+        require_keys_eq!(
+            history.oracle_prices,
+            oracle_prices_loader.key(),
+            ScopeError::UnexpectedAccount
+        );
+        let price = oracle_prices_loader.load_versioned()?.prices[usize::from(history.entry_id)];
+        history.record(&price);
+    }
+    Ok(())
+}
+
+fn record_entry_stats(
+    oracle_prices_loader: &AccountLoader<crate::OraclePrices>,
+    stats_loader: &AccountLoader<OracleEntryStats>,
+    refresh_outcomes: &[(usize, bool)],
+) -> Result<()> {
+    let mut stats = stats_loader.load_mut()?;
+    require_keys_eq!(
+        stats.oracle_prices,
+        oracle_prices_loader.key(),
+        ScopeError::UnexpectedAccount
+    );
+    let slot = Clock::get()?.slot;
+    for &(token_idx, failed) in refresh_outcomes {
+        if !stats.record_refresh(token_idx, failed, slot) {
+            continue;
+        }
+        msg!("Token {token_idx} is degraded after too many failed refreshes");
+        oracle_prices_loader.load_versioned_mut()?.prices[token_idx].status |=
+            DatedPrice::STATUS_DEGRADED;
+        emit!(EntryDegraded {
+            entry_id: u16::try_from(token_idx).unwrap(),
+            consecutive_failures: stats.entries[token_idx].consecutive_failures,
+            slot,
+        });
+    }
     Ok(())
 }
 
+/// Get the price of the first fallback entry of `token_idx` that has been updated more recently
+/// than the current price of `token_idx`.
+fn get_fallback_price(
+    oracle_mappings: &OracleMappings,
+    oracle_prices: &OraclePrices,
+    token_idx: usize,
+) -> Option<DatedPrice> {
+    let current_slot = oracle_prices.prices[token_idx].last_updated_slot;
+    oracle_mappings.fallback_sources[token_idx]
+        .iter()
+        .zip(1_u16..)
+        .find_map(|(fallback_idx, price_source)| {
+            let fallback_price = oracle_prices.prices.get(fallback_idx)?;
+            (fallback_price.last_updated_slot > current_slot && fallback_price.price.value != 0)
+                .then_some(DatedPrice {
+                    price_source,
+                    ema_1h: Price::default(),
+                    ..*fallback_price
+                })
+        })
+}
+
 /// Ensure that the refresh instruction is executed directly to avoid any manipulation:
 ///
-/// - Check that the current instruction is executed by our program id (not in CPI).
+/// - Check that the current instruction is executed by our program id (not in CPI), or by one of
+///   the `allowed_cpi_callers` calling us directly.
 /// - Check that instructions preceding the refresh are compute budget instructions.
-fn check_execution_ctx(instruction_sysvar_account_info: &AccountInfo) -> Result<()> {
+pub(super) fn check_execution_ctx(
+    instruction_sysvar_account_info: &AccountInfo,
+    allowed_cpi_callers: &[Pubkey],
+) -> Result<()> {
     let current_index: usize = load_current_index_checked(instruction_sysvar_account_info)?.into();
 
     // 1- Check that the current instruction is executed by our program id (not in CPI).
     let current_ix = load_instruction_at_checked(current_index, instruction_sysvar_account_info)?;
 
-    // the current ix must be executed by our program id. otherwise, it's a CPI.
-    if crate::ID != current_ix.program_id {
+    // the current ix must be executed by our program id or an allowed caller. otherwise, it's a CPI.
+    let expected_stack_height = if crate::ID == current_ix.program_id {
+        TRANSACTION_LEVEL_STACK_HEIGHT
+    } else if current_ix.program_id != Pubkey::default()
+        && allowed_cpi_callers.contains(&current_ix.program_id)
+    {
+        // The allowed caller must be the one invoking us, not a program it calls
+        TRANSACTION_LEVEL_STACK_HEIGHT + 1
+    } else {
         return err!(ScopeError::RefreshInCPI);
-    }
+    };
 
-    // The current stack height must be the initial one. Otherwise, it's a CPI.
-    if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+    // The current stack height must be the expected one. Otherwise, it's a (nested) CPI.
+    if get_stack_height() != expected_stack_height {
         return err!(ScopeError::RefreshInCPI);
     }
 
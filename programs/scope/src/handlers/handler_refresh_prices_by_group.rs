@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use solana_program::sysvar::instructions::ID as SYSVAR_INSTRUCTIONS_ID;
+
+use super::handler_refresh_prices::{check_execution_ctx, publish_sol_quotes, refresh_tokens};
+use crate::{
+    oracles::{check_remaining_accounts_exclude, OracleType},
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        consts::MAX_TX_COMPUTE_UNITS,
+    },
+    OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(group_id: u8)]
+pub struct RefreshPricesByGroup<'info> {
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    /// CHECK: Checked above
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, has_one = oracle_prices, has_one = oracle_mappings)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+    #[account(
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+    /// CHECK: Sysvar fixed address
+    #[account(address = SYSVAR_INSTRUCTIONS_ID)]
+    pub instruction_sysvar_account_info: AccountInfo<'info>,
+    /// If provided, the digest of the group is written after the refresh
+    #[account(mut, has_one = oracle_prices, constraint = group_digest.load()?.group_id == group_id)]
+    pub group_digest: Option<AccountLoader<'info, crate::PriceGroupDigest>>,
+    // Note: use remaining accounts as price accounts, in the order of the entries in the group
+}
+
+/// Refresh all the entries belonging to the given group, in increasing index order.
+///
+/// Entries are taken until the sum of their per-type compute budget would exceed the maximum
+/// compute units of a transaction, the remaining entries of the group are left untouched.
+pub fn refresh_prices_by_group<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshPricesByGroup<'info>>,
+    group_id: u8,
+) -> Result<()> {
+    check_execution_ctx(&ctx.accounts.instruction_sysvar_account_info, &[])?;
+
+    require_gt!(u64::BITS, u32::from(group_id), ScopeError::InvalidGroupId);
+
+    if ctx.accounts.configuration.load()?.is_paused() {
+        return err!(ScopeError::FeedPaused);
+    }
+
+    // The prices account is allowed as remaining account for the kToken oracles, see `refresh_list`
+    let mut instruction_accounts = vec![
+        ctx.accounts.oracle_mappings.key(),
+        ctx.accounts.oracle_twaps.key(),
+        ctx.accounts.configuration.key(),
+        ctx.accounts.tokens_metadata.key(),
+    ];
+    publish_sol_quotes(
+        &ctx.accounts.configuration.load()?,
+        &mut ctx.accounts.oracle_prices.load_versioned_mut()?,
+        &tokens,
+    );
+
+    if let Some(group_digest) = &ctx.accounts.group_digest {
+        instruction_accounts.push(group_digest.key());
+    }
+    check_remaining_accounts_exclude(ctx.remaining_accounts, &instruction_accounts)?;
+
+    let oracle_mappings = &load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
+
+    let tokens = {
+        let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+        let mut cu_budget: u32 = 0;
+        let mut tokens = Vec::new();
+        for (token_idx, token_metadata) in tokens_metadata.metadatas_array.iter().enumerate() {
+            if token_metadata.group_ids_bitset & (1 << group_id) == 0 {
+                continue;
+            }
+            let price_type: OracleType = oracle_mappings.price_types[token_idx]
+                .try_into()
+                .map_err(|_| ScopeError::BadTokenType)?;
+            cu_budget = cu_budget
+                .saturating_add(oracle_mappings.get_update_cu_budget(token_idx, price_type));
+            if cu_budget > MAX_TX_COMPUTE_UNITS {
+                msg!(
+                    "Group {} refresh truncated at token {} to fit the compute budget",
+                    group_id,
+                    token_idx
+                );
+                break;
+            }
+            tokens.push(u16::try_from(token_idx).map_err(|_| ScopeError::BadTokenNb)?);
+        }
+        tokens
+    };
+
+    if tokens.is_empty() {
+        return err!(ScopeError::EmptyTokenList);
+    }
+
+    refresh_tokens(
+        &ctx.accounts.oracle_prices,
+        oracle_mappings,
+        &mut oracle_twaps,
+        &ctx.accounts.configuration.load()?,
+        Some(&ctx.accounts.tokens_metadata.load()?),
+        ctx.remaining_accounts,
+        &tokens,
+    )?;
+
+    if let Some(group_digest) = &ctx.accounts.group_digest {
+        let oracle_prices = ctx.accounts.oracle_prices.load_versioned()?;
+        let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+        let mut group_digest = group_digest.load_mut()?;
+        group_digest.update(&oracle_prices, &tokens_metadata, Clock::get()?.slot);
+    }
+
+    Ok(())
+}
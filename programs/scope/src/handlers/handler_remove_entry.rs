@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::OracleType,
+    utils::{account_header::load_versioned_mut, pdas::seeds, scope_chain},
+    Configuration, MintsToScopeChains, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct RemoveEntry<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_mappings)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    /// CHECK: checked above + on deserialize
+    #[account(mut, owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    // Note: the mint maps of the feed to check are provided as remaining accounts
+}
+
+/// Remove the mapping of an entry.
+///
+/// The removal is rejected if the entry is still read by another entry, by a SOL quote publication
+/// or by one of the mint maps provided as remaining accounts, unless `force` is set. All the
+/// references found are logged in both cases.
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, RemoveEntry<'info>>,
+    entry_id: usize,
+    force: bool,
+    feed_name: String,
+) -> Result<()> {
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MappingUpdates)?;
+
+    msg!(
+        "RemoveEntry, feed: {}, token: {}, force: {}",
+        feed_name,
+        entry_id,
+        force
+    );
+
+    if entry_id >= crate::MAX_ENTRIES {
+        return err!(ScopeError::BadTokenNb);
+    }
+
+    let configuration = ctx.accounts.configuration.load()?;
+    let mut oracle_mappings = load_versioned_mut::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+
+    let mut is_referenced =
+        check_entry_unreferenced(&oracle_mappings, &configuration, entry_id).is_err();
+    for mint_map_acc in ctx.remaining_accounts {
+        let mint_map = Account::<MintsToScopeChains>::try_from(mint_map_acc)?;
+        require_keys_eq!(
+            mint_map.oracle_prices,
+            configuration.oracle_prices,
+            ScopeError::UnexpectedAccount
+        );
+        for mint_to_chain in mint_map.mapping.iter() {
+            if mint_to_chain
+                .scope_chain
+                .iter()
+                .any(|&chain_entry| usize::from(scope_chain::hop_entry(chain_entry)) == entry_id)
+            {
+                msg!(
+                    "Entry {entry_id} is referenced by the scope chain of mint {} in mint map {}",
+                    mint_to_chain.mint,
+                    mint_map_acc.key
+                );
+                is_referenced = true;
+            }
+        }
+    }
+
+    if is_referenced {
+        if !force {
+            return err!(ScopeError::EntryStillReferenced);
+        }
+        msg!("Forcing the removal of referenced entry {entry_id}");
+    }
+
+    oracle_mappings.price_info_accounts[entry_id] = Pubkey::default();
+    oracle_mappings.price_types[entry_id] = OracleType::Pyth.into();
+    oracle_mappings.twap_enabled[entry_id] = 0;
+    oracle_mappings.twap_source[entry_id] = 0;
+    oracle_mappings.ref_price[entry_id] = u16::MAX;
+    oracle_mappings.generic[entry_id] = [0; 20];
+    oracle_mappings.cu_budgets[entry_id] = 0;
+    oracle_mappings.smoothing_modes[entry_id] = 0;
+    oracle_mappings.unit_scales[entry_id] = 0;
+    oracle_mappings.suspended[entry_id] = 0;
+    oracle_mappings.confidence_policies[entry_id] = Default::default();
+
+    Ok(())
+}
+
+/// Check that no entry or SOL quote publication of the feed reads the price of `entry_id`,
+/// all the references found are logged
+pub fn check_entry_unreferenced(
+    oracle_mappings: &OracleMappings,
+    configuration: &Configuration,
+    entry_id: usize,
+) -> Result<()> {
+    let mut is_referenced = !oracle_mappings.find_references(entry_id).is_empty();
+
+    if usize::from(configuration.sol_usd_entry) == entry_id
+        && !configuration.sol_quote_publications().is_empty()
+    {
+        msg!("Entry {entry_id} is the SOL/USD entry of the SOL quote publications");
+        is_referenced = true;
+    }
+    for publication in configuration.sol_quote_publications() {
+        if usize::from(publication.usd_entry) == entry_id {
+            msg!(
+                "Entry {entry_id} is published in SOL into entry {}",
+                publication.sol_entry
+            );
+            is_referenced = true;
+        }
+    }
+
+    if is_referenced {
+        return err!(ScopeError::EntryStillReferenced);
+    }
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
 use solana_program::sysvar::instructions::ID as SYSVAR_INSTRUCTIONS_ID;
 
-use crate::{oracles::check_context, utils::pdas::seeds};
+use crate::{
+    oracles::check_context,
+    utils::{account_header::LoadVersioned, pdas::seeds},
+};
 
 #[derive(Accounts)]
 #[instruction(token:u64, feed_name: String)]
@@ -25,9 +28,13 @@ pub struct ResetTwap<'info> {
 
 pub fn process(ctx: Context<ResetTwap>, token: usize, _: String) -> Result<()> {
     check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::TwapOverrides)?;
 
-    let oracle = ctx.accounts.oracle_prices.load()?;
-    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
+    let oracle = ctx.accounts.oracle_prices.load_versioned()?;
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
 
     let clock = Clock::get()?;
 
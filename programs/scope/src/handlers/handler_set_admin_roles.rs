@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds};
+
+#[derive(Accounts)]
+#[instruction(mapping_admin: Pubkey, risk_admin: Pubkey, feed_name: String)]
+pub struct SetAdminRoles<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Set the delegated admin roles of the feed, a default pubkey removes the role
+pub fn process(
+    ctx: Context<SetAdminRoles>,
+    mapping_admin: Pubkey,
+    risk_admin: Pubkey,
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "Setting feed {} mapping admin to {} and risk admin to {}",
+        feed_name,
+        mapping_admin,
+        risk_admin
+    );
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.mapping_admin = mapping_admin;
+    configuration.risk_admin = risk_admin;
+
+    Ok(())
+}
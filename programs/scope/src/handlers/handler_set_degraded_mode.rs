@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use super::handler_update_degraded_mode::DegradedModeChanged;
+use crate::{
+    oracles::check_context,
+    utils::{consts::FULL_BPS, pdas::seeds},
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(forced: bool, max_age_factor: u16, slot_lag_bps: u16, feed_name: String)]
+pub struct SetDegradedMode<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Force (or stop forcing) the degraded mode of the feed and configure it: while degraded, the max
+/// age of the prices is multiplied by `max_age_factor`. A non-zero `slot_lag_bps` lets
+/// `update_degraded_mode` enter it automatically.
+pub fn process(
+    ctx: Context<SetDegradedMode>,
+    forced: bool,
+    max_age_factor: u16,
+    slot_lag_bps: u16,
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "Setting feed {} degraded mode forced {}, max age factor {}, slot lag {} bps",
+        feed_name,
+        forced,
+        max_age_factor,
+        slot_lag_bps
+    );
+
+    if max_age_factor == 0 || slot_lag_bps > FULL_BPS {
+        return err!(ScopeError::InvalidDegradedModeConfig);
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    let was_degraded = configuration.is_degraded();
+    configuration.degraded_mode_forced = u8::from(forced);
+    configuration.degraded_max_age_factor = max_age_factor;
+    configuration.degraded_slot_lag_bps = slot_lag_bps;
+    if slot_lag_bps == 0 {
+        configuration.degraded_mode_auto = 0;
+    }
+
+    if configuration.is_degraded() != was_degraded {
+        emit!(DegradedModeChanged {
+            degraded: configuration.is_degraded(),
+            forced,
+            max_age_factor,
+            slot_lag_bps: 0,
+            slot: Clock::get()?.slot,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, InstructionFlag, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(disabled_instructions: u64, feed_name: String)]
+pub struct SetDisabledInstructions<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Set the bitmask of the [`InstructionFlag`]s disabled on the feed. A zero mask enables all
+/// instructions.
+pub fn process(
+    ctx: Context<SetDisabledInstructions>,
+    disabled_instructions: u64,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!("SetDisabledInstructions, disabled_instructions: {disabled_instructions:#b}");
+
+    if disabled_instructions & !InstructionFlag::ALL != 0 {
+        msg!("Unknown instruction flags in {disabled_instructions:#b}");
+        return err!(ScopeError::InvalidInstructionFlags);
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.disabled_instructions = disabled_instructions;
+
+    Ok(())
+}
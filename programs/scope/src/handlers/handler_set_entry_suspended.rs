@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{
+        account_header::{load_versioned_mut, LoadVersioned},
+        pdas::seeds,
+    },
+    DatedPrice, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(token: u16, suspended: bool, feed_name: String)]
+pub struct SetEntrySuspended<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        has_one = oracle_prices,
+        constraint = configuration.load()?.is_risk_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    /// CHECK: checked above + on deserialize
+    #[account(mut, owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+}
+
+/// Suspend or resume the refreshes of an entry. The stored price of a suspended entry is frozen
+/// and flagged with [`DatedPrice::STATUS_SUSPENDED`].
+///
+/// The risk admin can suspend an entry, only the admin resumes it.
+pub fn process(
+    ctx: Context<SetEntrySuspended>,
+    entry_id: usize,
+    suspended: bool,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MappingUpdates)?;
+
+    msg!("SetEntrySuspended, token: {entry_id}, suspended: {suspended}");
+
+    if !suspended && ctx.accounts.admin.key() != ctx.accounts.configuration.load()?.admin {
+        msg!("Only the admin can resume an entry");
+        return err!(ScopeError::InvalidAdminRole);
+    }
+
+    let mut oracle_mappings = load_versioned_mut::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let entry_suspended = oracle_mappings
+        .suspended
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+    *entry_suspended = u8::from(suspended);
+
+    let mut oracle_prices = ctx.accounts.oracle_prices.load_versioned_mut()?;
+    let status = &mut oracle_prices.prices[entry_id].status;
+    if suspended {
+        *status |= DatedPrice::STATUS_SUSPENDED;
+    } else {
+        *status &= !DatedPrice::STATUS_SUSPENDED;
+    }
+
+    Ok(())
+}
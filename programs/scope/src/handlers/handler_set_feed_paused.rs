@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, ScopeError};
+
+#[derive(Accounts)]
+#[instruction(paused: bool, feed_name: String)]
+pub struct SetFeedPaused<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        constraint = configuration.load()?.is_risk_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+pub fn process(ctx: Context<SetFeedPaused>, paused: bool, feed_name: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!("Setting feed {} paused to {}", feed_name, paused);
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    // The risk admin is an emergency pauser, only the admin resumes the feed
+    if !paused && ctx.accounts.admin.key() != configuration.admin {
+        msg!("Only the admin can unpause feed {}", feed_name);
+        return err!(ScopeError::InvalidAdminRole);
+    }
+    configuration.paused = u8::from(paused);
+    // A feed being closed stays paused, unpausing it cancels the close
+    if !paused && configuration.is_close_proposed() {
+        msg!("Cancelling the close of feed {}", feed_name);
+        configuration.close_proposed_at = 0;
+        configuration.close_destination = Pubkey::default();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::pdas::seeds;
+
+#[derive(Accounts)]
+#[instruction(max_consecutive_failures: u16, feed_name: String)]
+pub struct SetMaxConsecutiveFailures<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(mut, has_one = oracle_prices)]
+    pub entry_stats: AccountLoader<'info, crate::OracleEntryStats>,
+}
+
+/// Set the number of consecutive failed refreshes after which an entry is marked as degraded,
+/// 0 to disable it. Entries already degraded stay so until acknowledged.
+pub fn process(
+    ctx: Context<SetMaxConsecutiveFailures>,
+    max_consecutive_failures: u16,
+    _: String,
+) -> Result<()> {
+    msg!("SetMaxConsecutiveFailures, max_consecutive_failures: {max_consecutive_failures}");
+
+    ctx.accounts
+        .entry_stats
+        .load_mut()?
+        .max_consecutive_failures = max_consecutive_failures;
+
+    Ok(())
+}
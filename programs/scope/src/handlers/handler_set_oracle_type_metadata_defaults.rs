@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::{check_context, OracleType},
+    utils::pdas::seeds,
+    OracleTypeMetadataDefaults, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(price_type: u8, feed_name: String)]
+pub struct SetOracleTypeMetadataDefaults<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+pub fn process(
+    ctx: Context<SetOracleTypeMetadataDefaults>,
+    price_type: u8,
+    defaults: OracleTypeMetadataDefaults,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    let oracle_type: OracleType = price_type
+        .try_into()
+        .map_err(|_| ScopeError::BadTokenType)?;
+
+    msg!(
+        "Setting metadata defaults for {:?}: max age {} slots, group IDs {:#b}",
+        oracle_type,
+        defaults.max_age_price_slots,
+        defaults.group_ids_bitset,
+    );
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    let type_defaults = configuration
+        .oracle_type_metadata_defaults
+        .get_mut(usize::from(price_type))
+        .ok_or(ScopeError::BadTokenType)?;
+    *type_defaults = defaults;
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, ScopeError, MAX_REFRESH_CPI_CALLERS};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetRefreshCpiCallers<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+pub fn process(
+    ctx: Context<SetRefreshCpiCallers>,
+    programs: &[Pubkey],
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::RefreshCpiCallers)?;
+
+    msg!(
+        "Setting feed {} programs allowed to refresh through CPI to {:?}",
+        feed_name,
+        programs
+    );
+
+    require_gte!(
+        MAX_REFRESH_CPI_CALLERS,
+        programs.len(),
+        ScopeError::InvalidRefreshCpiCallers
+    );
+    for (i, program) in programs.iter().enumerate() {
+        if *program == Pubkey::default() || *program == crate::ID || programs[..i].contains(program)
+        {
+            msg!(
+                "Program {} can't be allowed to refresh through CPI",
+                program
+            );
+            return err!(ScopeError::InvalidRefreshCpiCallers);
+        }
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.refresh_cpi_callers = Default::default();
+    configuration.refresh_cpi_callers[..programs.len()].copy_from_slice(programs);
+
+    Ok(())
+}
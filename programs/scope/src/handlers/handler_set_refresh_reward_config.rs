@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, RefreshRewardConfig};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetRefreshRewardConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+pub fn process(
+    ctx: Context<SetRefreshRewardConfig>,
+    reward_config: RefreshRewardConfig,
+    feed_name: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "Setting feed {} refresh reward config to {:?}",
+        feed_name,
+        reward_config
+    );
+
+    // Without a minimum staleness, every refresh of every entry would be paid
+    if reward_config.is_enabled() && reward_config.min_stale_slots == 0 {
+        msg!("Refresh rewards require a minimum staleness");
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.refresh_reward = reward_config;
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{account_header::load_versioned, pdas::seeds},
+    OracleMappings, ScopeError, SolQuotePublication, MAX_ENTRIES, MAX_SOL_QUOTE_PUBLICATIONS,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetSolQuotePublications<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_mappings)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    /// CHECK: checked above + on deserialize
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+}
+
+pub fn process(
+    ctx: Context<SetSolQuotePublications>,
+    sol_usd_entry: u16,
+    publications: &[SolQuotePublication],
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "SetSolQuotePublications, SOL/USD entry: {}, publications: {:?}",
+        sol_usd_entry,
+        publications
+    );
+
+    require_gte!(
+        MAX_SOL_QUOTE_PUBLICATIONS,
+        publications.len(),
+        ScopeError::InvalidSolQuotePublication
+    );
+    require_gt!(
+        MAX_ENTRIES,
+        usize::from(sol_usd_entry),
+        ScopeError::InvalidSolQuotePublication
+    );
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    for (i, publication) in publications.iter().enumerate() {
+        let usd_entry = usize::from(publication.usd_entry);
+        let sol_entry = usize::from(publication.sol_entry);
+        if usd_entry >= MAX_ENTRIES || sol_entry >= MAX_ENTRIES || usd_entry == sol_entry {
+            return err!(ScopeError::InvalidSolQuotePublication);
+        }
+        if publication.sol_entry == sol_usd_entry {
+            msg!("The SOL/USD entry can't be used as a companion entry");
+            return err!(ScopeError::InvalidSolQuotePublication);
+        }
+        if oracle_mappings.price_info_accounts[sol_entry] != Pubkey::default() {
+            msg!("Companion entry {} has an oracle mapping", sol_entry);
+            return err!(ScopeError::InvalidSolQuotePublication);
+        }
+        if publications[..i]
+            .iter()
+            .any(|other| other.sol_entry == publication.sol_entry)
+        {
+            msg!("Companion entry {} is used more than once", sol_entry);
+            return err!(ScopeError::InvalidSolQuotePublication);
+        }
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.sol_usd_entry = sol_usd_entry;
+    configuration.sol_quote_publications_count = publications.len().try_into().unwrap();
+    configuration.sol_quote_publications = Default::default();
+    configuration.sol_quote_publications[..publications.len()].copy_from_slice(publications);
+
+    Ok(())
+}
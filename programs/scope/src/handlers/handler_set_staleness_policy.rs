@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::{oracles::check_context, utils::pdas::seeds, StalenessPolicy};
+
+#[derive(Accounts)]
+#[instruction(enforce_on_refresh: bool, feed_name: String)]
+pub struct SetStalenessPolicy<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Set whether the refreshed prices older than the max age of their entry are rejected, see
+/// [`crate::Configuration::max_age_slots`] for the max age of an entry.
+pub fn process(
+    ctx: Context<SetStalenessPolicy>,
+    enforce_on_refresh: bool,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!("SetStalenessPolicy, enforce_on_refresh: {enforce_on_refresh}");
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    configuration.staleness_policy = StalenessPolicy {
+        enforce_on_refresh: enforce_on_refresh.into(),
+        ..Default::default()
+    };
+
+    Ok(())
+}
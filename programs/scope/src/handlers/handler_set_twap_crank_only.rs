@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{account_header::load_versioned_mut, pdas::seeds},
+    OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(token: u16, feed_name: String)]
+pub struct SetTwapCrankOnly<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_mappings)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    /// CHECK: checked above + on deserialize
+    #[account(mut, owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+}
+
+/// Choose whether the TWAP of an entry is sampled by the refreshes of its spot price or only by
+/// `update_twap_only`. `update_mapping` switches the entry back to sampling on refresh.
+pub fn process(
+    ctx: Context<SetTwapCrankOnly>,
+    entry_id: usize,
+    crank_only: bool,
+    _: String,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "SetTwapCrankOnly, token: {}, crank_only: {}",
+        entry_id,
+        crank_only
+    );
+
+    let mut oracle_mappings = load_versioned_mut::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let twap_enabled = oracle_mappings
+        .twap_enabled
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+    if *twap_enabled == 0 {
+        msg!("Token {entry_id} has no twap");
+        return Err(ProgramError::InvalidArgument.into());
+    }
+    *twap_enabled = if crank_only {
+        OracleMappings::TWAP_ENABLED_CRANK_ONLY
+    } else {
+        1
+    };
+
+    Ok(())
+}
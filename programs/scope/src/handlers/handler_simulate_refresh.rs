@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+
+use super::handler_refresh_prices::{apply_quote_entry, apply_unit_scale};
+use crate::{
+    oracles::{get_non_zero_price, OracleType},
+    utils::{
+        account_header::{load_versioned, LoadVersioned},
+        price_impl::{check_price_deviation_bps, check_ref_price_difference},
+    },
+    DatedPrice, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+pub struct SimulateRefresh<'info> {
+    #[account(has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    /// CHECK: Checked above
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(has_one = oracle_prices, has_one = oracle_mappings)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+    #[account(has_one = oracle_prices, has_one = oracle_mappings, has_one = oracle_twaps)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    // Note: use remaining accounts as price account and its extra accounts
+}
+
+/// Compute the price a refresh of `token` would store, without storing it, and return it.
+///
+/// The same checks as a single token refresh are applied (suspension, ref price, deviation), a
+/// failing one fails the instruction. The fallback sources are not tried and the TWAP is not
+/// sampled: the returned EMA is the one currently stored.
+pub fn process(ctx: Context<SimulateRefresh>, token: u16) -> Result<DatedPrice> {
+    let token_idx = usize::from(token);
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let oracle_twaps = ctx.accounts.oracle_twaps.load_versioned()?;
+
+    let price_account = oracle_mappings
+        .price_info_accounts
+        .get(token_idx)
+        .ok_or(ScopeError::BadTokenNb)?;
+    if *price_account == Pubkey::default() {
+        msg!("Token {token_idx} has no mapping");
+        return err!(ScopeError::PriceNotValid);
+    }
+    let price_type: OracleType = oracle_mappings.price_types[token_idx]
+        .try_into()
+        .map_err(|_| ScopeError::BadTokenType)?;
+    let mut accounts_iter = ctx.remaining_accounts.iter();
+    let received_account = accounts_iter
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    if received_account.key() != *price_account {
+        msg!(
+            "Invalid price account: {}, expected: {}",
+            received_account.key(),
+            price_account
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
+
+    let clock = Clock::get()?;
+    let price = get_non_zero_price(
+        price_type,
+        received_account,
+        &mut accounts_iter,
+        &clock,
+        &oracle_twaps,
+        &oracle_mappings,
+        &ctx.accounts.oracle_prices,
+        token_idx,
+    )?;
+    let price = apply_unit_scale(&oracle_mappings, token_idx, price)?;
+    let oracle_prices = ctx.accounts.oracle_prices.load_versioned()?;
+    let mut price = apply_quote_entry(&oracle_mappings, &oracle_prices, token_idx, price)?;
+
+    if oracle_mappings.is_suspended(token_idx) {
+        return err!(ScopeError::EntrySuspended);
+    }
+    if let Some(ref_idx) = oracle_mappings.get_ref_price(token_idx) {
+        check_ref_price_difference(
+            price.price,
+            oracle_prices.prices[ref_idx].price,
+            oracle_mappings.get_ref_price_tolerance_bps(token_idx),
+        )?;
+    }
+    let deviation_check = &oracle_mappings.deviation_checks[token_idx];
+    let previous_price = &oracle_prices.prices[token_idx];
+    if deviation_check.max_deviation_bps != 0
+        && previous_price.price.value != 0
+        && price.last_updated_slot
+            <= previous_price
+                .last_updated_slot
+                .saturating_add(deviation_check.window_slots.into())
+    {
+        check_price_deviation_bps(
+            price.price,
+            previous_price.price,
+            deviation_check.max_deviation_bps,
+        )?;
+    }
+
+    if oracle_mappings.is_twap_enabled(token_idx) {
+        price.ema_1h =
+            Decimal::from_scaled_val(oracle_twaps.twaps[token_idx].current_ema_1h).into();
+    }
+    price.status |= previous_price.status & DatedPrice::STATUS_DEGRADED;
+    price.index = token;
+
+    Ok(price)
+}
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{account_header::LoadVersioned, clock_unix_timestamp, pdas::seeds},
+    OraclePrices, OraclePricesSnapshot, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SnapshotPrices<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_prices,
+        constraint = configuration.load()?.is_risk_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+
+    pub oracle_prices: AccountLoader<'info, OraclePrices>,
+
+    // Account is pre-reserved/paid outside the program
+    #[account(zero)]
+    pub snapshot: AccountLoader<'info, OraclePricesSnapshot>,
+}
+
+pub fn process(ctx: Context<SnapshotPrices>, feed_name: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    let clock = Clock::get()?;
+    let oracle_prices = ctx.accounts.oracle_prices.load_versioned()?;
+    let mut snapshot = ctx.accounts.snapshot.load_init()?;
+
+    snapshot.oracle_prices = ctx.accounts.oracle_prices.key();
+    snapshot.snapshot_slot = clock.slot;
+    snapshot.snapshot_unix_timestamp = clock_unix_timestamp(&clock)?;
+    snapshot.prices = oracle_prices.prices;
+
+    msg!(
+        "Prices of feed {feed_name} snapshotted at slot {} into {}",
+        clock.slot,
+        ctx.accounts.snapshot.key()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{ConfidencePolicy, ScopeError};
+
+/// Set the policy used to check the confidence interval reported by the oracle of an entry.
+/// A default (zeroed) policy restores the provider checks.
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    policy: ConfidencePolicy,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!("UpdateConfidencePolicy, token: {entry_id}, policy: {policy:?}");
+
+    policy.validate()?;
+
+    let confidence_policy = oracle_mappings
+        .confidence_policies
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    *confidence_policy = policy;
+
+    Ok(())
+}
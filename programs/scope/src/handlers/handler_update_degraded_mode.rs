@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    utils::{consts::*, pdas::seeds},
+    ScopeError,
+};
+
+#[event]
+pub struct DegradedModeChanged {
+    pub degraded: bool,
+    pub forced: bool,
+    pub max_age_factor: u16,
+    /// Last observed slot production lag in bps, 0 if the change is not automatic
+    pub slot_lag_bps: u16,
+    pub slot: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct UpdateDegradedMode<'info> {
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+}
+
+/// Permissionless: observe the slot production since the last observation and enter (or leave)
+/// the degraded mode of the feed if it lags the wall clock by more than the configured threshold.
+pub fn process(ctx: Context<UpdateDegradedMode>, feed_name: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    let clock = Clock::get()?;
+    let current_ts: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .map_err(|_| ScopeError::BadTimestamp)?;
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    let previous_slot = configuration.slot_observation_slot;
+    let previous_ts = configuration.slot_observation_ts;
+    let elapsed_seconds = current_ts.saturating_sub(previous_ts);
+    if previous_ts != 0 && elapsed_seconds < DEGRADED_MODE_MIN_OBSERVATION_SECONDS {
+        msg!("Slot production of feed {feed_name} observed {elapsed_seconds}s ago");
+        return err!(ScopeError::SlotObservationTooRecent);
+    }
+
+    configuration.slot_observation_slot = clock.slot;
+    configuration.slot_observation_ts = current_ts;
+    if previous_ts == 0 || elapsed_seconds > DEGRADED_MODE_MAX_OBSERVATION_SECONDS {
+        msg!("Slot production observation of feed {feed_name} restarted");
+        return Ok(());
+    }
+
+    let slot_lag_bps =
+        slot_production_lag_bps(elapsed_seconds, clock.slot.saturating_sub(previous_slot));
+    let was_degraded = configuration.is_degraded();
+    configuration.degraded_mode_auto = u8::from(
+        configuration.degraded_slot_lag_bps != 0
+            && slot_lag_bps > configuration.degraded_slot_lag_bps,
+    );
+    msg!(
+        "Feed {feed_name} slot production lag {slot_lag_bps} bps over {elapsed_seconds}s, degraded: {}",
+        configuration.is_degraded()
+    );
+
+    if configuration.is_degraded() != was_degraded {
+        emit!(DegradedModeChanged {
+            degraded: configuration.is_degraded(),
+            forced: configuration.degraded_mode_forced != 0,
+            max_age_factor: configuration.degraded_max_age_factor,
+            slot_lag_bps,
+            slot: clock.slot,
+        });
+    }
+
+    Ok(())
+}
+
+/// Share of the slots expected over `elapsed_seconds` that were not produced, in bps
+fn slot_production_lag_bps(elapsed_seconds: u64, elapsed_slots: u64) -> u16 {
+    let expected_slots = elapsed_seconds.saturating_mul(1000) / TARGET_SLOT_DURATION_MS;
+    if expected_slots == 0 || elapsed_slots >= expected_slots {
+        return 0;
+    }
+    let lag_bps = (expected_slots - elapsed_slots) * u64::from(FULL_BPS) / expected_slots;
+    // Lower than FULL_BPS since elapsed_slots < expected_slots
+    lag_bps as u16
+}
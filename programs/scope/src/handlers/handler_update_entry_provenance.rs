@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{utils::pdas::seeds, EntryProvenanceInfo};
+
+#[derive(Accounts)]
+#[instruction(info: EntryProvenanceInfo, feed_name: String)]
+pub struct UpdateEntryProvenance<'info> {
+    pub admin: Signer<'info>,
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    #[account(mut, has_one = oracle_prices)]
+    pub entry_provenance: AccountLoader<'info, crate::EntryProvenance>,
+}
+
+/// Replace the provenance of an entry, e.g. after a new risk review
+pub fn process(
+    ctx: Context<UpdateEntryProvenance>,
+    info: EntryProvenanceInfo,
+    _: String,
+) -> Result<()> {
+    let mut entry_provenance = ctx.accounts.entry_provenance.load_mut()?;
+    msg!(
+        "UpdateEntryProvenance, token: {}",
+        entry_provenance.entry_id
+    );
+
+    entry_provenance.info = info;
+    entry_provenance.last_update_slot = Clock::get()?.slot;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{FallbackSources, ScopeError};
+
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    fallbacks: &[u16],
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!(
+        "UpdateFallbackSources, token: {}, fallbacks: {:?}",
+        entry_id,
+        fallbacks
+    );
+
+    for fallback in fallbacks {
+        let fallback = usize::from(*fallback);
+        if fallback >= crate::MAX_ENTRIES || fallback == entry_id {
+            return err!(ScopeError::InvalidFallbackSources);
+        }
+    }
+
+    let fallback_sources = oracle_mappings
+        .fallback_sources
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    require_gte!(
+        fallback_sources.entries.len(),
+        fallbacks.len(),
+        ScopeError::InvalidFallbackSources
+    );
+    let count = u16::try_from(fallbacks.len()).map_err(|_| ScopeError::InvalidFallbackSources)?;
+
+    *fallback_sources = FallbackSources::default();
+    fallback_sources.count = count;
+    fallback_sources.entries[..fallbacks.len()].copy_from_slice(fallbacks);
+
+    Ok(())
+}
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 
+use super::handler_remove_entry::check_entry_unreferenced;
 use crate::{
-    oracles::{check_context, validate_oracle_cfg, OracleType},
-    utils::{pdas::seeds, zero_copy_deserialize_mut},
+    oracles::{check_context, check_unique_accounts, pyth, validate_oracle_cfg, OracleType},
+    scope_log,
+    utils::{account_header::load_versioned_mut, logs::LogCode, pdas::seeds},
     OracleMappings, ScopeError,
 };
 
@@ -18,7 +20,12 @@ use crate::{
 )]
 pub struct UpdateOracleMapping<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_mappings)]
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        constraint = configuration.load()?.is_mapping_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
     pub configuration: AccountLoader<'info, crate::Configuration>,
 
     /// CHECK: checked above + on deserialize
@@ -26,6 +33,9 @@ pub struct UpdateOracleMapping<'info> {
     pub oracle_mappings: AccountInfo<'info>,
     /// CHECK: We trust the admin to provide a trustable account here. Some basic sanity checks are done based on type
     pub price_info: Option<AccountInfo<'info>>,
+    /// If provided, the entry metadata left unset is populated with the oracle type defaults
+    #[account(mut, address = configuration.load()?.tokens_metadata)]
+    pub tokens_metadata: Option<AccountLoader<'info, crate::TokenMetadatas>>,
 }
 
 pub fn process(
@@ -38,8 +48,14 @@ pub fn process(
     generic_data: &[u8; 20],
 ) -> Result<()> {
     check_context(&ctx)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MappingUpdates)?;
 
-    msg!(
+    scope_log!(
+        LogCode::UpdateMapping,
+        [entry_id, price_type, twap_enabled, twap_source],
         "UpdateOracleMapping, token: {}, price_type: {}, twap_enabled: {}, twap_source: {}, ref_price_index: {}",
         entry_id,
         price_type,
@@ -48,22 +64,63 @@ pub fn process(
         ref_price_index
     );
 
-    let mut oracle_mappings =
-        zero_copy_deserialize_mut::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    if let Some(price_info) = &ctx.accounts.price_info {
+        check_unique_accounts(&[
+            price_info.key(),
+            ctx.accounts.oracle_mappings.key(),
+            ctx.accounts.configuration.key(),
+        ])?;
+    }
+
+    let is_sol_quote_companion = ctx
+        .accounts
+        .configuration
+        .load()?
+        .sol_quote_publications()
+        .iter()
+        .any(|publication| usize::from(publication.sol_entry) == entry_id);
+    if is_sol_quote_companion {
+        msg!("Entry {} is reserved to a SOL quote publication", entry_id);
+        return err!(ScopeError::InvalidSolQuotePublication);
+    }
+
+    let mut oracle_mappings = load_versioned_mut::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let price_type: OracleType = price_type
+        .try_into()
+        .map_err(|_| ScopeError::BadTokenType)?;
+
+    let mut generic_data = *generic_data;
+    if matches!(
+        price_type,
+        OracleType::Pyth
+            | OracleType::PythEMA
+            | OracleType::PythPullBased
+            | OracleType::PythPullBasedEMA
+    ) {
+        let confidence_policy = oracle_mappings
+            .confidence_policies
+            .get_mut(entry_id)
+            .ok_or(ScopeError::BadTokenNb)?;
+        pyth::migrate_legacy_confidence_factor(&mut generic_data, confidence_policy);
+    }
+
     let price_pubkey = oracle_mappings
         .price_info_accounts
         .get_mut(entry_id)
         .ok_or(ScopeError::BadTokenNb)?;
-    let price_type: OracleType = price_type
-        .try_into()
-        .map_err(|_| ScopeError::BadTokenType)?;
 
-    validate_oracle_cfg(
+    let cu_budget = validate_oracle_cfg(
         price_type,
         &ctx.accounts.price_info,
         twap_source,
-        generic_data,
+        &generic_data,
     )?;
+    scope_log!(
+        LogCode::UpdateMappingCuBudget,
+        [cu_budget],
+        "Estimated refresh compute budget: {}",
+        cu_budget
+    );
 
     match &ctx.accounts.price_info {
         Some(price_info_acc) => {
@@ -73,7 +130,14 @@ pub fn process(
         }
         None => {
             match price_type {
-                OracleType::ScopeTwap | OracleType::FixedPrice => *price_pubkey = crate::id(),
+                OracleType::ScopeTwap
+                | OracleType::FixedPrice
+                | OracleType::CappedFloored
+                | OracleType::MedianOf
+                | OracleType::MostRecentOf
+                | OracleType::DiscountToMaturity
+                | OracleType::FixedRateAccrual
+                | OracleType::FixedMultiplier => *price_pubkey = crate::id(),
 
                 _ => {
                     // if no price_info account is passed, it means that the mapping has to be removed so it is set to Pubkey::default
@@ -87,7 +151,46 @@ pub fn process(
     oracle_mappings.twap_enabled[entry_id] = u8::from(twap_enabled);
     oracle_mappings.twap_source[entry_id] = twap_source;
     oracle_mappings.ref_price[entry_id] = ref_price_index;
-    oracle_mappings.generic[entry_id].copy_from_slice(generic_data);
+    oracle_mappings.generic[entry_id].copy_from_slice(&generic_data);
+    oracle_mappings.cu_budgets[entry_id] = cu_budget;
+
+    let is_entry_removed = oracle_mappings.price_info_accounts[entry_id] == Pubkey::default();
+    if is_entry_removed {
+        // Use `remove_entry` to force the removal of a referenced entry
+        check_entry_unreferenced(
+            &oracle_mappings,
+            &ctx.accounts.configuration.load()?,
+            entry_id,
+        )?;
+    }
+    if let (Some(tokens_metadata), false) = (&ctx.accounts.tokens_metadata, is_entry_removed) {
+        let configuration = ctx.accounts.configuration.load()?;
+        let defaults = configuration
+            .oracle_type_metadata_defaults
+            .get(usize::from(u8::from(price_type)))
+            .ok_or(ScopeError::BadTokenType)?;
+        let mut tokens_metadata = tokens_metadata.load_mut()?;
+        let token_metadata = tokens_metadata
+            .metadatas_array
+            .get_mut(entry_id)
+            .ok_or(ScopeError::BadTokenNb)?;
+        if token_metadata.max_age_price_slots == 0 && defaults.max_age_price_slots != 0 {
+            msg!(
+                "Setting default max age for token {} to {}",
+                entry_id,
+                defaults.max_age_price_slots
+            );
+            token_metadata.max_age_price_slots = defaults.max_age_price_slots;
+        }
+        if token_metadata.group_ids_bitset == 0 && defaults.group_ids_bitset != 0 {
+            msg!(
+                "Setting default group IDs for token {} to {:#b}",
+                entry_id,
+                defaults.group_ids_bitset
+            );
+            token_metadata.group_ids_bitset = defaults.group_ids_bitset;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_remaining_accounts_exclude,
+    utils::{account_header::load_versioned, pdas::seeds, scope_chain, unpack_mint},
+    MintToScopeChain, MintsToScopeChains, OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(updates: Vec<(u8, [u16; 4])>)]
+pub struct UpdateMintMap<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(has_one = admin, has_one = oracle_mappings)]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    /// CHECK: Checked above
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [seeds::MINTS_TO_SCOPE_CHAINS, configuration.load()?.oracle_prices.as_ref(), mappings.seed_pk.as_ref(), &mappings.seed_id.to_le_bytes()],
+        bump = mappings.bump,
+        constraint = mappings.oracle_prices == configuration.load()?.oracle_prices,
+        realloc = 8 + MintsToScopeChains::size_from_len(mappings.len_after_updates(&updates)),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub mappings: Account<'info, MintsToScopeChains>,
+
+    pub system_program: Program<'info, System>,
+    // The mint of each update is passed as extra account
+}
+
+/// Replace the mint and scope chain at the given indexes of the map.
+///
+/// An update at the current length of the map appends a new mapping, so that integrations (e.g.
+/// a pool adding a custody) can evolve without closing and recreating the map.
+pub fn process(ctx: Context<UpdateMintMap>, updates: Vec<(u8, [u16; 4])>) -> Result<()> {
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_instruction_enabled(crate::InstructionFlag::MintMaps)?;
+
+    require_eq!(ctx.remaining_accounts.len(), updates.len());
+    check_remaining_accounts_exclude(
+        ctx.remaining_accounts,
+        &[
+            ctx.accounts.configuration.key(),
+            ctx.accounts.oracle_mappings.key(),
+            ctx.accounts.mappings.key(),
+        ],
+    )?;
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let mappings = &mut ctx.accounts.mappings;
+    for ((index, chain), mint) in updates.iter().zip(ctx.remaining_accounts.iter()) {
+        let index = usize::from(*index);
+        scope_chain::validate_chain(&oracle_mappings, chain).map_err(|e| {
+            msg!("Invalid scope chain {chain:?} for mint {}: {e:?}", mint.key);
+            ScopeError::from(e)
+        })?;
+        unpack_mint(mint)?;
+        let mapping = MintToScopeChain {
+            mint: *mint.key,
+            scope_chain: *chain,
+        };
+        match mappings.mapping.get_mut(index) {
+            Some(existing) => {
+                msg!(
+                    "Mint map index {index}: {} {:?} -> {} {chain:?}",
+                    existing.mint,
+                    existing.scope_chain,
+                    mint.key
+                );
+                *existing = mapping;
+            }
+            None if index == mappings.mapping.len() => {
+                msg!("Mint map index {index}: new mapping {} {chain:?}", mint.key);
+                mappings.mapping.push(mapping);
+            }
+            None => {
+                msg!(
+                    "Mint map update index {index} is past the end of the map ({} mappings)",
+                    mappings.mapping.len()
+                );
+                return err!(ScopeError::InvalidMintMapUpdate);
+            }
+        }
+    }
+
+    Ok(())
+}
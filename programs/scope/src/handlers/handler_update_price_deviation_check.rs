@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{PriceDeviationCheck, ScopeError};
+
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    max_deviation_bps: u16,
+    window_slots: u16,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!(
+        "UpdatePriceDeviationCheck, token: {}, max_deviation_bps: {}, window_slots: {}",
+        entry_id,
+        max_deviation_bps,
+        window_slots
+    );
+
+    let deviation_check = oracle_mappings
+        .deviation_checks
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    *deviation_check = PriceDeviationCheck {
+        max_deviation_bps,
+        window_slots,
+    };
+
+    Ok(())
+}
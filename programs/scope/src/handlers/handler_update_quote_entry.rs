@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{QuoteEntry, ScopeError};
+
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    quote_entry: Option<u16>,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!(
+        "UpdateQuoteEntry, token: {}, quote_entry: {:?}",
+        entry_id,
+        quote_entry
+    );
+
+    if let Some(quote_entry) = quote_entry {
+        let quote_entry = usize::from(quote_entry);
+        if quote_entry >= crate::MAX_ENTRIES || quote_entry == entry_id {
+            return err!(ScopeError::InvalidQuoteEntry);
+        }
+    }
+
+    let entry_quote = oracle_mappings
+        .quote_entries
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    *entry_quote = match quote_entry {
+        Some(entry) => QuoteEntry {
+            is_set: 1,
+            entry,
+            ..Default::default()
+        },
+        None => QuoteEntry::default(),
+    };
+
+    Ok(())
+}
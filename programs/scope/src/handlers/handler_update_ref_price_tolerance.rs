@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{utils::consts::FULL_BPS, ScopeError};
+
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    tolerance_bps: u16,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!(
+        "UpdateRefPriceTolerance, token: {}, tolerance_bps: {}",
+        entry_id,
+        tolerance_bps
+    );
+
+    if tolerance_bps > FULL_BPS {
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let entry_tolerance_bps = oracle_mappings
+        .ref_price_tolerance_bps
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    *entry_tolerance_bps = tolerance_bps;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{utils::account_header::LoadVersioned, ScopeError, SmoothingMode};
+
+#[derive(Accounts)]
+pub struct UpdateSmoothingMode<'info> {
+    pub entry_config: UpdateEntryConfig<'info>,
+    #[account(mut, address = entry_config.configuration.load()?.oracle_twaps)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+}
+
+/// Set the write-time smoothing of an entry, the raw prices recorded so far are forgotten
+pub fn process(
+    ctx: Context<UpdateSmoothingMode>,
+    entry_id: usize,
+    mode: u8,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.entry_config.load_mappings(&ctx)?;
+
+    let smoothing_mode =
+        SmoothingMode::try_from(mode).map_err(|_| ProgramError::InvalidArgument)?;
+
+    msg!(
+        "UpdateSmoothingMode, token: {}, mode: {:?}",
+        entry_id,
+        smoothing_mode
+    );
+
+    let entry_mode = oracle_mappings
+        .smoothing_modes
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+    *entry_mode = smoothing_mode.into();
+
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
+    oracle_twaps.twaps[entry_id].clear_smoothing_samples();
+
+    Ok(())
+}
@@ -8,6 +8,7 @@ pub enum UpdateTokenMetadataMode {
     Name = 0,
     MaxPriceAgeSlots = 1,
     GroupIds = 2,
+    MinRefreshIntervalSlots = 3,
 }
 
 impl UpdateTokenMetadataMode {
@@ -20,6 +21,7 @@ impl UpdateTokenMetadataMode {
             UpdateTokenMetadataMode::Name => 0,
             UpdateTokenMetadataMode::MaxPriceAgeSlots => 1,
             UpdateTokenMetadataMode::GroupIds => 2,
+            UpdateTokenMetadataMode::MinRefreshIntervalSlots => 3,
         }
     }
 }
@@ -28,7 +30,13 @@ impl UpdateTokenMetadataMode {
 #[instruction(index: u64, mode: u64,  feed_name: String, value: Vec<u8>)]
 pub struct UpdateTokensMetadata<'info> {
     pub admin: Signer<'info>,
-    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = tokens_metadata)]
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = tokens_metadata,
+        constraint = configuration.load()?.is_risk_admin(&admin.key()) @ ScopeError::InvalidAdminRole,
+    )]
     pub configuration: AccountLoader<'info, crate::Configuration>,
 
     #[account(mut)]
@@ -83,6 +91,20 @@ pub fn process(
             );
             token_metadata.group_ids_bitset = value;
         }
+        UpdateTokenMetadataMode::MinRefreshIntervalSlots => {
+            // Stored in the configuration, which all the refresh instructions read
+            let value = u64::from_le_bytes(value[..8].try_into().unwrap());
+            msg!(
+                "Setting token min refresh interval for index {} to {} slots",
+                index,
+                value
+            );
+            ctx.accounts
+                .configuration
+                .load_mut()?
+                .min_refresh_intervals_slots[index] =
+                u32::try_from(value).map_err(|_| ScopeError::IntegerOverflow)?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+use solana_program::sysvar::instructions::ID as SYSVAR_INSTRUCTIONS_ID;
+
+use super::handler_refresh_prices::check_execution_ctx;
+use crate::{
+    oracles::twap,
+    utils::account_header::{load_versioned, LoadVersioned},
+    OracleMappings, ScopeError,
+};
+
+#[derive(Accounts)]
+pub struct UpdateTwapOnly<'info> {
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, crate::OraclePrices>,
+    /// CHECK: Checked above
+    #[account(owner = crate::ID)]
+    pub oracle_mappings: AccountInfo<'info>,
+    #[account(mut, has_one = oracle_prices, has_one = oracle_mappings)]
+    pub oracle_twaps: AccountLoader<'info, crate::OracleTwaps>,
+    #[account(
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, crate::Configuration>,
+    pub tokens_metadata: AccountLoader<'info, crate::TokenMetadatas>,
+    /// CHECK: Sysvar fixed address
+    #[account(address = SYSVAR_INSTRUCTIONS_ID)]
+    pub instruction_sysvar_account_info: AccountInfo<'info>,
+}
+
+/// Add the currently stored spot price of the given tokens as a sample of their TWAP, at the
+/// current slot, without reading their oracles.
+///
+/// This lets the TWAP sampling cadence be managed independently from the spot refreshes, see
+/// [`OracleMappings::TWAP_ENABLED_CRANK_ONLY`]. Tokens without TWAP or whose spot price is older
+/// than their `max_age_price_slots` (scaled while the feed is degraded) are skipped.
+pub fn process(ctx: Context<UpdateTwapOnly>, tokens: &[u16]) -> Result<()> {
+    check_execution_ctx(&ctx.accounts.instruction_sysvar_account_info, &[])?;
+
+    let configuration = ctx.accounts.configuration.load()?;
+    if configuration.is_paused() {
+        return err!(ScopeError::FeedPaused);
+    }
+    if tokens.is_empty() {
+        return err!(ScopeError::EmptyTokenList);
+    }
+
+    let oracle_mappings = load_versioned::<OracleMappings>(&ctx.accounts.oracle_mappings)?;
+    let mut oracle_prices = ctx.accounts.oracle_prices.load_versioned_mut()?;
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_versioned_mut()?;
+    let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+    let clock = Clock::get()?;
+    let current_ts: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .map_err(|_| ScopeError::BadTimestamp)?;
+
+    for &token in tokens {
+        let token_idx = usize::from(token);
+        if token_idx >= crate::MAX_ENTRIES {
+            return err!(ScopeError::BadTokenNb);
+        }
+        if !oracle_mappings.is_twap_enabled(token_idx) {
+            msg!("Token {token_idx} has no twap, skipped");
+            continue;
+        }
+        let stored_price = &mut oracle_prices.prices[token_idx];
+        let age_slots = clock.slot.saturating_sub(stored_price.last_updated_slot);
+        let max_age_slots = configuration.effective_max_age_slots(
+            tokens_metadata.metadatas_array[token_idx].max_age_price_slots,
+        );
+        if stored_price.price.value == 0 || age_slots > max_age_slots {
+            msg!("Token {token_idx} spot price is not set or too old ({age_slots} slots), skipped");
+            continue;
+        }
+
+        let mut sample = *stored_price;
+        sample.last_updated_slot = clock.slot;
+        sample.unix_timestamp = current_ts;
+        twap::update_twap(&mut oracle_twaps, token_idx, &sample)?;
+        stored_price.ema_1h =
+            Decimal::from_scaled_val(oracle_twaps.twaps[token_idx].current_ema_1h).into();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use super::entry_config::UpdateEntryConfig;
+use crate::{OracleMappings, ScopeError};
+
+/// Set the number of tokens (as a power of ten) the oracle of an entry quotes a price for, so that
+/// the price stored on refresh is always the one of a whole token
+pub fn process(
+    ctx: Context<UpdateEntryConfig>,
+    entry_id: usize,
+    unit_scale: i8,
+    _: String,
+) -> Result<()> {
+    let mut oracle_mappings = ctx.accounts.load_mappings(&ctx)?;
+
+    msg!(
+        "UpdateUnitScale, token: {}, unit_scale: {}",
+        entry_id,
+        unit_scale
+    );
+
+    if unit_scale.unsigned_abs() > OracleMappings::MAX_UNIT_SCALE.unsigned_abs() {
+        msg!(
+            "Unit scale must be between -{0} and {0}",
+            OracleMappings::MAX_UNIT_SCALE
+        );
+        return Err(ProgramError::InvalidArgument.into());
+    }
+
+    let entry_unit_scale = oracle_mappings
+        .unit_scales
+        .get_mut(entry_id)
+        .ok_or(ScopeError::BadTokenNb)?;
+    *entry_unit_scale = unit_scale;
+
+    Ok(())
+}
@@ -1,19 +1,115 @@
+pub mod entry_config;
+pub mod handler_acknowledge_degraded_entry;
 pub mod handler_approve_admin_cached;
+pub mod handler_audit_mappings;
+pub mod handler_bootstrap_twap;
+pub mod handler_clone_mappings;
+pub mod handler_close_entry_provenance;
+pub mod handler_close_feed;
+pub mod handler_close_group_definitions;
 pub mod handler_close_mint_map;
+pub mod handler_close_price_history;
+pub mod handler_create_entry_provenance;
+pub mod handler_create_entry_stats;
+pub mod handler_create_group_definitions;
+pub mod handler_create_mappings_audit_report;
 pub mod handler_create_mint_map;
+pub mod handler_create_price_group_digest;
+pub mod handler_create_price_history;
+pub mod handler_get_entry_dependencies;
+pub mod handler_get_validated_prices;
 pub mod handler_initialize;
+pub mod handler_inject_twap_samples;
+pub mod handler_migrate_account;
+pub mod handler_propose_close_feed;
+pub mod handler_recompute_group_ids;
 pub mod handler_refresh_prices;
+pub mod handler_refresh_prices_by_group;
+pub mod handler_remove_entry;
 pub mod handler_reset_twap;
 pub mod handler_set_admin_cached;
+pub mod handler_set_admin_roles;
+pub mod handler_set_degraded_mode;
+pub mod handler_set_disabled_instructions;
+pub mod handler_set_entry_suspended;
+pub mod handler_set_feed_paused;
+pub mod handler_set_max_consecutive_failures;
+pub mod handler_set_oracle_type_metadata_defaults;
+pub mod handler_set_refresh_cpi_callers;
+pub mod handler_set_refresh_reward_config;
+pub mod handler_set_sol_quote_publications;
+pub mod handler_set_staleness_policy;
+pub mod handler_set_twap_crank_only;
+pub mod handler_simulate_refresh;
+pub mod handler_snapshot_prices;
+pub mod handler_update_confidence_policy;
+pub mod handler_update_degraded_mode;
+pub mod handler_update_entry_provenance;
+pub mod handler_update_fallback_sources;
 pub mod handler_update_mapping;
+pub mod handler_update_mint_map;
+pub mod handler_update_price_deviation_check;
+pub mod handler_update_quote_entry;
+pub mod handler_update_ref_price_tolerance;
+pub mod handler_update_smoothing_mode;
 pub mod handler_update_token_metadata;
+pub mod handler_update_twap_only;
+pub mod handler_update_unit_scale;
 
+pub use entry_config::*;
+pub use handler_acknowledge_degraded_entry::*;
 pub use handler_approve_admin_cached::*;
+pub use handler_audit_mappings::*;
+pub use handler_bootstrap_twap::*;
+pub use handler_clone_mappings::*;
+pub use handler_close_entry_provenance::*;
+pub use handler_close_feed::*;
+pub use handler_close_group_definitions::*;
 pub use handler_close_mint_map::*;
+pub use handler_close_price_history::*;
+pub use handler_create_entry_provenance::*;
+pub use handler_create_entry_stats::*;
+pub use handler_create_group_definitions::*;
+pub use handler_create_mappings_audit_report::*;
 pub use handler_create_mint_map::*;
+pub use handler_create_price_group_digest::*;
+pub use handler_create_price_history::*;
+pub use handler_get_entry_dependencies::*;
+pub use handler_get_validated_prices::*;
 pub use handler_initialize::*;
+pub use handler_inject_twap_samples::*;
+pub use handler_migrate_account::*;
+pub use handler_propose_close_feed::*;
+pub use handler_recompute_group_ids::*;
 pub use handler_refresh_prices::*;
+pub use handler_refresh_prices_by_group::*;
+pub use handler_remove_entry::*;
 pub use handler_reset_twap::*;
 pub use handler_set_admin_cached::*;
+pub use handler_set_admin_roles::*;
+pub use handler_set_degraded_mode::*;
+pub use handler_set_disabled_instructions::*;
+pub use handler_set_entry_suspended::*;
+pub use handler_set_feed_paused::*;
+pub use handler_set_max_consecutive_failures::*;
+pub use handler_set_oracle_type_metadata_defaults::*;
+pub use handler_set_refresh_cpi_callers::*;
+pub use handler_set_refresh_reward_config::*;
+pub use handler_set_sol_quote_publications::*;
+pub use handler_set_staleness_policy::*;
+pub use handler_set_twap_crank_only::*;
+pub use handler_simulate_refresh::*;
+pub use handler_snapshot_prices::*;
+pub use handler_update_confidence_policy::*;
+pub use handler_update_degraded_mode::*;
+pub use handler_update_entry_provenance::*;
+pub use handler_update_fallback_sources::*;
 pub use handler_update_mapping::*;
+pub use handler_update_mint_map::*;
+pub use handler_update_price_deviation_check::*;
+pub use handler_update_quote_entry::*;
+pub use handler_update_ref_price_tolerance::*;
+pub use handler_update_smoothing_mode::*;
 pub use handler_update_token_metadata::*;
+pub use handler_update_twap_only::*;
+pub use handler_update_unit_scale::*;
@@ -26,6 +26,17 @@ declare_id!(PROGRAM_ID);
 
 // Note: Need to be directly integer value to not confuse the IDL generator
 pub const MAX_ENTRIES_U16: u16 = 512;
+/// Number of entries of a feed.
+///
+/// It cannot be raised in place:
+/// - `OracleMappings` stores one array per field, a bigger feed is a new layout of the account
+///   and not an extension of it.
+/// - Entry ids at or above this value mean "no entry" in the configurations stored on chain (e.g.
+///   scope chains, most recent of sources).
+/// - The oracles referencing other entries (TWAP, median, capped/floored, quote entries,
+///   fallbacks...) resolve them in the single `OraclePrices` account of the refresh.
+///
+/// More entries are listed with additional feeds.
 // Note: Need to be directly integer value to not confuse the IDL generator
 pub const MAX_ENTRIES: usize = 512;
 pub const VALUE_BYTE_ARRAY_LEN: usize = 32;
@@ -46,6 +57,66 @@ pub mod scope {
         handler_refresh_prices::refresh_price_list(ctx, &tokens)
     }
 
+    pub fn refresh_price_list_rewarded<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshListRewarded<'info>>,
+        tokens: Vec<u16>,
+    ) -> Result<()> {
+        handler_refresh_prices::refresh_price_list_rewarded(ctx, &tokens)
+    }
+
+    pub fn refresh_price_list_two_feeds<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshTwoFeeds<'info>>,
+        first_tokens: Vec<u16>,
+        second_tokens: Vec<u16>,
+        first_feed_accounts: u16,
+    ) -> Result<()> {
+        handler_refresh_prices::refresh_price_list_two_feeds(
+            ctx,
+            &first_tokens,
+            &second_tokens,
+            first_feed_accounts.into(),
+        )
+    }
+
+    pub fn update_twap_only(ctx: Context<UpdateTwapOnly>, tokens: Vec<u16>) -> Result<()> {
+        handler_update_twap_only::process(ctx, &tokens)
+    }
+
+    pub fn set_twap_crank_only(
+        ctx: Context<SetTwapCrankOnly>,
+        token: u16,
+        feed_name: String,
+        crank_only: bool,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_set_twap_crank_only::process(ctx, entry_id, crank_only, feed_name)
+    }
+
+    pub fn refresh_prices_by_group<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshPricesByGroup<'info>>,
+        group_id: u8,
+    ) -> Result<()> {
+        handler_refresh_prices_by_group::refresh_prices_by_group(ctx, group_id)
+    }
+
+    pub fn simulate_refresh(ctx: Context<SimulateRefresh>, token: u16) -> Result<DatedPrice> {
+        handler_simulate_refresh::process(ctx, token)
+    }
+
+    pub fn get_validated_prices(
+        ctx: Context<GetValidatedPrices>,
+        tokens: Vec<u16>,
+    ) -> Result<Vec<DatedPrice>> {
+        handler_get_validated_prices::process(ctx, &tokens)
+    }
+
+    pub fn get_entry_dependencies(
+        ctx: Context<GetEntryDependencies>,
+        token: u16,
+    ) -> Result<Vec<u16>> {
+        handler_get_entry_dependencies::process(ctx, token)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn update_mapping(
         ctx: Context<UpdateOracleMapping>,
@@ -72,6 +143,106 @@ pub mod scope {
         )
     }
 
+    pub fn remove_entry<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveEntry<'info>>,
+        token: u16,
+        feed_name: String,
+        force: bool,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_remove_entry::process(ctx, entry_id, force, feed_name)
+    }
+
+    pub fn clone_mappings(
+        ctx: Context<CloneMappings>,
+        start_entry: u16,
+        end_entry: u16,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_clone_mappings::process(ctx, start_entry, end_entry, feed_name)
+    }
+
+    pub fn update_fallback_sources(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        fallbacks: Vec<u16>,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_fallback_sources::process(ctx, entry_id, &fallbacks, feed_name)
+    }
+
+    pub fn update_confidence_policy(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        policy: ConfidencePolicy,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_confidence_policy::process(ctx, entry_id, policy, feed_name)
+    }
+
+    pub fn update_price_deviation_check(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        max_deviation_bps: u16,
+        window_slots: u16,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_price_deviation_check::process(
+            ctx,
+            entry_id,
+            max_deviation_bps,
+            window_slots,
+            feed_name,
+        )
+    }
+
+    pub fn update_quote_entry(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        quote_entry: Option<u16>,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_quote_entry::process(ctx, entry_id, quote_entry, feed_name)
+    }
+
+    pub fn update_ref_price_tolerance(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_ref_price_tolerance::process(ctx, entry_id, tolerance_bps, feed_name)
+    }
+
+    pub fn update_smoothing_mode(
+        ctx: Context<UpdateSmoothingMode>,
+        token: u16,
+        feed_name: String,
+        mode: u8,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_smoothing_mode::process(ctx, entry_id, mode, feed_name)
+    }
+
+    pub fn update_unit_scale(
+        ctx: Context<UpdateEntryConfig>,
+        token: u16,
+        feed_name: String,
+        unit_scale: i8,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_update_unit_scale::process(ctx, entry_id, unit_scale, feed_name)
+    }
+
+    pub fn migrate_account(ctx: Context<MigrateAccount>, feed_name: String) -> Result<()> {
+        handler_migrate_account::process(ctx, feed_name)
+    }
+
     pub fn reset_twap(ctx: Context<ResetTwap>, token: u64, feed_name: String) -> Result<()> {
         let entry_id: usize = token
             .try_into()
@@ -79,6 +250,31 @@ pub mod scope {
         handler_reset_twap::process(ctx, entry_id, feed_name)
     }
 
+    pub fn bootstrap_twap(
+        ctx: Context<BootstrapTwap>,
+        token: u64,
+        feed_name: String,
+        seed_price: Price,
+        num_refreshes: u64,
+    ) -> Result<()> {
+        let entry_id: usize = token
+            .try_into()
+            .map_err(|_| ScopeError::OutOfRangeIntegralConversion)?;
+        handler_bootstrap_twap::process(ctx, entry_id, seed_price, num_refreshes, feed_name)
+    }
+
+    pub fn inject_twap_samples(
+        ctx: Context<InjectTwapSamples>,
+        token: u64,
+        feed_name: String,
+        samples: Vec<TwapSample>,
+    ) -> Result<()> {
+        let entry_id: usize = token
+            .try_into()
+            .map_err(|_| ScopeError::OutOfRangeIntegralConversion)?;
+        handler_inject_twap_samples::process(ctx, entry_id, &samples, feed_name)
+    }
+
     pub fn update_token_metadata(
         ctx: Context<UpdateTokensMetadata>,
         index: u64,
@@ -98,6 +294,24 @@ pub mod scope {
         handler_update_token_metadata::process(ctx, index, mode, value, feed_name)
     }
 
+    pub fn set_oracle_type_metadata_defaults(
+        ctx: Context<SetOracleTypeMetadataDefaults>,
+        price_type: u8,
+        feed_name: String,
+        defaults: OracleTypeMetadataDefaults,
+    ) -> Result<()> {
+        handler_set_oracle_type_metadata_defaults::process(ctx, price_type, defaults, feed_name)
+    }
+
+    pub fn set_sol_quote_publications(
+        ctx: Context<SetSolQuotePublications>,
+        feed_name: String,
+        sol_usd_entry: u16,
+        publications: Vec<SolQuotePublication>,
+    ) -> Result<()> {
+        handler_set_sol_quote_publications::process(ctx, sol_usd_entry, &publications, feed_name)
+    }
+
     pub fn set_admin_cached(
         ctx: Context<SetAdminCached>,
         new_admin: Pubkey,
@@ -106,6 +320,95 @@ pub mod scope {
         handler_set_admin_cached::process(ctx, new_admin, feed_name)
     }
 
+    pub fn set_admin_roles(
+        ctx: Context<SetAdminRoles>,
+        mapping_admin: Pubkey,
+        risk_admin: Pubkey,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_admin_roles::process(ctx, mapping_admin, risk_admin, feed_name)
+    }
+
+    pub fn set_entry_suspended(
+        ctx: Context<SetEntrySuspended>,
+        token: u16,
+        suspended: bool,
+        feed_name: String,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_set_entry_suspended::process(ctx, entry_id, suspended, feed_name)
+    }
+
+    pub fn set_feed_paused(
+        ctx: Context<SetFeedPaused>,
+        paused: bool,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_feed_paused::process(ctx, paused, feed_name)
+    }
+
+    pub fn set_disabled_instructions(
+        ctx: Context<SetDisabledInstructions>,
+        disabled_instructions: u64,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_disabled_instructions::process(ctx, disabled_instructions, feed_name)
+    }
+
+    pub fn set_staleness_policy(
+        ctx: Context<SetStalenessPolicy>,
+        enforce_on_refresh: bool,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_staleness_policy::process(ctx, enforce_on_refresh, feed_name)
+    }
+
+    pub fn snapshot_prices(ctx: Context<SnapshotPrices>, feed_name: String) -> Result<()> {
+        handler_snapshot_prices::process(ctx, feed_name)
+    }
+
+    pub fn set_degraded_mode(
+        ctx: Context<SetDegradedMode>,
+        forced: bool,
+        max_age_factor: u16,
+        slot_lag_bps: u16,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_degraded_mode::process(ctx, forced, max_age_factor, slot_lag_bps, feed_name)
+    }
+
+    pub fn update_degraded_mode(ctx: Context<UpdateDegradedMode>, feed_name: String) -> Result<()> {
+        handler_update_degraded_mode::process(ctx, feed_name)
+    }
+
+    pub fn propose_close_feed(
+        ctx: Context<ProposeCloseFeed>,
+        feed_name: String,
+        destination: Pubkey,
+    ) -> Result<()> {
+        handler_propose_close_feed::process(ctx, destination, feed_name)
+    }
+
+    pub fn close_feed(ctx: Context<CloseFeed>, feed_name: String) -> Result<()> {
+        handler_close_feed::process(ctx, feed_name)
+    }
+
+    pub fn set_refresh_reward_config(
+        ctx: Context<SetRefreshRewardConfig>,
+        feed_name: String,
+        reward_config: RefreshRewardConfig,
+    ) -> Result<()> {
+        handler_set_refresh_reward_config::process(ctx, reward_config, feed_name)
+    }
+
+    pub fn set_refresh_cpi_callers(
+        ctx: Context<SetRefreshCpiCallers>,
+        feed_name: String,
+        programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        handler_set_refresh_cpi_callers::process(ctx, &programs, feed_name)
+    }
+
     pub fn approve_admin_cached(ctx: Context<ApproveAdminCached>, feed_name: String) -> Result<()> {
         handler_approve_admin_cached::process(ctx, feed_name)
     }
@@ -123,4 +426,107 @@ pub mod scope {
     pub fn close_mint_map(ctx: Context<CloseMintMap>) -> Result<()> {
         handler_close_mint_map::process(ctx)
     }
+
+    pub fn update_mint_map(
+        ctx: Context<UpdateMintMap>,
+        updates: Vec<(u8, [u16; 4])>,
+    ) -> Result<()> {
+        handler_update_mint_map::process(ctx, updates)
+    }
+
+    pub fn create_group_definitions(
+        ctx: Context<CreateGroupDefinitions>,
+        feed_name: String,
+        groups: Vec<GroupDefinition>,
+    ) -> Result<()> {
+        handler_create_group_definitions::process(ctx, groups, feed_name)
+    }
+
+    pub fn close_group_definitions(
+        ctx: Context<CloseGroupDefinitions>,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_close_group_definitions::process(ctx, feed_name)
+    }
+
+    pub fn create_price_group_digest(
+        ctx: Context<CreatePriceGroupDigest>,
+        group_id: u8,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_create_price_group_digest::process(ctx, group_id, feed_name)
+    }
+
+    pub fn create_price_history(
+        ctx: Context<CreatePriceHistory>,
+        token: u16,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_create_price_history::process(ctx, token, feed_name)
+    }
+
+    pub fn close_price_history(ctx: Context<ClosePriceHistory>, feed_name: String) -> Result<()> {
+        handler_close_price_history::process(ctx, feed_name)
+    }
+
+    pub fn create_entry_provenance(
+        ctx: Context<CreateEntryProvenance>,
+        token: u16,
+        feed_name: String,
+        info: EntryProvenanceInfo,
+    ) -> Result<()> {
+        handler_create_entry_provenance::process(ctx, token, info, feed_name)
+    }
+
+    pub fn update_entry_provenance(
+        ctx: Context<UpdateEntryProvenance>,
+        info: EntryProvenanceInfo,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_update_entry_provenance::process(ctx, info, feed_name)
+    }
+
+    pub fn close_entry_provenance(
+        ctx: Context<CloseEntryProvenance>,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_close_entry_provenance::process(ctx, feed_name)
+    }
+
+    pub fn create_entry_stats(
+        ctx: Context<CreateEntryStats>,
+        feed_name: String,
+        max_consecutive_failures: u16,
+    ) -> Result<()> {
+        handler_create_entry_stats::process(ctx, max_consecutive_failures, feed_name)
+    }
+
+    pub fn set_max_consecutive_failures(
+        ctx: Context<SetMaxConsecutiveFailures>,
+        max_consecutive_failures: u16,
+        feed_name: String,
+    ) -> Result<()> {
+        handler_set_max_consecutive_failures::process(ctx, max_consecutive_failures, feed_name)
+    }
+
+    pub fn acknowledge_degraded_entry(
+        ctx: Context<AcknowledgeDegradedEntry>,
+        token: u16,
+        feed_name: String,
+    ) -> Result<()> {
+        let entry_id: usize = token.into();
+        handler_acknowledge_degraded_entry::process(ctx, entry_id, feed_name)
+    }
+
+    pub fn create_mappings_audit_report(ctx: Context<CreateMappingsAuditReport>) -> Result<()> {
+        handler_create_mappings_audit_report::process(ctx)
+    }
+
+    pub fn audit_mappings(ctx: Context<AuditMappings>, tokens: Vec<u16>) -> Result<()> {
+        handler_audit_mappings::process(ctx, &tokens)
+    }
+
+    pub fn recompute_group_ids(ctx: Context<RecomputeGroupIds>, feed_name: String) -> Result<()> {
+        handler_recompute_group_ids::process(ctx, feed_name)
+    }
 }
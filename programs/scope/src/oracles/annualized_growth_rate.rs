@@ -0,0 +1,383 @@
+//! Annualized growth rate of an entry, from its current price and the price recorded in its
+//! [`OraclePriceHistory`] a configured number of days ago, e.g. to publish the staking APY of an
+//! LST entry for interest-rate products
+
+use anchor_lang::prelude::*;
+use decimal_wad::{
+    common::{TryDiv, TryMul, TrySub},
+    decimal::Decimal,
+};
+
+use crate::{
+    utils::{clock_unix_timestamp, SECONDS_PER_DAY, SECONDS_PER_YEAR},
+    DatedPrice, OraclePriceHistory, OraclePrices, Price, ScopeError, ScopeResult,
+};
+
+/// Configuration of an annualized growth rate entry, stored (borsh serialized) in the entry
+/// generic data. The source entry is the one of the price history account of the mapping.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnnualizedGrowthRateData {
+    /// The reference price is the latest recorded at least `lookback_days` days ago
+    pub lookback_days: u16,
+    /// The reference price must have been recorded at most `max_lookback_days` days ago, so that
+    /// gaps in the history do not smooth the rate over an unexpected period
+    pub max_lookback_days: u16,
+}
+
+impl AnnualizedGrowthRateData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::AnnualizedGrowthRateInvalid))
+    }
+}
+
+/// Get the simple (non compounded) annualized growth rate (a rate of 1 is 100%) of the price history entry between the
+/// reference price and its current price.
+///
+/// The returned slot and timestamp are the ones of the current price of the source entry. A
+/// decreasing price is rejected as a rate cannot be negative, and an unchanged one like any zero
+/// price.
+pub fn get_price(
+    history_account: &AccountInfo,
+    oracle_prices_pk: &Pubkey,
+    oracle_prices: &OraclePrices,
+    entry_id: usize,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice> {
+    let data = AnnualizedGrowthRateData::from_generic_data(generic_data)?;
+    let history_loader = AccountLoader::<OraclePriceHistory>::try_from(history_account)?;
+    let history = history_loader.load()?;
+    require_keys_eq!(
+        history.oracle_prices,
+        *oracle_prices_pk,
+        ScopeError::UnexpectedAccount
+    );
+    let source_entry = usize::from(history.entry_id);
+    if source_entry == entry_id {
+        msg!("Annualized growth rate entry {entry_id} cannot use its own price history");
+        return err!(ScopeError::AnnualizedGrowthRateInvalid);
+    }
+
+    let current = oracle_prices
+        .prices
+        .get(source_entry)
+        .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+    if current.price.value == 0 {
+        msg!("Annualized growth rate source entry {source_entry} has no price");
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let now = clock_unix_timestamp(clock)?;
+    let lookback_start = now.saturating_sub(u64::from(data.lookback_days) * SECONDS_PER_DAY as u64);
+    let lookback_limit =
+        now.saturating_sub(u64::from(data.max_lookback_days) * SECONDS_PER_DAY as u64);
+    let Some(reference) = history
+        .get_price_at_timestamp(lookback_start)
+        .filter(|reference| reference.unix_timestamp >= lookback_limit)
+    else {
+        msg!(
+            "No price of entry {source_entry} recorded between {lookback_limit} and {lookback_start}"
+        );
+        return err!(ScopeError::AnnualizedGrowthRateNoReference);
+    };
+
+    let elapsed_seconds = current
+        .unix_timestamp
+        .checked_sub(reference.unix_timestamp)
+        .filter(|elapsed| *elapsed > 0)
+        .ok_or_else(|| {
+            msg!(
+                "Current price of entry {source_entry} ({}) is not newer than the reference ({})",
+                current.unix_timestamp,
+                reference.unix_timestamp
+            );
+            error!(ScopeError::AnnualizedGrowthRateNoReference)
+        })?;
+    if Decimal::from(current.price) < Decimal::from(reference.price) {
+        msg!(
+            "Price of entry {source_entry} decreased from {:?} to {:?}",
+            reference.price,
+            current.price
+        );
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let price = annualized_growth_rate(reference.price, current.price, elapsed_seconds)?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: current.last_updated_slot,
+        unix_timestamp: current.unix_timestamp,
+        ..Default::default()
+    })
+}
+
+/// (current / reference - 1) * year / elapsed
+fn annualized_growth_rate(
+    reference: Price,
+    current: Price,
+    elapsed_seconds: u64,
+) -> ScopeResult<Price> {
+    let rate = current
+        .try_to_decimal()?
+        .try_div(reference.try_to_decimal()?)?
+        .try_sub(Decimal::one())?
+        .try_mul(SECONDS_PER_YEAR as u64)?
+        .try_div(elapsed_seconds)?;
+    Price::try_from_decimal(rate)
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let Some(account) = account else {
+        msg!("The price history of the source entry is expected with an annualized growth rate");
+        return err!(ScopeError::AnnualizedGrowthRateInvalid);
+    };
+    // The feed and the source entry of the history are checked at refresh
+    AccountLoader::<OraclePriceHistory>::try_from(account).map_err(|e| {
+        msg!("Account {} is not a price history", account.key);
+        e
+    })?;
+
+    let data = AnnualizedGrowthRateData::from_generic_data(generic_data)?;
+    if data.lookback_days == 0 || data.max_lookback_days < data.lookback_days {
+        msg!(
+            "Invalid lookback of {} days (at most {} days)",
+            data.lookback_days,
+            data.max_lookback_days
+        );
+        return err!(ScopeError::AnnualizedGrowthRateInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{
+        Discriminator,
+        __private::bytemuck::{self, Zeroable},
+    };
+
+    use super::*;
+    use crate::oracles::test_utils::{clock, dated_price, generic_data, oracle_prices, price};
+
+    const ENTRY_ID: usize = 5;
+    const SOURCE: u16 = 1;
+    const NOW: u64 = 100 * SECONDS_PER_DAY as u64;
+    /// A tenth of a year before `NOW`, between the lookback of 30 days and its limit of 40 days
+    const REFERENCE_TS: u64 = NOW - SECONDS_PER_YEAR as u64 / 10;
+
+    fn lookback(lookback_days: u16, max_lookback_days: u16) -> [u8; 20] {
+        generic_data(&AnnualizedGrowthRateData {
+            lookback_days,
+            max_lookback_days,
+        })
+    }
+
+    /// Data of the price history of `entry_id` in the feed `oracle_prices_pk`
+    fn history_data(oracle_prices_pk: Pubkey, entry_id: u16, recorded: &[DatedPrice]) -> Vec<u8> {
+        let mut history: Box<OraclePriceHistory> = Box::new(Zeroable::zeroed());
+        history.oracle_prices = oracle_prices_pk;
+        history.entry_id = entry_id;
+        for price in recorded {
+            assert!(history.record(price));
+        }
+        let mut data = OraclePriceHistory::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(history.as_ref()));
+        data
+    }
+
+    /// Growth rate of the `SOURCE` entry, whose current price is `current`, with a lookback of 30
+    /// days (at most 40)
+    fn growth_rate(recorded: &[DatedPrice], current: DatedPrice) -> Result<DatedPrice> {
+        let oracle_prices_pk = Pubkey::new_unique();
+        let mut data = history_data(oracle_prices_pk, SOURCE, recorded);
+        get_test_price(&mut data, &oracle_prices_pk, current, &lookback(30, 40))
+    }
+
+    fn get_test_price(
+        history_data: &mut [u8],
+        oracle_prices_pk: &Pubkey,
+        current: DatedPrice,
+        generic_data: &[u8; 20],
+    ) -> Result<DatedPrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let history_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            history_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        get_price(
+            &history_account,
+            oracle_prices_pk,
+            &oracle_prices([(SOURCE, current)]),
+            ENTRY_ID,
+            &clock(500, NOW as i64),
+            generic_data,
+        )
+    }
+
+    #[test]
+    fn test_growth_rate() {
+        let recorded = [
+            dated_price(price(99, 2), 10, REFERENCE_TS - SECONDS_PER_DAY as u64),
+            dated_price(price(100, 2), 20, REFERENCE_TS),
+            // Too recent to be the reference
+            dated_price(price(1_004, 3), 30, NOW - 10 * SECONDS_PER_DAY as u64),
+        ];
+        // +0.5% in a tenth of a year
+        let rate = growth_rate(&recorded, dated_price(price(1_005, 3), 40, NOW)).unwrap();
+        assert!((f64::from(rate.price) - 0.05).abs() < 1e-12);
+        assert_eq!(rate.last_updated_slot, 40);
+        assert_eq!(rate.unix_timestamp, NOW);
+
+        // An unchanged price gives a zero rate, rejected as any zero price
+        let rate = growth_rate(&recorded, dated_price(price(1, 0), 40, NOW)).unwrap();
+        assert_eq!(rate.price.value, 0);
+    }
+
+    #[test]
+    fn test_stale_source() {
+        let recorded = [dated_price(price(100, 2), 20, REFERENCE_TS)];
+        // The rate is computed over the elapsed time of the source price, and dated by it
+        let source_ts = REFERENCE_TS + SECONDS_PER_YEAR as u64 / 20;
+        let rate = growth_rate(&recorded, dated_price(price(10_025, 4), 30, source_ts)).unwrap();
+        assert!((f64::from(rate.price) - 0.05).abs() < 1e-12);
+        assert_eq!(rate.last_updated_slot, 30);
+        assert_eq!(rate.unix_timestamp, source_ts);
+    }
+
+    #[test]
+    fn test_no_elapsed_time() {
+        let recorded = [dated_price(price(100, 2), 20, REFERENCE_TS)];
+        for current_ts in [REFERENCE_TS, REFERENCE_TS - 1] {
+            assert_eq!(
+                growth_rate(&recorded, dated_price(price(101, 2), 30, current_ts)).unwrap_err(),
+                error!(ScopeError::AnnualizedGrowthRateNoReference)
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_reference() {
+        let current = dated_price(price(101, 2), 40, NOW);
+        for recorded in [
+            // Empty history
+            vec![],
+            // Older than the lookback limit
+            vec![dated_price(
+                price(100, 2),
+                10,
+                NOW - 41 * SECONDS_PER_DAY as u64,
+            )],
+            // More recent than the lookback
+            vec![dated_price(
+                price(100, 2),
+                10,
+                NOW - 29 * SECONDS_PER_DAY as u64,
+            )],
+        ] {
+            assert_eq!(
+                growth_rate(&recorded, current).unwrap_err(),
+                error!(ScopeError::AnnualizedGrowthRateNoReference)
+            );
+        }
+    }
+
+    #[test]
+    fn test_decreasing_price() {
+        let recorded = [dated_price(price(100, 2), 20, REFERENCE_TS)];
+        assert_eq!(
+            growth_rate(&recorded, dated_price(price(99, 2), 30, NOW)).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            annualized_growth_rate(price(1, 18), price(u64::MAX, 0), 1),
+            Err(ScopeError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn test_invalid_source() {
+        let oracle_prices_pk = Pubkey::new_unique();
+        let recorded = [dated_price(price(100, 2), 20, REFERENCE_TS)];
+        let current = dated_price(price(101, 2), 30, NOW);
+
+        // History of another feed
+        let mut data = history_data(Pubkey::new_unique(), SOURCE, &recorded);
+        assert_eq!(
+            get_test_price(&mut data, &oracle_prices_pk, current, &lookback(30, 40)).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        // History of the entry itself
+        let mut data = history_data(oracle_prices_pk, ENTRY_ID as u16, &recorded);
+        assert_eq!(
+            get_test_price(&mut data, &oracle_prices_pk, current, &lookback(30, 40)).unwrap_err(),
+            error!(ScopeError::AnnualizedGrowthRateInvalid)
+        );
+
+        // Source without price
+        assert_eq!(
+            growth_rate(&recorded, DatedPrice::default()).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = history_data(Pubkey::new_unique(), SOURCE, &[]);
+        let history_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let history_account = Some(history_account);
+
+        validate_mapping_cfg(&history_account, &lookback(30, 40)).unwrap();
+        validate_mapping_cfg(&history_account, &lookback(30, 30)).unwrap();
+        for invalid in [lookback(0, 40), lookback(30, 29)] {
+            assert_eq!(
+                validate_mapping_cfg(&history_account, &invalid).unwrap_err(),
+                error!(ScopeError::AnnualizedGrowthRateInvalid)
+            );
+        }
+        assert_eq!(
+            validate_mapping_cfg(&None, &lookback(30, 40)).unwrap_err(),
+            error!(ScopeError::AnnualizedGrowthRateInvalid)
+        );
+
+        let mut other_data = vec![0; 8];
+        let mut other_lamports = 0;
+        let other_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        assert!(validate_mapping_cfg(&Some(other_account), &lookback(30, 40)).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+
+use crate::{DatedPrice, OraclePrices, ScopeError, MAX_ENTRIES_U16};
+
+/// Configuration of a capped/floored entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CappedFlooredData {
+    /// Entry giving the price to be bounded
+    pub source_entry: u16,
+    /// Entry giving the maximum price, if any
+    pub cap_entry: Option<u16>,
+    /// Entry giving the minimum price, if any
+    pub floor_entry: Option<u16>,
+}
+
+impl CappedFlooredData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::CappedFlooredInvalid))
+    }
+}
+
+/// Get the price of the source entry bounded by the cap and floor entries:
+/// `min(cap, max(floor, source))`
+///
+/// The returned slot and timestamp are the oldest of all the entries involved, so that a stale
+/// bound makes the price stale. Fails if any of the entries has no price.
+pub fn get_price(oracle_prices: &OraclePrices, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+    let data = CappedFlooredData::from_generic_data(generic_data)?;
+
+    let get_entry_price = |entry: u16| -> Result<DatedPrice> {
+        let dated_price = oracle_prices
+            .prices
+            .get(usize::from(entry))
+            .copied()
+            .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+        if dated_price.price.value == 0 {
+            msg!("Capped/floored entry {entry} has no price");
+            return err!(ScopeError::PriceNotValid);
+        }
+        Ok(dated_price)
+    };
+
+    let source_price = get_entry_price(data.source_entry)?;
+    let floor_price = data.floor_entry.map(get_entry_price).transpose()?;
+    let cap_price = data.cap_entry.map(get_entry_price).transpose()?;
+
+    let mut price = source_price.price;
+    let mut last_updated_slot = source_price.last_updated_slot;
+    let mut unix_timestamp = source_price.unix_timestamp;
+
+    if let Some(floor_price) = floor_price {
+        if Decimal::from(floor_price.price) > Decimal::from(price) {
+            price = floor_price.price;
+        }
+        last_updated_slot = last_updated_slot.min(floor_price.last_updated_slot);
+        unix_timestamp = unix_timestamp.min(floor_price.unix_timestamp);
+    }
+
+    if let Some(cap_price) = cap_price {
+        if Decimal::from(cap_price.price) < Decimal::from(price) {
+            price = cap_price.price;
+        }
+        last_updated_slot = last_updated_slot.min(cap_price.last_updated_slot);
+        unix_timestamp = unix_timestamp.min(cap_price.unix_timestamp);
+    }
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot,
+        unix_timestamp,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a capped/floored oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = CappedFlooredData::from_generic_data(generic_data)?;
+
+    if data.cap_entry.is_none() && data.floor_entry.is_none() {
+        msg!("At least one of cap or floor entry must be set");
+        return err!(ScopeError::CappedFlooredInvalid);
+    }
+
+    let entries = [Some(data.source_entry), data.cap_entry, data.floor_entry];
+    if entries
+        .iter()
+        .flatten()
+        .any(|&entry| entry >= MAX_ENTRIES_U16)
+    {
+        msg!("Capped/floored entries must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::CappedFlooredInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        oracles::test_utils::{
+            check_missing_source, check_validate_mapping_cfg, dated_price, generic_data, price,
+        },
+        Price,
+    };
+
+    const SOURCE: u16 = 0;
+    const CAP: u16 = 1;
+    const FLOOR: u16 = 2;
+
+    fn bounds(source_entry: u16, cap_entry: Option<u16>, floor_entry: Option<u16>) -> [u8; 20] {
+        generic_data(&CappedFlooredData {
+            source_entry,
+            cap_entry,
+            floor_entry,
+        })
+    }
+
+    fn capped_floored() -> [u8; 20] {
+        bounds(SOURCE, Some(CAP), Some(FLOOR))
+    }
+
+    /// Prices with a cap of 1.05 and a floor of 0.95, updated at slot 100 and time 1000
+    fn oracle_prices(source_price: Price) -> Box<OraclePrices> {
+        crate::oracles::test_utils::oracle_prices([
+            (SOURCE, dated_price(source_price, 100, 1000)),
+            (CAP, dated_price(price(105, 2), 100, 1000)),
+            (FLOOR, dated_price(price(95, 2), 100, 1000)),
+        ])
+    }
+
+    #[test]
+    fn test_price_within_bounds() {
+        let source_price = price(1_001, 3);
+        let price = get_price(&oracle_prices(source_price), &capped_floored()).unwrap();
+        assert_eq!(price.price, source_price);
+    }
+
+    #[test]
+    fn test_price_capped() {
+        let oracle_prices = oracle_prices(price(11_000, 4));
+        let capped_price = get_price(&oracle_prices, &capped_floored()).unwrap();
+        assert_eq!(capped_price.price, price(105, 2));
+
+        // Only capped
+        let capped_price = get_price(&oracle_prices, &bounds(SOURCE, Some(CAP), None)).unwrap();
+        assert_eq!(capped_price.price, price(105, 2));
+    }
+
+    #[test]
+    fn test_price_floored() {
+        let oracle_prices = oracle_prices(price(9, 1));
+        let floored_price = get_price(&oracle_prices, &capped_floored()).unwrap();
+        assert_eq!(floored_price.price, price(95, 2));
+
+        // Only floored
+        let floored_price = get_price(&oracle_prices, &bounds(SOURCE, None, Some(FLOOR))).unwrap();
+        assert_eq!(floored_price.price, price(95, 2));
+    }
+
+    #[test]
+    fn test_missing_source() {
+        // The floor must not stand in for a source without price
+        check_missing_source(
+            &oracle_prices(price(1, 0)),
+            SOURCE,
+            |oracle_prices, source| {
+                get_price(oracle_prices, &bounds(source, Some(CAP), Some(FLOOR)))
+            },
+        );
+    }
+
+    #[test]
+    fn test_stale_source() {
+        let mut stale_source = oracle_prices(price(1, 0));
+        let source = &mut stale_source.prices[usize::from(SOURCE)];
+        source.last_updated_slot = 10;
+        source.unix_timestamp = 500;
+        let bounded_price = get_price(&stale_source, &capped_floored()).unwrap();
+        assert_eq!(bounded_price.last_updated_slot, 10);
+        assert_eq!(bounded_price.unix_timestamp, 500);
+
+        // A stale bound makes the price stale too, even when it does not apply
+        let mut stale_cap = oracle_prices(price(1, 0));
+        stale_cap.prices[usize::from(CAP)].unix_timestamp = 200;
+        let bounded_price = get_price(&stale_cap, &capped_floored()).unwrap();
+        assert_eq!(bounded_price.price, price(1, 0));
+        assert_eq!(bounded_price.last_updated_slot, 100);
+        assert_eq!(bounded_price.unix_timestamp, 200);
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        check_validate_mapping_cfg(
+            validate_mapping_cfg,
+            &[capped_floored(), bounds(SOURCE, None, Some(FLOOR))],
+            &[
+                bounds(SOURCE, None, None),
+                bounds(SOURCE, Some(MAX_ENTRIES_U16), None),
+            ],
+            ScopeError::CappedFlooredInvalid,
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Confidence interval check shared by the providers reporting a confidence interval or a
+//! standard deviation along with their price (pyth and switchboard oracles).
+//!
+//! The check is configured per entry with a [`ConfidencePolicy`], see its fields for the options.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::{
+        consts::FULL_BPS,
+        math::{check_confidence_interval, cmp_scaled},
+    },
+    ConfidenceFailureMode, ConfidencePolicy, ScopeError, ScopeResult,
+};
+
+/// Maximum exponent of the absolute floor of a policy
+const MAX_ABS_FLOOR_EXP: u8 = 18;
+
+impl ConfidencePolicy {
+    pub fn get_failure_mode(&self) -> ConfidenceFailureMode {
+        // Invalid modes are rejected on update
+        ConfidenceFailureMode::try_from(self.failure_mode).unwrap_or_default()
+    }
+
+    /// Check that the policy can be applied
+    pub fn validate(&self) -> ScopeResult<()> {
+        if ConfidenceFailureMode::try_from(self.failure_mode).is_err() {
+            msg!("Invalid confidence failure mode {}", self.failure_mode);
+            return Err(ScopeError::InvalidConfidencePolicy);
+        }
+        if self.factor != 0 && self.max_bps != 0 {
+            msg!("A confidence policy has either a factor or a maximum in bps, not both");
+            return Err(ScopeError::InvalidConfidencePolicy);
+        }
+        if self.max_bps > FULL_BPS {
+            msg!("Maximum confidence {} bps is above 100%", self.max_bps);
+            return Err(ScopeError::InvalidConfidencePolicy);
+        }
+        if self.abs_floor_exp > MAX_ABS_FLOOR_EXP {
+            msg!(
+                "Absolute floor exponent {} is above {MAX_ABS_FLOOR_EXP}",
+                self.abs_floor_exp
+            );
+            return Err(ScopeError::InvalidConfidencePolicy);
+        }
+        Ok(())
+    }
+
+    /// Check the `confidence` interval (or standard deviation) reported with the price.
+    ///
+    /// `default_factor` is the confidence factor of the provider, used when the policy has neither
+    /// a factor nor a maximum in bps.
+    pub fn check(
+        &self,
+        price: u128,
+        price_exp: u32,
+        confidence: u128,
+        confidence_exp: u32,
+        default_factor: u32,
+    ) -> ScopeResult<()> {
+        match self.check_interval(price, price_exp, confidence, confidence_exp, default_factor) {
+            Err(ScopeError::ConfidenceIntervalCheckFailed)
+                if self.get_failure_mode() == ConfidenceFailureMode::Warn =>
+            {
+                msg!(
+                    "Confidence interval check failed (accepted by the entry policy): price {price} exp {price_exp}, confidence {confidence} exp {confidence_exp}"
+                );
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    fn check_interval(
+        &self,
+        price: u128,
+        price_exp: u32,
+        confidence: u128,
+        confidence_exp: u32,
+        default_factor: u32,
+    ) -> ScopeResult<()> {
+        if self.abs_floor_value != 0
+            && cmp_scaled(
+                confidence,
+                confidence_exp,
+                self.abs_floor_value.into(),
+                self.abs_floor_exp.into(),
+            )?
+            .is_le()
+        {
+            return Ok(());
+        }
+
+        if self.max_bps != 0 {
+            // We return an error if price * max_bps <= confidence * FULL_BPS
+            let price_tolerance = price
+                .checked_mul(self.max_bps.into())
+                .ok_or(ScopeError::MathOverflow)?;
+            let confidence_bps = confidence
+                .checked_mul(FULL_BPS.into())
+                .ok_or(ScopeError::MathOverflow)?;
+            if cmp_scaled(price_tolerance, price_exp, confidence_bps, confidence_exp)?.is_le() {
+                return Err(ScopeError::ConfidenceIntervalCheckFailed);
+            }
+            return Ok(());
+        }
+
+        let factor = if self.factor == 0 {
+            default_factor
+        } else {
+            self.factor
+        };
+        check_confidence_interval(price, price_exp, confidence, confidence_exp, factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::consts::ORACLE_CONFIDENCE_FACTOR;
+
+    // 100.00 with a confidence of 1.50 (1.5%)
+    const PRICE: u128 = 10_000;
+    const CONF: u128 = 150;
+    const EXP: u32 = 2;
+
+    fn check(policy: &ConfidencePolicy, confidence: u128) -> ScopeResult<()> {
+        policy.check(PRICE, EXP, confidence, EXP, ORACLE_CONFIDENCE_FACTOR)
+    }
+
+    #[test]
+    fn test_default_policy_uses_provider_factor() {
+        let policy = ConfidencePolicy::default();
+        assert!(check(&policy, CONF).is_ok());
+        assert!(check(&policy, 200).is_err());
+        assert!(policy.check(PRICE, EXP, CONF, EXP, 100).is_err());
+    }
+
+    #[test]
+    fn test_factor() {
+        let policy = ConfidencePolicy {
+            factor: 100,
+            ..Default::default()
+        };
+        assert!(check(&policy, 99).is_ok());
+        assert!(check(&policy, 100).is_err());
+    }
+
+    #[test]
+    fn test_max_bps() {
+        let policy = ConfidencePolicy {
+            max_bps: 150,
+            ..Default::default()
+        };
+        assert!(check(&policy, CONF - 1).is_ok());
+        assert!(check(&policy, CONF).is_err());
+        // Exponents of the price and the confidence may differ
+        assert!(policy.check(PRICE, EXP, 1_490, EXP + 1, 0).is_ok());
+        assert!(policy.check(PRICE, EXP, 1_500, EXP + 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_abs_floor() {
+        // 0.02 is always accepted
+        let policy = ConfidencePolicy {
+            abs_floor_value: 2,
+            abs_floor_exp: 2,
+            max_bps: 1,
+            ..Default::default()
+        };
+        assert!(policy.check(PRICE, EXP, 2, EXP, 0).is_ok());
+        assert!(policy.check(PRICE, EXP, 20, EXP + 1, 0).is_ok());
+        assert!(policy.check(PRICE, EXP, 3, EXP, 0).is_err());
+    }
+
+    #[test]
+    fn test_warn_mode() {
+        let policy = ConfidencePolicy {
+            max_bps: 100,
+            failure_mode: ConfidenceFailureMode::Warn.into(),
+            ..Default::default()
+        };
+        assert!(check(&policy, CONF).is_ok());
+        // Overflows are still errors
+        assert!(policy.check(u128::MAX, 0, CONF, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ConfidencePolicy::default().validate().is_ok());
+        let invalid_policies = [
+            ConfidencePolicy {
+                factor: 50,
+                max_bps: 200,
+                ..Default::default()
+            },
+            ConfidencePolicy {
+                max_bps: FULL_BPS + 1,
+                ..Default::default()
+            },
+            ConfidencePolicy {
+                abs_floor_exp: MAX_ABS_FLOOR_EXP + 1,
+                ..Default::default()
+            },
+            ConfidencePolicy {
+                failure_mode: 2,
+                ..Default::default()
+            },
+        ];
+        for policy in invalid_policies {
+            assert!(policy.validate().is_err(), "{policy:?}");
+        }
+    }
+}
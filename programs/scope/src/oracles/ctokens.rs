@@ -3,13 +3,39 @@ use anchor_lang::{
     solana_program::{clock, program_pack::Pack},
 };
 
-use self::solend::Reserve;
-use crate::{DatedPrice, Price, Result, ScopeResult};
+use self::solend::{Reserve, SLOTS_PER_YEAR};
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS},
+    DatedPrice, Price, Result, ScopeError, ScopeResult,
+};
 
 const DECIMALS: u32 = 15u32;
 
+/// Configuration of a cToken entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CTokenData {
+    /// Maximum growth of the rate accrued at read time, as a yearly rate in bps applied pro rata
+    /// to the slots elapsed since the last update of the reserve. 0 disables the check.
+    ///
+    /// The interest accrued at read time uses the current utilization of the reserve for all the
+    /// elapsed slots, so a utilization spike in the refresh slot would inflate the rate.
+    pub max_rate_growth_bps_per_year: u32,
+}
+
+impl CTokenData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data).map_err(|_| error!(ScopeError::CTokenInvalid))
+    }
+}
+
 // Gives the price of 1 cToken in the collateral token
-pub fn get_price(solend_reserve_account: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    solend_reserve_account: &AccountInfo,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice> {
+    let data = CTokenData::from_generic_data(generic_data)?;
     let mut reserve = Reserve::unpack(&solend_reserve_account.data.borrow()).map_err(|e| {
         msg!(
             "Error unpacking CToken account {}",
@@ -17,17 +43,19 @@ pub fn get_price(solend_reserve_account: &AccountInfo, clock: &Clock) -> Result<
         );
         e
     })?;
+    let reserve_last_update_slot = reserve.last_update.slot;
+    let rate_before_accrual = scaled_rate(&reserve)?;
 
     // Manual refresh of the reserve to ensure the most accurate price
     let (last_updated_slot, unix_timestamp) = if reserve.accrue_interest(clock.slot).is_ok() {
         // We have just refreshed the price so we can use the current slot
-        (clock.slot, u64::try_from(clock.unix_timestamp).unwrap())
+        (clock.slot, clock_unix_timestamp(clock)?)
     } else {
         // This should never happen but on simulations when the current slot is not valid
         // yet we have a default value
         (
             reserve.last_update.slot,
-            u64::try_from(clock.unix_timestamp).unwrap().saturating_sub(
+            clock_unix_timestamp(clock)?.saturating_sub(
                 clock
                     .slot
                     .saturating_sub(reserve.last_update.slot)
@@ -44,6 +72,15 @@ pub fn get_price(solend_reserve_account: &AccountInfo, clock: &Clock) -> Result<
         e
     })?;
 
+    if data.max_rate_growth_bps_per_year != 0 {
+        check_rate_growth(
+            rate_before_accrual,
+            value,
+            clock.slot.saturating_sub(reserve_last_update_slot),
+            data.max_rate_growth_bps_per_year,
+        )?;
+    }
+
     let price = Price {
         value,
         exp: DECIMALS.into(),
@@ -58,6 +95,32 @@ pub fn get_price(solend_reserve_account: &AccountInfo, clock: &Clock) -> Result<
     Ok(dated_price)
 }
 
+pub fn validate_mapping_cfg(generic_data: &[u8; 20]) -> Result<()> {
+    let _ = CTokenData::from_generic_data(generic_data)?;
+    Ok(())
+}
+
+/// Check that the rate accrued over `slots_elapsed` did not grow by more than
+/// `max_growth_bps_per_year` pro rata
+fn check_rate_growth(
+    rate_before: u64,
+    rate_after: u64,
+    slots_elapsed: u64,
+    max_growth_bps_per_year: u32,
+) -> Result<()> {
+    let growth = u128::from(rate_after.saturating_sub(rate_before));
+    let max_growth =
+        u128::from(rate_before) * u128::from(max_growth_bps_per_year) * u128::from(slots_elapsed)
+            / (u128::from(FULL_BPS) * u128::from(SLOTS_PER_YEAR));
+    if growth > max_growth {
+        msg!(
+            "CToken rate grew from {rate_before} to {rate_after} over {slots_elapsed} slots, max allowed growth is {max_growth}"
+        );
+        return err!(ScopeError::CTokenRateGrowthExceeded);
+    }
+    Ok(())
+}
+
 fn scaled_rate(reserve: &Reserve) -> ScopeResult<u64> {
     const FACTOR: u64 = 10u64.pow(DECIMALS);
     let rate = reserve.collateral_exchange_rate()?;
@@ -678,3 +741,120 @@ pub mod solend {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use decimal_wad::decimal::Decimal;
+
+    use super::{
+        solend::{LastUpdate, ReserveCollateral, ReserveConfig, ReserveLiquidity},
+        *,
+    };
+    use crate::oracles::test_utils::{clock, generic_data};
+
+    const LAST_UPDATE_SLOT: u64 = 100;
+    /// A tenth of a year after the last update of the reserve
+    const ELAPSED_SLOTS: u64 = SLOTS_PER_YEAR / 10;
+    /// Rate of 1 collateral token per cToken
+    const RATE_BEFORE: u64 = 10u64.pow(DECIMALS);
+
+    /// Reserve at 50% utilization and a rate of 1 at its last update, whose borrow rate of
+    /// 31.25% per year grows the cToken rate by ~15.9% per year
+    fn reserve_data() -> Vec<u8> {
+        let reserve = Reserve {
+            version: solend::PROGRAM_VERSION,
+            last_update: LastUpdate {
+                slot: LAST_UPDATE_SLOT,
+                stale: false,
+            },
+            liquidity: ReserveLiquidity {
+                available_amount: 1_000_000,
+                borrowed_amount_wads: Decimal::from(1_000_000_u64),
+                ..Default::default()
+            },
+            collateral: ReserveCollateral {
+                mint_total_supply: 2_000_000,
+                ..Default::default()
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: 80,
+                min_borrow_rate: 0,
+                optimal_borrow_rate: 50,
+                max_borrow_rate: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut data = vec![0; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+        data
+    }
+
+    fn get_test_price(max_rate_growth_bps_per_year: u32) -> Result<DatedPrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = reserve_data();
+        let reserve =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+        get_price(
+            &reserve,
+            &clock(LAST_UPDATE_SLOT + ELAPSED_SLOTS, 1_700_000_000),
+            &generic_data(&CTokenData {
+                max_rate_growth_bps_per_year,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_rate_growth_at_cap() {
+        // 10% per year over a tenth of a year
+        let max_growth = RATE_BEFORE / 100;
+        check_rate_growth(RATE_BEFORE, RATE_BEFORE + max_growth, ELAPSED_SLOTS, 1_000).unwrap();
+        // A decreasing or unchanged rate is always accepted
+        check_rate_growth(RATE_BEFORE, RATE_BEFORE, 0, 1_000).unwrap();
+        check_rate_growth(RATE_BEFORE, RATE_BEFORE - 1, ELAPSED_SLOTS, 1_000).unwrap();
+    }
+
+    #[test]
+    fn test_rate_growth_above_cap() {
+        let max_growth = RATE_BEFORE / 100;
+        assert_eq!(
+            check_rate_growth(
+                RATE_BEFORE,
+                RATE_BEFORE + max_growth + 1,
+                ELAPSED_SLOTS,
+                1_000
+            )
+            .unwrap_err(),
+            error!(ScopeError::CTokenRateGrowthExceeded)
+        );
+        // No growth is allowed in the slot of the last update
+        assert_eq!(
+            check_rate_growth(RATE_BEFORE, RATE_BEFORE + 1, 0, 1_000).unwrap_err(),
+            error!(ScopeError::CTokenRateGrowthExceeded)
+        );
+    }
+
+    #[test]
+    fn test_accrued_rate_cap() {
+        let price = get_test_price(2_000).unwrap();
+        assert!(price.price.value > RATE_BEFORE);
+        assert_eq!(price.price.exp, u64::from(DECIMALS));
+        assert_eq!(price.last_updated_slot, LAST_UPDATE_SLOT + ELAPSED_SLOTS);
+
+        assert_eq!(
+            get_test_price(1_000).unwrap_err(),
+            error!(ScopeError::CTokenRateGrowthExceeded)
+        );
+    }
+
+    #[test]
+    fn test_zero_config_disables_cap() {
+        assert_eq!(
+            CTokenData::from_generic_data(&[0; 20]).unwrap(),
+            CTokenData::default()
+        );
+        // Same accrued rate as with a cap above its growth
+        assert_eq!(get_test_price(0).unwrap(), get_test_price(2_000).unwrap());
+    }
+}
@@ -0,0 +1,274 @@
+//! Price of a token redeemable at par at maturity (e.g. a tokenized T-bill), in the redemption
+//! token, discounted by a piecewise constant yearly rate until maturity
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS, SECONDS_PER_DAY, SECONDS_PER_YEAR},
+    DatedPrice, Price, ScopeError,
+};
+
+/// Maximum number of breakpoints that fit in the entry generic data
+pub const MAX_DISCOUNT_BREAKPOINTS: usize = 4;
+
+/// Decimals of the computed prices
+const DISCOUNT_PRICE_EXP: u32 = 12;
+
+/// Yearly discount rate applying up to a number of days to maturity
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiscountBreakpoint {
+    /// Days to maturity up to which the rate applies (from the previous breakpoint), 0 if unused
+    pub days_to_maturity: u16,
+    pub discount_per_year_bps: u16,
+}
+
+/// Configuration of a discount to maturity entry, stored (borsh serialized) in the entry generic
+/// data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiscountToMaturityData {
+    /// Unix timestamp from which the token is worth par
+    pub maturity_timestamp: u32,
+    /// Rates from the closest to the furthest from maturity, the rate of the last used
+    /// breakpoint also applies beyond it. A single breakpoint gives a linear discount to maturity.
+    pub schedule: [DiscountBreakpoint; MAX_DISCOUNT_BREAKPOINTS],
+}
+
+impl DiscountToMaturityData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::DiscountToMaturityInvalid))
+    }
+
+    fn breakpoints(&self) -> impl Iterator<Item = &DiscountBreakpoint> {
+        self.schedule
+            .iter()
+            .take_while(|breakpoint| breakpoint.days_to_maturity != 0)
+    }
+
+    /// Discount accumulated over `seconds_to_maturity`, in bps-seconds per year
+    fn discount_bps_seconds(&self, seconds_to_maturity: u64) -> u128 {
+        let mut discount = 0_u128;
+        let mut segment_start = 0_u64;
+        let mut last_rate_bps = 0_u16;
+        for breakpoint in self.breakpoints() {
+            let segment_end = u64::from(breakpoint.days_to_maturity) * SECONDS_PER_DAY as u64;
+            let segment_seconds = seconds_to_maturity.min(segment_end) - segment_start;
+            discount += u128::from(breakpoint.discount_per_year_bps) * u128::from(segment_seconds);
+            last_rate_bps = breakpoint.discount_per_year_bps;
+            if seconds_to_maturity <= segment_end {
+                return discount;
+            }
+            segment_start = segment_end;
+        }
+        discount + u128::from(last_rate_bps) * u128::from(seconds_to_maturity - segment_start)
+    }
+}
+
+pub fn get_price(clock: &Clock, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+    let data = DiscountToMaturityData::from_generic_data(generic_data)?;
+
+    let seconds_to_maturity =
+        u64::try_from(i64::from(data.maturity_timestamp).saturating_sub(clock.unix_timestamp))
+            .unwrap_or(0);
+    let par = 10_u128.pow(DISCOUNT_PRICE_EXP);
+    let discount = data.discount_bps_seconds(seconds_to_maturity) * par
+        / (u128::from(FULL_BPS) * SECONDS_PER_YEAR as u128);
+    if discount >= par {
+        msg!("Discount to maturity is larger than par ({seconds_to_maturity}s to maturity)");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+
+    Ok(DatedPrice {
+        price: Price {
+            value: u64::try_from(par - discount)
+                .map_err(|_| ScopeError::DiscountToMaturityInvalid)?,
+            exp: DISCOUNT_PRICE_EXP.into(),
+        },
+        last_updated_slot: clock.slot,
+        unix_timestamp: clock_unix_timestamp(clock)?,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a discount to maturity oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = DiscountToMaturityData::from_generic_data(generic_data)?;
+
+    if data.maturity_timestamp == 0 {
+        msg!("Discount to maturity requires a maturity timestamp");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+
+    let used_breakpoints = data.breakpoints().count();
+    if used_breakpoints == 0 {
+        msg!("Discount to maturity requires at least one breakpoint");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+    if data.schedule[used_breakpoints..]
+        .iter()
+        .any(|breakpoint| *breakpoint != DiscountBreakpoint::default())
+    {
+        msg!("Unused discount breakpoints must follow the used ones and be zeroed");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+    if data
+        .breakpoints()
+        .zip(data.breakpoints().skip(1))
+        .any(|(closer, further)| closer.days_to_maturity >= further.days_to_maturity)
+    {
+        msg!("Discount breakpoints must be strictly increasing in days to maturity");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+    if data
+        .breakpoints()
+        .any(|breakpoint| breakpoint.discount_per_year_bps > FULL_BPS)
+    {
+        msg!("Discount rates must not exceed {FULL_BPS} bps per year");
+        return err!(ScopeError::DiscountToMaturityInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATURITY: u32 = 1_800_000_000;
+    const PAR: u64 = 1_000_000_000_000;
+
+    fn generic_data(maturity_timestamp: u32, breakpoints: &[(u16, u16)]) -> [u8; 20] {
+        let mut schedule = [DiscountBreakpoint::default(); MAX_DISCOUNT_BREAKPOINTS];
+        for (i, &(days_to_maturity, discount_per_year_bps)) in breakpoints.iter().enumerate() {
+            schedule[i] = DiscountBreakpoint {
+                days_to_maturity,
+                discount_per_year_bps,
+            };
+        }
+        let serialized = DiscountToMaturityData {
+            maturity_timestamp,
+            schedule,
+        }
+        .try_to_vec()
+        .unwrap();
+        serialized.try_into().unwrap()
+    }
+
+    fn generic_data_with_rate(discount_per_year_bps: u16) -> [u8; 20] {
+        generic_data(MATURITY, &[(365, discount_per_year_bps)])
+    }
+
+    fn price_at(days_to_maturity: i64, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+        let clock = Clock {
+            slot: 100,
+            unix_timestamp: i64::from(MATURITY) - days_to_maturity * SECONDS_PER_DAY,
+            ..Default::default()
+        };
+        get_price(&clock, generic_data)
+    }
+
+    fn price_value(days_to_maturity: i64, generic_data: &[u8; 20]) -> u64 {
+        let price = price_at(days_to_maturity, generic_data).unwrap().price;
+        assert_eq!(price.exp, u64::from(DISCOUNT_PRICE_EXP));
+        price.value
+    }
+
+    #[test]
+    fn test_before_maturity() {
+        // 5% per year
+        let generic_data = generic_data_with_rate(500);
+        assert_eq!(price_value(365, &generic_data), PAR / 100 * 95);
+        assert_eq!(price_value(73, &generic_data), PAR / 100 * 99);
+        // The rate of the last breakpoint applies beyond it
+        assert_eq!(price_value(730, &generic_data), PAR / 100 * 90);
+
+        let price = price_at(365, &generic_data).unwrap();
+        assert_eq!(price.last_updated_slot, 100);
+        assert_eq!(
+            price.unix_timestamp,
+            u64::from(MATURITY) - 365 * SECONDS_PER_DAY as u64
+        );
+    }
+
+    #[test]
+    fn test_piecewise_schedule() {
+        // 4% per year over the last 30 days, 5% before
+        let generic_data = generic_data(MATURITY, &[(30, 400), (365, 500)]);
+        let linear_4pct = generic_data_with_rate(400);
+        assert_eq!(
+            price_value(30, &generic_data),
+            price_value(30, &linear_4pct)
+        );
+        assert_eq!(
+            price_value(10, &generic_data),
+            price_value(10, &linear_4pct)
+        );
+
+        // (30 days at 4% + 30 days at 5%) / 365 = 0.739726...%
+        assert_eq!(price_value(60, &generic_data), 992_602_739_727);
+    }
+
+    #[test]
+    fn test_at_and_after_maturity() {
+        let generic_data = generic_data(MATURITY, &[(30, 400), (365, 500)]);
+        assert_eq!(price_value(0, &generic_data), PAR);
+        assert_eq!(price_value(-1, &generic_data), PAR);
+        assert_eq!(price_value(-1000, &generic_data), PAR);
+
+        // One second before maturity
+        let clock = Clock {
+            unix_timestamp: i64::from(MATURITY) - 1,
+            ..Default::default()
+        };
+        let price = get_price(&clock, &generic_data).unwrap().price;
+        // Discount of 4% over 1s of a year: 1268.39, rounded down
+        assert_eq!(price.value, PAR - 1268);
+    }
+
+    #[test]
+    fn test_discount_larger_than_par() {
+        let generic_data = generic_data_with_rate(FULL_BPS);
+        // 1 day left at 100% per year: 1 / 365 = 0.0027397260273..., rounded up
+        assert_eq!(price_value(364, &generic_data), 2_739_726_028);
+        assert_eq!(
+            price_at(365, &generic_data).unwrap_err(),
+            error!(ScopeError::DiscountToMaturityInvalid)
+        );
+        assert_eq!(
+            price_at(400, &generic_data).unwrap_err(),
+            error!(ScopeError::DiscountToMaturityInvalid)
+        );
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        validate_mapping_cfg(&None, &generic_data(MATURITY, &[(365, 500)])).unwrap();
+        validate_mapping_cfg(
+            &None,
+            &generic_data(MATURITY, &[(30, 400), (90, 450), (180, 500), (365, 550)]),
+        )
+        .unwrap();
+
+        for invalid in [
+            generic_data(0, &[(365, 500)]),
+            generic_data(MATURITY, &[]),
+            // Breakpoint after an unused one
+            generic_data(MATURITY, &[(30, 400), (0, 0), (365, 500)]),
+            // Rate of an unused breakpoint
+            generic_data(MATURITY, &[(30, 400), (0, 500)]),
+            generic_data(MATURITY, &[(365, 500), (30, 400)]),
+            generic_data(MATURITY, &[(30, 400), (30, 500)]),
+            generic_data(MATURITY, &[(365, FULL_BPS + 1)]),
+        ] {
+            assert_eq!(
+                validate_mapping_cfg(&None, &invalid).unwrap_err(),
+                error!(ScopeError::DiscountToMaturityInvalid)
+            );
+        }
+    }
+}
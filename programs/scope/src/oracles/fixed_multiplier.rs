@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::price_impl::PriceU128, DatedPrice, OraclePrices, Price, ScopeError, MAX_ENTRIES_U16,
+};
+
+/// Configuration of a fixed multiplier entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedMultiplierData {
+    /// Entry giving the price to be multiplied
+    pub source_entry: u16,
+    /// Constant the source price is multiplied by, e.g. 1 to publish a wrapped token at exactly
+    /// the price of its canonical counterpart
+    pub multiplier: Price,
+}
+
+impl FixedMultiplierData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::FixedMultiplierInvalid))
+    }
+}
+
+/// Get the price of the source entry multiplied by the configured multiplier.
+///
+/// The returned slot and timestamp are the ones of the source entry.
+pub fn get_price(oracle_prices: &OraclePrices, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+    let data = FixedMultiplierData::from_generic_data(generic_data)?;
+
+    let source_price = oracle_prices
+        .prices
+        .get(usize::from(data.source_entry))
+        .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+    if source_price.price.value == 0 {
+        msg!(
+            "Fixed multiplier source entry {} has no price",
+            data.source_entry
+        );
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let price: Price = PriceU128::from(source_price.price)
+        .mul_price(data.multiplier)?
+        .reduce_exp(18)
+        .try_into()?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: source_price.last_updated_slot,
+        unix_timestamp: source_price.unix_timestamp,
+        confidence_bps: source_price.confidence_bps,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a fixed multiplier oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = FixedMultiplierData::from_generic_data(generic_data)?;
+
+    if data.source_entry >= MAX_ENTRIES_U16 {
+        msg!("Fixed multiplier source entry must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::FixedMultiplierInvalid);
+    }
+    if data.multiplier.value == 0 || data.multiplier.exp > 18 {
+        msg!("Invalid fixed multiplier {:?}", data.multiplier);
+        return err!(ScopeError::FixedMultiplierInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracles::test_utils::{check_missing_source, check_validate_mapping_cfg, price};
+
+    const SOURCE: u16 = 3;
+
+    fn generic_data(source_entry: u16, multiplier: Price) -> [u8; 20] {
+        crate::oracles::test_utils::generic_data(&FixedMultiplierData {
+            source_entry,
+            multiplier,
+        })
+    }
+
+    fn oracle_prices(source_price: Price) -> Box<OraclePrices> {
+        crate::oracles::test_utils::oracle_prices([(
+            SOURCE,
+            DatedPrice {
+                price: source_price,
+                last_updated_slot: 42,
+                unix_timestamp: 1_700_000_000,
+                confidence_bps: 25,
+                ..Default::default()
+            },
+        )])
+    }
+
+    fn multiplied(source_price: Price, multiplier: Price) -> Result<Price> {
+        get_price(
+            &oracle_prices(source_price),
+            &generic_data(SOURCE, multiplier),
+        )
+        .map(|dated_price| dated_price.price)
+    }
+
+    #[test]
+    fn test_multiplier_application() {
+        // A multiplier of 1 publishes the source price unchanged
+        assert_eq!(
+            multiplied(price(123_456, 6), price(1, 0)).unwrap(),
+            price(123_456, 6)
+        );
+        // 2.0 * 1.5
+        assert_eq!(
+            multiplied(price(2_000_000, 6), price(15, 1)).unwrap(),
+            price(30_000_000, 7)
+        );
+        // 2.0 * 0.25
+        assert_eq!(
+            multiplied(price(2_000_000, 6), price(25, 2)).unwrap(),
+            price(50_000_000, 8)
+        );
+    }
+
+    #[test]
+    fn test_source_dates_and_confidence_are_kept() {
+        let dated_price = get_price(
+            &oracle_prices(price(2_000_000, 6)),
+            &generic_data(SOURCE, price(15, 1)),
+        )
+        .unwrap();
+
+        assert_eq!(dated_price.last_updated_slot, 42);
+        assert_eq!(dated_price.unix_timestamp, 1_700_000_000);
+        assert_eq!(dated_price.confidence_bps, 25);
+    }
+
+    #[test]
+    fn test_exponent_handling() {
+        // 1.5 * 2.0 with 12 + 10 decimals is reduced to 18 decimals
+        assert_eq!(
+            multiplied(price(1_500_000_000_000, 12), price(20_000_000_000, 10)).unwrap(),
+            price(3_000_000_000_000_000_000, 18)
+        );
+        // Decimals beyond the 18th are truncated
+        assert_eq!(
+            multiplied(price(1_999_999_999_999, 12), price(1_000_000_001, 9)).unwrap(),
+            price(2_000_000_001_998_999_999, 18)
+        );
+        // A product exceeding the u64 range drops its least significant decimals
+        assert_eq!(
+            multiplied(price(u64::MAX, 6), price(10, 0)).unwrap(),
+            price(u64::MAX, 5)
+        );
+        // Unless there are none to drop
+        assert_eq!(
+            multiplied(price(u64::MAX, 0), price(10, 0)).unwrap_err(),
+            error!(ScopeError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn test_missing_source() {
+        check_missing_source(
+            &oracle_prices(price(1_000_000, 6)),
+            SOURCE,
+            |oracle_prices, source| get_price(oracle_prices, &generic_data(source, price(1, 0))),
+        );
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        check_validate_mapping_cfg(
+            validate_mapping_cfg,
+            &[
+                generic_data(SOURCE, price(15, 1)),
+                generic_data(SOURCE, price(1, 18)),
+            ],
+            &[
+                generic_data(MAX_ENTRIES_U16, price(1, 0)),
+                generic_data(SOURCE, price(0, 0)),
+                generic_data(SOURCE, price(1, 19)),
+            ],
+            ScopeError::FixedMultiplierInvalid,
+        );
+    }
+}
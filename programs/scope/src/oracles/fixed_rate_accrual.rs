@@ -0,0 +1,257 @@
+//! Price growing at a fixed yearly rate from a start price, for interest-bearing wrappers whose
+//! accrual formula is known but that no oracle publishes
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS, price_impl::PriceU128, SECONDS_PER_YEAR},
+    DatedPrice, Price, ScopeError,
+};
+
+/// Configuration of a fixed rate accrual entry, stored (borsh serialized) in the entry generic
+/// data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedRateAccrualData {
+    /// Price at `start_timestamp`, with `start_price_exp` decimals
+    pub start_price_value: u64,
+    pub start_price_exp: u8,
+    /// Unix timestamp from which the price accrues
+    pub start_timestamp: i64,
+    /// Simple (non compounded) yearly rate
+    pub apr_bps: u16,
+}
+
+impl FixedRateAccrualData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::FixedRateAccrualInvalid))
+    }
+}
+
+/// Get the start price accrued at the configured rate until now, the start price before the start
+/// timestamp
+pub fn get_price(clock: &Clock, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+    let data = FixedRateAccrualData::from_generic_data(generic_data)?;
+
+    let elapsed_seconds =
+        u64::try_from(clock.unix_timestamp.saturating_sub(data.start_timestamp)).unwrap_or(0);
+    let year_bps = u128::from(FULL_BPS) * SECONDS_PER_YEAR as u128;
+    // The accrued amount cannot overflow (10^4 * 2^64), the final product can with a start price
+    // close to `u64::MAX` after very long periods
+    let accrued_value = (year_bps + u128::from(data.apr_bps) * u128::from(elapsed_seconds))
+        .checked_mul(u128::from(data.start_price_value))
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?
+        / year_bps;
+    let price: Price = PriceU128 {
+        value: accrued_value,
+        exp: data.start_price_exp.into(),
+    }
+    .try_into()?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: clock.slot,
+        unix_timestamp: clock_unix_timestamp(clock)?,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a fixed rate accrual oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = FixedRateAccrualData::from_generic_data(generic_data)?;
+
+    if data.start_price_value == 0 || data.start_price_exp > 18 {
+        msg!(
+            "Invalid fixed rate accrual start price: {} with {} decimals",
+            data.start_price_value,
+            data.start_price_exp
+        );
+        return err!(ScopeError::FixedRateAccrualInvalid);
+    }
+    if data.start_timestamp <= 0 {
+        msg!("Fixed rate accrual requires a start timestamp");
+        return err!(ScopeError::FixedRateAccrualInvalid);
+    }
+    if data.apr_bps > FULL_BPS {
+        msg!("Fixed rate accrual APR must not exceed {FULL_BPS} bps");
+        return err!(ScopeError::FixedRateAccrualInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: i64 = 1_700_000_000;
+
+    fn generic_data(start_price_value: u64, start_price_exp: u8, apr_bps: u16) -> [u8; 20] {
+        let mut generic_data = [0; 20];
+        let serialized = FixedRateAccrualData {
+            start_price_value,
+            start_price_exp,
+            start_timestamp: START,
+            apr_bps,
+        }
+        .try_to_vec()
+        .unwrap();
+        generic_data[..serialized.len()].copy_from_slice(&serialized);
+        generic_data
+    }
+
+    fn price_at(elapsed_seconds: i64, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+        let clock = Clock {
+            slot: 100,
+            unix_timestamp: START + elapsed_seconds,
+            ..Default::default()
+        };
+        get_price(&clock, generic_data)
+    }
+
+    #[test]
+    fn test_accrual() {
+        // 1.0 at 5% per year
+        let generic_data = generic_data(1_000_000, 6, 500);
+        let price = |elapsed_seconds| price_at(elapsed_seconds, &generic_data).unwrap().price;
+
+        assert_eq!(
+            price(0),
+            Price {
+                value: 1_000_000,
+                exp: 6
+            }
+        );
+        assert_eq!(
+            price(SECONDS_PER_YEAR / 2),
+            Price {
+                value: 1_025_000,
+                exp: 6
+            }
+        );
+        assert_eq!(
+            price(SECONDS_PER_YEAR),
+            Price {
+                value: 1_050_000,
+                exp: 6
+            }
+        );
+        // Not compounded
+        assert_eq!(
+            price(10 * SECONDS_PER_YEAR),
+            Price {
+                value: 1_500_000,
+                exp: 6
+            }
+        );
+        // Rounded down
+        assert_eq!(
+            price(1),
+            Price {
+                value: 1_000_000,
+                exp: 6
+            }
+        );
+        assert_eq!(
+            price(631),
+            Price {
+                value: 1_000_001,
+                exp: 6
+            }
+        );
+
+        let dated_price = price_at(SECONDS_PER_YEAR, &generic_data).unwrap();
+        assert_eq!(dated_price.last_updated_slot, 100);
+        assert_eq!(
+            dated_price.unix_timestamp,
+            u64::try_from(START + SECONDS_PER_YEAR).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_before_start() {
+        let generic_data = generic_data(1_000_000, 6, 500);
+        assert_eq!(
+            price_at(-SECONDS_PER_YEAR, &generic_data).unwrap().price,
+            Price {
+                value: 1_000_000,
+                exp: 6
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_rate() {
+        let generic_data = generic_data(1_234, 3, 0);
+        assert_eq!(
+            price_at(100 * SECONDS_PER_YEAR, &generic_data)
+                .unwrap()
+                .price,
+            Price {
+                value: 1_234,
+                exp: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_overflow_bounds() {
+        // Values above `u64::MAX` drop decimals
+        let max_start_price = generic_data(u64::MAX, 2, FULL_BPS);
+        assert_eq!(
+            price_at(SECONDS_PER_YEAR, &max_start_price).unwrap().price,
+            Price {
+                value: u64::MAX / 5,
+                exp: 1
+            }
+        );
+
+        // No decimal left to drop
+        let max_start_price = generic_data(u64::MAX, 0, FULL_BPS);
+        assert!(price_at(SECONDS_PER_YEAR, &max_start_price).is_err());
+
+        // Intermediate product above `u128::MAX`
+        assert_eq!(
+            price_at(i64::MAX - START, &max_start_price).unwrap_err(),
+            error!(ScopeError::MathOverflow)
+        );
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        validate_mapping_cfg(&None, &generic_data(1_000_000, 6, 500)).unwrap();
+        validate_mapping_cfg(&None, &generic_data(1, 18, FULL_BPS)).unwrap();
+
+        for invalid in [
+            generic_data(0, 6, 500),
+            generic_data(1_000_000, 19, 500),
+            generic_data(1_000_000, 6, FULL_BPS + 1),
+        ] {
+            assert_eq!(
+                validate_mapping_cfg(&None, &invalid).unwrap_err(),
+                error!(ScopeError::FixedRateAccrualInvalid)
+            );
+        }
+
+        let mut no_start = [0; 20];
+        no_start[..19].copy_from_slice(
+            &FixedRateAccrualData {
+                start_price_value: 1_000_000,
+                start_price_exp: 6,
+                start_timestamp: 0,
+                apr_bps: 500,
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        assert_eq!(
+            validate_mapping_cfg(&None, &no_start).unwrap_err(),
+            error!(ScopeError::FixedRateAccrualInvalid)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Layouts of the entry generic data of the oracle types configured through it, exported as IDL
+//! constants (named after the oracle type) so that clients can encode the configuration of an
+//! entry without out-of-band documentation.
+//!
+//! A layout lists the fields of the generic data in order, as `name: type` separated by commas.
+//! The fields are borsh serialized, the generic data (20 bytes) is zero padded after them. Types:
+//! - `u8`, `u16`, `u32`, `u64`, `i64`, `u128`, `bool`
+//! - `option<T>`: 1 byte tag (0 for none), followed by `T` if set
+//! - `[T; N]`: `N` consecutive `T`, `vec<T; N>`: u32 length followed by at most `N` `T`
+//! - `{field: T, ...}`: nested fields
+//! - `enum(A, B, ...)`: u8 index of the variant
+//!
+//! The oracle types without a layout have no generic data configuration (all zeros).
+
+use anchor_lang::prelude::*;
+
+use crate::oracles::OracleType;
+
+// Note: the IDL generator does not expand macros, the constants have to be written out
+
+/// The confidence factor of the pyth entries is part of their confidence policy, `reserved` must
+/// be 0
+#[constant]
+pub const GENERIC_DATA_LAYOUT_PYTH: &str = "reserved: u32, min_publishers: u8";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_SWITCHBOARD_ON_DEMAND: &str =
+    "feed_hash_prefix: [u8; 12], min_samples: u8, max_staleness_slots: u32, max_variance_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_C_TOKEN: &str = "max_rate_growth_bps_per_year: u32";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_SPL_STAKE: &str = "max_fee_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_MSOL_STAKE: &str = "mode: enum(DelayedUnstake, InstantExit)";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_SCOPE_TWAP: &str = "ema_type: enum(Ema1h, Ema24h, Ema7d)";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_ORCA_WHIRLPOOL: &str = "min_liquidity: u128";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_RAYDIUM_AMM_V3: &str = "min_liquidity: u128";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_RAYDIUM_AMM_V3_TWAP: &str = "window_seconds: u32";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_METEORA_DLMM: &str = "min_reserve_x: u64, min_reserve_y: u64";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_RAYDIUM_CP: &str = "ref_price_entry: u16, max_deviation_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_FIXED_PRICE: &str = "value: u64, exp: u64";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_JITO_RESTAKING: &str = "max_fee_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_STAKING_RATE: &str =
+    "provider: enum(SplStakePool, Marinade, JitoRestaking), max_fee_bps: u16, haircut_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_CAPPED_FLOORED: &str =
+    "source_entry: u16, cap_entry: option<u16>, floor_entry: option<u16>";
+/// The high bit of a source entry inverts its price (1/price)
+#[constant]
+pub const GENERIC_DATA_LAYOUT_MEDIAN_OF: &str = "source_entries: vec<u16; 5>";
+/// The high bit of a source entry inverts its price (1/price)
+#[constant]
+pub const GENERIC_DATA_LAYOUT_MOST_RECENT_OF: &str = "source_entries: [u16; 4], max_divergence_bps: [u16; 4], sources_max_age_s: u16, min_fresh_sources: u8, prefer_healthy_sources: bool";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_DISCOUNT_TO_MATURITY: &str =
+    "maturity_timestamp: u32, schedule: [{days_to_maturity: u16, discount_per_year_bps: u16}; 4]";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_FIXED_RATE_ACCRUAL: &str =
+    "start_price_value: u64, start_price_exp: u8, start_timestamp: i64, apr_bps: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_FIXED_MULTIPLIER: &str =
+    "source_entry: u16, multiplier: {value: u64, exp: u64}";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_ANNUALIZED_GROWTH_RATE: &str =
+    "lookback_days: u16, max_lookback_days: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_ORCA_WHIRLPOOL_POSITION: &str =
+    "price_a_entry: u16, price_b_entry: u16";
+#[constant]
+pub const GENERIC_DATA_LAYOUT_GENERIC_VAULT_SHARE: &str = "total_assets_offset: u16, total_assets_width: u8, total_shares_offset: u16, total_shares_width: u8, asset_entry: u16, asset_decimals: u8, share_decimals: u8, discriminator: [u8; 8]";
+
+/// Map the oracle types to the layout of their generic data
+macro_rules! generic_data_layouts {
+    ($($layout:ident => $($price_type:ident)|+;)*) => {
+        /// Layout of the generic data of the entries of type `price_type`, `None` if the type has
+        /// no generic data configuration
+        pub fn get_generic_data_layout(price_type: OracleType) -> Option<&'static str> {
+            match price_type {
+                $($(OracleType::$price_type)|+ => Some($layout),)*
+                _ => None,
+            }
+        }
+
+        #[cfg(test)]
+        const ALL_LAYOUTS: &[(&str, &str)] = &[$((stringify!($layout), $layout)),*];
+    };
+}
+
+generic_data_layouts! {
+    GENERIC_DATA_LAYOUT_PYTH => Pyth;
+    GENERIC_DATA_LAYOUT_SWITCHBOARD_ON_DEMAND => SwitchboardOnDemand;
+    GENERIC_DATA_LAYOUT_C_TOKEN => CToken;
+    GENERIC_DATA_LAYOUT_SPL_STAKE => SplStake;
+    GENERIC_DATA_LAYOUT_MSOL_STAKE => MsolStake;
+    GENERIC_DATA_LAYOUT_SCOPE_TWAP => ScopeTwap;
+    GENERIC_DATA_LAYOUT_ORCA_WHIRLPOOL => OrcaWhirlpoolAtoB | OrcaWhirlpoolBtoA;
+    GENERIC_DATA_LAYOUT_RAYDIUM_AMM_V3 => RaydiumAmmV3AtoB | RaydiumAmmV3BtoA;
+    GENERIC_DATA_LAYOUT_RAYDIUM_AMM_V3_TWAP => RaydiumAmmV3TwapAtoB | RaydiumAmmV3TwapBtoA;
+    GENERIC_DATA_LAYOUT_METEORA_DLMM => MeteoraDlmmAtoB | MeteoraDlmmBtoA;
+    GENERIC_DATA_LAYOUT_RAYDIUM_CP => RaydiumCpAtoB | RaydiumCpBtoA;
+    GENERIC_DATA_LAYOUT_FIXED_PRICE => FixedPrice;
+    GENERIC_DATA_LAYOUT_JITO_RESTAKING => JitoRestaking;
+    GENERIC_DATA_LAYOUT_STAKING_RATE => StakingRate;
+    GENERIC_DATA_LAYOUT_CAPPED_FLOORED => CappedFloored;
+    GENERIC_DATA_LAYOUT_MEDIAN_OF => MedianOf;
+    GENERIC_DATA_LAYOUT_MOST_RECENT_OF => MostRecentOf;
+    GENERIC_DATA_LAYOUT_DISCOUNT_TO_MATURITY => DiscountToMaturity;
+    GENERIC_DATA_LAYOUT_FIXED_RATE_ACCRUAL => FixedRateAccrual;
+    GENERIC_DATA_LAYOUT_FIXED_MULTIPLIER => FixedMultiplier;
+    GENERIC_DATA_LAYOUT_ANNUALIZED_GROWTH_RATE => AnnualizedGrowthRate;
+    GENERIC_DATA_LAYOUT_ORCA_WHIRLPOOL_POSITION => OrcaWhirlpoolPosition;
+    GENERIC_DATA_LAYOUT_GENERIC_VAULT_SHARE => GenericVaultShare;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracles::{
+        capped_floored::CappedFlooredData, fixed_rate_accrual::FixedRateAccrualData,
+        generic_vault_share::GenericVaultShareData, most_recent_of::MostRecentOfData,
+        switchboard_on_demand::SwitchboardOnDemandData,
+    };
+
+    /// Split `list` at the commas that are not nested in a type
+    fn split_top_level(list: &str) -> Vec<&str> {
+        let mut parts = vec![];
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in list.char_indices() {
+            match c {
+                '<' | '[' | '{' | '(' => depth += 1,
+                '>' | ']' | '}' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(&list[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(&list[start..]);
+        parts
+    }
+
+    fn item_and_count(inner: &str) -> (&str, usize) {
+        let (item, count) = inner.rsplit_once(';').expect("missing count");
+        (item, count.trim().parse().expect("invalid count"))
+    }
+
+    /// Maximum serialized size of a type of the layout notation
+    fn max_size(ty: &str) -> usize {
+        let ty = ty.trim();
+        match ty {
+            "u8" | "bool" => 1,
+            "u16" => 2,
+            "u32" => 4,
+            "u64" | "i64" => 8,
+            "u128" => 16,
+            _ if ty.starts_with("enum(") && ty.ends_with(')') => 1,
+            _ => {
+                if let Some(inner) = ty.strip_prefix("option<").and_then(|t| t.strip_suffix('>')) {
+                    1 + max_size(inner)
+                } else if let Some(inner) =
+                    ty.strip_prefix("vec<").and_then(|t| t.strip_suffix('>'))
+                {
+                    let (item, count) = item_and_count(inner);
+                    4 + max_size(item) * count
+                } else if let Some(inner) = ty.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+                    let (item, count) = item_and_count(inner);
+                    max_size(item) * count
+                } else if let Some(fields) = ty.strip_prefix('{').and_then(|t| t.strip_suffix('}'))
+                {
+                    fields_max_size(fields)
+                } else {
+                    panic!("Unknown type {ty}")
+                }
+            }
+        }
+    }
+
+    fn fields_max_size(fields: &str) -> usize {
+        split_top_level(fields)
+            .into_iter()
+            .map(|field| {
+                let (name, ty) = field.split_once(':').expect("missing field type");
+                assert!(!name.trim().is_empty(), "missing field name in {fields}");
+                max_size(ty)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_layouts_fit_generic_data() {
+        for (name, layout) in ALL_LAYOUTS {
+            assert!(fields_max_size(layout) <= 20, "{name}: {layout}");
+        }
+    }
+
+    #[test]
+    fn test_layouts_match_serialized_data() {
+        let capped_floored = CappedFlooredData {
+            source_entry: 1,
+            cap_entry: Some(2),
+            floor_entry: Some(3),
+        };
+        let most_recent_of = MostRecentOfData {
+            source_entries: [1; 4],
+            max_divergence_bps: [100; 4],
+            sources_max_age_s: 60,
+            min_fresh_sources: 1,
+            prefer_healthy_sources: false,
+        };
+        let fixed_rate_accrual = FixedRateAccrualData {
+            start_price_value: 1,
+            start_price_exp: 0,
+            start_timestamp: 0,
+            apr_bps: 500,
+        };
+        let generic_vault_share = GenericVaultShareData {
+            total_assets_offset: 8,
+            total_assets_width: 8,
+            total_shares_offset: 16,
+            total_shares_width: 8,
+            asset_entry: 0,
+            asset_decimals: 6,
+            share_decimals: 6,
+            discriminator: [0; 8],
+        };
+        for (price_type, serialized) in [
+            (
+                OracleType::CappedFloored,
+                capped_floored.try_to_vec().unwrap(),
+            ),
+            (
+                OracleType::MostRecentOf,
+                most_recent_of.try_to_vec().unwrap(),
+            ),
+            (
+                OracleType::FixedRateAccrual,
+                fixed_rate_accrual.try_to_vec().unwrap(),
+            ),
+            (
+                OracleType::GenericVaultShare,
+                generic_vault_share.try_to_vec().unwrap(),
+            ),
+            (
+                OracleType::SwitchboardOnDemand,
+                SwitchboardOnDemandData::default().try_to_vec().unwrap(),
+            ),
+        ] {
+            let layout = get_generic_data_layout(price_type).unwrap();
+            assert_eq!(fields_max_size(layout), serialized.len(), "{price_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_types_without_configuration() {
+        assert!(get_generic_data_layout(OracleType::SwitchboardV2).is_none());
+        assert!(get_generic_data_layout(OracleType::JupiterLpFetch).is_none());
+    }
+}
@@ -0,0 +1,254 @@
+//! Price of the shares of a vault exposing its total assets and total shares in its state (in the
+//! style of ERC-4626 vaults), configured through the generic data so that new vault programs can
+//! be onboarded without a program upgrade
+
+use anchor_lang::prelude::*;
+use decimal_wad::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+};
+
+use crate::{
+    utils::math::ten_pow, DatedPrice, OraclePrices, Price, ScopeError, ScopeResult, MAX_ENTRIES_U16,
+};
+
+/// Maximum width of the integer fields read from the vault account (u128)
+const MAX_FIELD_WIDTH: u8 = 16;
+
+/// Maximum number of decimals of the asset and share tokens
+const MAX_DECIMALS: u8 = 18;
+
+/// Configuration of a generic vault share entry, stored (borsh serialized) in the entry generic
+/// data. The mapped account is the vault state.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenericVaultShareData {
+    /// Offset in the vault account data of the total assets (little endian unsigned integer)
+    pub total_assets_offset: u16,
+    /// Width in bytes of the total assets, at most 16
+    pub total_assets_width: u8,
+    /// Offset in the vault account data of the total shares (little endian unsigned integer)
+    pub total_shares_offset: u16,
+    /// Width in bytes of the total shares, at most 16
+    pub total_shares_width: u8,
+    /// Entry giving the price of the asset of the vault
+    pub asset_entry: u16,
+    pub asset_decimals: u8,
+    pub share_decimals: u8,
+    /// Expected first 8 bytes of the vault account (e.g. its anchor discriminator), not checked
+    /// if zero
+    pub discriminator: [u8; 8],
+}
+
+impl GenericVaultShareData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::GenericVaultShareInvalid))
+    }
+
+    fn total_assets(&self, vault_data: &[u8]) -> ScopeResult<u128> {
+        read_le_uint(
+            vault_data,
+            self.total_assets_offset,
+            self.total_assets_width,
+        )
+    }
+
+    fn total_shares(&self, vault_data: &[u8]) -> ScopeResult<u128> {
+        read_le_uint(
+            vault_data,
+            self.total_shares_offset,
+            self.total_shares_width,
+        )
+    }
+
+    fn check_discriminator(&self, vault_data: &[u8]) -> ScopeResult<()> {
+        if self.discriminator == [0; 8] {
+            return Ok(());
+        }
+        if vault_data.get(..8) != Some(self.discriminator.as_slice()) {
+            msg!(
+                "Vault account discriminator {:?} does not match the expected {:?}",
+                vault_data.get(..8),
+                self.discriminator
+            );
+            return Err(ScopeError::InvalidAccountDiscriminator);
+        }
+        Ok(())
+    }
+}
+
+/// Read a little endian unsigned integer of `width` bytes at `offset` of `data`
+fn read_le_uint(data: &[u8], offset: u16, width: u8) -> ScopeResult<u128> {
+    if width == 0 || width > MAX_FIELD_WIDTH {
+        msg!("Invalid vault field width {width}");
+        return Err(ScopeError::GenericVaultShareInvalid);
+    }
+    let start = usize::from(offset);
+    let bytes = data.get(start..start + usize::from(width)).ok_or_else(|| {
+        msg!(
+            "Vault field at offset {offset} ({width} bytes) is out of the account ({} bytes)",
+            data.len()
+        );
+        ScopeError::GenericVaultShareInvalid
+    })?;
+    let mut le_bytes = [0_u8; 16];
+    le_bytes[..bytes.len()].copy_from_slice(bytes);
+    Ok(u128::from_le_bytes(le_bytes))
+}
+
+/// Get the price of one share of the vault: the assets per share times the price of the asset.
+///
+/// The returned slot and timestamp are the ones of the asset entry.
+pub fn get_price(
+    vault: &AccountInfo,
+    oracle_prices: &OraclePrices,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice> {
+    let data = GenericVaultShareData::from_generic_data(generic_data)?;
+
+    let (total_assets, total_shares) = {
+        let vault_data = vault.try_borrow_data()?;
+        data.check_discriminator(&vault_data)?;
+        (
+            data.total_assets(&vault_data)?,
+            data.total_shares(&vault_data)?,
+        )
+    };
+    if total_shares == 0 {
+        msg!("Vault {} has no shares", vault.key);
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let asset_price = oracle_prices
+        .prices
+        .get(usize::from(data.asset_entry))
+        .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+    if asset_price.price.value == 0 {
+        msg!("Vault asset entry {} has no price", data.asset_entry);
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let price = share_price(&data, total_assets, total_shares, asset_price.price)?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: asset_price.last_updated_slot,
+        unix_timestamp: asset_price.unix_timestamp,
+        confidence_bps: asset_price.confidence_bps,
+        ..Default::default()
+    })
+}
+
+fn share_price(
+    data: &GenericVaultShareData,
+    total_assets: u128,
+    total_shares: u128,
+    asset_price: Price,
+) -> ScopeResult<Price> {
+    // (total_assets / 10^asset_decimals) / (total_shares / 10^share_decimals) * asset_price
+    let price_dec = Decimal::from(total_assets)
+        .try_mul(Decimal::from(ten_pow(data.share_decimals)?))?
+        .try_div(Decimal::from(total_shares))?
+        .try_div(Decimal::from(ten_pow(data.asset_decimals)?))?
+        .try_mul(asset_price.try_to_decimal()?)?;
+    Price::try_from_decimal(price_dec)
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let Some(account) = account else {
+        msg!("The vault account is expected with a generic vault share oracle");
+        return err!(ScopeError::GenericVaultShareInvalid);
+    };
+
+    let data = GenericVaultShareData::from_generic_data(generic_data)?;
+
+    if data.asset_entry >= MAX_ENTRIES_U16 {
+        msg!("Vault asset entry must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::GenericVaultShareInvalid);
+    }
+    if data.asset_decimals > MAX_DECIMALS || data.share_decimals > MAX_DECIMALS {
+        msg!(
+            "Vault asset decimals {} and share decimals {} must be at most {MAX_DECIMALS}",
+            data.asset_decimals,
+            data.share_decimals
+        );
+        return err!(ScopeError::GenericVaultShareInvalid);
+    }
+
+    let vault_data = account.try_borrow_data()?;
+    data.check_discriminator(&vault_data)?;
+    let total_assets = data.total_assets(&vault_data)?;
+    let total_shares = data.total_shares(&vault_data)?;
+    msg!(
+        "Vault {} total assets: {total_assets}, total shares: {total_shares}",
+        account.key
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> GenericVaultShareData {
+        GenericVaultShareData {
+            total_assets_offset: 8,
+            total_assets_width: 8,
+            total_shares_offset: 16,
+            total_shares_width: 16,
+            asset_entry: 0,
+            asset_decimals: 6,
+            share_decimals: 9,
+            discriminator: [1; 8],
+        }
+    }
+
+    #[test]
+    fn test_generic_data_fits() {
+        let generic_data = data().try_to_vec().unwrap();
+        assert!(generic_data.len() <= 20);
+        let mut padded = [0; 20];
+        padded[..generic_data.len()].copy_from_slice(&generic_data);
+        assert_eq!(
+            GenericVaultShareData::from_generic_data(&padded).unwrap(),
+            data()
+        );
+    }
+
+    #[test]
+    fn test_read_fields() {
+        let mut vault_data = vec![1_u8; 8];
+        vault_data.extend_from_slice(&1_500_000_u64.to_le_bytes());
+        vault_data.extend_from_slice(&1_000_000_000_u128.to_le_bytes());
+
+        let data = data();
+        assert!(data.check_discriminator(&vault_data).is_ok());
+        assert_eq!(data.total_assets(&vault_data).unwrap(), 1_500_000);
+        assert_eq!(data.total_shares(&vault_data).unwrap(), 1_000_000_000);
+
+        assert!(data.check_discriminator(&[2; 40]).is_err());
+        assert!(data.total_shares(&vault_data[..31]).is_err());
+        assert!(read_le_uint(&vault_data, 0, 0).is_err());
+        assert!(read_le_uint(&vault_data, 0, 17).is_err());
+        assert!(read_le_uint(&vault_data, u16::MAX, 16).is_err());
+    }
+
+    #[test]
+    fn test_share_price() {
+        // 1.5 assets (6 decimals) for 1 share (9 decimals), the asset being worth 2.00
+        let price = share_price(
+            &data(),
+            1_500_000,
+            1_000_000_000,
+            Price { value: 200, exp: 2 },
+        )
+        .unwrap();
+        assert_eq!(
+            price.try_to_decimal().unwrap(),
+            Decimal::from(3_u64),
+            "{price:?}"
+        );
+    }
+}
@@ -1,26 +1,92 @@
 use anchor_lang::prelude::*;
 use decimal_wad::decimal::Decimal;
 
+use super::staking_rate::{StakingRateSample, DEFAULT_MAX_FEE_BPS};
 use crate::{
-    utils::{consts::FULL_BPS, math, zero_copy_deserialize},
-    DatedPrice, Price,
+    utils::{clock_unix_timestamp, consts::FULL_BPS, math, unpack_mint, zero_copy_deserialize},
+    DatedPrice, Price, ScopeError,
 };
 
-/// Jito restaking price oracle gives the amount of JitoSOL per VRT token on withdrawal
-/// WARNING: Assumes both tokens have the same decimals (9)
-pub fn get_price(jito_vault: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+/// Configuration of a Jito restaking entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JitoRestakingData {
+    /// Maximum deposit fee and withdrawal fee (program fee included) of the vault, 0 for
+    /// [`DEFAULT_MAX_FEE_BPS`]
+    pub max_fee_bps: u16,
+}
+
+impl JitoRestakingData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::JitoRestakingInvalid))
+    }
+
+    pub fn max_fee_bps(&self) -> u16 {
+        if self.max_fee_bps == 0 {
+            DEFAULT_MAX_FEE_BPS
+        } else {
+            self.max_fee_bps
+        }
+    }
+}
+
+/// Jito restaking price oracle gives the amount of supported token (e.g. JitoSOL) per VRT token on
+/// withdrawal, net of the withdrawal fees
+///
+/// Required extra accounts:
+/// - Mint of the VRT token
+/// - Mint of the supported token
+pub fn get_price<'a, 'b>(
+    jito_vault: &AccountInfo,
+    clock: &Clock,
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    let vrt_mint_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let supported_mint_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let data = JitoRestakingData::from_generic_data(generic_data)?;
     let vault = zero_copy_deserialize::<jito_vault_core::Vault>(jito_vault)?;
 
+    require_keys_eq!(
+        vault.vrt_mint,
+        vrt_mint_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        vault.supported_mint,
+        supported_mint_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    let vrt_decimals = unpack_mint(vrt_mint_acc)?.decimals;
+    let supported_decimals = unpack_mint(supported_mint_acc)?.decimals;
+
+    check_fees(&vault, data.max_fee_bps())?;
+
+    let lamport_price = get_price_int(&vault);
     let dated_price = DatedPrice {
-        price: get_price_int(&vault),
+        price: math::price_of_lamports_to_price_of_tokens(
+            lamport_price,
+            vrt_decimals.into(),
+            supported_decimals.into(),
+        )?,
         last_updated_slot: clock.slot,
-        unix_timestamp: u64::try_from(clock.unix_timestamp).unwrap(),
+        unix_timestamp: clock_unix_timestamp(clock)?,
         ..Default::default()
     };
 
     Ok(dated_price)
 }
 
+/// Amount of supported token lamports per VRT lamport on withdrawal
 fn get_price_int(vault: &jito_vault_core::Vault) -> Price {
     let vrt_supply = vault.vrt_supply.get();
     if vrt_supply == 0 {
@@ -29,14 +95,61 @@ fn get_price_int(vault: &jito_vault_core::Vault) -> Price {
 
     let total_deposits = vault.tokens_deposited.get();
 
-    let total_fee_bps = vault.program_fee_bps.get() + vault.withdrawal_fee_bps.get();
-
-    let withdrawable_amount = math::mul_bps(total_deposits, FULL_BPS.saturating_sub(total_fee_bps));
+    let withdrawable_amount = math::mul_bps(
+        total_deposits,
+        FULL_BPS.saturating_sub(withdrawal_fee_bps(vault)),
+    );
 
     let price_dec = Decimal::from(withdrawable_amount) / vrt_supply;
     price_dec.into()
 }
 
+/// Fee taken on withdrawal: the program fee on top of the vault withdrawal fee, the highest of the
+/// current and the next epoch one so that a scheduled fee increase is accounted for right away
+fn withdrawal_fee_bps(vault: &jito_vault_core::Vault) -> u16 {
+    let vault_fee_bps = vault
+        .withdrawal_fee_bps
+        .get()
+        .max(vault.next_withdrawal_fee_bps.get());
+    vault.program_fee_bps.get().saturating_add(vault_fee_bps)
+}
+
+fn check_fees(vault: &jito_vault_core::Vault, max_fee_bps: u16) -> Result<()> {
+    require_gte!(
+        max_fee_bps,
+        withdrawal_fee_bps(vault),
+        ScopeError::StakeFeeTooHigh
+    );
+    require_gte!(
+        max_fee_bps,
+        vault.deposit_fee_bps.get(),
+        ScopeError::StakeFeeTooHigh
+    );
+    Ok(())
+}
+
+/// Staking rate of a restaking vault, see [`super::staking_rate`]
+///
+/// The program fee is taken on withdrawal on top of the vault withdrawal fee.
+/// WARNING: Assumes both tokens have the same decimals (9)
+pub fn get_staking_rate_sample(jito_vault: &AccountInfo) -> Result<StakingRateSample> {
+    let vault = zero_copy_deserialize::<jito_vault_core::Vault>(jito_vault)?;
+
+    let vrt_supply = vault.vrt_supply.get();
+    let rate = if vrt_supply == 0 {
+        Price::default()
+    } else {
+        (Decimal::from(vault.tokens_deposited.get()) / vrt_supply).into()
+    };
+
+    Ok(StakingRateSample {
+        rate,
+        withdrawal_fee_bps: withdrawal_fee_bps(&vault),
+        deposit_fee_bps: vault.deposit_fee_bps.get(),
+        last_update_epoch: None,
+    })
+}
+
 pub fn validate_account(vault: &Option<AccountInfo>) -> Result<()> {
     let Some(vault) = vault else {
         msg!("No vault account provided");
@@ -46,6 +159,15 @@ pub fn validate_account(vault: &Option<AccountInfo>) -> Result<()> {
     Ok(())
 }
 
+pub fn validate_mapping_cfg(vault: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let data = JitoRestakingData::from_generic_data(generic_data)?;
+    if data.max_fee_bps > FULL_BPS {
+        msg!("Invalid Jito restaking max fee {} bps", data.max_fee_bps);
+        return err!(ScopeError::JitoRestakingInvalid);
+    }
+    validate_account(vault)
+}
+
 pub mod jito_vault_core {
     use anchor_lang::Discriminator;
     use bytemuck::{Pod, Zeroable};
@@ -243,3 +365,97 @@ pub mod jito_vault_core {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{jito_vault_core::Vault, *};
+    use crate::oracles::test_utils::generic_data;
+
+    /// Vault with 1.2 supported tokens deposited per VRT
+    fn vault(withdrawal_fee_bps: u16, next_withdrawal_fee_bps: u16, program_fee_bps: u16) -> Vault {
+        let mut vault = Vault::default();
+        vault.vrt_supply.set(1_000_000_000);
+        vault.tokens_deposited.set(1_200_000_000);
+        vault.withdrawal_fee_bps.set(withdrawal_fee_bps);
+        vault.next_withdrawal_fee_bps.set(next_withdrawal_fee_bps);
+        vault.program_fee_bps.set(program_fee_bps);
+        vault
+    }
+
+    fn assert_price_eq(price: Price, expected: f64) {
+        assert!((f64::from(price) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_vault_ratio() {
+        assert_price_eq(get_price_int(&vault(0, 0, 0)), 1.2);
+
+        let mut empty_vault = vault(0, 0, 0);
+        empty_vault.vrt_supply.set(0);
+        assert_eq!(get_price_int(&empty_vault), Price::default());
+    }
+
+    #[test]
+    fn test_withdrawal_fees() {
+        // Program fee on top of the vault fee
+        let current_fee_vault = vault(10, 0, 10);
+        assert_eq!(withdrawal_fee_bps(&current_fee_vault), 20);
+        assert_price_eq(get_price_int(&current_fee_vault), 1.1976);
+
+        // Scheduled fee increase
+        let next_fee_vault = vault(10, 30, 10);
+        assert_eq!(withdrawal_fee_bps(&next_fee_vault), 40);
+        assert_price_eq(get_price_int(&next_fee_vault), 1.1952);
+
+        // Scheduled fee decrease
+        assert_eq!(withdrawal_fee_bps(&vault(30, 10, 10)), 40);
+    }
+
+    #[test]
+    fn test_max_fees() {
+        let default_max_fee_bps = JitoRestakingData::default().max_fee_bps();
+        assert_eq!(default_max_fee_bps, DEFAULT_MAX_FEE_BPS);
+
+        check_fees(&vault(20, 30, 20), default_max_fee_bps).unwrap();
+        assert_eq!(
+            check_fees(&vault(20, 31, 20), default_max_fee_bps).unwrap_err(),
+            error!(ScopeError::StakeFeeTooHigh)
+        );
+        check_fees(&vault(20, 31, 20), 51).unwrap();
+
+        let mut deposit_fee_vault = vault(0, 0, 0);
+        deposit_fee_vault
+            .deposit_fee_bps
+            .set(default_max_fee_bps + 1);
+        assert_eq!(
+            check_fees(&deposit_fee_vault, default_max_fee_bps).unwrap_err(),
+            error!(ScopeError::StakeFeeTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_generic_data() {
+        assert_eq!(
+            JitoRestakingData::from_generic_data(&generic_data(&JitoRestakingData {
+                max_fee_bps: 100
+            }))
+            .unwrap()
+            .max_fee_bps(),
+            100
+        );
+        assert_eq!(
+            validate_mapping_cfg(
+                &None,
+                &generic_data(&JitoRestakingData {
+                    max_fee_bps: FULL_BPS + 1
+                })
+            )
+            .unwrap_err(),
+            error!(ScopeError::JitoRestakingInvalid)
+        );
+        assert_eq!(
+            validate_mapping_cfg(&None, &generic_data(&JitoRestakingData::default())).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+    }
+}
@@ -1,20 +1,26 @@
 use std::ops::Deref;
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::spl_token::state::Mint;
-use decimal_wad::decimal::Decimal;
 pub use jup_perp_itf as perpetuals;
 pub use perpetuals::utils::{check_mint_pk, get_mint_pk};
 use perpetuals::Custody;
-use solana_program::program_pack::Pack;
 
 use crate::{
     scope_chain::get_price_from_chain,
-    utils::{account_deserialize, math::ten_pow},
+    utils::{
+        account_deserialize, clock_unix_timestamp, math::ten_pow, price_impl::PriceU128,
+        unpack_mint,
+    },
     DatedPrice, MintToScopeChain, MintsToScopeChains, OraclePrices, Price, Result, ScopeError,
+    ScopeResult,
 };
 pub const POOL_VALUE_SCALE_DECIMALS: u8 = 6;
 
+/// Compute budget of an AUM recomputation, independent of the number of custodies
+const RECOMPUTE_BASE_CU_BUDGET: u32 = 30_000;
+/// Compute budget of an AUM recomputation, for each custody of the pool
+const RECOMPUTE_CUSTODY_CU_BUDGET: u32 = 18_000;
+
 /// Gives the price of 1 JLP token in USD
 ///
 /// Uses the AUM of the pool and the supply of the JLP token to compute the price
@@ -36,10 +42,7 @@ where
     check_mint_pk(jup_pool_pk, mint_acc.key, jup_pool.lp_token_bump)
         .map_err(|_| ScopeError::UnexpectedAccount)?;
 
-    let mint = {
-        let mint_borrow = mint_acc.data.borrow();
-        Mint::unpack(&mint_borrow)
-    }?;
+    let mint = unpack_mint(mint_acc)?;
 
     let lp_value = jup_pool.aum_usd;
     let lp_token_supply = mint.supply;
@@ -48,12 +51,12 @@ where
     // This allows to just divide the two values to get the price
     require_eq!(mint.decimals, POOL_VALUE_SCALE_DECIMALS);
 
-    let price_dec = Decimal::from(lp_value) / lp_token_supply;
+    let price = PriceU128::from_ratio(lp_value, lp_token_supply)?.try_into()?;
     let dated_price = DatedPrice {
-        price: price_dec.into(),
+        price,
         // TODO: find a way to get the last update time
         last_updated_slot: clock.slot,
-        unix_timestamp: u64::try_from(clock.unix_timestamp).unwrap(),
+        unix_timestamp: clock_unix_timestamp(clock)?,
         ..Default::default()
     };
 
@@ -69,6 +72,19 @@ pub fn validate_jlp_pool(account: &Option<AccountInfo>) -> Result<()> {
     Ok(())
 }
 
+/// Estimate the compute budget of a refresh recomputing the AUM of the given pool, which
+/// grows with the number of custodies of the pool
+pub fn get_recompute_cu_budget(account: &Option<AccountInfo>) -> Result<u32> {
+    let Some(account) = account else {
+        msg!("No jlp pool account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let jlp_pool: perpetuals::Pool = account_deserialize(account)?;
+    let num_custodies = u32::try_from(jlp_pool.custodies.len())
+        .map_err(|_| ScopeError::OutOfRangeIntegralConversion)?;
+    Ok(RECOMPUTE_BASE_CU_BUDGET + RECOMPUTE_CUSTODY_CU_BUDGET * num_custodies)
+}
+
 /// Get the price of 1 JLP token in USD
 ///
 /// This function recompute the AUM of the pool from the custodies and the oracles
@@ -140,7 +156,8 @@ where
             *oracle_acc.key,
             ScopeError::UnexpectedAccount
         );
-        let dated_price = super::pyth::get_price(oracle_acc, clock)?;
+        // Custody oracles are not scope entries, the default confidence factor and policy apply
+        let dated_price = super::pyth::get_price(oracle_acc, clock, &[0; 20], &Default::default())?;
         compute_custody_aum(&custody, &dated_price)
     };
 
@@ -227,7 +244,7 @@ where
     );
 
     require_eq!(
-        u64::try_from(entry_id).unwrap(),
+        u64::try_from(entry_id).map_err(|_| ScopeError::OutOfRangeIntegralConversion)?,
         mint_to_price_map.seed_id,
         ScopeError::UnexpectedAccount
     );
@@ -257,8 +274,13 @@ where
         );
         let dated_price =
             get_price_from_chain(oracle_prices, &mint_to_chain.scope_chain).map_err(|e| {
-                msg!("Error while getting price from scope chain: {:?}", e);
-                ScopeError::BadScopeChainOrPrices
+                msg!(
+                    "Error while getting price of mint {} from scope chain {:?}: {:?}",
+                    mint_to_chain.mint,
+                    mint_to_chain.scope_chain,
+                    e
+                );
+                ScopeError::from(e)
             })?;
         compute_custody_aum(&custody, &dated_price)
     };
@@ -286,7 +308,7 @@ fn compute_price_from_custodies_and_prices<T>(
     custodies_and_prices_iter: impl Iterator<Item = T>,
     aum_and_age_getter: impl Fn(T, &Clock) -> Result<CustodyAumResult>,
 ) -> Result<DatedPrice> {
-    let mut oldest_price_ts: u64 = clock.unix_timestamp.try_into().unwrap();
+    let mut oldest_price_ts: u64 = clock_unix_timestamp(clock)?;
     let mut oldest_price_slot: u64 = clock.slot;
 
     let lp_value: u128 = {
@@ -311,10 +333,10 @@ fn compute_price_from_custodies_and_prices<T>(
     };
 
     // 5. Compute price
-    let price_dec = Decimal::from(lp_value) / lp_token_supply;
+    let price = PriceU128::from_ratio(lp_value, lp_token_supply)?.try_into()?;
 
     let dated_price = DatedPrice {
-        price: price_dec.into(),
+        price,
         last_updated_slot: oldest_price_slot,
         unix_timestamp: oldest_price_ts,
         ..Default::default()
@@ -343,8 +365,7 @@ fn check_accounts(
 }
 
 fn get_lp_token_supply(mint_acc: &AccountInfo) -> Result<u64> {
-    let mint_borrow = mint_acc.data.borrow();
-    let mint = Mint::unpack(&mint_borrow)?;
+    let mint = unpack_mint(mint_acc)?;
 
     // This is a sanity check to make sure the mint is configured as expected
     // This allows to just divide aum by the supply to get the price
@@ -367,7 +388,7 @@ fn compute_custody_aum(custody: &Custody, dated_price: &DatedPrice) -> Result<Cu
 
     let (token_amount_usd, trader_short_profits) = if custody.is_stable {
         (
-            asset_amount_to_usd(&price, custody.assets.owned, custody.decimals),
+            asset_amount_to_usd(&price, custody.assets.owned, custody.decimals)?,
             0,
         )
     } else {
@@ -378,9 +399,9 @@ fn compute_custody_aum(custody: &Custody, dated_price: &DatedPrice) -> Result<Cu
             let (global_pnl_delta, trader_has_profit) = custody
                 .get_global_short_pnl(
                     price
-                        .to_scaled_value(POOL_VALUE_SCALE_DECIMALS)
+                        .to_scaled_value(POOL_VALUE_SCALE_DECIMALS)?
                         .try_into()
-                        .unwrap(),
+                        .map_err(|_| ScopeError::IntegerOverflow)?,
                 )
                 .ok_or_else(|| error!(ScopeError::MathOverflow))?;
 
@@ -400,7 +421,7 @@ fn compute_custody_aum(custody: &Custody, dated_price: &DatedPrice) -> Result<Cu
             .owned
             .checked_sub(custody.assets.locked)
             .ok_or_else(|| error!(ScopeError::MathOverflow))?;
-        let net_assets_usd = asset_amount_to_usd(&price, net_assets_token, custody.decimals);
+        let net_assets_usd = asset_amount_to_usd(&price, net_assets_token, custody.decimals)?;
         pool_amount_usd += net_assets_usd;
 
         (pool_amount_usd, trader_short_profits)
@@ -415,21 +436,24 @@ fn compute_custody_aum(custody: &Custody, dated_price: &DatedPrice) -> Result<Cu
 }
 
 /// Return the value of the number of tokens in USD scaled by `POOL_VALUE_SCALE_DECIMALS` decimals
-fn asset_amount_to_usd(price: &Price, token_amount: u64, token_decimals: u8) -> u128 {
+fn asset_amount_to_usd(price: &Price, token_amount: u64, token_decimals: u8) -> ScopeResult<u128> {
     let price_value: u128 = price.value.into();
     let token_amount: u128 = token_amount.into();
-    let token_decimals: u8 = token_decimals;
-    let price_decimals: u8 = price.exp.try_into().unwrap();
+    let price_decimals: u8 = price.exp.try_into()?;
+    let total_decimals = price_decimals
+        .checked_add(token_decimals)
+        .ok_or(ScopeError::IntegerOverflow)?;
+    let nom = price_value
+        .checked_mul(token_amount)
+        .ok_or(ScopeError::IntegerOverflow)?;
 
     // price * 10^(-price_decimals) * token_amount * 10^(-token_decimals) * 10^POOL_VALUE_SCALE_DECIMALS
-    if price_decimals + token_decimals > POOL_VALUE_SCALE_DECIMALS {
-        let diff = price_decimals + token_decimals - POOL_VALUE_SCALE_DECIMALS;
-        let nom = price_value * token_amount;
-        let denom = ten_pow(diff);
-
-        nom / denom
+    if total_decimals > POOL_VALUE_SCALE_DECIMALS {
+        let diff = total_decimals - POOL_VALUE_SCALE_DECIMALS;
+        Ok(nom / ten_pow(diff)?)
     } else {
-        let diff = POOL_VALUE_SCALE_DECIMALS - (price_decimals + token_decimals);
-        price_value * token_amount * ten_pow(diff)
+        let diff = POOL_VALUE_SCALE_DECIMALS - total_decimals;
+        nom.checked_mul(ten_pow(diff)?)
+            .ok_or(ScopeError::IntegerOverflow)
     }
 }
@@ -1,7 +1,11 @@
 use std::ops::Deref;
 
 use anchor_lang::{prelude::*, Result};
-use decimal_wad::{decimal::Decimal, rate::U128};
+use decimal_wad::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+    rate::U128,
+};
 use kamino::{
     clmm::{orca_clmm::OrcaClmm, Clmm},
     operations::vault_operations::common::underlying_unit,
@@ -134,7 +138,10 @@ where
         None,
         clock.slot,
     )
-    .map_err(|_| ScopeError::KTokenUnderlyingPriceNotValid)?;
+    .map_err(|e| {
+        msg!("Error getting kToken underlying prices: {:?}", e);
+        ScopeError::KTokenUnderlyingPriceNotValid
+    })?;
 
     let holdings = holdings(&strategy_account_ref, clmm.as_ref(), &token_prices)?;
 
@@ -142,7 +149,7 @@ where
         holdings.total_sum,
         strategy_account_ref.shares_issued,
         strategy_account_ref.shares_mint_decimals,
-    );
+    )?;
 
     // Get the least-recently updated component price from both scope chains
     let (last_updated_slot, unix_timestamp) = get_component_px_last_update(
@@ -168,7 +175,10 @@ pub(super) fn get_clmm<'a, 'info>(
     position: &'a AccountInfo<'info>,
     strategy: &WhirlpoolStrategy,
 ) -> ScopeResult<Box<dyn Clmm + 'a>> {
-    let dex = DEX::try_from(strategy.strategy_dex).unwrap();
+    let dex = DEX::try_from(strategy.strategy_dex).map_err(|_| {
+        msg!("Unknown strategy DEX {}", strategy.strategy_dex);
+        ScopeError::KTokenUnderlyingPriceNotValid
+    })?;
     let clmm: Box<dyn Clmm> = match dex {
         DEX::Orca => {
             let pool = account_deserialize::<OrcaWhirlpool>(pool)
@@ -222,19 +232,46 @@ fn get_component_px_last_update(
     let collateral_info_a = collateral_infos.infos[token_a.to_usize()];
     let collateral_info_b = collateral_infos.infos[token_b.to_usize()];
     let token_a_chain: yvaults::utils::scope::ScopeConversionChain =
-        collateral_info_a
-            .try_into()
-            .map_err(|_| ScopeError::BadScopeChainOrPrices)?;
+        collateral_info_a.try_into().map_err(|_| {
+            msg!(
+                "Invalid scope chain for collateral {}",
+                strategy.token_a_collateral_id
+            );
+            ScopeError::BadScopeChainOrPrices
+        })?;
     let token_b_chain: yvaults::utils::scope::ScopeConversionChain =
-        collateral_info_b
-            .try_into()
-            .map_err(|_| ScopeError::BadScopeChainOrPrices)?;
-
-    let price_chain = token_a_chain
-        .iter()
-        .chain(token_b_chain.iter())
-        .map(|&token_id| scope_prices.prices[usize::from(token_id)])
-        .collect::<Vec<yvaults::scope::DatedPrice>>();
+        collateral_info_b.try_into().map_err(|_| {
+            msg!(
+                "Invalid scope chain for collateral {}",
+                strategy.token_b_collateral_id
+            );
+            ScopeError::BadScopeChainOrPrices
+        })?;
+
+    let mut price_chain = Vec::new();
+    for (collateral_id, chain) in [
+        (strategy.token_a_collateral_id, &token_a_chain),
+        (strategy.token_b_collateral_id, &token_b_chain),
+    ] {
+        for (hop, &token_id) in chain.iter().enumerate() {
+            let price = scope_prices
+                .prices
+                .get(usize::from(token_id))
+                .ok_or_else(|| {
+                    msg!(
+                        "Scope chain of collateral {collateral_id} has a missing entry {token_id} at hop {hop}"
+                    );
+                    ScopeError::ScopeChainMissingEntry
+                })?;
+            if price.price.value == 0 {
+                msg!(
+                    "Scope chain of collateral {collateral_id} has a zero price for entry {token_id} at hop {hop}"
+                );
+                return Err(ScopeError::ScopeChainZeroPrice);
+            }
+            price_chain.push(*price);
+        }
+    }
 
     let (last_updated_slot, unix_timestamp): (u64, u64) =
         price_chain
@@ -328,15 +365,19 @@ fn get_price_per_full_share(
     total_holdings_value_scaled: U128,
     shares_issued: u64,
     shares_decimals: u64,
-) -> Price {
+) -> ScopeResult<Price> {
     if shares_issued == 0 {
         // Assume price is 0 without shares issued
-        Price { value: 0, exp: 1 }
+        Ok(Price { value: 0, exp: 1 })
     } else {
+        let holdings_value = Decimal::from(total_holdings_value_scaled.as_u128());
+        let shares_value = u128::from(SCALE_FACTOR)
+            .checked_mul(u128::from(shares_issued))
+            .ok_or(ScopeError::IntegerOverflow)?;
         let price_decimal = Decimal::from(underlying_unit(shares_decimals))
-            * total_holdings_value_scaled
-            / (u128::from(SCALE_FACTOR) * u128::from(shares_issued));
-        (price_decimal).into()
+            .try_mul(holdings_value)?
+            .try_div(Decimal::from(shares_value))?;
+        Price::try_from_decimal(price_decimal)
     }
 }
 
@@ -347,24 +388,18 @@ pub(super) mod price_utils {
 
     // Helper
 
-    fn pow(base: u64, exp: u64) -> U128 {
-        U128::from(base).pow(U128::from(exp))
-    }
-
-    fn abs_diff(a: i32, b: i32) -> u32 {
-        if a > b {
-            a.checked_sub(b).unwrap().try_into().unwrap()
-        } else {
-            b.checked_sub(a).unwrap().try_into().unwrap()
-        }
+    fn pow(base: u64, exp: u64) -> Result<U128> {
+        U128::from(base)
+            .checked_pow(U128::from(exp))
+            .ok_or_else(|| error!(ScopeError::IntegerOverflow))
     }
 
     fn decimals_factor(decimals_a: u64, decimals_b: u64) -> Result<(U128, u64)> {
         let decimals_a = i32::try_from(decimals_a).map_err(|_e| ScopeError::IntegerOverflow)?;
         let decimals_b = i32::try_from(decimals_b).map_err(|_e| ScopeError::IntegerOverflow)?;
 
-        let diff = abs_diff(decimals_a, decimals_b);
-        let factor = U128::from(10_u64.pow(diff));
+        let diff = decimals_a.abs_diff(decimals_b);
+        let factor = pow(10, diff.into())?;
         Ok((factor, u64::from(diff)))
     }
 
@@ -384,9 +419,11 @@ pub(super) mod price_utils {
         let price_a_dec = Decimal::from(a);
         let price_b_dec = Decimal::from(b);
 
-        let price_a_to_b_dec = price_a_dec / price_b_dec;
+        let price_a_to_b_dec = price_a_dec
+            .try_div(price_b_dec)
+            .map_err(|_| ScopeError::MathOverflow)?;
 
-        let price_a_to_b: crate::Price = price_a_to_b_dec.into();
+        let price_a_to_b = crate::Price::try_from_decimal(price_a_to_b_dec)?;
 
         Ok(yvaults::utils::price::Price {
             value: price_a_to_b.value,
@@ -409,20 +446,30 @@ pub(super) mod price_utils {
         let (decimals_factor, decimals_diff) = decimals_factor(decimals_a, decimals_b)?;
         let px = U128::from(price.value);
         let (scaled_price, final_exp) = if decimals_b > decimals_a {
-            (px.checked_mul(decimals_factor).unwrap(), price.exp)
+            (
+                px.checked_mul(decimals_factor)
+                    .ok_or(ScopeError::IntegerOverflow)?,
+                price.exp,
+            )
         } else {
             // If we divide by 10 ^ (decimals_a - decimals_b) here we lose precision
             // So instead we lift the price even more (by the diff) and assume a bigger exp
-            (px, price.exp.checked_add(decimals_diff).unwrap())
+            (
+                px,
+                price
+                    .exp
+                    .checked_add(decimals_diff)
+                    .ok_or(ScopeError::IntegerOverflow)?,
+            )
         };
 
-        let two_factor = pow(2, 64);
+        let two_factor = pow(2, 64)?;
         let x = scaled_price
             .integer_sqrt()
             .checked_mul(two_factor)
             .ok_or(ScopeError::IntegerOverflow)?;
 
-        let sqrt_factor = pow(10, final_exp).integer_sqrt();
+        let sqrt_factor = pow(10, final_exp)?.integer_sqrt();
 
         Ok(x.checked_div(sqrt_factor)
             .ok_or(ScopeError::IntegerOverflow)?
@@ -11,13 +11,14 @@ use yvaults::{
 
 use super::ktokens::price_utils;
 use crate::{
-    utils::{
-        math::{price_of_lamports_to_price_of_tokens, u64_div_to_price},
-        zero_copy_deserialize,
-    },
+    utils::{clock_unix_timestamp, price_impl::PriceU128, unpack_mint, zero_copy_deserialize},
     DatedPrice, Price, ScopeError, ScopeResult,
 };
 
+/// Maximum exponent of a number of tokens per kToken, a larger one comes from inconsistent decimals
+/// and would give a plausible-looking but wrong ratio
+pub const MAX_TOKEN_X_PER_SHARE_EXP: u64 = 24;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenTypes {
     TokenA,
@@ -30,6 +31,9 @@ pub enum TokenTypes {
 /// Underlying asset is the sum of invested, uninvested and fees of either token_a or token_b
 /// Reward tokens are included if equal to token_a or token_b
 ///
+/// The decimals stored in the strategy are checked against the shares and token mints, expected
+/// as the last extra accounts.
+///
 /// The kToken price timestamp is current time
 pub fn get_token_x_per_share<'a, 'b>(
     k_account: &AccountInfo,
@@ -67,6 +71,14 @@ where
         .next()
         .ok_or(ScopeError::AccountsAndTokenMismatch)?;
 
+    let shares_mint_account_info = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let token_mint_account_info = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
     let account_check = |account: &AccountInfo, expected, name| {
         let pk = account.key();
         if pk != expected {
@@ -104,6 +116,27 @@ where
         strategy_account_ref.scope_prices,
         "scope_prices",
     )?;
+    account_check(
+        shares_mint_account_info,
+        strategy_account_ref.shares_mint,
+        "shares_mint",
+    )?;
+    let (token_mint, token_decimals) = match token {
+        TokenTypes::TokenA => (
+            strategy_account_ref.token_a_mint,
+            strategy_account_ref.token_a_mint_decimals,
+        ),
+        TokenTypes::TokenB => (
+            strategy_account_ref.token_b_mint,
+            strategy_account_ref.token_b_mint_decimals,
+        ),
+    };
+    account_check(token_mint_account_info, token_mint, "token_mint")?;
+
+    // Check the decimals of the strategy against the mints
+    let share_decimals = strategy_account_ref.shares_mint_decimals;
+    check_mint_decimals(shares_mint_account_info, share_decimals)?;
+    check_mint_decimals(token_mint_account_info, token_decimals)?;
 
     // Deserialize accounts
     let collateral_infos_ref =
@@ -137,27 +170,24 @@ where
 
     // Get the least-recently updated component price from both scope chains
     let last_updated_slot = clock.slot;
-    let unix_timestamp = u64::try_from(clock.unix_timestamp).expect("Unix timestamp negative");
+    let unix_timestamp = clock_unix_timestamp(clock)?;
 
     let price = if num_shares == 0 {
         // Assume price is 0 without shares issued
         Price { value: 0, exp: 1 }
     } else {
-        let price_lamport_to_lamport = u64_div_to_price(num_token_x, num_shares);
-
         // Final price need to be adjusted by the number of decimals of the kToken and the token X
-        let share_decimals = strategy_account_ref.shares_mint_decimals;
-        let token_decimals = match token {
-            TokenTypes::TokenA => strategy_account_ref.token_a_mint_decimals,
-            TokenTypes::TokenB => strategy_account_ref.token_b_mint_decimals,
-        };
-
-        price_of_lamports_to_price_of_tokens(
-            price_lamport_to_lamport,
-            share_decimals,
-            token_decimals,
-        )
+        PriceU128::from_ratio(num_token_x.into(), num_shares)?
+            .lamports_to_tokens(share_decimals, token_decimals)?
+            .try_into()?
     };
+    if price.exp > MAX_TOKEN_X_PER_SHARE_EXP {
+        msg!(
+            "Ktoken token per share: exponent {} is above {MAX_TOKEN_X_PER_SHARE_EXP} (share decimals {share_decimals}, token decimals {token_decimals})",
+            price.exp
+        );
+        return Err(ScopeError::KTokenDecimalsMismatch);
+    }
 
     Ok(DatedPrice {
         price,
@@ -167,6 +197,18 @@ where
     })
 }
 
+fn check_mint_decimals(mint_account: &AccountInfo, expected_decimals: u64) -> ScopeResult<()> {
+    let decimals = unpack_mint(mint_account)?.decimals;
+    if u64::from(decimals) != expected_decimals {
+        msg!(
+            "Ktoken token per share: mint {} has {decimals} decimals, the strategy expects {expected_decimals}",
+            mint_account.key
+        );
+        return Err(ScopeError::KTokenDecimalsMismatch);
+    }
+    Ok(())
+}
+
 /// Returns amount of token x in the strategy
 /// Use a sqrt price derived from price_a and price_b, not from the pool as it cannot be considered reliable
 pub fn holdings_of_token_x(
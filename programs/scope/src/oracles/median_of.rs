@@ -0,0 +1,258 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+
+use crate::{
+    scope_chain::{hop_entry, is_divide_hop},
+    DatedPrice, OraclePrices, Price, ScopeError, MAX_ENTRIES_U16,
+};
+
+/// Minimum number of sources for the median to be meaningful
+pub const MIN_MEDIAN_SOURCES: usize = 3;
+/// Maximum number of sources that fit in the entry generic data
+pub const MAX_MEDIAN_SOURCES: usize = 5;
+
+/// Configuration of a median entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MedianOfData {
+    /// Entries the median is computed from.
+    ///
+    /// A source published in the opposite direction is inverted (1/price) when the high bit of its
+    /// entry is set, as a dividing hop of a scope chain (see [`crate::scope_chain::divide_hop`]).
+    pub source_entries: Vec<u16>,
+}
+
+impl MedianOfData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data).map_err(|_| error!(ScopeError::MedianOfInvalid))
+    }
+
+    /// Entries of the sources, without their direction
+    pub fn source_entry_ids(&self) -> impl Iterator<Item = u16> + '_ {
+        self.source_entries.iter().map(|&source| hop_entry(source))
+    }
+}
+
+/// Get the median price of the source entries.
+///
+/// With an even number of sources the two middle prices are averaged.
+/// The returned slot and timestamp are the oldest among the prices the median is computed from.
+pub fn get_price(oracle_prices: &OraclePrices, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+    let data = MedianOfData::from_generic_data(generic_data)?;
+
+    let mut prices = data
+        .source_entries
+        .iter()
+        .map(|&source| {
+            let entry = hop_entry(source);
+            let mut dated_price = *oracle_prices
+                .prices
+                .get(usize::from(entry))
+                .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+            if dated_price.price.value == 0 {
+                msg!("Median source entry {entry} has no price");
+                return err!(ScopeError::PriceNotValid);
+            }
+            if is_divide_hop(source) {
+                dated_price.price = dated_price.price.inverse()?;
+            }
+            Ok((Decimal::from(dated_price.price), dated_price))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if prices.len() < MIN_MEDIAN_SOURCES {
+        return err!(ScopeError::MedianOfInvalid);
+    }
+
+    prices.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mid = prices.len() / 2;
+    let median_inputs = if prices.len() % 2 == 0 {
+        &prices[mid - 1..=mid]
+    } else {
+        &prices[mid..=mid]
+    };
+
+    let price: Price = if let [(low, _), (high, _)] = median_inputs {
+        Price::try_from_decimal((*low + *high) / 2)?
+    } else {
+        prices[mid].1.price
+    };
+    let last_updated_slot = median_inputs
+        .iter()
+        .map(|(_, p)| p.last_updated_slot)
+        .min()
+        .ok_or_else(|| error!(ScopeError::MedianOfInvalid))?;
+    let unix_timestamp = median_inputs
+        .iter()
+        .map(|(_, p)| p.unix_timestamp)
+        .min()
+        .ok_or_else(|| error!(ScopeError::MedianOfInvalid))?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot,
+        unix_timestamp,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a median oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = MedianOfData::from_generic_data(generic_data)?;
+
+    let sources_count = data.source_entries.len();
+    if !(MIN_MEDIAN_SOURCES..=MAX_MEDIAN_SOURCES).contains(&sources_count) {
+        msg!(
+            "Median requires between {MIN_MEDIAN_SOURCES} and {MAX_MEDIAN_SOURCES} sources, got {sources_count}"
+        );
+        return err!(ScopeError::MedianOfInvalid);
+    }
+
+    if data
+        .source_entry_ids()
+        .any(|entry| entry >= MAX_ENTRIES_U16)
+    {
+        msg!("Median source entries must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::MedianOfInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        oracles::test_utils::{
+            check_missing_source, check_validate_mapping_cfg, sequential_oracle_prices,
+        },
+        scope_chain::divide_hop,
+    };
+
+    fn generic_data(source_entries: &[u16]) -> [u8; 20] {
+        crate::oracles::test_utils::generic_data(&MedianOfData {
+            source_entries: source_entries.to_vec(),
+        })
+    }
+
+    #[test]
+    fn test_odd_sources() {
+        let oracle_prices = sequential_oracle_prices(&[
+            Price { value: 103, exp: 2 },
+            Price { value: 99, exp: 2 },
+            Price { value: 101, exp: 2 },
+            Price { value: 150, exp: 2 },
+            Price { value: 1, exp: 2 },
+        ]);
+        let price = get_price(&oracle_prices, &generic_data(&[0, 1, 2])).unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+        assert_eq!(price.last_updated_slot, 102);
+        assert_eq!(price.unix_timestamp, 1002);
+
+        // Outliers on both sides are ignored
+        let price = get_price(&oracle_prices, &generic_data(&[0, 1, 2, 3, 4])).unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+    }
+
+    #[test]
+    fn test_even_sources() {
+        let oracle_prices = sequential_oracle_prices(&[
+            Price { value: 100, exp: 2 },
+            Price { value: 200, exp: 2 },
+            Price { value: 102, exp: 2 },
+            Price { value: 50, exp: 2 },
+        ]);
+        // Average of the two middle prices, dated by the oldest of them
+        let price = get_price(&oracle_prices, &generic_data(&[0, 1, 2, 3])).unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+        assert_eq!(price.last_updated_slot, 100);
+        assert_eq!(price.unix_timestamp, 1000);
+    }
+
+    #[test]
+    fn test_stale_or_missing_sources() {
+        let mut prices = sequential_oracle_prices(&[
+            Price { value: 100, exp: 2 },
+            Price { value: 101, exp: 2 },
+            Price { value: 102, exp: 2 },
+        ]);
+        // A stale median source makes the median stale
+        prices.prices[1].last_updated_slot = 1;
+        prices.prices[1].unix_timestamp = 10;
+        let price = get_price(&prices, &generic_data(&[0, 1, 2])).unwrap();
+        assert_eq!(price.last_updated_slot, 1);
+        assert_eq!(price.unix_timestamp, 10);
+
+        // A stale outlier does not
+        prices.prices[1] = prices.prices[2];
+        prices.prices[0].unix_timestamp = 10;
+        let price = get_price(&prices, &generic_data(&[0, 1, 2])).unwrap();
+        assert_eq!(price.unix_timestamp, 1002);
+
+        // Any source without price fails the median
+        check_missing_source(&prices, 2, |oracle_prices, source| {
+            get_price(oracle_prices, &generic_data(&[0, 1, source]))
+        });
+        assert_eq!(
+            get_price(&prices, &generic_data(&[0, 1])).unwrap_err(),
+            error!(ScopeError::MedianOfInvalid)
+        );
+    }
+
+    #[test]
+    fn test_exponent_mismatch() {
+        let oracle_prices = sequential_oracle_prices(&[
+            Price {
+                value: 1_020_000,
+                exp: 6,
+            },
+            Price { value: 1, exp: 0 },
+            Price {
+                value: 99_000_000,
+                exp: 8,
+            },
+            Price { value: 104, exp: 2 },
+        ]);
+        // Sorted by value, not by raw integer
+        let price = get_price(&oracle_prices, &generic_data(&[0, 1, 2])).unwrap();
+        assert_eq!(price.price, Price { value: 1, exp: 0 });
+        let price = get_price(&oracle_prices, &generic_data(&[0, 1, 2, 3])).unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+    }
+
+    #[test]
+    fn test_inverted_sources() {
+        let oracle_prices = sequential_oracle_prices(&[
+            Price { value: 101, exp: 2 },
+            Price { value: 2, exp: 0 },
+            Price { value: 99, exp: 2 },
+        ]);
+        // 1/2 is the lowest price once inverted
+        let price = get_price(&oracle_prices, &generic_data(&[0, divide_hop(1), 2])).unwrap();
+        assert_eq!(price.price, Price { value: 99, exp: 2 });
+        let price = get_price(&oracle_prices, &generic_data(&[divide_hop(1), 0, 2])).unwrap();
+        assert_eq!(price.price, Price { value: 99, exp: 2 });
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        check_validate_mapping_cfg(
+            validate_mapping_cfg,
+            &[
+                generic_data(&[0, 1, 2]),
+                generic_data(&[0, 1, divide_hop(2)]),
+            ],
+            &[
+                generic_data(&[0, 1]),
+                generic_data(&[0, 1, 2, 3, 4, 5]),
+                generic_data(&[0, 1, MAX_ENTRIES_U16]),
+            ],
+            ScopeError::MedianOfInvalid,
+        );
+    }
+}
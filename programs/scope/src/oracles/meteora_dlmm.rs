@@ -1,21 +1,27 @@
 use std::cell::Ref;
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::spl_token::state::Mint;
 use decimal_wad::decimal::U192;
 pub use lb_clmm_itf as lb_clmm;
-use solana_program::program_pack::Pack;
 
+use super::pool_liquidity::DlmmReservesGuard;
 use crate::{
-    utils::{math, zero_copy_deserialize},
+    utils::{clock_unix_timestamp, math, unpack_mint, zero_copy_deserialize},
     DatedPrice, Result, ScopeError,
 };
 
 /// Gives the price of the given token pair in the given pool
+///
+/// When minimum reserves are configured in the generic data (see [`DlmmReservesGuard`]), the
+/// reserve accounts of the pool are expected after the mints and the price is rejected if the
+/// reserves are below the minimum.
+///
+/// The price is rejected if the pair is not active, see [`check_pair_active`].
 pub fn get_price<'a, 'b>(
     a_to_b: bool,
     pool: &AccountInfo,
     clock: &Clock,
+    generic_data: &[u8; 20],
     extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
 ) -> Result<DatedPrice>
 where
@@ -31,6 +37,7 @@ where
 
     // Load main account
     let lb_pair_state: Ref<'_, lb_clmm::LbPair> = zero_copy_deserialize(pool)?;
+    check_pair_active(&lb_pair_state, clock.slot)?;
 
     // Check extra accounts pubkeys
     require_keys_eq!(
@@ -45,16 +52,31 @@ where
         ScopeError::AccountsAndTokenMismatch
     );
 
+    let reserves_guard = DlmmReservesGuard::from_generic_data(generic_data)?;
+    if reserves_guard.is_enabled() {
+        let reserve_x_acc = extra_accounts
+            .next()
+            .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+        let reserve_y_acc = extra_accounts
+            .next()
+            .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+        require_keys_eq!(
+            lb_pair_state.reserve_x,
+            reserve_x_acc.key(),
+            ScopeError::AccountsAndTokenMismatch
+        );
+        require_keys_eq!(
+            lb_pair_state.reserve_y,
+            reserve_y_acc.key(),
+            ScopeError::AccountsAndTokenMismatch
+        );
+        reserves_guard.check(pool.key, reserve_x_acc, reserve_y_acc)?;
+    }
+
     // Load extra accounts
-    let mint_a_decimals = {
-        let mint_borrow = mint_token_a_account_info.data.borrow();
-        Mint::unpack(&mint_borrow)?.decimals
-    };
+    let mint_a_decimals = unpack_mint(mint_token_a_account_info)?.decimals;
 
-    let mint_b_decimals = {
-        let mint_borrow = mint_token_b_account_info.data.borrow();
-        Mint::unpack(&mint_borrow)?.decimals
-    };
+    let mint_b_decimals = unpack_mint(mint_token_b_account_info)?.decimals;
 
     // Compute price
     let q64x64_price =
@@ -83,17 +105,51 @@ where
         lamport_price,
         src_token_decimals.into(),
         dst_token_decimals.into(),
-    );
+    )?;
 
     // Return price
     Ok(DatedPrice {
         price,
         last_updated_slot: clock.slot,
-        unix_timestamp: clock.unix_timestamp as u64,
+        unix_timestamp: clock_unix_timestamp(clock)?,
         ..Default::default()
     })
 }
 
+/// Check that the price of the pair can be trusted at the given slot:
+/// - the pair is enabled,
+/// - the activation slot (of permissioned pairs) has passed,
+/// - the swap cap period following the activation is over, as buys are limited during it,
+/// - the bin array of the active bin is initialized when it is tracked by the pair bitmap.
+pub fn check_pair_active(lb_pair: &lb_clmm::LbPair, slot: u64) -> Result<()> {
+    if lb_pair.status != lb_clmm::PairStatus::Enabled as u8 {
+        msg!("Meteora DLMM pair is disabled (status {})", lb_pair.status);
+        return err!(ScopeError::MeteoraPairNotActive);
+    }
+    if slot < lb_pair.activation_slot {
+        msg!(
+            "Meteora DLMM pair is not activated yet: current slot {slot}, activation slot {}",
+            lb_pair.activation_slot
+        );
+        return err!(ScopeError::MeteoraPairNotActive);
+    }
+    if lb_pair.swap_cap_deactivate_slot != 0 && slot <= lb_pair.swap_cap_deactivate_slot {
+        msg!(
+            "Meteora DLMM pair swaps are capped: current slot {slot}, swap cap deactivation slot {}",
+            lb_pair.swap_cap_deactivate_slot
+        );
+        return err!(ScopeError::MeteoraPairNotActive);
+    }
+    if lb_pair.is_active_bin_array_initialized() == Some(false) {
+        msg!(
+            "Meteora DLMM bin array of the active bin {} is not initialized",
+            lb_pair.active_id
+        );
+        return err!(ScopeError::MeteoraPairNotActive);
+    }
+    Ok(())
+}
+
 pub fn validate_pool_account(pool: &Option<AccountInfo>) -> Result<()> {
     let Some(pool) = pool else {
         msg!("No pool account provided");
@@ -102,3 +158,66 @@ pub fn validate_pool_account(pool: &Option<AccountInfo>) -> Result<()> {
     let _: Ref<'_, lb_clmm::LbPair> = zero_copy_deserialize(pool)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::__private::bytemuck::Zeroable;
+
+    use super::*;
+
+    fn active_pair(active_id: i32) -> lb_clmm::LbPair {
+        let mut lb_pair = lb_clmm::LbPair::zeroed();
+        lb_pair.active_id = active_id;
+        // Mark the bin array of the active bin as initialized
+        let offset = usize::try_from(
+            active_id.div_euclid(lb_clmm::MAX_BIN_PER_ARRAY) + lb_clmm::BIN_ARRAY_BITMAP_SIZE,
+        )
+        .unwrap();
+        lb_pair.bin_array_bitmap[offset / 64] |= 1 << (offset % 64);
+        lb_pair
+    }
+
+    #[test]
+    fn test_active_pair() {
+        assert!(check_pair_active(&active_pair(0), 100).is_ok());
+        assert!(check_pair_active(&active_pair(-71), 100).is_ok());
+        assert!(check_pair_active(&active_pair(35_839), 100).is_ok());
+    }
+
+    #[test]
+    fn test_disabled_pair() {
+        let mut lb_pair = active_pair(0);
+        lb_pair.status = lb_clmm::PairStatus::Disabled as u8;
+        assert!(check_pair_active(&lb_pair, 100).is_err());
+    }
+
+    #[test]
+    fn test_pair_before_activation() {
+        let mut lb_pair = active_pair(0);
+        lb_pair.activation_slot = 100;
+        assert!(check_pair_active(&lb_pair, 99).is_err());
+        assert!(check_pair_active(&lb_pair, 100).is_ok());
+    }
+
+    #[test]
+    fn test_pair_during_swap_cap() {
+        let mut lb_pair = active_pair(0);
+        lb_pair.activation_slot = 100;
+        lb_pair.swap_cap_deactivate_slot = 200;
+        assert!(check_pair_active(&lb_pair, 150).is_err());
+        assert!(check_pair_active(&lb_pair, 200).is_err());
+        assert!(check_pair_active(&lb_pair, 201).is_ok());
+    }
+
+    #[test]
+    fn test_uninitialized_active_bin_array() {
+        let mut lb_pair = active_pair(0);
+        lb_pair.active_id = 70;
+        assert!(check_pair_active(&lb_pair, 100).is_err());
+        lb_pair.active_id = -1;
+        assert!(check_pair_active(&lb_pair, 100).is_err());
+        // Outside of the pair bitmap, the bin array is not checked
+        lb_pair.active_id = 35_840;
+        assert!(check_pair_active(&lb_pair, 100).is_ok());
+    }
+}
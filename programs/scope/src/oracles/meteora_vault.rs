@@ -0,0 +1,443 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+
+use crate::{
+    utils::{clock_unix_timestamp, unpack_mint},
+    DatedPrice, Result, ScopeError,
+};
+
+/// Gives the amount of underlying token for 1 LP token of a Meteora dynamic vault
+///
+/// Only the unlocked amount of the vault is accounted for: the profit of the vault is released
+/// linearly (locked profit degradation) to avoid sandwiching the vault reports.
+///
+/// Required extra accounts:
+/// - Mint of the vault LP token
+/// - Mint of the vault underlying token
+pub fn get_price<'a, 'b>(
+    vault_acc: &AccountInfo,
+    clock: &Clock,
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    let lp_mint_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let token_mint_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let vault = dynamic_vault::load_vault(vault_acc)?;
+
+    require_keys_eq!(
+        vault.lp_mint,
+        lp_mint_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        vault.token_mint,
+        token_mint_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    let lp_mint = unpack_mint(lp_mint_acc)?;
+    let token_mint_decimals = unpack_mint(token_mint_acc)?.decimals;
+
+    // The LP token is created with the decimals of the underlying token
+    // This allows to just divide the two values to get the price
+    require_eq!(
+        lp_mint.decimals,
+        token_mint_decimals,
+        ScopeError::UnexpectedMeteoraVaultConfiguration
+    );
+
+    if lp_mint.supply == 0 {
+        msg!("Meteora vault LP supply is 0");
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    let current_ts = clock_unix_timestamp(clock)?;
+    let unlocked_amount = vault
+        .get_unlocked_amount(current_ts)
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?;
+
+    let price_dec = Decimal::from(unlocked_amount) / lp_mint.supply;
+
+    Ok(DatedPrice {
+        price: price_dec.into(),
+        last_updated_slot: clock.slot,
+        unix_timestamp: current_ts,
+        ..Default::default()
+    })
+}
+
+pub fn validate_vault_account(vault: &Option<AccountInfo>) -> Result<()> {
+    let Some(vault) = vault else {
+        msg!("No Meteora vault account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let _ = dynamic_vault::load_vault(vault)?;
+    Ok(())
+}
+
+/// Minimal layout of the Meteora dynamic vault program accounts
+pub mod dynamic_vault {
+    use solana_program::pubkey;
+
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi");
+
+    /// Anchor discriminator of the `Vault` account
+    pub const VAULT_DISCRIMINATOR: [u8; 8] = [211, 8, 232, 43, 2, 152, 117, 119];
+
+    pub const LOCKED_PROFIT_DEGRADATION_DENOMINATOR: u128 = 1_000_000_000_000;
+
+    pub const MAX_STRATEGY: usize = 30;
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Clone, Copy)]
+    pub struct VaultBumps {
+        pub vault_bump: u8,
+        pub token_vault_bump: u8,
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Clone, Copy)]
+    pub struct LockedProfitTracker {
+        pub last_updated_locked_profit: u64,
+        pub last_report: u64,
+        pub locked_profit_degradation: u64,
+    }
+
+    impl LockedProfitTracker {
+        /// Profit still locked at `current_time`, released linearly since the last report
+        pub fn calculate_locked_profit(&self, current_time: u64) -> Option<u64> {
+            let duration = u128::from(current_time.checked_sub(self.last_report)?);
+            let locked_profit_degradation = u128::from(self.locked_profit_degradation);
+            let locked_fund_ratio = duration * locked_profit_degradation;
+
+            if locked_fund_ratio > LOCKED_PROFIT_DEGRADATION_DENOMINATOR {
+                return Some(0);
+            }
+            let locked_profit = u128::from(self.last_updated_locked_profit);
+
+            let locked_profit = (locked_profit
+                * (LOCKED_PROFIT_DEGRADATION_DENOMINATOR - locked_fund_ratio))
+                / LOCKED_PROFIT_DEGRADATION_DENOMINATOR;
+            u64::try_from(locked_profit).ok()
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy)]
+    pub struct Vault {
+        pub enabled: u8,
+        pub bumps: VaultBumps,
+        pub total_amount: u64,
+        pub token_vault: Pubkey,
+        pub fee_vault: Pubkey,
+        pub token_mint: Pubkey,
+        pub lp_mint: Pubkey,
+        pub strategies: [Pubkey; MAX_STRATEGY],
+        pub base: Pubkey,
+        pub admin: Pubkey,
+        pub operator: Pubkey,
+        pub locked_profit_tracker: LockedProfitTracker,
+    }
+
+    impl Vault {
+        /// Amount of the vault that is not locked profit at `current_time`
+        pub fn get_unlocked_amount(&self, current_time: u64) -> Option<u64> {
+            self.total_amount.checked_sub(
+                self.locked_profit_tracker
+                    .calculate_locked_profit(current_time)?,
+            )
+        }
+    }
+
+    pub fn load_vault(account: &AccountInfo) -> Result<Vault> {
+        require_keys_eq!(*account.owner, ID, ScopeError::UnexpectedAccount);
+        let data = account.data.borrow();
+        let (discriminator, mut vault_data) = data.split_at(8.min(data.len()));
+        if discriminator != VAULT_DISCRIMINATOR {
+            msg!(
+                "Account {} is not a Meteora vault (discriminator {:?})",
+                account.key,
+                discriminator
+            );
+            return err!(ScopeError::InvalidAccountDiscriminator);
+        }
+        Vault::deserialize(&mut vault_data).map_err(|_| {
+            msg!(
+                "Meteora vault account {} deserialization failed",
+                account.key
+            );
+            error!(ScopeError::UnableToDeserializeAccount)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::token::spl_token::state::Mint;
+    use solana_program::program_pack::Pack;
+
+    use super::{dynamic_vault::*, *};
+
+    const DECIMALS: u8 = 6;
+
+    /// Vault whose locked profit is fully released 1000 seconds after the last report
+    fn vault(total_amount: u64, locked_profit: u64) -> Vault {
+        Vault {
+            enabled: 1,
+            bumps: VaultBumps::default(),
+            total_amount,
+            token_vault: Pubkey::new_unique(),
+            fee_vault: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            strategies: [Pubkey::default(); MAX_STRATEGY],
+            base: Pubkey::new_unique(),
+            admin: Pubkey::new_unique(),
+            operator: Pubkey::new_unique(),
+            locked_profit_tracker: LockedProfitTracker {
+                last_updated_locked_profit: locked_profit,
+                last_report: 1000,
+                locked_profit_degradation: (LOCKED_PROFIT_DEGRADATION_DENOMINATOR / 1000) as u64,
+            },
+        }
+    }
+
+    fn vault_data(vault: &Vault) -> Vec<u8> {
+        let mut data = VAULT_DISCRIMINATOR.to_vec();
+        vault.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn mint_data(supply: u64, decimals: u8) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                supply,
+                decimals,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    fn clock(unix_timestamp: i64) -> Clock {
+        Clock {
+            slot: 100,
+            unix_timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn get_test_price(
+        vault: &Vault,
+        lp_supply: u64,
+        token_decimals: u8,
+        current_ts: i64,
+    ) -> Result<DatedPrice> {
+        let vault_key = Pubkey::new_unique();
+        let (mut vault_lamports, mut lp_mint_lamports, mut token_mint_lamports) = (0, 0, 0);
+        let mut vault_data = vault_data(vault);
+        let mut lp_mint_data = mint_data(lp_supply, DECIMALS);
+        let mut token_mint_data = mint_data(0, token_decimals);
+        let vault_acc = AccountInfo::new(
+            &vault_key,
+            false,
+            false,
+            &mut vault_lamports,
+            &mut vault_data,
+            &ID,
+            false,
+            0,
+        );
+        let extra_accounts = [
+            AccountInfo::new(
+                &vault.lp_mint,
+                false,
+                false,
+                &mut lp_mint_lamports,
+                &mut lp_mint_data,
+                &anchor_spl::token::ID,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &vault.token_mint,
+                false,
+                false,
+                &mut token_mint_lamports,
+                &mut token_mint_data,
+                &anchor_spl::token::ID,
+                false,
+                0,
+            ),
+        ];
+        get_price(&vault_acc, &clock(current_ts), &mut extra_accounts.iter())
+    }
+
+    #[test]
+    fn test_locked_profit_degradation() {
+        let tracker = vault(0, 1_000_000).locked_profit_tracker;
+        assert_eq!(tracker.calculate_locked_profit(1000), Some(1_000_000));
+        assert_eq!(tracker.calculate_locked_profit(1250), Some(750_000));
+        assert_eq!(tracker.calculate_locked_profit(1500), Some(500_000));
+        assert_eq!(tracker.calculate_locked_profit(2000), Some(0));
+        assert_eq!(tracker.calculate_locked_profit(10_000), Some(0));
+        // Report in the future
+        assert_eq!(tracker.calculate_locked_profit(999), None);
+    }
+
+    #[test]
+    fn test_unlocked_amount() {
+        let vault = vault(3_000_000, 1_000_000);
+        assert_eq!(vault.get_unlocked_amount(1000), Some(2_000_000));
+        assert_eq!(vault.get_unlocked_amount(1500), Some(2_500_000));
+        assert_eq!(vault.get_unlocked_amount(2000), Some(3_000_000));
+
+        // More locked profit than the vault holds
+        let vault = Vault {
+            total_amount: 500_000,
+            ..vault
+        };
+        assert_eq!(vault.get_unlocked_amount(1000), None);
+    }
+
+    #[test]
+    fn test_share_price() {
+        let vault = vault(3_000_000, 1_000_000);
+
+        let price = get_test_price(&vault, 2_000_000, DECIMALS, 1000).unwrap();
+        assert_eq!(f64::from(price.price), 1.0);
+        assert_eq!(price.last_updated_slot, 100);
+        assert_eq!(price.unix_timestamp, 1000);
+
+        // The share price grows as the profit is released
+        let price = get_test_price(&vault, 2_000_000, DECIMALS, 1500).unwrap();
+        assert_eq!(f64::from(price.price), 1.25);
+        let price = get_test_price(&vault, 2_000_000, DECIMALS, 3000).unwrap();
+        assert_eq!(f64::from(price.price), 1.5);
+    }
+
+    #[test]
+    fn test_share_price_invalid_vault() {
+        let vault = vault(3_000_000, 1_000_000);
+
+        assert_eq!(
+            get_test_price(&vault, 0, DECIMALS, 1000).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+        assert_eq!(
+            get_test_price(&vault, 2_000_000, DECIMALS + 1, 1000).unwrap_err(),
+            error!(ScopeError::UnexpectedMeteoraVaultConfiguration)
+        );
+        assert_eq!(
+            get_test_price(&vault, 2_000_000, DECIMALS, 999).unwrap_err(),
+            error!(ScopeError::MathOverflow)
+        );
+    }
+
+    #[test]
+    fn test_share_price_unexpected_mints() {
+        let vault = vault(3_000_000, 1_000_000);
+        let vault_key = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let (mut vault_lamports, mut lp_mint_lamports, mut token_mint_lamports) = (0, 0, 0);
+        let mut vault_data = vault_data(&vault);
+        let mut lp_mint_data = mint_data(2_000_000, DECIMALS);
+        let mut token_mint_data = mint_data(0, DECIMALS);
+        let vault_acc = AccountInfo::new(
+            &vault_key,
+            false,
+            false,
+            &mut vault_lamports,
+            &mut vault_data,
+            &ID,
+            false,
+            0,
+        );
+        let lp_mint_acc = AccountInfo::new(
+            &vault.lp_mint,
+            false,
+            false,
+            &mut lp_mint_lamports,
+            &mut lp_mint_data,
+            &anchor_spl::token::ID,
+            false,
+            0,
+        );
+        let mut token_mint_acc = AccountInfo::new(
+            &vault.token_mint,
+            false,
+            false,
+            &mut token_mint_lamports,
+            &mut token_mint_data,
+            &anchor_spl::token::ID,
+            false,
+            0,
+        );
+
+        // Mints in the wrong order
+        let swapped = [token_mint_acc.clone(), lp_mint_acc.clone()];
+        assert_eq!(
+            get_price(&vault_acc, &clock(1000), &mut swapped.iter()).unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+
+        token_mint_acc.key = &other_mint;
+        let extra_accounts = [lp_mint_acc, token_mint_acc];
+        assert_eq!(
+            get_price(&vault_acc, &clock(1000), &mut extra_accounts.iter()).unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+
+        assert_eq!(
+            get_price(&vault_acc, &clock(1000), &mut extra_accounts[..1].iter()).unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+    }
+
+    #[test]
+    fn test_validate_vault_account() {
+        let vault = vault(3_000_000, 1_000_000);
+        let vault_key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vault_data(&vault);
+        let mut vault_acc = AccountInfo::new(
+            &vault_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &ID,
+            false,
+            0,
+        );
+        validate_vault_account(&Some(vault_acc.clone())).unwrap();
+
+        assert_eq!(
+            validate_vault_account(&None).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+
+        let mut wrong_owner = vault_acc.clone();
+        wrong_owner.owner = &anchor_spl::token::ID;
+        assert_eq!(
+            validate_vault_account(&Some(wrong_owner)).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        vault_acc.data.borrow_mut()[0] += 1;
+        assert_eq!(
+            validate_vault_account(&Some(vault_acc)).unwrap_err(),
+            error!(ScopeError::InvalidAccountDiscriminator)
+        );
+    }
+}
@@ -1,4 +1,12 @@
+pub mod annualized_growth_rate;
+pub mod capped_floored;
+pub mod confidence;
 pub mod ctokens;
+pub mod discount_to_maturity;
+pub mod fixed_multiplier;
+pub mod fixed_rate_accrual;
+pub mod generic_data_layouts;
+pub mod generic_vault_share;
 #[cfg(feature = "yvaults")]
 pub mod ktokens;
 #[cfg(feature = "yvaults")]
@@ -6,29 +14,47 @@ pub mod ktokens_token_x;
 
 pub mod jito_restaking;
 pub mod jupiter_lp;
+pub mod median_of;
 pub mod meteora_dlmm;
+pub mod meteora_vault;
+pub mod most_recent_of;
 pub mod msol_stake;
 pub mod orca_whirlpool;
+pub mod orca_whirlpool_position;
+pub mod pool_liquidity;
 pub mod pyth;
 pub mod pyth_ema;
 pub mod pyth_pull_based;
 pub mod pyth_pull_based_ema;
 pub mod raydium_ammv3;
+pub mod raydium_ammv3_twap;
+pub mod raydium_cpmm;
+pub mod sanctum_inf;
 pub mod spl_stake;
+pub mod staking_rate;
 pub mod switchboard_on_demand;
 pub mod switchboard_v2;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod twap;
 
 use std::ops::Deref;
 
-use anchor_lang::{accounts::account_loader::AccountLoader, prelude::*};
+use anchor_lang::{
+    accounts::account_loader::AccountLoader,
+    prelude::*,
+    solana_program::clock::{DEFAULT_MS_PER_SLOT, DEFAULT_SLOTS_PER_EPOCH},
+};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "yvaults")]
 use self::ktokens_token_x::TokenTypes;
-use crate::{DatedPrice, OracleMappings, OraclePrices, OracleTwaps, Price, ScopeError};
+use crate::{
+    utils::{account_header::LoadVersioned, clock_unix_timestamp},
+    DatedPrice, OracleMappings, OraclePrices, OracleTwaps, Price, ScopeError,
+};
 
 pub fn check_context<T>(ctx: &Context<T>) -> Result<()> {
     //make sure there are no extra accounts
@@ -39,6 +65,45 @@ pub fn check_context<T>(ctx: &Context<T>) -> Result<()> {
     Ok(())
 }
 
+/// Check that no account is provided under several roles of the same instruction
+pub fn check_unique_accounts(accounts: &[Pubkey]) -> Result<()> {
+    for (i, account) in accounts.iter().enumerate() {
+        if accounts[i + 1..].contains(account) {
+            msg!("Account {} is provided more than once", account);
+            return err!(ScopeError::DuplicateAccount);
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that none of the named accounts of an instruction is also used as a remaining account
+pub fn check_remaining_accounts_exclude(
+    remaining_accounts: &[AccountInfo],
+    accounts: &[Pubkey],
+) -> Result<()> {
+    if let Some(account) = remaining_accounts
+        .iter()
+        .find(|remaining| accounts.contains(remaining.key))
+    {
+        msg!(
+            "Instruction account {} is provided as a remaining account",
+            account.key
+        );
+        return err!(ScopeError::InstructionAccountInRemainingAccounts);
+    }
+
+    Ok(())
+}
+
+/// Built-in max age of the prices pushed continuously by oracle networks
+const PUSH_ORACLE_MAX_AGE_SLOTS: u64 = 25;
+/// Built-in max age of the prices of pull oracles and of the prices read from on-chain states
+const PULL_ORACLE_MAX_AGE_SLOTS: u64 = (60 * 1000) / DEFAULT_MS_PER_SLOT; // 1 minute
+/// Built-in max age of the rates updated once per epoch, possibly an hour after the epoch start
+const EPOCH_RATE_MAX_AGE_SLOTS: u64 =
+    DEFAULT_SLOTS_PER_EPOCH + (60 * 60 * 1000) / DEFAULT_MS_PER_SLOT;
+
 #[derive(IntoPrimitive, TryFromPrimitive, Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u8)]
@@ -107,6 +172,40 @@ pub enum OracleType {
     SwitchboardOnDemand = 24,
     /// Jito restaking tokens
     JitoRestaking = 25, // TODO adjust if we merge ALP first
+    /// Price of a source entry bounded by the prices of optional cap and floor entries
+    CappedFloored = 26,
+    /// Median price of up to 5 source entries
+    MedianOf = 27,
+    /// Sanctum Infinity (INF) multi-LST pool token, in SOL, computed from scope prices
+    SanctumInf = 28,
+    /// Meteora's dynamic vault LP token, in underlying token
+    MeteoraVault = 29,
+    /// Value of an Orca's whirlpool position computed from scope prices
+    OrcaWhirlpoolPosition = 30,
+    /// Raydium's CP-Swap (constant product) price A to B
+    RaydiumCpAtoB = 31,
+    /// Raydium's CP-Swap (constant product) price B to A
+    RaydiumCpBtoA = 32,
+    /// Liquid staking token in its underlying token, read from the staking program configured in
+    /// the generic data
+    StakingRate = 33,
+    /// Most recent price of up to 4 source entries, checked against the other fresh sources
+    MostRecentOf = 34,
+    /// Token redeemable at par at maturity, discounted by the rate schedule of the generic data
+    DiscountToMaturity = 35,
+    /// Start price accruing at a fixed yearly rate, configured in the generic data
+    FixedRateAccrual = 36,
+    /// Price of a source entry multiplied by a constant configured in the generic data
+    FixedMultiplier = 37,
+    /// Simple annualized growth rate of an entry, from its price history account
+    AnnualizedGrowthRate = 38,
+    /// Time-weighted average price A to B of a Raydium's AMM v3 pool, from its observations
+    RaydiumAmmV3TwapAtoB = 39,
+    /// Time-weighted average price B to A of a Raydium's AMM v3 pool, from its observations
+    RaydiumAmmV3TwapBtoA = 40,
+    /// Share of a vault priced from its total assets and total shares, read at the offsets
+    /// configured in the generic data, and the price of its asset entry
+    GenericVaultShare = 41,
 }
 
 impl OracleType {
@@ -114,7 +213,121 @@ impl OracleType {
         matches!(self, OracleType::ScopeTwap)
     }
 
+    /// Minimum number of extra accounts expected after the base account of an entry of this type
+    ///
+    /// The count is exact except for the pools whose number of extra accounts depends on the
+    /// pool state (Jupiter LP recomputed prices and Sanctum Infinity), for which it is the
+    /// minimum.
+    pub fn min_extra_accounts(&self, generic_data: &[u8; 20]) -> usize {
+        match self {
+            OracleType::KToken => 5,
+            OracleType::KTokenToTokenA | OracleType::KTokenToTokenB => 7,
+            OracleType::OrcaWhirlpoolPosition => 5,
+            OracleType::SanctumInf => 3,
+            OracleType::JupiterLpScope => 2,
+            OracleType::OrcaWhirlpoolAtoB
+            | OracleType::OrcaWhirlpoolBtoA
+            | OracleType::RaydiumCpAtoB
+            | OracleType::RaydiumCpBtoA
+            | OracleType::MeteoraVault
+            | OracleType::JitoRestaking => 2,
+            OracleType::MeteoraDlmmAtoB | OracleType::MeteoraDlmmBtoA => {
+                pool_liquidity::DlmmReservesGuard::from_generic_data(generic_data)
+                    .map_or(2, |guard| if guard.is_enabled() { 4 } else { 2 })
+            }
+            OracleType::JupiterLpFetch
+            | OracleType::JupiterLpCompute
+            | OracleType::RaydiumAmmV3TwapAtoB
+            | OracleType::RaydiumAmmV3TwapBtoA => 1,
+            OracleType::MostRecentOf => {
+                most_recent_of::MostRecentOfData::from_generic_data(generic_data)
+                    .map_or(0, |data| usize::from(data.prefer_healthy_sources))
+            }
+            OracleType::MsolStake => msol_stake::MsolStakeData::from_generic_data(generic_data)
+                .map_or(0, |data| {
+                    usize::from(data.mode == msol_stake::MsolStakeMode::InstantExit)
+                }),
+            OracleType::Pyth
+            | OracleType::PythPullBased
+            | OracleType::PythPullBasedEMA
+            | OracleType::PythEMA
+            | OracleType::SwitchboardV2
+            | OracleType::SwitchboardOnDemand
+            | OracleType::CToken
+            | OracleType::SplStake
+            | OracleType::ScopeTwap
+            | OracleType::RaydiumAmmV3AtoB
+            | OracleType::RaydiumAmmV3BtoA
+            | OracleType::FixedPrice
+            | OracleType::CappedFloored
+            | OracleType::MedianOf
+            | OracleType::StakingRate
+            | OracleType::DiscountToMaturity
+            | OracleType::FixedRateAccrual
+            | OracleType::FixedMultiplier
+            | OracleType::AnnualizedGrowthRate
+            | OracleType::GenericVaultShare
+            | OracleType::DeprecatedPlaceholder1
+            | OracleType::DeprecatedPlaceholder2 => 0,
+        }
+    }
+
+    /// Built-in max age of the prices of an entry of this type, used when neither the entry nor
+    /// the metadata defaults of the type configure one, see
+    /// [`crate::Configuration::max_age_slots`].
+    ///
+    /// `None` for the types whose prices are computed from other entries (dated like their
+    /// sources) or from the clock only.
+    pub fn default_max_age_slots(&self) -> Option<u64> {
+        match self {
+            OracleType::Pyth | OracleType::PythEMA => Some(PUSH_ORACLE_MAX_AGE_SLOTS),
+            OracleType::PythPullBased
+            | OracleType::PythPullBasedEMA
+            | OracleType::SwitchboardV2
+            | OracleType::SwitchboardOnDemand
+            | OracleType::CToken
+            | OracleType::KToken
+            | OracleType::KTokenToTokenA
+            | OracleType::KTokenToTokenB
+            | OracleType::JupiterLpFetch
+            | OracleType::JupiterLpCompute
+            | OracleType::OrcaWhirlpoolAtoB
+            | OracleType::OrcaWhirlpoolBtoA
+            | OracleType::RaydiumAmmV3AtoB
+            | OracleType::RaydiumAmmV3BtoA
+            | OracleType::RaydiumAmmV3TwapAtoB
+            | OracleType::RaydiumAmmV3TwapBtoA
+            | OracleType::MeteoraDlmmAtoB
+            | OracleType::MeteoraDlmmBtoA
+            | OracleType::MeteoraVault
+            | OracleType::RaydiumCpAtoB
+            | OracleType::RaydiumCpBtoA => Some(PULL_ORACLE_MAX_AGE_SLOTS),
+            OracleType::SplStake
+            | OracleType::MsolStake
+            | OracleType::StakingRate
+            | OracleType::JitoRestaking => Some(EPOCH_RATE_MAX_AGE_SLOTS),
+            OracleType::ScopeTwap
+            | OracleType::FixedPrice
+            | OracleType::CappedFloored
+            | OracleType::MedianOf
+            | OracleType::MostRecentOf
+            | OracleType::FixedMultiplier
+            | OracleType::AnnualizedGrowthRate
+            | OracleType::DiscountToMaturity
+            | OracleType::FixedRateAccrual
+            | OracleType::JupiterLpScope
+            | OracleType::SanctumInf
+            | OracleType::OrcaWhirlpoolPosition
+            | OracleType::GenericVaultShare
+            | OracleType::DeprecatedPlaceholder1
+            | OracleType::DeprecatedPlaceholder2 => None,
+        }
+    }
+
     /// Get the number of compute unit needed to refresh the price of a token
+    ///
+    /// This is the budget of a typical configuration of the type, see
+    /// [`crate::OracleMappings::get_update_cu_budget`] for the budget estimated for a given entry.
     pub fn get_update_cu_budget(&self) -> u32 {
         match self {
             OracleType::FixedPrice => 10_000,
@@ -137,10 +350,23 @@ impl OracleType {
             | OracleType::RaydiumAmmV3BtoA => 25_000,
             OracleType::MeteoraDlmmAtoB | OracleType::MeteoraDlmmBtoA => 30_000,
             OracleType::JupiterLpCompute | OracleType::JupiterLpScope => 120_000,
-            OracleType::JitoRestaking => 25_000,
-            OracleType::DeprecatedPlaceholder1 | OracleType::DeprecatedPlaceholder2 => {
-                panic!("DeprecatedPlaceholder is not a valid oracle type")
-            }
+            OracleType::JitoRestaking => 35_000,
+            OracleType::CappedFloored => 20_000,
+            OracleType::MedianOf => 30_000,
+            OracleType::SanctumInf => 150_000,
+            OracleType::MeteoraVault => 30_000,
+            OracleType::OrcaWhirlpoolPosition => 80_000,
+            OracleType::RaydiumCpAtoB | OracleType::RaydiumCpBtoA => 30_000,
+            OracleType::StakingRate => 25_000,
+            OracleType::MostRecentOf => 30_000,
+            OracleType::DiscountToMaturity => 10_000,
+            OracleType::FixedRateAccrual => 10_000,
+            OracleType::FixedMultiplier => 15_000,
+            OracleType::AnnualizedGrowthRate => 30_000,
+            OracleType::RaydiumAmmV3TwapAtoB | OracleType::RaydiumAmmV3TwapBtoA => 50_000,
+            OracleType::GenericVaultShare => 20_000,
+            // Cannot be refreshed
+            OracleType::DeprecatedPlaceholder1 | OracleType::DeprecatedPlaceholder2 => 0,
         }
     }
 }
@@ -165,20 +391,49 @@ where
     'a: 'b,
 {
     let price = match price_type {
-        OracleType::Pyth => pyth::get_price(base_account, clock),
-        OracleType::PythPullBased => pyth_pull_based::get_price(base_account, clock),
-        OracleType::PythPullBasedEMA => pyth_pull_based_ema::get_price(base_account, clock),
-        OracleType::SwitchboardV2 => switchboard_v2::get_price(base_account).map_err(Into::into),
-        OracleType::SwitchboardOnDemand => {
-            switchboard_on_demand::get_price(base_account, clock).map_err(Into::into)
+        OracleType::Pyth => pyth::get_price(
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            &oracle_mappings.confidence_policies[index],
+        ),
+        OracleType::PythPullBased => pyth_pull_based::get_price(
+            base_account,
+            clock,
+            &oracle_mappings.confidence_policies[index],
+        ),
+        OracleType::PythPullBasedEMA => pyth_pull_based_ema::get_price(
+            base_account,
+            clock,
+            &oracle_mappings.confidence_policies[index],
+        ),
+        OracleType::SwitchboardV2 => {
+            switchboard_v2::get_price(base_account, &oracle_mappings.confidence_policies[index])
+                .map_err(Into::into)
+        }
+        OracleType::SwitchboardOnDemand => switchboard_on_demand::get_price(
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            &oracle_mappings.confidence_policies[index],
+        )
+        .map_err(Into::into),
+        OracleType::CToken => {
+            ctokens::get_price(base_account, clock, &oracle_mappings.generic[index])
+        }
+        OracleType::SplStake => {
+            spl_stake::get_price(base_account, clock, &oracle_mappings.generic[index])
         }
-        OracleType::CToken => ctokens::get_price(base_account, clock),
-        OracleType::SplStake => spl_stake::get_price(base_account, clock),
         #[cfg(not(feature = "yvaults"))]
         OracleType::KToken => {
-            panic!("yvaults feature is not enabled, KToken oracle type is not available")
+            msg!("yvaults feature is not enabled, KToken oracle type is not available");
+            err!(ScopeError::BadTokenType)
         }
-        OracleType::PythEMA => pyth_ema::get_price(base_account, clock),
+        OracleType::PythEMA => pyth_ema::get_price(
+            base_account,
+            clock,
+            &oracle_mappings.confidence_policies[index],
+        ),
         #[cfg(feature = "yvaults")]
         OracleType::KToken => {
             ktokens::get_price(base_account, clock, extra_accounts).map_err(|e| {
@@ -209,14 +464,16 @@ where
             e.into()
         }),
         #[cfg(not(feature = "yvaults"))]
-        OracleType::KTokenToTokenA => {
-            panic!("yvaults feature is not enabled, KToken oracle type is not available")
+        OracleType::KTokenToTokenA | OracleType::KTokenToTokenB => {
+            msg!("yvaults feature is not enabled, KToken oracle type is not available");
+            err!(ScopeError::BadTokenType)
         }
-        #[cfg(not(feature = "yvaults"))]
-        OracleType::KTokenToTokenB => {
-            panic!("yvaults feature is not enabled, KToken oracle type is not available")
-        }
-        OracleType::MsolStake => msol_stake::get_price(base_account, clock).map_err(Into::into),
+        OracleType::MsolStake => msol_stake::get_price(
+            base_account,
+            clock,
+            extra_accounts,
+            &oracle_mappings.generic[index],
+        ),
         OracleType::JupiterLpFetch => {
             jupiter_lp::get_price_no_recompute(base_account, clock, extra_accounts).map_err(|e| {
                 msg!("Error getting Jupiter LP price: {:?}", e);
@@ -228,20 +485,76 @@ where
                 msg!("Error getting Scope TWAP price: {:?}", e);
                 e.into()
             }),
-        OracleType::OrcaWhirlpoolAtoB => {
-            orca_whirlpool::get_price(true, base_account, clock, extra_accounts)
-        }
-        OracleType::OrcaWhirlpoolBtoA => {
-            orca_whirlpool::get_price(false, base_account, clock, extra_accounts)
-        }
-        OracleType::RaydiumAmmV3AtoB => raydium_ammv3::get_price(true, base_account, clock),
-        OracleType::RaydiumAmmV3BtoA => raydium_ammv3::get_price(false, base_account, clock),
-        OracleType::MeteoraDlmmAtoB => {
-            meteora_dlmm::get_price(true, base_account, clock, extra_accounts)
+        OracleType::OrcaWhirlpoolAtoB => orca_whirlpool::get_price(
+            true,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::OrcaWhirlpoolBtoA => orca_whirlpool::get_price(
+            false,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::OrcaWhirlpoolPosition => orca_whirlpool_position::get_price(
+            base_account,
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::RaydiumAmmV3AtoB => {
+            raydium_ammv3::get_price(true, base_account, clock, &oracle_mappings.generic[index])
         }
-        OracleType::MeteoraDlmmBtoA => {
-            meteora_dlmm::get_price(false, base_account, clock, extra_accounts)
+        OracleType::RaydiumAmmV3BtoA => {
+            raydium_ammv3::get_price(false, base_account, clock, &oracle_mappings.generic[index])
         }
+        OracleType::RaydiumAmmV3TwapAtoB => raydium_ammv3_twap::get_price(
+            true,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::RaydiumAmmV3TwapBtoA => raydium_ammv3_twap::get_price(
+            false,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::RaydiumCpAtoB => raydium_cpmm::get_price(
+            true,
+            base_account,
+            clock,
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::RaydiumCpBtoA => raydium_cpmm::get_price(
+            false,
+            base_account,
+            clock,
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::MeteoraDlmmAtoB => meteora_dlmm::get_price(
+            true,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
+        OracleType::MeteoraDlmmBtoA => meteora_dlmm::get_price(
+            false,
+            base_account,
+            clock,
+            &oracle_mappings.generic[index],
+            extra_accounts,
+        ),
         OracleType::JupiterLpCompute => {
             jupiter_lp::get_price_recomputed(base_account, clock, extra_accounts)
         }
@@ -250,24 +563,79 @@ where
             base_account,
             clock,
             &oracle_prices.key(),
-            oracle_prices.load()?.deref(),
+            oracle_prices.load_versioned()?.deref(),
             extra_accounts,
         ),
         OracleType::FixedPrice => {
             let mut price_data: &[u8] = &oracle_mappings.generic[index];
-            let price = AnchorDeserialize::deserialize(&mut price_data).unwrap();
+            let price = AnchorDeserialize::deserialize(&mut price_data)
+                .map_err(|_| error!(ScopeError::FixedPriceInvalid))?;
             Ok(DatedPrice {
                 price,
                 last_updated_slot: clock.slot,
-                unix_timestamp: clock.unix_timestamp.try_into().unwrap(),
+                unix_timestamp: clock_unix_timestamp(clock)?,
                 ..Default::default()
             })
         }
-        OracleType::JitoRestaking => {
-            jito_restaking::get_price(base_account, clock).map_err(Into::into)
+        OracleType::JitoRestaking => jito_restaking::get_price(
+            base_account,
+            clock,
+            extra_accounts,
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::StakingRate => {
+            staking_rate::get_price(base_account, clock, &oracle_mappings.generic[index])
         }
+        OracleType::CappedFloored => capped_floored::get_price(
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::MedianOf => median_of::get_price(
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::DiscountToMaturity => {
+            discount_to_maturity::get_price(clock, &oracle_mappings.generic[index])
+        }
+        OracleType::FixedRateAccrual => {
+            fixed_rate_accrual::get_price(clock, &oracle_mappings.generic[index])
+        }
+        OracleType::FixedMultiplier => fixed_multiplier::get_price(
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::MostRecentOf => most_recent_of::get_price(
+            &oracle_prices.key(),
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+            clock,
+            extra_accounts,
+        ),
+        OracleType::AnnualizedGrowthRate => annualized_growth_rate::get_price(
+            base_account,
+            &oracle_prices.key(),
+            oracle_prices.load_versioned()?.deref(),
+            index,
+            clock,
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::GenericVaultShare => generic_vault_share::get_price(
+            base_account,
+            oracle_prices.load_versioned()?.deref(),
+            &oracle_mappings.generic[index],
+        ),
+        OracleType::MeteoraVault => meteora_vault::get_price(base_account, clock, extra_accounts),
+        OracleType::SanctumInf => sanctum_inf::get_price(
+            index,
+            base_account,
+            clock,
+            &oracle_prices.key(),
+            oracle_prices.load_versioned()?.deref(),
+            extra_accounts,
+        ),
         OracleType::DeprecatedPlaceholder1 | OracleType::DeprecatedPlaceholder2 => {
-            panic!("DeprecatedPlaceholder is not a valid oracle type")
+            msg!("DeprecatedPlaceholder is not a valid oracle type");
+            err!(ScopeError::BadTokenType)
         }
     }?;
     // The price providers above are performing their type-specific validations, but are still free
@@ -279,51 +647,147 @@ where
     Ok(price)
 }
 
+/// Entries read, through the generic data, by an entry of the given type
+///
+/// Invalid generic data is considered as referencing no entry.
+pub fn get_generic_data_references(price_type: OracleType, generic_data: &[u8; 20]) -> Vec<u16> {
+    match price_type {
+        OracleType::CappedFloored => {
+            capped_floored::CappedFlooredData::from_generic_data(generic_data)
+                .map(|data| {
+                    [Some(data.source_entry), data.cap_entry, data.floor_entry]
+                        .into_iter()
+                        .flatten()
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        OracleType::MedianOf => median_of::MedianOfData::from_generic_data(generic_data)
+            .map(|data| data.source_entry_ids().collect())
+            .unwrap_or_default(),
+        OracleType::FixedMultiplier => {
+            fixed_multiplier::FixedMultiplierData::from_generic_data(generic_data)
+                .map(|data| vec![data.source_entry])
+                .unwrap_or_default()
+        }
+        OracleType::MostRecentOf => {
+            most_recent_of::MostRecentOfData::from_generic_data(generic_data)
+                .map(|data| data.source_entry_ids().collect())
+                .unwrap_or_default()
+        }
+        OracleType::OrcaWhirlpoolPosition => {
+            orca_whirlpool_position::OrcaWhirlpoolPositionData::from_generic_data(generic_data)
+                .map(|data| vec![data.price_a_entry, data.price_b_entry])
+                .unwrap_or_default()
+        }
+        OracleType::RaydiumCpAtoB | OracleType::RaydiumCpBtoA => {
+            raydium_cpmm::RaydiumCpmmData::from_generic_data(generic_data)
+                .map(|data| vec![data.ref_price_entry])
+                .unwrap_or_default()
+        }
+        OracleType::GenericVaultShare => {
+            generic_vault_share::GenericVaultShareData::from_generic_data(generic_data)
+                .map(|data| vec![data.asset_entry])
+                .unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
 /// Validate the given account as being an appropriate price account for the
 /// given oracle type.
 ///
 /// This function shall be called before update of oracle mappings
+///
+/// Returns the estimated compute budget needed to refresh the entry with this exact configuration
+/// (0 when the entry is removed).
 pub fn validate_oracle_cfg(
     price_type: OracleType,
     price_account: &Option<AccountInfo>,
     twap_source: u16,
     generic_data: &[u8; 20],
-) -> crate::Result<()> {
+) -> crate::Result<u32> {
     // when we remove something from the config there is no validation needed
     if price_type == OracleType::Pyth && price_account.is_none() {
-        return Ok(());
+        return Ok(0);
     }
 
+    validate_oracle_type_cfg(price_type, price_account, twap_source, generic_data)?;
+
     match price_type {
-        OracleType::Pyth => pyth::validate_pyth_price_info(price_account),
-        OracleType::PythPullBased => pyth_pull_based::validate_price_update_v2_info(price_account),
-        OracleType::PythPullBasedEMA => {
+        OracleType::JupiterLpCompute | OracleType::JupiterLpScope => {
+            jupiter_lp::get_recompute_cu_budget(price_account)
+        }
+        _ => Ok(price_type.get_update_cu_budget()),
+    }
+}
+
+/// Whether [`validate_oracle_cfg`] checks the mapped account of the given oracle type, the mapped
+/// account of the other types (see the TODOs below) is accepted as is
+pub fn is_mapped_account_validated(price_type: OracleType) -> bool {
+    !matches!(
+        price_type,
+        OracleType::SwitchboardV2
+            | OracleType::CToken
+            | OracleType::KToken
+            | OracleType::KTokenToTokenA
+            | OracleType::KTokenToTokenB
+    )
+}
+
+fn validate_oracle_type_cfg(
+    price_type: OracleType,
+    price_account: &Option<AccountInfo>,
+    twap_source: u16,
+    generic_data: &[u8; 20],
+) -> crate::Result<()> {
+    match price_type {
+        OracleType::Pyth => {
+            pyth::validate_push_generic_data(generic_data)?;
+            pyth::validate_pyth_price_info(price_account)
+        }
+        OracleType::PythPullBased | OracleType::PythPullBasedEMA => {
+            pyth::validate_generic_data(generic_data)?;
             pyth_pull_based::validate_price_update_v2_info(price_account)
         }
         OracleType::SwitchboardOnDemand => {
-            switchboard_on_demand::validate_price_account(price_account)
+            switchboard_on_demand::validate_price_account(price_account, generic_data)
         }
         OracleType::SwitchboardV2 => Ok(()), // TODO at least check account ownership?
-        OracleType::CToken => Ok(()),        // TODO how shall we validate ctoken account?
-        OracleType::SplStake => Ok(()),
+        OracleType::CToken => ctokens::validate_mapping_cfg(generic_data), // TODO how shall we validate ctoken account?
+        OracleType::SplStake => spl_stake::validate_mapping_cfg(price_account, generic_data),
         OracleType::KToken => Ok(()), // TODO, should validate ownership of the ktoken account
         OracleType::KTokenToTokenA => Ok(()), // TODO, should validate ownership of the ktoken account
         OracleType::KTokenToTokenB => Ok(()), // TODO, should validate ownership of the ktoken account
-        OracleType::PythEMA => pyth::validate_pyth_price_info(price_account),
-        OracleType::MsolStake => Ok(()),
+        OracleType::PythEMA => {
+            pyth::validate_generic_data(generic_data)?;
+            pyth::validate_pyth_price_info(price_account)
+        }
+        OracleType::MsolStake => msol_stake::validate_mapping_cfg(price_account, generic_data),
         OracleType::JupiterLpFetch | OracleType::JupiterLpCompute | OracleType::JupiterLpScope => {
             jupiter_lp::validate_jlp_pool(price_account)
         }
-        OracleType::ScopeTwap => twap::validate_price_account(price_account, twap_source),
+        OracleType::ScopeTwap => {
+            twap::validate_price_account(price_account, twap_source, generic_data)
+        }
         OracleType::OrcaWhirlpoolAtoB | OracleType::OrcaWhirlpoolBtoA => {
+            pool_liquidity::ClmmLiquidityGuard::from_generic_data(generic_data)?;
             orca_whirlpool::validate_pool_account(price_account)
         }
         OracleType::RaydiumAmmV3AtoB | OracleType::RaydiumAmmV3BtoA => {
+            pool_liquidity::ClmmLiquidityGuard::from_generic_data(generic_data)?;
             raydium_ammv3::validate_pool_account(price_account)
         }
+        OracleType::RaydiumAmmV3TwapAtoB | OracleType::RaydiumAmmV3TwapBtoA => {
+            raydium_ammv3_twap::validate_mapping_cfg(price_account, generic_data)
+        }
         OracleType::MeteoraDlmmAtoB | OracleType::MeteoraDlmmBtoA => {
+            pool_liquidity::DlmmReservesGuard::from_generic_data(generic_data)?;
             meteora_dlmm::validate_pool_account(price_account)
         }
+        OracleType::RaydiumCpAtoB | OracleType::RaydiumCpBtoA => {
+            raydium_cpmm::validate_mapping_cfg(price_account, generic_data)
+        }
         OracleType::FixedPrice => {
             if price_account.is_some() {
                 msg!("No account is expected with a fixed price oracle");
@@ -334,9 +798,103 @@ pub fn validate_oracle_cfg(
                 .map_err(|_| error!(ScopeError::FixedPriceInvalid))?;
             Ok(())
         }
-        OracleType::JitoRestaking => jito_restaking::validate_account(price_account),
+        OracleType::JitoRestaking => {
+            jito_restaking::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::StakingRate => staking_rate::validate_mapping_cfg(price_account, generic_data),
+        OracleType::CappedFloored => {
+            capped_floored::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::MedianOf => median_of::validate_mapping_cfg(price_account, generic_data),
+        OracleType::MostRecentOf => {
+            most_recent_of::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::DiscountToMaturity => {
+            discount_to_maturity::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::FixedRateAccrual => {
+            fixed_rate_accrual::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::FixedMultiplier => {
+            fixed_multiplier::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::AnnualizedGrowthRate => {
+            annualized_growth_rate::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::GenericVaultShare => {
+            generic_vault_share::validate_mapping_cfg(price_account, generic_data)
+        }
+        OracleType::SanctumInf => sanctum_inf::validate_pool_account(price_account),
+        OracleType::MeteoraVault => meteora_vault::validate_vault_account(price_account),
+        OracleType::OrcaWhirlpoolPosition => {
+            orca_whirlpool_position::validate_mapping_cfg(price_account, generic_data)
+        }
         OracleType::DeprecatedPlaceholder1 | OracleType::DeprecatedPlaceholder2 => {
-            panic!("DeprecatedPlaceholder is not a valid oracle type")
+            msg!("DeprecatedPlaceholder is not a valid oracle type");
+            err!(ScopeError::BadTokenType)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_unique_accounts() {
+        let [prices, mappings, twaps] = [(); 3].map(|_| Pubkey::new_unique());
+        assert!(check_unique_accounts(&[prices, mappings, twaps]).is_ok());
+        // The same account under two roles (e.g. prices aliased as twaps)
+        assert_eq!(
+            check_unique_accounts(&[prices, mappings, prices]).unwrap_err(),
+            error!(ScopeError::DuplicateAccount)
+        );
+        assert_eq!(
+            check_unique_accounts(&[prices, twaps, twaps]).unwrap_err(),
+            error!(ScopeError::DuplicateAccount)
+        );
+    }
+
+    #[test]
+    fn test_check_remaining_accounts_exclude() {
+        let owner = crate::ID;
+        // prices, mappings, twaps, configuration and a kToken strategy
+        let keys = [(); 5].map(|_| Pubkey::new_unique());
+        let [prices, mappings, twaps, configuration, _] = keys;
+        let mut lamports = [0_u64; 5];
+        let mut data = [[0_u8; 8]; 5];
+        let infos = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+            })
+            .collect::<Vec<_>>();
+        let (prices_info, strategy_info) = (&infos[0], &infos[4]);
+        // Accounts excluded from the remaining accounts of a refresh
+        let refresh_accounts = [mappings, twaps, configuration];
+
+        // kToken style: the strategy refers to the refreshed feed prices as its scope prices
+        assert!(check_remaining_accounts_exclude(
+            &[strategy_info.clone(), prices_info.clone()],
+            &refresh_accounts
+        )
+        .is_ok());
+
+        for aliased in &infos[1..4] {
+            assert_eq!(
+                check_remaining_accounts_exclude(
+                    &[strategy_info.clone(), aliased.clone()],
+                    &refresh_accounts
+                )
+                .unwrap_err(),
+                error!(ScopeError::InstructionAccountInRemainingAccounts)
+            );
         }
+        // Handlers not reading the prices of their feed exclude them too
+        assert!(
+            check_remaining_accounts_exclude(&[prices_info.clone()], &[prices, mappings]).is_err()
+        );
     }
 }
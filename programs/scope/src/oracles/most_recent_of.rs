@@ -0,0 +1,510 @@
+use std::cmp::Reverse;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    scope_chain::{hop_entry, is_divide_hop},
+    utils::{clock_unix_timestamp, consts::FULL_BPS, price_impl::check_price_deviation_bps},
+    DatedPrice, OracleEntryStats, OraclePrices, ScopeError, MAX_ENTRIES_U16,
+};
+
+/// Maximum number of sources that fit in the entry generic data
+pub const MAX_MOST_RECENT_OF_SOURCES: usize = 4;
+
+/// Configuration of a most recent entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MostRecentOfData {
+    /// Entries the price is taken from, the unused slots are set to `MAX_ENTRIES_U16`.
+    ///
+    /// A source published in the opposite direction is inverted (1/price) when the high bit of its
+    /// entry is set, as a dividing hop of a scope chain (see [`crate::scope_chain::divide_hop`]).
+    pub source_entries: [u16; MAX_MOST_RECENT_OF_SOURCES],
+    /// Maximum divergence of each source versus the most recent price, in bps of the most recent
+    /// price, so that noisier sources can be given a wider band
+    pub max_divergence_bps: [u16; MAX_MOST_RECENT_OF_SOURCES],
+    /// Maximum age of a source price for the source to be considered fresh
+    pub sources_max_age_s: u16,
+    /// Minimum number of fresh sources required to publish a price
+    pub min_fresh_sources: u8,
+    /// When set, the sources as fresh as each other are ranked by the health recorded in the
+    /// entry stats of the feed, which must then be provided as extra account
+    pub prefer_healthy_sources: bool,
+}
+
+impl MostRecentOfData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::MostRecentOfInvalid))
+    }
+
+    /// Configured sources along with their maximum divergence
+    pub fn sources(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.source_entries
+            .iter()
+            .zip(self.max_divergence_bps.iter())
+            .filter(|(&entry, _)| entry != MAX_ENTRIES_U16)
+            .map(|(&entry, &max_divergence_bps)| (entry, max_divergence_bps))
+    }
+
+    /// Entries of the configured sources, without their direction
+    pub fn source_entry_ids(&self) -> impl Iterator<Item = u16> + '_ {
+        self.sources().map(|(entry, _)| hop_entry(entry))
+    }
+}
+
+/// Get the most recent price among the fresh source entries.
+///
+/// If `prefer_healthy_sources` is set, the most recent sources updated at the same time are
+/// ranked by their health: a source that is not degraded, then with the fewest consecutive
+/// failures, is preferred.
+///
+/// Fails with [`ScopeError::MostRecentOfStaleSources`] if less than `min_fresh_sources` sources
+/// are fresh, and with [`ScopeError::MostRecentOfDivergence`] if a fresh source diverges from the
+/// most recent price by more than its own maximum divergence.
+pub fn get_price<'a, 'b>(
+    oracle_prices_pk: &Pubkey,
+    oracle_prices: &OraclePrices,
+    generic_data: &[u8; 20],
+    clock: &Clock,
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    let data = MostRecentOfData::from_generic_data(generic_data)?;
+    let stats_loader = if data.prefer_healthy_sources {
+        let stats_acc = extra_accounts
+            .next()
+            .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+        let stats_loader = AccountLoader::<OracleEntryStats>::try_from(stats_acc)?;
+        require_keys_eq!(
+            stats_loader.load()?.oracle_prices,
+            *oracle_prices_pk,
+            ScopeError::UnexpectedAccount
+        );
+        Some(stats_loader)
+    } else {
+        None
+    };
+    let stats = stats_loader
+        .as_ref()
+        .map(|loader| loader.load())
+        .transpose()?;
+    // Higher is healthier, all the sources are as healthy when the health is not considered
+    let source_health = |entry: u16| {
+        stats.as_ref().map_or((true, Reverse(0)), |stats| {
+            let entry = usize::from(entry);
+            (
+                !stats.is_degraded(entry),
+                Reverse(stats.entries[entry].consecutive_failures),
+            )
+        })
+    };
+    let current_ts: u64 = clock_unix_timestamp(clock)?;
+
+    let fresh_prices = data
+        .sources()
+        .map(|(source, max_divergence_bps)| {
+            let entry = hop_entry(source);
+            let dated_price = oracle_prices
+                .prices
+                .get(usize::from(entry))
+                .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+            Ok((
+                entry,
+                is_divide_hop(source),
+                max_divergence_bps,
+                *dated_price,
+            ))
+        })
+        .filter(|source| {
+            source.as_ref().map_or(true, |(_, _, _, dated_price)| {
+                dated_price.price.value != 0
+                    && current_ts.saturating_sub(dated_price.unix_timestamp)
+                        <= u64::from(data.sources_max_age_s)
+            })
+        })
+        .map(|source| {
+            let (entry, inverted, max_divergence_bps, mut dated_price) = source?;
+            if inverted {
+                dated_price.price = dated_price.price.inverse()?;
+            }
+            Ok((entry, max_divergence_bps, dated_price))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let min_fresh_sources = usize::from(data.min_fresh_sources.max(1));
+    if fresh_prices.len() < min_fresh_sources {
+        msg!(
+            "Only {} fresh sources, {min_fresh_sources} required",
+            fresh_prices.len()
+        );
+        return err!(ScopeError::MostRecentOfStaleSources);
+    }
+
+    let (most_recent_entry, _, most_recent) = *fresh_prices
+        .iter()
+        .max_by_key(|(entry, _, dated_price)| {
+            (
+                dated_price.unix_timestamp,
+                dated_price.last_updated_slot,
+                source_health(*entry),
+            )
+        })
+        .ok_or_else(|| error!(ScopeError::MostRecentOfStaleSources))?;
+
+    for (entry, max_divergence_bps, dated_price) in fresh_prices.iter() {
+        if *entry == most_recent_entry {
+            continue;
+        }
+        check_price_deviation_bps(dated_price.price, most_recent.price, *max_divergence_bps)
+            .map_err(|_| {
+                msg!("Source entry {entry} diverges from the most recent source entry {most_recent_entry}");
+                error!(ScopeError::MostRecentOfDivergence)
+            })?;
+    }
+
+    Ok(DatedPrice {
+        price: most_recent.price,
+        last_updated_slot: most_recent.last_updated_slot,
+        unix_timestamp: most_recent.unix_timestamp,
+        confidence_bps: most_recent.confidence_bps,
+        ..Default::default()
+    })
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    if account.is_some() {
+        msg!("No account is expected with a most recent oracle");
+        return err!(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = MostRecentOfData::from_generic_data(generic_data)?;
+
+    let sources_count = data.sources().count();
+    if sources_count == 0 {
+        msg!("Most recent requires at least one source");
+        return err!(ScopeError::MostRecentOfInvalid);
+    }
+    if usize::from(data.min_fresh_sources) > sources_count {
+        msg!(
+            "{} fresh sources required out of {sources_count} sources",
+            data.min_fresh_sources
+        );
+        return err!(ScopeError::MostRecentOfInvalid);
+    }
+    if data.sources_max_age_s == 0 {
+        msg!("Most recent sources max age must be set");
+        return err!(ScopeError::MostRecentOfInvalid);
+    }
+    if data
+        .source_entry_ids()
+        .any(|entry| entry >= MAX_ENTRIES_U16)
+    {
+        msg!("Most recent source entries must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::MostRecentOfInvalid);
+    }
+    if data
+        .sources()
+        .any(|(_, max_divergence_bps)| max_divergence_bps == 0 || max_divergence_bps > FULL_BPS)
+    {
+        msg!("Most recent sources max divergence must be between 1 and {FULL_BPS} bps");
+        return err!(ScopeError::MostRecentOfInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{
+        Discriminator,
+        __private::bytemuck::{self, Zeroable},
+    };
+
+    use super::*;
+    use crate::{
+        oracles::test_utils::{check_validate_mapping_cfg, clock, dated_price, price},
+        scope_chain::divide_hop,
+        EntryStatus, Price,
+    };
+
+    const NOW: i64 = 1010;
+
+    fn generic_data(
+        sources: &[(u16, u16)],
+        sources_max_age_s: u16,
+        min_fresh_sources: u8,
+        prefer_healthy_sources: bool,
+    ) -> [u8; 20] {
+        let mut source_entries = [MAX_ENTRIES_U16; MAX_MOST_RECENT_OF_SOURCES];
+        let mut max_divergence_bps = [0; MAX_MOST_RECENT_OF_SOURCES];
+        for (i, &(entry, divergence)) in sources.iter().enumerate() {
+            source_entries[i] = entry;
+            max_divergence_bps[i] = divergence;
+        }
+        crate::oracles::test_utils::generic_data(&MostRecentOfData {
+            source_entries,
+            max_divergence_bps,
+            sources_max_age_s,
+            min_fresh_sources,
+            prefer_healthy_sources,
+        })
+    }
+
+    /// Prices of the entries 0, 1, 2... given as (price, slot, unix timestamp)
+    fn oracle_prices(prices: &[(Price, u64, u64)]) -> Box<OraclePrices> {
+        crate::oracles::test_utils::oracle_prices((0..).zip(prices).map(
+            |(entry, &(price, last_updated_slot, unix_timestamp))| {
+                (entry, dated_price(price, last_updated_slot, unix_timestamp))
+            },
+        ))
+    }
+
+    fn get_test_price(oracle_prices: &OraclePrices, generic_data: &[u8; 20]) -> Result<DatedPrice> {
+        get_price(
+            &Pubkey::new_unique(),
+            oracle_prices,
+            generic_data,
+            &clock(200, NOW),
+            &mut std::iter::empty(),
+        )
+    }
+
+    #[test]
+    fn test_most_recent_source() {
+        let oracle_prices = oracle_prices(&[
+            (price(100, 2), 100, 1000),
+            (price(101, 2), 102, 1002),
+            (price(99, 2), 101, 1001),
+        ]);
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(0, 500), (1, 500), (2, 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+        assert_eq!(price.last_updated_slot, 102);
+        assert_eq!(price.unix_timestamp, 1002);
+    }
+
+    #[test]
+    fn test_ties() {
+        // Same timestamp: the most recent slot wins
+        let slot_tie = oracle_prices(&[(price(100, 2), 101, 1000), (price(101, 2), 100, 1000)]);
+        let most_recent = get_test_price(
+            &slot_tie,
+            &generic_data(&[(0, 500), (1, 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(most_recent.price, price(100, 2));
+
+        // Same timestamp and slot: the last configured source wins
+        let full_tie = oracle_prices(&[(price(100, 2), 100, 1000), (price(101, 2), 100, 1000)]);
+        let most_recent = get_test_price(
+            &full_tie,
+            &generic_data(&[(0, 500), (1, 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(most_recent.price, price(101, 2));
+        let most_recent = get_test_price(
+            &full_tie,
+            &generic_data(&[(1, 500), (0, 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(most_recent.price, price(100, 2));
+    }
+
+    #[test]
+    fn test_stale_sources() {
+        // Entry 2 has no price
+        let oracle_prices = oracle_prices(&[
+            (price(100, 2), 100, 900),
+            (price(101, 2), 101, 949),
+            (price(0, 0), 102, 1005),
+            (price(102, 2), 103, 1000),
+        ]);
+
+        // All sources too old (or without price)
+        assert_eq!(
+            get_test_price(
+                &oracle_prices,
+                &generic_data(&[(0, 500), (1, 500), (2, 500)], 60, 1, false)
+            )
+            .unwrap_err(),
+            error!(ScopeError::MostRecentOfStaleSources)
+        );
+
+        // A source at exactly the max age is fresh
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(0, 500), (1, 500), (2, 500)], 61, 1, false),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 101, exp: 2 });
+
+        // Not enough fresh sources
+        assert_eq!(
+            get_test_price(
+                &oracle_prices,
+                &generic_data(&[(0, 500), (1, 500), (3, 500)], 60, 2, false)
+            )
+            .unwrap_err(),
+            error!(ScopeError::MostRecentOfStaleSources)
+        );
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(0, 500), (1, 500), (3, 500)], 61, 2, false),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 102, exp: 2 });
+    }
+
+    #[test]
+    fn test_divergence() {
+        let oracle_prices = oracle_prices(&[
+            (price(100, 2), 100, 1000),
+            (price(104, 2), 101, 1001),
+            (price(150, 2), 90, 900),
+        ]);
+
+        // 4% from the most recent price
+        assert_eq!(
+            get_test_price(
+                &oracle_prices,
+                &generic_data(&[(0, 300), (1, 300)], 60, 1, false)
+            )
+            .unwrap_err(),
+            error!(ScopeError::MostRecentOfDivergence)
+        );
+        // Each source has its own band, the one of the most recent source is not used
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(0, 400), (1, 300)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 104, exp: 2 });
+
+        // Stale sources are not checked
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(0, 400), (1, 300), (2, 1)], 60, 1, false),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 104, exp: 2 });
+    }
+
+    #[test]
+    fn test_inverted_sources() {
+        // Entry 0 is quoted in the opposite direction: 1 / 2.02 = 0.495
+        let oracle_prices = oracle_prices(&[(price(202, 2), 101, 1001), (price(50, 2), 100, 1000)]);
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(divide_hop(0), 500), (1, 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert!((f64::from(price.price) - 1.0 / 2.02).abs() < 1e-12);
+        assert_eq!(price.last_updated_slot, 101);
+
+        // The divergence is checked on the inverted price
+        let price = get_test_price(
+            &oracle_prices,
+            &generic_data(&[(1, 500), (divide_hop(0), 500)], 60, 1, false),
+        )
+        .unwrap();
+        assert!((f64::from(price.price) - 1.0 / 2.02).abs() < 1e-12);
+        assert_eq!(
+            get_test_price(
+                &oracle_prices,
+                &generic_data(&[(0, 500), (1, 500)], 60, 1, false)
+            )
+            .unwrap_err(),
+            error!(ScopeError::MostRecentOfDivergence)
+        );
+    }
+
+    #[test]
+    fn test_prefer_healthy_sources() {
+        let oracle_prices_pk = Pubkey::new_unique();
+        let oracle_prices = oracle_prices(&[
+            (price(100, 2), 100, 1000),
+            (price(101, 2), 100, 1000),
+            (price(102, 2), 100, 1000),
+        ]);
+        let generic_data = generic_data(&[(0, 500), (1, 500), (2, 500)], 60, 1, true);
+
+        let mut stats: Box<OracleEntryStats> = Box::new(Zeroable::zeroed());
+        stats.oracle_prices = oracle_prices_pk;
+        stats.entries[2].status = EntryStatus::Degraded.into();
+        stats.entries[1].consecutive_failures = 1;
+        let mut stats_data = OracleEntryStats::DISCRIMINATOR.to_vec();
+        stats_data.extend_from_slice(bytemuck::bytes_of(stats.as_ref()));
+        let stats_pk = Pubkey::new_unique();
+        let mut lamports = 0;
+        let stats_acc = AccountInfo::new(
+            &stats_pk,
+            false,
+            false,
+            &mut lamports,
+            &mut stats_data,
+            &crate::ID,
+            false,
+            0,
+        );
+
+        // Entry 2 is degraded and entry 1 failed its last refresh
+        let price = get_price(
+            &oracle_prices_pk,
+            &oracle_prices,
+            &generic_data,
+            &clock(200, NOW),
+            &mut std::iter::once(&stats_acc),
+        )
+        .unwrap();
+        assert_eq!(price.price, Price { value: 100, exp: 2 });
+
+        // Stats of another feed
+        assert_eq!(
+            get_price(
+                &Pubkey::new_unique(),
+                &oracle_prices,
+                &generic_data,
+                &clock(200, NOW),
+                &mut std::iter::once(&stats_acc),
+            )
+            .unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        assert_eq!(
+            get_test_price(&oracle_prices, &generic_data).unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+    }
+
+    #[test]
+    fn test_validate_mapping_cfg() {
+        check_validate_mapping_cfg(
+            validate_mapping_cfg,
+            &[
+                generic_data(&[(0, 100), (1, 200)], 60, 2, false),
+                generic_data(&[(divide_hop(0), 100)], 60, 1, false),
+            ],
+            &[
+                // No source
+                generic_data(&[], 60, 0, false),
+                // More fresh sources required than configured
+                generic_data(&[(0, 100), (1, 200)], 60, 3, false),
+                // No max age
+                generic_data(&[(0, 100), (1, 200)], 0, 1, false),
+                // Out of range entry
+                generic_data(&[(0, 100), (MAX_ENTRIES_U16 + 1, 200)], 60, 1, false),
+                // No divergence band
+                generic_data(&[(0, 100), (1, 0)], 60, 1, false),
+                generic_data(&[(0, 100), (1, FULL_BPS + 1)], 60, 1, false),
+            ],
+            ScopeError::MostRecentOfInvalid,
+        );
+    }
+}
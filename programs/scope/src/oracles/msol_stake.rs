@@ -1,27 +1,71 @@
 use anchor_lang::prelude::*;
-use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::{borsh0_10::try_from_slice_unchecked, pubkey};
 
 use self::msol_stake_pool::State;
-use crate::{DatedPrice, Price, ScopeError, ScopeResult};
+use super::staking_rate::StakingRateSample;
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS, math},
+    DatedPrice, Price, ScopeError, ScopeResult,
+};
 
 const DECIMALS: u32 = 15u32;
 
+const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+const LIQ_POOL_SOL_LEG_SEED: &[u8] = b"liq_sol";
+
+/// Exit of the mSOL holder the price is computed for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MsolStakeMode {
+    /// SOL received through a delayed unstake (ticket), i.e. the mSOL share of the staked SOL
+    #[default]
+    DelayedUnstake,
+    /// SOL received through an instant liquid unstake, net of the current fee of the Marinade
+    /// liquidity pool. Requires the SOL leg of the liquidity pool as extra account.
+    InstantExit,
+}
+
+/// Configuration of a mSOL entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MsolStakeData {
+    pub mode: MsolStakeMode,
+}
+
+impl MsolStakeData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data).map_err(|_| error!(ScopeError::MsolStakeInvalid))
+    }
+}
+
 // Gives the price of 1 staked SOL in SOL
-pub fn get_price(
+pub fn get_price<'a, 'b>(
     msol_pool_account_info: &AccountInfo,
     current_clock: &Clock,
-) -> ScopeResult<DatedPrice> {
-    let stake_pool = try_from_slice_unchecked::<State>(&msol_pool_account_info.data.borrow()[8..])
-        .map_err(|_| {
-            msg!("Provided pubkey is not a valid MSOL Stake account");
-            ScopeError::UnexpectedAccount
-        })?;
-
-    let value = scaled_rate(&stake_pool).map_err(|e| {
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    let data = MsolStakeData::from_generic_data(generic_data)?;
+    let stake_pool = load_state(msol_pool_account_info)?;
+
+    let mut value = scaled_rate(&stake_pool).map_err(|e| {
         msg!("Error while calculating the scaled rate: {:?}", e);
         e
     })?;
 
+    if data.mode == MsolStakeMode::InstantExit {
+        let sol_leg = extra_accounts
+            .next()
+            .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+        let fee_bps = liquid_unstake_fee_bps(msol_pool_account_info, &stake_pool, sol_leg)?;
+        // Cannot overflow as the fee is at most `FULL_BPS`
+        value = u64::try_from(math::mul_bps(value, FULL_BPS.saturating_sub(fee_bps)))
+            .map_err(|_| ScopeError::IntegerOverflow)?;
+    }
+
     let price = Price {
         value,
         exp: DECIMALS.into(),
@@ -29,19 +73,113 @@ pub fn get_price(
     let dated_price = DatedPrice {
         price,
         last_updated_slot: current_clock.slot,
-        unix_timestamp: u64::try_from(current_clock.unix_timestamp).unwrap(),
+        unix_timestamp: clock_unix_timestamp(current_clock)?,
         ..Default::default()
     };
 
     Ok(dated_price)
 }
 
+/// Staking rate of Marinade, see [`super::staking_rate`]
+///
+/// The fees of Marinade are not part of the known state layout and the state does not track its
+/// last update epoch: only the rate is provided.
+pub fn get_staking_rate_sample(
+    msol_pool_account_info: &AccountInfo,
+) -> ScopeResult<StakingRateSample> {
+    let stake_pool = load_state(msol_pool_account_info)?;
+
+    Ok(StakingRateSample {
+        rate: Price {
+            value: scaled_rate(&stake_pool)?,
+            exp: DECIMALS.into(),
+        },
+        withdrawal_fee_bps: 0,
+        deposit_fee_bps: 0,
+        last_update_epoch: None,
+    })
+}
+
+pub fn validate_state_account(msol_pool_account: &Option<AccountInfo>) -> Result<()> {
+    let Some(msol_pool_account) = msol_pool_account else {
+        msg!("No Marinade state account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    if *msol_pool_account.owner != MARINADE_PROGRAM_ID {
+        msg!(
+            "Marinade state account is owned by an unknown program {}",
+            msol_pool_account.owner
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
+    let _ = load_state(msol_pool_account)?;
+    Ok(())
+}
+
+pub fn validate_mapping_cfg(
+    msol_pool_account: &Option<AccountInfo>,
+    generic_data: &[u8; 20],
+) -> Result<()> {
+    let _ = MsolStakeData::from_generic_data(generic_data)?;
+    validate_state_account(msol_pool_account)
+}
+
+/// Fee currently taken by the Marinade liquidity pool on a (small) liquid unstake.
+///
+/// The fee decreases linearly from the max fee, with no SOL in the pool, to the min fee once the
+/// SOL available in the pool reaches the liquidity target.
+fn liquid_unstake_fee_bps(
+    msol_pool_account_info: &AccountInfo,
+    stake_pool: &State,
+    sol_leg: &AccountInfo,
+) -> Result<u16> {
+    let liq_pool = &stake_pool.liq_pool;
+    let expected_sol_leg = Pubkey::create_program_address(
+        &[
+            msol_pool_account_info.key.as_ref(),
+            LIQ_POOL_SOL_LEG_SEED,
+            &[liq_pool.sol_leg_bump_seed],
+        ],
+        &MARINADE_PROGRAM_ID,
+    )
+    .map_err(|_| error!(ScopeError::UnexpectedAccount))?;
+    require_keys_eq!(
+        expected_sol_leg,
+        sol_leg.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    let available_lamports = sol_leg
+        .lamports()
+        .saturating_sub(stake_pool.rent_exempt_for_token_acc);
+    let max_fee_bps = liq_pool.lp_max_fee.basis_points;
+    let min_fee_bps = liq_pool.lp_min_fee.basis_points.min(max_fee_bps);
+    let fee_bps = if available_lamports >= liq_pool.lp_liquidity_target {
+        min_fee_bps
+    } else {
+        let fee_decrease = u128::from(max_fee_bps - min_fee_bps) * u128::from(available_lamports)
+            / u128::from(liq_pool.lp_liquidity_target);
+        // Lower than `max_fee_bps` as `available_lamports < lp_liquidity_target`
+        max_fee_bps.saturating_sub(u32::try_from(fee_decrease).unwrap_or(u32::MAX))
+    };
+    Ok(u16::try_from(fee_bps).unwrap_or(u16::MAX).min(FULL_BPS))
+}
+
+fn load_state(msol_pool_account_info: &AccountInfo) -> ScopeResult<State> {
+    let data = msol_pool_account_info.data.borrow();
+    let state_data = data.get(8..).ok_or(ScopeError::UnexpectedAccount)?;
+    try_from_slice_unchecked::<State>(state_data).map_err(|_| {
+        msg!("Provided pubkey is not a valid MSOL Stake account");
+        ScopeError::UnexpectedAccount
+    })
+}
+
 fn scaled_rate(stake_pool: &State) -> ScopeResult<u64> {
     const FACTOR: u64 = 10u64.pow(DECIMALS);
     stake_pool.calc_lamports_from_msol_amount(FACTOR)
 }
 
-mod msol_stake_pool {
+pub(crate) mod msol_stake_pool {
     use anchor_lang::prelude::borsh::BorshSchema;
 
     /// calculate amount*numerator/denominator
@@ -225,3 +363,176 @@ mod msol_stake_pool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        msol_stake_pool::{Fee, LiqPool, ValidatorSystem},
+        *,
+    };
+    use crate::oracles::test_utils::{clock, generic_data};
+
+    const RENT_EXEMPT_LAMPORTS: u64 = 1_000;
+    const LIQUIDITY_TARGET: u64 = 10_000;
+    /// Rate of 1.1 SOL per mSOL
+    const DELAYED_UNSTAKE_RATE: u64 = 1_100_000_000_000_000;
+
+    fn state(sol_leg_bump_seed: u8) -> State {
+        State {
+            rent_exempt_for_token_acc: RENT_EXEMPT_LAMPORTS,
+            validator_system: ValidatorSystem {
+                total_active_balance: 1_100_000,
+                ..Default::default()
+            },
+            liq_pool: LiqPool {
+                sol_leg_bump_seed,
+                lp_liquidity_target: LIQUIDITY_TARGET,
+                lp_max_fee: Fee { basis_points: 300 },
+                lp_min_fee: Fee { basis_points: 30 },
+                ..Default::default()
+            },
+            msol_supply: 1_000_000,
+            ..Default::default()
+        }
+    }
+
+    fn state_data(state: &State) -> Vec<u8> {
+        let mut data = vec![0; 8];
+        data.extend(state.try_to_vec().unwrap());
+        data
+    }
+
+    /// Price of the mSOL entry with the SOL leg of the liquidity pool holding `sol_leg_lamports`,
+    /// the SOL leg being at `sol_leg_key` if provided, at its address otherwise
+    fn get_test_price(
+        mode: MsolStakeMode,
+        sol_leg_lamports: u64,
+        sol_leg_key: Option<Pubkey>,
+    ) -> Result<DatedPrice> {
+        let state_key = Pubkey::new_unique();
+        let (expected_sol_leg_key, sol_leg_bump_seed) = Pubkey::find_program_address(
+            &[state_key.as_ref(), LIQ_POOL_SOL_LEG_SEED],
+            &MARINADE_PROGRAM_ID,
+        );
+        let sol_leg_key = sol_leg_key.unwrap_or(expected_sol_leg_key);
+        let mut state_lamports = 0;
+        let mut data = state_data(&state(sol_leg_bump_seed));
+        let state_account = AccountInfo::new(
+            &state_key,
+            false,
+            false,
+            &mut state_lamports,
+            &mut data,
+            &MARINADE_PROGRAM_ID,
+            false,
+            0,
+        );
+        let mut sol_leg_lamports = sol_leg_lamports;
+        let mut sol_leg_data: [u8; 0] = [];
+        let sol_leg = AccountInfo::new(
+            &sol_leg_key,
+            false,
+            false,
+            &mut sol_leg_lamports,
+            &mut sol_leg_data,
+            &MARINADE_PROGRAM_ID,
+            false,
+            0,
+        );
+        get_price(
+            &state_account,
+            &clock(100, 1_700_000_000),
+            &mut [sol_leg].iter(),
+            &generic_data(&MsolStakeData { mode }),
+        )
+    }
+
+    #[test]
+    fn test_delayed_unstake() {
+        let price = get_test_price(MsolStakeMode::DelayedUnstake, 0, None).unwrap();
+        assert_eq!(price.price.value, DELAYED_UNSTAKE_RATE);
+        assert_eq!(price.price.exp, u64::from(DECIMALS));
+        assert_eq!(price.last_updated_slot, 100);
+    }
+
+    #[test]
+    fn test_instant_exit_fee() {
+        let instant_exit_rate = |available_lamports: u64| {
+            get_test_price(
+                MsolStakeMode::InstantExit,
+                RENT_EXEMPT_LAMPORTS + available_lamports,
+                None,
+            )
+            .unwrap()
+            .price
+            .value
+        };
+        // Max fee of 3% with an empty pool
+        assert_eq!(instant_exit_rate(0), 1_067_000_000_000_000);
+        // Halfway to the liquidity target: 3% - (3% - 0.3%) / 2
+        assert_eq!(
+            instant_exit_rate(LIQUIDITY_TARGET / 2),
+            1_081_850_000_000_000
+        );
+        // Min fee of 0.3% at and above the liquidity target
+        assert_eq!(instant_exit_rate(LIQUIDITY_TARGET), 1_096_700_000_000_000);
+        assert_eq!(
+            instant_exit_rate(2 * LIQUIDITY_TARGET),
+            1_096_700_000_000_000
+        );
+    }
+
+    #[test]
+    fn test_instant_exit_sol_leg_mismatch() {
+        assert_eq!(
+            get_test_price(
+                MsolStakeMode::InstantExit,
+                RENT_EXEMPT_LAMPORTS,
+                Some(Pubkey::new_unique())
+            )
+            .unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+    }
+
+    #[test]
+    fn test_generic_data() {
+        assert_eq!(
+            MsolStakeData::from_generic_data(&[0; 20]).unwrap().mode,
+            MsolStakeMode::DelayedUnstake
+        );
+        assert_eq!(
+            MsolStakeData::from_generic_data(&generic_data(&MsolStakeData {
+                mode: MsolStakeMode::InstantExit
+            }))
+            .unwrap()
+            .mode,
+            MsolStakeMode::InstantExit
+        );
+        let mut unknown_mode = [0; 20];
+        unknown_mode[0] = 2;
+        assert_eq!(
+            MsolStakeData::from_generic_data(&unknown_mode).unwrap_err(),
+            error!(ScopeError::MsolStakeInvalid)
+        );
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = state_data(&state(0));
+        let state_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &MARINADE_PROGRAM_ID,
+            false,
+            0,
+        );
+        validate_mapping_cfg(&Some(state_account.clone()), &[0; 20]).unwrap();
+        assert_eq!(
+            validate_mapping_cfg(&Some(state_account), &unknown_mode).unwrap_err(),
+            error!(ScopeError::MsolStakeInvalid)
+        );
+    }
+}
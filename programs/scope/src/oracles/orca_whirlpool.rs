@@ -1,18 +1,21 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::spl_token::state::Mint;
-use solana_program::program_pack::Pack;
 use whirlpool::state::Whirlpool;
 
+use super::pool_liquidity::ClmmLiquidityGuard;
 use crate::{
-    utils::{account_deserialize, math::sqrt_price_to_price},
+    utils::{account_deserialize, clock_unix_timestamp, math::sqrt_price_to_price, unpack_mint},
     DatedPrice, Result, ScopeError,
 };
 
 /// Gives the price of the given token pair in the given pool
+///
+/// The price is rejected if the in-range liquidity of the pool is below the minimum configured in
+/// the generic data, see [`ClmmLiquidityGuard`].
 pub fn get_price<'a, 'b>(
     a_to_b: bool,
     pool: &AccountInfo,
     clock: &Clock,
+    generic_data: &[u8; 20],
     extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
 ) -> Result<DatedPrice>
 where
@@ -28,6 +31,7 @@ where
 
     // Load main account
     let pool_data: Whirlpool = account_deserialize(pool)?;
+    ClmmLiquidityGuard::from_generic_data(generic_data)?.check(pool.key, pool_data.liquidity)?;
 
     // Check extra accounts pubkeys
     require_keys_eq!(
@@ -43,15 +47,9 @@ where
     );
 
     // Load extra accounts
-    let mint_a_decimals = {
-        let mint_borrow = mint_token_a_account_info.data.borrow();
-        Mint::unpack(&mint_borrow)?.decimals
-    };
+    let mint_a_decimals = unpack_mint(mint_token_a_account_info)?.decimals;
 
-    let mint_b_decimals = {
-        let mint_borrow = mint_token_b_account_info.data.borrow();
-        Mint::unpack(&mint_borrow)?.decimals
-    };
+    let mint_b_decimals = unpack_mint(mint_token_b_account_info)?.decimals;
 
     // Compute price
     let price = sqrt_price_to_price(
@@ -69,7 +67,7 @@ where
     Ok(DatedPrice {
         price,
         last_updated_slot: clock.slot,
-        unix_timestamp: clock.unix_timestamp as u64,
+        unix_timestamp: clock_unix_timestamp(clock)?,
         ..Default::default()
     })
 }
@@ -0,0 +1,433 @@
+use anchor_lang::prelude::*;
+use decimal_wad::{
+    common::{TryAdd, TryDiv, TryMul},
+    decimal::Decimal,
+};
+use raydium_amm_v3::libraries::U256;
+use whirlpool::{
+    manager::tick_manager::next_fee_growths_inside,
+    math::{get_amount_delta_a, get_amount_delta_b, sqrt_price_from_tick_index},
+    state::{Position, TickArray, Whirlpool},
+};
+
+use crate::{
+    utils::{
+        account_deserialize,
+        math::{price_to_sqrt_price, ten_pow},
+        unpack_mint, zero_copy_deserialize,
+    },
+    DatedPrice, OraclePrices, Price, Result, ScopeError, ScopeResult, MAX_ENTRIES_U16,
+};
+
+/// Configuration of an Orca position entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrcaWhirlpoolPositionData {
+    /// Entry giving the price of the pool token A
+    pub price_a_entry: u16,
+    /// Entry giving the price of the pool token B
+    pub price_b_entry: u16,
+}
+
+impl OrcaWhirlpoolPositionData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::OrcaWhirlpoolPositionInvalid))
+    }
+}
+
+/// Gives the value of an Orca whirlpool position (liquidity and uncollected fees) in the unit
+/// of the scope prices of the pool tokens
+///
+/// Rewards are excluded from the calculation as they are generally lower value/mcap and can be manipulated
+///
+/// The token amounts of the liquidity are computed at a sqrt price derived from the scope prices of
+/// the tokens, the sqrt price of the pool cannot be considered reliable
+///
+/// The price timestamp is taken from the least-recently updated of the two token prices
+///
+/// Required extra accounts:
+/// - The whirlpool of the position
+/// - The tick array containing the lower tick of the position
+/// - The tick array containing the upper tick of the position
+/// - Mint of the token A
+/// - Mint of the token B
+pub fn get_price<'a, 'b>(
+    position_acc: &AccountInfo,
+    oracle_prices: &OraclePrices,
+    generic_data: &[u8; 20],
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    // 1. Get accounts
+    let whirlpool_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let tick_array_lower_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let tick_array_upper_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let mint_a_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let mint_b_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let data = OrcaWhirlpoolPositionData::from_generic_data(generic_data)?;
+    let position: Position = account_deserialize(position_acc)?;
+    let pool: Whirlpool = account_deserialize(whirlpool_acc)?;
+
+    // 2. Check accounts
+    require_keys_eq!(
+        position.whirlpool,
+        whirlpool_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        pool.token_mint_a,
+        mint_a_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        pool.token_mint_b,
+        mint_b_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    let decimals_a = unpack_mint(mint_a_acc)?.decimals;
+    let decimals_b = unpack_mint(mint_b_acc)?.decimals;
+
+    // 3. Get token prices
+    let get_entry_price = |entry: u16| -> Result<DatedPrice> {
+        let price = oracle_prices
+            .prices
+            .get(usize::from(entry))
+            .copied()
+            .ok_or_else(|| error!(ScopeError::BadTokenNb))?;
+        if price.price.value == 0 {
+            msg!("Orca position token price entry {entry} has no price");
+            return err!(ScopeError::PriceNotValid);
+        }
+        Ok(price)
+    };
+    let price_a = get_entry_price(data.price_a_entry)?;
+    let price_b = get_entry_price(data.price_b_entry)?;
+
+    // 4. Compute the token amounts
+    let (fees_a, fees_b) = get_fees_owed(
+        &position,
+        &pool,
+        tick_array_lower_acc,
+        tick_array_upper_acc,
+        whirlpool_acc.key,
+    )?;
+
+    // 5. Compute the value of the position
+    let value = get_position_value(
+        &position,
+        (price_a.price, decimals_a),
+        (price_b.price, decimals_b),
+        (fees_a, fees_b),
+    )?;
+
+    let (last_updated_slot, unix_timestamp) = if price_a.unix_timestamp < price_b.unix_timestamp {
+        (price_a.last_updated_slot, price_a.unix_timestamp)
+    } else {
+        (price_b.last_updated_slot, price_b.unix_timestamp)
+    };
+
+    Ok(DatedPrice {
+        price: Price::try_from_decimal(value)?,
+        last_updated_slot,
+        unix_timestamp,
+        ..Default::default()
+    })
+}
+
+/// Value of the position liquidity and of its `fees` (amounts of token A and B) given the price and
+/// decimals of the two tokens
+fn get_position_value(
+    position: &Position,
+    (price_a, decimals_a): (Price, u8),
+    (price_b, decimals_b): (Price, u8),
+    (fees_a, fees_b): (u64, u64),
+) -> Result<Decimal> {
+    let price_a_in_b = Decimal::from(price_a)
+        .try_div(Decimal::from(price_b))
+        .map_err(|_| ScopeError::PriceNotValid)?;
+    let price_a_in_b = Price::try_from_decimal(price_a_in_b)?;
+    let sqrt_price = price_to_sqrt_price(price_a_in_b, decimals_a, decimals_b)?;
+    let (liquidity_a, liquidity_b) = get_liquidity_amounts(position, sqrt_price)?;
+
+    let amount_a = u128::from(liquidity_a) + u128::from(fees_a);
+    let amount_b = u128::from(liquidity_b) + u128::from(fees_b);
+    let value = token_value(price_a, amount_a, decimals_a)?
+        .try_add(token_value(price_b, amount_b, decimals_b)?)
+        .map_err(|_| ScopeError::MathOverflow)?;
+    Ok(value)
+}
+
+fn token_value(price: Price, amount: u128, decimals: u8) -> ScopeResult<Decimal> {
+    Ok(Decimal::from(price)
+        .try_mul(Decimal::from(amount))?
+        .try_div(Decimal::from(ten_pow(decimals)?))?)
+}
+
+/// Token amounts of the position liquidity at the given sqrt price
+fn get_liquidity_amounts(position: &Position, sqrt_price: u128) -> Result<(u64, u64)> {
+    let sqrt_price_lower = sqrt_price_from_tick_index(position.tick_lower_index);
+    let sqrt_price_upper = sqrt_price_from_tick_index(position.tick_upper_index);
+    let liquidity = position.liquidity;
+
+    let amounts = if sqrt_price <= sqrt_price_lower {
+        (
+            get_amount_delta_a(sqrt_price_lower, sqrt_price_upper, liquidity, false),
+            Ok(0),
+        )
+    } else if sqrt_price >= sqrt_price_upper {
+        (
+            Ok(0),
+            get_amount_delta_b(sqrt_price_lower, sqrt_price_upper, liquidity, false),
+        )
+    } else {
+        (
+            get_amount_delta_a(sqrt_price, sqrt_price_upper, liquidity, false),
+            get_amount_delta_b(sqrt_price_lower, sqrt_price, liquidity, false),
+        )
+    };
+
+    match amounts {
+        (Ok(amount_a), Ok(amount_b)) => Ok((amount_a, amount_b)),
+        _ => err!(ScopeError::MathOverflow),
+    }
+}
+
+/// Fees owed to the position, including the ones not yet accounted in the position
+fn get_fees_owed(
+    position: &Position,
+    pool: &Whirlpool,
+    tick_array_lower_acc: &AccountInfo,
+    tick_array_upper_acc: &AccountInfo,
+    whirlpool_pk: &Pubkey,
+) -> Result<(u64, u64)> {
+    let tick_array_lower = zero_copy_deserialize::<TickArray>(tick_array_lower_acc)?;
+    let tick_array_upper = zero_copy_deserialize::<TickArray>(tick_array_upper_acc)?;
+    require_keys_eq!(
+        tick_array_lower.whirlpool,
+        *whirlpool_pk,
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        tick_array_upper.whirlpool,
+        *whirlpool_pk,
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    let tick_lower = tick_array_lower
+        .get_tick(position.tick_lower_index, pool.tick_spacing)
+        .map_err(|_| {
+            msg!("Lower tick array does not contain the position lower tick");
+            error!(ScopeError::UnexpectedAccount)
+        })?;
+    let tick_upper = tick_array_upper
+        .get_tick(position.tick_upper_index, pool.tick_spacing)
+        .map_err(|_| {
+            msg!("Upper tick array does not contain the position upper tick");
+            error!(ScopeError::UnexpectedAccount)
+        })?;
+
+    let (fee_growth_inside_a, fee_growth_inside_b) = next_fee_growths_inside(
+        pool.tick_current_index,
+        tick_lower,
+        position.tick_lower_index,
+        tick_upper,
+        position.tick_upper_index,
+        pool.fee_growth_global_a,
+        pool.fee_growth_global_b,
+    );
+
+    // Fee growths are Q64.64 and wrap around
+    let fees_delta = |fee_growth_inside: u128, fee_growth_checkpoint: u128| -> Result<u64> {
+        let growth_delta = fee_growth_inside.wrapping_sub(fee_growth_checkpoint);
+        let fees = (U256::from(growth_delta) * U256::from(position.liquidity)) >> U256::from(64);
+        if fees > U256::from(u64::MAX) {
+            return err!(ScopeError::MathOverflow);
+        }
+        Ok(fees.as_u64())
+    };
+
+    let fees_a = position
+        .fee_owed_a
+        .checked_add(fees_delta(
+            fee_growth_inside_a,
+            position.fee_growth_checkpoint_a,
+        )?)
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?;
+    let fees_b = position
+        .fee_owed_b
+        .checked_add(fees_delta(
+            fee_growth_inside_b,
+            position.fee_growth_checkpoint_b,
+        )?)
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?;
+
+    Ok((fees_a, fees_b))
+}
+
+pub fn validate_mapping_cfg(position: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let Some(position) = position else {
+        msg!("No position account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let _: Position = account_deserialize(position)?;
+
+    let data = OrcaWhirlpoolPositionData::from_generic_data(generic_data)?;
+    if data.price_a_entry >= MAX_ENTRIES_U16 || data.price_b_entry >= MAX_ENTRIES_U16 {
+        msg!("Orca position price entries must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::OrcaWhirlpoolPositionInvalid);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+
+    fn position(tick_lower_index: i32, tick_upper_index: i32) -> Position {
+        Position {
+            liquidity: LIQUIDITY,
+            tick_lower_index,
+            tick_upper_index,
+            ..Default::default()
+        }
+    }
+
+    /// Amounts of the position when it is entirely made of token A, resp. token B
+    fn full_amounts(position: &Position) -> (u64, u64) {
+        let sqrt_price_lower = sqrt_price_from_tick_index(position.tick_lower_index);
+        let sqrt_price_upper = sqrt_price_from_tick_index(position.tick_upper_index);
+        (
+            get_amount_delta_a(sqrt_price_lower, sqrt_price_upper, LIQUIDITY, false).unwrap(),
+            get_amount_delta_b(sqrt_price_lower, sqrt_price_upper, LIQUIDITY, false).unwrap(),
+        )
+    }
+
+    fn price(value: u64, exp: u64) -> Price {
+        Price { value, exp }
+    }
+
+    fn to_f64(value: Decimal) -> f64 {
+        f64::from(Price::try_from_decimal(value).unwrap())
+    }
+
+    #[test]
+    fn test_liquidity_amounts() {
+        let position = position(-100, 100);
+        let (full_a, full_b) = full_amounts(&position);
+
+        // Below the range, the position is only token A
+        let below = sqrt_price_from_tick_index(-200);
+        assert_eq!(
+            get_liquidity_amounts(&position, below).unwrap(),
+            (full_a, 0)
+        );
+        let lower = sqrt_price_from_tick_index(-100);
+        assert_eq!(
+            get_liquidity_amounts(&position, lower).unwrap(),
+            (full_a, 0)
+        );
+
+        // Above the range, the position is only token B
+        let above = sqrt_price_from_tick_index(200);
+        assert_eq!(
+            get_liquidity_amounts(&position, above).unwrap(),
+            (0, full_b)
+        );
+
+        // In the middle of a symmetric range, the position holds about the same amount of both tokens
+        let (amount_a, amount_b) =
+            get_liquidity_amounts(&position, sqrt_price_from_tick_index(0)).unwrap();
+        assert!(amount_a > 0 && amount_a < full_a);
+        assert!(amount_b > 0 && amount_b < full_b);
+        assert!(amount_a.abs_diff(amount_b) <= amount_a / 1_000_000);
+    }
+
+    #[test]
+    fn test_position_value_in_range() {
+        let position = position(-100, 100);
+        let (amount_a, amount_b) =
+            get_liquidity_amounts(&position, sqrt_price_from_tick_index(0)).unwrap();
+
+        // Both tokens are worth 2 (and so A is worth 1 B)
+        let value =
+            get_position_value(&position, (price(2, 0), 6), (price(2, 0), 6), (0, 0)).unwrap();
+        let expected = 2.0 * (amount_a + amount_b) as f64 / 1e6;
+        assert!((to_f64(value) - expected).abs() < 1e-3);
+
+        // Fees are added to the liquidity amounts
+        let value_with_fees = get_position_value(
+            &position,
+            (price(2, 0), 6),
+            (price(2, 0), 6),
+            (1_000_000, 3_000_000),
+        )
+        .unwrap();
+        assert!((to_f64(value_with_fees) - expected - 8.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_position_value_out_of_range() {
+        let position = position(-100, 100);
+        let (full_a, full_b) = full_amounts(&position);
+
+        // A is worth 2 B, above the range: only token B
+        let value =
+            get_position_value(&position, (price(3, 0), 6), (price(15, 1), 6), (0, 0)).unwrap();
+        let expected = 1.5 * full_b as f64 / 1e6;
+        assert!((to_f64(value) - expected).abs() < 1e-3);
+
+        // A is worth 0.5 B, below the range: only token A
+        let value =
+            get_position_value(&position, (price(1, 0), 6), (price(2, 0), 6), (0, 0)).unwrap();
+        let expected = full_a as f64 / 1e6;
+        assert!((to_f64(value) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_position_value_decimals() {
+        // 1 A (9 decimals) is worth 1 B (6 decimals): the pool price is 10^-3 B lamports per A
+        // lamport, the position is in range of ticks around -69078
+        let position = position(-69_100, -69_000);
+        let (amount_a, amount_b) =
+            get_liquidity_amounts(&position, price_to_sqrt_price(price(1, 0), 9, 6).unwrap())
+                .unwrap();
+        assert!(amount_a > 0 && amount_b > 0);
+
+        let value =
+            get_position_value(&position, (price(1, 0), 9), (price(1, 0), 6), (0, 0)).unwrap();
+        let expected = amount_a as f64 / 1e9 + amount_b as f64 / 1e6;
+        assert!((to_f64(value) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_mapping_cfg_generic_data() {
+        let mut generic_data = [0; 20];
+        let data = OrcaWhirlpoolPositionData {
+            price_a_entry: 3,
+            price_b_entry: 4,
+        };
+        generic_data[..4].copy_from_slice(&data.try_to_vec().unwrap());
+        assert_eq!(
+            OrcaWhirlpoolPositionData::from_generic_data(&generic_data).unwrap(),
+            data
+        );
+    }
+}
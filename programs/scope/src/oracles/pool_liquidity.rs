@@ -0,0 +1,256 @@
+//! Minimum liquidity of the pools whose spot price is published (CLMM and DLMM), configured in the
+//! generic data of their entries
+//!
+//! Thin pools can give spot prices that are technically valid but absurd, the entries of such
+//! pools can be configured to reject their price while the pool liquidity is below a threshold.
+//! Zeroed generic data (the default) disables the check.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::state::Account as TokenAccount;
+use solana_program::program_pack::Pack;
+
+use crate::ScopeError;
+
+/// Minimum in-range liquidity of a CLMM pool (Orca whirlpool, Raydium AMM v3)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClmmLiquidityGuard {
+    pub min_liquidity: u128,
+}
+
+impl ClmmLiquidityGuard {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::PoolLiquidityGuardInvalid))
+    }
+
+    pub fn check(&self, pool: &Pubkey, liquidity: u128) -> Result<()> {
+        if liquidity < self.min_liquidity {
+            msg!(
+                "Pool {pool} in-range liquidity {liquidity} is below the minimum {}",
+                self.min_liquidity
+            );
+            return err!(ScopeError::PoolLiquidityTooLow);
+        }
+        Ok(())
+    }
+}
+
+/// Minimum reserves of a DLMM pool (Meteora), read from the reserve accounts of the pool which are
+/// then expected as extra accounts
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DlmmReservesGuard {
+    /// Minimum amount (in lamports) of token X in the pool
+    pub min_reserve_x: u64,
+    /// Minimum amount (in lamports) of token Y in the pool
+    pub min_reserve_y: u64,
+}
+
+impl DlmmReservesGuard {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::PoolLiquidityGuardInvalid))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.min_reserve_x > 0 || self.min_reserve_y > 0
+    }
+
+    /// Check the reserve accounts of the pool, expected to have been checked against it
+    pub fn check(
+        &self,
+        pool: &Pubkey,
+        reserve_x_acc: &AccountInfo,
+        reserve_y_acc: &AccountInfo,
+    ) -> Result<()> {
+        let reserve_x = get_vault_amount(reserve_x_acc)?;
+        let reserve_y = get_vault_amount(reserve_y_acc)?;
+        if reserve_x < self.min_reserve_x || reserve_y < self.min_reserve_y {
+            msg!(
+                "Pool {pool} reserves ({reserve_x}, {reserve_y}) are below the minimum ({}, {})",
+                self.min_reserve_x,
+                self.min_reserve_y
+            );
+            return err!(ScopeError::PoolLiquidityTooLow);
+        }
+        Ok(())
+    }
+}
+
+/// Amount of tokens held by a pool vault (SPL token account)
+pub fn get_vault_amount(vault_acc: &AccountInfo) -> Result<u64> {
+    let data = vault_acc.data.borrow();
+    let vault = data
+        .get(..TokenAccount::LEN)
+        .ok_or_else(|| error!(ScopeError::UnableToDeserializeAccount))
+        .and_then(|data| {
+            TokenAccount::unpack_from_slice(data)
+                .map_err(|_| error!(ScopeError::UnableToDeserializeAccount))
+        })?;
+    Ok(vault.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::token::spl_token::state::AccountState;
+
+    use super::*;
+    use crate::oracles::test_utils::generic_data;
+
+    fn vault_data(amount: u64) -> Vec<u8> {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                amount,
+                state: AccountState::Initialized,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    /// Check of the DLMM reserves guard with vaults holding the given amounts
+    fn check_reserves(
+        guard: &DlmmReservesGuard,
+        mut reserve_x_data: Vec<u8>,
+        mut reserve_y_data: Vec<u8>,
+    ) -> Result<()> {
+        let (reserve_x_pk, reserve_y_pk) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut reserve_x_lamports, mut reserve_y_lamports) = (0, 0);
+        let reserve_x = AccountInfo::new(
+            &reserve_x_pk,
+            false,
+            false,
+            &mut reserve_x_lamports,
+            &mut reserve_x_data,
+            &anchor_spl::token::ID,
+            false,
+            0,
+        );
+        let reserve_y = AccountInfo::new(
+            &reserve_y_pk,
+            false,
+            false,
+            &mut reserve_y_lamports,
+            &mut reserve_y_data,
+            &anchor_spl::token::ID,
+            false,
+            0,
+        );
+        guard.check(&Pubkey::new_unique(), &reserve_x, &reserve_y)
+    }
+
+    #[test]
+    fn test_clmm_guard_generic_data() {
+        assert_eq!(
+            ClmmLiquidityGuard::from_generic_data(&[0; 20]).unwrap(),
+            ClmmLiquidityGuard::default()
+        );
+        let guard = ClmmLiquidityGuard {
+            min_liquidity: u128::MAX - 1,
+        };
+        assert_eq!(
+            ClmmLiquidityGuard::from_generic_data(&generic_data(&guard)).unwrap(),
+            guard
+        );
+    }
+
+    #[test]
+    fn test_clmm_guard_threshold() {
+        let pool = Pubkey::new_unique();
+        let guard = ClmmLiquidityGuard {
+            min_liquidity: 1_000,
+        };
+        guard.check(&pool, 1_000).unwrap();
+        guard.check(&pool, u128::MAX).unwrap();
+        assert_eq!(
+            guard.check(&pool, 999).unwrap_err(),
+            error!(ScopeError::PoolLiquidityTooLow)
+        );
+
+        // Disabled by default, even for an empty pool
+        ClmmLiquidityGuard::default().check(&pool, 0).unwrap();
+    }
+
+    #[test]
+    fn test_dlmm_guard_generic_data() {
+        let guard = DlmmReservesGuard::from_generic_data(&[0; 20]).unwrap();
+        assert_eq!(guard, DlmmReservesGuard::default());
+        assert!(!guard.is_enabled());
+
+        for guard in [
+            DlmmReservesGuard {
+                min_reserve_x: 1,
+                min_reserve_y: 0,
+            },
+            DlmmReservesGuard {
+                min_reserve_x: 0,
+                min_reserve_y: u64::MAX,
+            },
+        ] {
+            let parsed = DlmmReservesGuard::from_generic_data(&generic_data(&guard)).unwrap();
+            assert_eq!(parsed, guard);
+            assert!(parsed.is_enabled());
+        }
+    }
+
+    #[test]
+    fn test_dlmm_guard_threshold() {
+        let guard = DlmmReservesGuard {
+            min_reserve_x: 1_000,
+            min_reserve_y: 2_000,
+        };
+        check_reserves(&guard, vault_data(1_000), vault_data(2_000)).unwrap();
+        assert_eq!(
+            check_reserves(&guard, vault_data(999), vault_data(2_000)).unwrap_err(),
+            error!(ScopeError::PoolLiquidityTooLow)
+        );
+        assert_eq!(
+            check_reserves(&guard, vault_data(1_000), vault_data(1_999)).unwrap_err(),
+            error!(ScopeError::PoolLiquidityTooLow)
+        );
+
+        // Only the configured reserve is checked
+        let guard = DlmmReservesGuard {
+            min_reserve_x: 0,
+            min_reserve_y: 2_000,
+        };
+        check_reserves(&guard, vault_data(0), vault_data(2_000)).unwrap();
+    }
+
+    #[test]
+    fn test_vault_amount() {
+        let mut data = vault_data(1_234);
+        // Token-2022 accounts with extensions are longer
+        data.extend_from_slice(&[1; 10]);
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let vault = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &anchor_spl::token::ID,
+            false,
+            0,
+        );
+        assert_eq!(get_vault_amount(&vault).unwrap(), 1_234);
+
+        let guard = DlmmReservesGuard {
+            min_reserve_x: 1,
+            min_reserve_y: 1,
+        };
+        let mut truncated = vault_data(1_000);
+        truncated.pop();
+        assert_eq!(
+            check_reserves(&guard, vault_data(1_000), truncated).unwrap_err(),
+            error!(ScopeError::UnableToDeserializeAccount)
+        );
+    }
+}
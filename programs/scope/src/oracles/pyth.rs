@@ -8,22 +8,92 @@
 //!    update.
 //! 2. Upon usage the current price state is checked in [`validate_valid_price`]
 //! 3. The confidence interval is also checked in this same function with [`ORACLE_CONFIDENCE_FACTOR`]
+//!    unless the entry confidence policy sets its own check (see [`ConfidencePolicy`])
+//! 4. A minimum number of publishers of the aggregate price can also be configured in the generic
+//!    data of the entries of this (push) type (see [`get_min_publishers`])
 
 use std::convert::{TryFrom, TryInto};
 
 use anchor_lang::{prelude::*, solana_program::clock::DEFAULT_MS_PER_SLOT};
+use arrayref::array_ref;
 use pyth_client::PriceType;
 use pyth_sdk_solana::state as pyth_client;
 
 use crate::{
-    utils::{consts::ORACLE_CONFIDENCE_FACTOR, math::check_confidence_interval},
-    DatedPrice, Price, ScopeError,
+    utils::consts::{FULL_BPS, ORACLE_CONFIDENCE_FACTOR},
+    ConfidencePolicy, DatedPrice, Price, ScopeError,
 };
 
 /// Only update with prices not older than 10 minutes, users can still check actual price age
 const STALENESS_SLOT_THRESHOLD: u64 = (10 * 60 * 1000) / DEFAULT_MS_PER_SLOT; // 10 minutes
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+/// Number of leading bytes of the generic data of the pyth entries that held their confidence
+/// factor (u32 little endian), now configured in the entry [`ConfidencePolicy`]
+const LEGACY_CONFIDENCE_FACTOR_LEN: usize = 4;
+
+/// Offset in the generic data of the minimum number of publishers (u8) of a pyth push entry
+const MIN_PUBLISHERS_OFFSET: usize = LEGACY_CONFIDENCE_FACTOR_LEN;
+
+/// Move a confidence factor set in the generic data of a pyth entry (its previous location) to
+/// the confidence policy of the entry, and clear it from the generic data.
+///
+/// A policy already setting a factor or a maximum in bps is kept as is.
+pub fn migrate_legacy_confidence_factor(
+    generic_data: &mut [u8; 20],
+    confidence_policy: &mut ConfidencePolicy,
+) {
+    let factor = u32::from_le_bytes(*array_ref![generic_data, 0, LEGACY_CONFIDENCE_FACTOR_LEN]);
+    if factor == 0 {
+        return;
+    }
+    generic_data[..LEGACY_CONFIDENCE_FACTOR_LEN].fill(0);
+    if confidence_policy.factor == 0 && confidence_policy.max_bps == 0 {
+        msg!("Moving the generic data confidence factor {factor} to the entry confidence policy");
+        confidence_policy.factor = factor;
+    } else {
+        msg!("Ignoring the generic data confidence factor {factor}, the entry confidence policy already sets the check");
+    }
+}
+
+/// Check that the generic data of a pyth entry is empty, its confidence check being configured in
+/// the entry confidence policy
+pub fn validate_generic_data(generic_data: &[u8; 20]) -> Result<()> {
+    if generic_data.iter().any(|&byte| byte != 0) {
+        msg!("Pyth generic data must be empty, the confidence factor is set in the entry confidence policy");
+        return err!(ScopeError::InvalidConfidenceFactor);
+    }
+    Ok(())
+}
+
+/// Get the minimum number of publishers of the aggregate price configured for a pyth push entry,
+/// stored as a u8 after the (legacy) confidence factor in the generic data. 0 disables the check.
+pub fn get_min_publishers(generic_data: &[u8; 20]) -> u8 {
+    generic_data[MIN_PUBLISHERS_OFFSET]
+}
+
+/// Check that the generic data of a pyth push entry only contains a minimum number of publishers
+pub fn validate_push_generic_data(generic_data: &[u8; 20]) -> Result<()> {
+    let mut other_data = *generic_data;
+    other_data[MIN_PUBLISHERS_OFFSET] = 0;
+    validate_generic_data(&other_data)?;
+    msg!(
+        "Pyth entry minimum publishers: {}",
+        get_min_publishers(generic_data)
+    );
+    Ok(())
+}
+
+/// Get the price of a pyth push price account.
+///
+/// When a minimum number of publishers is configured, the aggregate price must have been computed
+/// from at least this number of publishers. The previous price, whose number of publishers is not
+/// stored, is then never used as a fallback.
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+    confidence_policy: &ConfidencePolicy,
+) -> Result<DatedPrice> {
     let data = price_info.try_borrow_data()?;
     let price_account: &pyth_client::SolanaPriceAccount =
         pyth_client::load_price_account(data.as_ref()).map_err(|e| {
@@ -32,11 +102,21 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         })?;
 
     let oldest_accepted_slot = clock.slot.saturating_sub(STALENESS_SLOT_THRESHOLD);
+    let min_publishers = get_min_publishers(generic_data);
 
     let (pyth_price, slot, timestamp) = if price_account.agg.status
         == pyth_client::PriceStatus::Trading
         && price_account.agg.pub_slot >= oldest_accepted_slot
     {
+        if price_account.num_qt < u32::from(min_publishers) {
+            msg!(
+                "Price in pyth account {} is computed from {} publishers, {} required",
+                price_info.key,
+                price_account.num_qt,
+                min_publishers
+            );
+            return err!(ScopeError::PythNotEnoughPublishers);
+        }
         let pyth_price = pyth_client::Price {
             conf: price_account.agg.conf,
             expo: price_account.expo,
@@ -48,7 +128,7 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
             price_account.agg.pub_slot,
             price_account.timestamp,
         )
-    } else if price_account.prev_slot >= oldest_accepted_slot {
+    } else if min_publishers == 0 && price_account.prev_slot >= oldest_accepted_slot {
         let pyth_price = pyth_client::Price {
             conf: price_account.prev_conf,
             expo: price_account.expo,
@@ -62,7 +142,7 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         )
     } else {
         msg!(
-            "Price in pyth account {} is older than 10 minutes",
+            "No recent (10 minutes) trading price in pyth account {}",
             price_info.key
         );
         return Err(ScopeError::PriceNotValid.into());
@@ -77,7 +157,7 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         return Err(ScopeError::PriceNotValid.into());
     }
 
-    let price = validate_valid_price(&pyth_price, ORACLE_CONFIDENCE_FACTOR).map_err(|e| {
+    let price = validate_valid_price(&pyth_price, confidence_policy).map_err(|e| {
         msg!(
             "Price validity check failed on pyth account {}",
             price_info.key
@@ -88,17 +168,23 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
     Ok(DatedPrice {
         price,
         last_updated_slot: slot,
-        unix_timestamp: u64::try_from(timestamp).unwrap(),
+        unix_timestamp: u64::try_from(timestamp).map_err(|_| ScopeError::BadTimestamp)?,
+        confidence_bps: confidence_bps(&pyth_price),
         ..Default::default()
     })
 }
 
+/// Check the price and its confidence interval against the confidence policy of the entry,
+/// [`ORACLE_CONFIDENCE_FACTOR`] being the default factor of the pyth prices
 pub fn validate_valid_price(
     pyth_price: &pyth_client::Price,
-    oracle_confidence_factor: u32,
+    confidence_policy: &ConfidencePolicy,
 ) -> std::result::Result<Price, ScopeError> {
-    let price = u64::try_from(pyth_price.price).unwrap();
-    let price_exp: u32 = pyth_price.expo.abs().try_into().unwrap();
+    let price = u64::try_from(pyth_price.price).map_err(|_| {
+        msg!("Pyth price is negative: {}", pyth_price.price);
+        ScopeError::PriceNotValid
+    })?;
+    let price_exp: u32 = pyth_price.expo.unsigned_abs();
 
     if cfg!(feature = "skip_price_validation") {
         return Ok(Price {
@@ -108,17 +194,18 @@ pub fn validate_valid_price(
     }
 
     let conf: u128 = pyth_price.conf.into();
-    check_confidence_interval(
-        price.into(),
-        price_exp,
-        conf,
-        price_exp,
-        oracle_confidence_factor,
-    )
-    .map_err(|e| {
-        msg!("Confidence interval check failed conf {conf}",);
-        e
-    })?;
+    confidence_policy
+        .check(
+            price.into(),
+            price_exp,
+            conf,
+            price_exp,
+            ORACLE_CONFIDENCE_FACTOR,
+        )
+        .map_err(|e| {
+            msg!("Confidence interval check failed conf {conf}",);
+            e
+        })?;
 
     Ok(Price {
         value: price,
@@ -126,6 +213,19 @@ pub fn validate_valid_price(
     })
 }
 
+/// Confidence interval of a pyth price in bps of the price, rounded up and saturated at `u16::MAX`
+pub fn confidence_bps(pyth_price: &pyth_client::Price) -> u16 {
+    let Ok(price) = u128::try_from(pyth_price.price) else {
+        return u16::MAX;
+    };
+    if price == 0 {
+        return u16::MAX;
+    }
+    // The confidence uses the exponent of the price
+    let bps = (u128::from(pyth_price.conf) * u128::from(FULL_BPS) + price - 1) / price;
+    u16::try_from(bps).unwrap_or(u16::MAX)
+}
+
 fn validate_pyth_price(pyth_price: &pyth_client::SolanaPriceAccount) -> Result<()> {
     if pyth_price.magic != pyth_client::MAGIC {
         msg!("Pyth price account provided is not a valid Pyth account");
@@ -155,7 +255,88 @@ pub fn validate_pyth_price_info(pyth_price_info: &Option<AccountInfo>) -> Result
         return err!(ScopeError::PriceNotValid);
     };
     let pyth_price_data = pyth_price_info.try_borrow_data()?;
-    let pyth_price = pyth_client::load_price_account(&pyth_price_data).unwrap();
+    let pyth_price = pyth_client::load_price_account(&pyth_price_data).map_err(|e| {
+        msg!(
+            "Error loading pyth account {}: {:?}",
+            pyth_price_info.key,
+            e
+        );
+        ScopeError::PriceNotValid
+    })?;
 
     validate_pyth_price(pyth_price)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generic_data(legacy_factor: u32, min_publishers: u8) -> [u8; 20] {
+        let mut generic_data = [0; 20];
+        generic_data[..LEGACY_CONFIDENCE_FACTOR_LEN].copy_from_slice(&legacy_factor.to_le_bytes());
+        generic_data[MIN_PUBLISHERS_OFFSET] = min_publishers;
+        generic_data
+    }
+
+    #[test]
+    fn test_migrate_legacy_confidence_factor() {
+        let mut data = generic_data(40, 3);
+        let mut policy = ConfidencePolicy::default();
+        migrate_legacy_confidence_factor(&mut data, &mut policy);
+        assert_eq!(data, generic_data(0, 3));
+        assert_eq!(policy.factor, 40);
+        assert!(validate_push_generic_data(&data).is_ok());
+
+        // The policy wins over the generic data
+        let mut data = generic_data(40, 0);
+        let mut policy = ConfidencePolicy {
+            max_bps: 100,
+            ..Default::default()
+        };
+        migrate_legacy_confidence_factor(&mut data, &mut policy);
+        assert_eq!(data, [0; 20]);
+        assert_eq!(policy.factor, 0);
+        assert_eq!(policy.max_bps, 100);
+
+        // Nothing to migrate
+        let mut data = generic_data(0, 0);
+        let mut policy = ConfidencePolicy {
+            factor: 10,
+            ..Default::default()
+        };
+        migrate_legacy_confidence_factor(&mut data, &mut policy);
+        assert_eq!(data, [0; 20]);
+        assert_eq!(policy.factor, 10);
+    }
+
+    #[test]
+    fn test_validate_generic_data() {
+        assert!(validate_generic_data(&[0; 20]).is_ok());
+        assert!(validate_generic_data(&generic_data(40, 0)).is_err());
+        assert!(validate_generic_data(&generic_data(0, 3)).is_err());
+        assert!(validate_push_generic_data(&generic_data(0, 3)).is_ok());
+        assert!(validate_push_generic_data(&generic_data(40, 3)).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "skip_price_validation"))]
+    fn test_confidence_check_uses_policy() {
+        // 100.00 with a confidence of 1.50 (1.5%)
+        let pyth_price = pyth_client::Price {
+            price: 10_000,
+            conf: 150,
+            expo: -2,
+            publish_time: 0,
+        };
+        assert!(validate_valid_price(&pyth_price, &ConfidencePolicy::default()).is_ok());
+
+        let policy = ConfidencePolicy {
+            factor: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_valid_price(&pyth_price, &policy),
+            Err(ScopeError::ConfidenceIntervalCheckFailed)
+        );
+    }
+}
@@ -7,19 +7,23 @@
 //!    expected to be checked by the admin to ensure the product has the expected quality prior the mapping
 //!    update.
 //! 2. Upon usage the current ema price state is checked in [`validate_valid_price`]
-//! 3. The confidence interval is also checked in this same function with [`ORACLE_CONFIDENCE_FACTOR`]
+//! 3. The confidence interval is also checked in this same function with the entry confidence policy
 
 use std::convert::TryFrom;
 
 use anchor_lang::prelude::*;
 use pyth_sdk_solana::state as pyth_client;
 
-use crate::{utils::consts::ORACLE_CONFIDENCE_FACTOR, DatedPrice, Result, ScopeError};
+use crate::{ConfidencePolicy, DatedPrice, Result, ScopeError};
 
 /// Only update with prices not older than 10 minutes, users can still check actual price age
 const STALENESS_THRESHOLD: u64 = 10 * 60; // 10 minutes
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    confidence_policy: &ConfidencePolicy,
+) -> Result<DatedPrice> {
     let data = price_info.try_borrow_data()?;
     let price_account: &pyth_client::SolanaPriceAccount =
         pyth_client::load_price_account(data.as_ref()).map_err(|_| {
@@ -53,17 +57,19 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         return Err(ScopeError::PriceNotValid.into());
     }
 
-    let price =
-        crate::oracles::pyth::validate_valid_price(&pyth_ema_price, ORACLE_CONFIDENCE_FACTOR)
-            .map_err(|e| {
-                msg!("Invalid EMA price on pyth account {}", price_info.key);
-                e
-            })?;
+    let confidence_bps = crate::oracles::pyth::confidence_bps(&pyth_ema_price);
+    let price = crate::oracles::pyth::validate_valid_price(&pyth_ema_price, confidence_policy)
+        .map_err(|e| {
+            msg!("Invalid EMA price on pyth account {}", price_info.key);
+            e
+        })?;
 
     Ok(DatedPrice {
         price,
         last_updated_slot: price_account.valid_slot,
-        unix_timestamp: u64::try_from(price_account.timestamp).unwrap(),
+        unix_timestamp: u64::try_from(price_account.timestamp)
+            .map_err(|_| ScopeError::BadTimestamp)?,
+        confidence_bps,
         ..Default::default()
     })
 }
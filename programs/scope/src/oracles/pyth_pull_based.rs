@@ -1,20 +1,23 @@
 use anchor_lang::{prelude::*, solana_program::clock};
 use pyth_solana_receiver_sdk::price_update::{self, PriceUpdateV2, VerificationLevel};
 
-use crate::{utils::account_deserialize, DatedPrice, ScopeError};
+use crate::{utils::account_deserialize, ConfidencePolicy, DatedPrice, ScopeError};
 pub const MAXIMUM_AGE: u64 = 10 * 60; // Ten minutes
 use pyth_sdk_solana::state as pyth_client;
 
 use self::utils::get_last_updated_slot;
-use super::pyth::validate_valid_price;
-use crate::utils::consts::ORACLE_CONFIDENCE_FACTOR;
+use super::pyth::{confidence_bps, validate_valid_price};
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    confidence_policy: &ConfidencePolicy,
+) -> Result<DatedPrice> {
     let price_account: PriceUpdateV2 = account_deserialize(price_info)?;
 
     let price = price_account.get_price_no_older_than_with_custom_verification_level(
         clock,
-        i64::MAX.try_into().unwrap(), // MAXIMUM_AGE, // this should be filtered by the caller
+        i64::MAX as u64, // MAXIMUM_AGE, // this should be filtered by the caller
         &price_account.price_message.feed_id,
         VerificationLevel::Full, // All our prices and the sponsored feeds are full verified
     )?;
@@ -42,7 +45,8 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         price,
         publish_time,
     };
-    let price = validate_valid_price(&old_pyth_price, ORACLE_CONFIDENCE_FACTOR).map_err(|e| {
+    let confidence_bps = confidence_bps(&old_pyth_price);
+    let price = validate_valid_price(&old_pyth_price, confidence_policy).map_err(|e| {
         msg!(
             "Confidence interval check failed on pyth account {}",
             price_info.key
@@ -55,7 +59,10 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
     Ok(DatedPrice {
         price,
         last_updated_slot,
-        unix_timestamp: publish_time.try_into().unwrap(),
+        unix_timestamp: publish_time
+            .try_into()
+            .map_err(|_| ScopeError::BadTimestamp)?,
+        confidence_bps,
         ..Default::default()
     })
 }
@@ -75,11 +82,14 @@ pub fn validate_price_update_v2_info(price_info: &Option<AccountInfo>) -> Result
 pub mod utils {
     use super::*;
 
+    /// Estimate the slot of a price published at `publish_time`, a negative timestamp is
+    /// considered as the oldest possible one
     pub fn get_last_updated_slot(clock: &Clock, publish_time: i64) -> u64 {
         let elapsed_time_s = u64::try_from(clock.unix_timestamp)
-            .unwrap()
-            .saturating_sub(u64::try_from(publish_time).unwrap());
-        let elapsed_slot_estimate = elapsed_time_s * 1000 / clock::DEFAULT_MS_PER_SLOT;
+            .unwrap_or(0)
+            .saturating_sub(u64::try_from(publish_time).unwrap_or(0));
+        let elapsed_slot_estimate =
+            elapsed_time_s.saturating_mul(1000) / clock::DEFAULT_MS_PER_SLOT;
         clock.slot.saturating_sub(elapsed_slot_estimate)
     }
 }
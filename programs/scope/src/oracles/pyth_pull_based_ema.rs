@@ -4,14 +4,20 @@ use pyth_solana_receiver_sdk::{
     price_update::{PriceUpdateV2, VerificationLevel},
 };
 
-use crate::{utils::account_deserialize, DatedPrice, ScopeError};
+use crate::{utils::account_deserialize, ConfidencePolicy, DatedPrice, ScopeError};
 pub const MAXIMUM_AGE: u64 = 10 * 60; // Ten minutes
 use pyth_sdk_solana::Price as PythPrice;
 
-use super::{pyth::validate_valid_price, pyth_pull_based::utils::get_last_updated_slot};
-use crate::utils::consts::ORACLE_CONFIDENCE_FACTOR;
+use super::{
+    pyth::{confidence_bps, validate_valid_price},
+    pyth_pull_based::utils::get_last_updated_slot,
+};
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    confidence_policy: &ConfidencePolicy,
+) -> Result<DatedPrice> {
     let price_account: PriceUpdateV2 = account_deserialize(price_info)?;
     let exponent = price_account.price_message.exponent;
     let conf = price_account.price_message.conf;
@@ -34,7 +40,8 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         price: price.price,
         publish_time,
     };
-    let price = validate_valid_price(&old_pyth_price, ORACLE_CONFIDENCE_FACTOR).map_err(|e| {
+    let confidence_bps = confidence_bps(&old_pyth_price);
+    let price = validate_valid_price(&old_pyth_price, confidence_policy).map_err(|e| {
         msg!(
             "Confidence interval check failed on pyth account {}",
             price_info.key
@@ -47,7 +54,10 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
     Ok(DatedPrice {
         price,
         last_updated_slot,
-        unix_timestamp: publish_time.try_into().unwrap(),
+        unix_timestamp: publish_time
+            .try_into()
+            .map_err(|_| ScopeError::BadTimestamp)?,
+        confidence_bps,
         ..Default::default()
     })
 }
@@ -1,15 +1,25 @@
 use anchor_lang::prelude::*;
 use raydium_amm_v3::states::PoolState;
 
+use super::pool_liquidity::ClmmLiquidityGuard;
 use crate::{
-    utils::{account_deserialize, math::sqrt_price_to_price},
+    utils::{account_deserialize, clock_unix_timestamp, math::sqrt_price_to_price},
     DatedPrice, Result, ScopeError,
 };
 
 /// Gives the price of the given token pair in the given pool
-pub fn get_price(a_to_b: bool, pool: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+///
+/// The price is rejected if the in-range liquidity of the pool is below the minimum configured in
+/// the generic data, see [`ClmmLiquidityGuard`].
+pub fn get_price(
+    a_to_b: bool,
+    pool: &AccountInfo,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice> {
     // Load main account
     let pool_data: PoolState = account_deserialize(pool)?;
+    ClmmLiquidityGuard::from_generic_data(generic_data)?.check(pool.key, pool_data.liquidity)?;
 
     // Compute price
     let price = sqrt_price_to_price(
@@ -27,7 +37,7 @@ pub fn get_price(a_to_b: bool, pool: &AccountInfo, clock: &Clock) -> Result<Date
     Ok(DatedPrice {
         price,
         last_updated_slot: clock.slot,
-        unix_timestamp: clock.unix_timestamp as u64,
+        unix_timestamp: clock_unix_timestamp(clock)?,
         ..Default::default()
     })
 }
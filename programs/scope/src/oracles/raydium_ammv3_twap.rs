@@ -0,0 +1,265 @@
+//! Time-weighted average price of a Raydium AMM v3 (CLMM) pool, computed from the tick cumulatives
+//! stored by the pool in its observation account
+//!
+//! Unlike [`super::twap`] which averages the prices sampled by the scope refreshes, the window is
+//! covered by the pool itself so the average cannot be skewed by a refresh cadence that is too low.
+//! Orca whirlpools do not record price observations, only Raydium pools are supported.
+
+use anchor_lang::prelude::*;
+use raydium_amm_v3::{
+    libraries::tick_math::get_sqrt_price_at_tick,
+    states::{ObservationState, PoolState, OBSERVATION_NUM},
+};
+
+use crate::{
+    utils::{
+        account_deserialize, clock_unix_timestamp, math::sqrt_price_to_price,
+        zero_copy_deserialize, SECONDS_PER_DAY,
+    },
+    DatedPrice, Result, ScopeError,
+};
+
+/// Configuration of a Raydium AMM v3 TWAP entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumAmmV3TwapData {
+    /// Minimum duration of the window the price is averaged over. The window starts at the latest
+    /// observation recorded at least `window_seconds` ago so it can be slightly longer.
+    pub window_seconds: u32,
+}
+
+impl RaydiumAmmV3TwapData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::RaydiumTwapInvalid))
+    }
+}
+
+/// Gives the time-weighted average price of the given token pair in the given pool, the
+/// observation account of the pool being expected as extra account
+///
+/// The current tick of the pool is used from the latest observation until now (as done by the
+/// pool at the next observation).
+pub fn get_price<'a, 'b>(
+    a_to_b: bool,
+    pool: &AccountInfo,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    let data = RaydiumAmmV3TwapData::from_generic_data(generic_data)?;
+    let observation_account_info = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let pool_data: PoolState = account_deserialize(pool)?;
+    require_keys_eq!(
+        pool_data.observation_key,
+        observation_account_info.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    // Too large to be copied on the stack
+    let observation_state = zero_copy_deserialize::<ObservationState>(observation_account_info)?;
+
+    let now = clock_unix_timestamp(clock)?;
+    let average_tick = get_average_tick(
+        &observation_state,
+        pool_data.tick_current,
+        now,
+        data.window_seconds,
+    )
+    .map_err(|e| {
+        msg!(
+            "Cannot compute the {}s average tick of pool {}",
+            data.window_seconds,
+            pool.key
+        );
+        e
+    })?;
+
+    let sqrt_price = get_sqrt_price_at_tick(average_tick)?;
+    let price = sqrt_price_to_price(
+        a_to_b,
+        sqrt_price,
+        pool_data.mint_decimals_0,
+        pool_data.mint_decimals_1,
+    )
+    .map_err(|e| {
+        msg!("Error while computing the price of the tokens from tick {average_tick}: {e:?}");
+        e
+    })?;
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: clock.slot,
+        unix_timestamp: now,
+        ..Default::default()
+    })
+}
+
+/// Average tick between the latest observation recorded at least `window_seconds` before `now` and
+/// `now`, rounded towards negative infinity
+fn get_average_tick(
+    observation_state: &ObservationState,
+    tick_current: i32,
+    now: u64,
+    window_seconds: u32,
+) -> Result<i32> {
+    let latest_index = usize::from(observation_state.observation_index) % OBSERVATION_NUM;
+    let latest = observation_state.observations[latest_index];
+    let latest_timestamp = u64::from(latest.block_timestamp);
+    if latest_timestamp == 0 || latest_timestamp > now {
+        msg!("Invalid latest observation timestamp {latest_timestamp} (now {now})");
+        return err!(ScopeError::RaydiumTwapWindowNotCovered);
+    }
+    let latest_tick_cumulative = i128::from(latest.tick_cumulative);
+    let tick_cumulative_now =
+        latest_tick_cumulative + i128::from(tick_current) * i128::from(now - latest_timestamp);
+
+    let window_start = now.saturating_sub(u64::from(window_seconds));
+    // Walk the ring buffer from the latest observation backwards
+    let start = (0..OBSERVATION_NUM)
+        .map(|age| {
+            observation_state.observations[(latest_index + OBSERVATION_NUM - age) % OBSERVATION_NUM]
+        })
+        .take_while(|observation| observation.block_timestamp != 0)
+        .find(|observation| u64::from(observation.block_timestamp) <= window_start)
+        .ok_or_else(|| {
+            msg!("No observation recorded before {window_start}");
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        })?;
+
+    let elapsed = i128::from(now - u64::from(start.block_timestamp));
+    if elapsed == 0 {
+        return err!(ScopeError::RaydiumTwapWindowNotCovered);
+    }
+    let tick_delta = tick_cumulative_now - i128::from(start.tick_cumulative);
+    let average_tick = tick_delta.div_euclid(elapsed);
+    i32::try_from(average_tick).map_err(|_| error!(ScopeError::MathOverflow))
+}
+
+pub fn validate_mapping_cfg(pool: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let Some(pool) = pool else {
+        msg!("No pool account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let _: PoolState = account_deserialize(pool)?;
+
+    let data = RaydiumAmmV3TwapData::from_generic_data(generic_data)?;
+    if data.window_seconds == 0 || i64::from(data.window_seconds) > SECONDS_PER_DAY {
+        msg!(
+            "Raydium TWAP window must be between 1s and 1 day, got {}s",
+            data.window_seconds
+        );
+        return err!(ScopeError::RaydiumTwapInvalid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::__private::bytemuck::Zeroable;
+
+    use super::*;
+
+    /// Observations of a pool given as (block timestamp, tick cumulative) from the oldest to the
+    /// latest, the oldest being recorded at `first_index` of the ring buffer
+    fn observation_state(first_index: usize, records: &[(u32, i64)]) -> Box<ObservationState> {
+        let mut state: Box<ObservationState> = Box::new(Zeroable::zeroed());
+        let mut observations = state.observations;
+        for (i, &(block_timestamp, tick_cumulative)) in records.iter().enumerate() {
+            let observation = &mut observations[(first_index + i) % OBSERVATION_NUM];
+            observation.block_timestamp = block_timestamp;
+            observation.tick_cumulative = tick_cumulative;
+        }
+        state.observations = observations;
+        state.observation_index = ((first_index + records.len() - 1) % OBSERVATION_NUM) as u16;
+        state
+    }
+
+    /// Tick 10 from 1000 to 1100 and 20 from 1100 to 1200, the current tick being 30
+    const RECORDS: [(u32, i64); 3] = [(1000, 0), (1100, 1_000), (1200, 3_000)];
+    const TICK_CURRENT: i32 = 30;
+    const NOW: u64 = 1250;
+
+    fn check_window_edges(state: &ObservationState) {
+        let average_tick =
+            |window_seconds| get_average_tick(state, TICK_CURRENT, NOW, window_seconds);
+
+        // Window starting exactly at the oldest observation: (3_000 + 30 * 50) / 250
+        assert_eq!(average_tick(250).unwrap(), 18);
+        // Window starting exactly at an observation: (4_500 - 1_000) / 150
+        assert_eq!(average_tick(150).unwrap(), 23);
+        // The window starts at the latest observation before its start, so it is slightly longer
+        assert_eq!(average_tick(149).unwrap(), 23);
+        // From the latest observation, only the current tick is used
+        assert_eq!(average_tick(50).unwrap(), TICK_CURRENT);
+        assert_eq!(average_tick(1).unwrap(), TICK_CURRENT);
+        // Not enough history for the window
+        assert_eq!(
+            average_tick(251).unwrap_err(),
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        );
+    }
+
+    #[test]
+    fn test_window_edges() {
+        check_window_edges(&observation_state(0, &RECORDS));
+    }
+
+    #[test]
+    fn test_ring_wraparound() {
+        // The observations wrap around the end of the ring buffer
+        check_window_edges(&observation_state(OBSERVATION_NUM - 2, &RECORDS));
+
+        // Full ring buffer, the oldest observations were overwritten by the latest ones
+        let records: Vec<(u32, i64)> = (0..OBSERVATION_NUM as u32 + 10)
+            .map(|i| (1000 + 10 * i, 100 * i64::from(i)))
+            .collect();
+        let state = observation_state(5, &records);
+        let oldest_ts = records[10].0;
+        let latest_ts = records.last().unwrap().0;
+        let now = u64::from(latest_ts);
+        let window = latest_ts - oldest_ts;
+        // Tick 10 all along
+        assert_eq!(get_average_tick(&state, 10, now, window).unwrap(), 10);
+        assert_eq!(
+            get_average_tick(&state, 10, now, window + 1).unwrap_err(),
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        );
+    }
+
+    #[test]
+    fn test_insufficient_history() {
+        // No observation
+        let state: Box<ObservationState> = Box::new(Zeroable::zeroed());
+        assert_eq!(
+            get_average_tick(&state, TICK_CURRENT, NOW, 1).unwrap_err(),
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        );
+
+        let state = observation_state(0, &RECORDS);
+        // Latest observation in the future
+        assert_eq!(
+            get_average_tick(&state, TICK_CURRENT, 1199, 1).unwrap_err(),
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        );
+        // Empty window at the latest observation
+        assert_eq!(
+            get_average_tick(&state, TICK_CURRENT, 1200, 0).unwrap_err(),
+            error!(ScopeError::RaydiumTwapWindowNotCovered)
+        );
+    }
+
+    #[test]
+    fn test_negative_ticks() {
+        // Tick -10 from 1000 to 1100, then -25 for 2s
+        let state = observation_state(0, &[(1000, 0), (1100, -1_000)]);
+        assert_eq!(get_average_tick(&state, -10, 1100, 100).unwrap(), -10);
+        // (-1_000 - 50) / 102 = -10.29, rounded towards negative infinity
+        assert_eq!(get_average_tick(&state, -25, 1102, 102).unwrap(), -11);
+    }
+}
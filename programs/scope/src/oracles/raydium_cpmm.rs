@@ -0,0 +1,466 @@
+use anchor_lang::prelude::*;
+use decimal_wad::{
+    common::{TryDiv, TryMul},
+    decimal::Decimal,
+};
+
+use super::pool_liquidity::get_vault_amount;
+use crate::{
+    utils::{
+        clock_unix_timestamp, math::ten_pow, price_impl::check_price_deviation_bps,
+        zero_copy_deserialize,
+    },
+    DatedPrice, OraclePrices, Price, Result, ScopeError, ScopeResult, MAX_ENTRIES_U16,
+};
+
+/// Configuration of a Raydium CP-Swap entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumCpmmData {
+    /// Entry giving the reference price, in the same direction as the pool price
+    pub ref_price_entry: u16,
+    /// Maximum deviation of the pool price versus the reference price
+    pub max_deviation_bps: u16,
+}
+
+impl RaydiumCpmmData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::RaydiumCpmmInvalid))
+    }
+}
+
+/// Gives the price of the given token pair in the given pool from the ratio of its reserves
+///
+/// Constant product pools are cheap to manipulate, the price is rejected if it deviates more
+/// than the configured threshold from the reference entry.
+///
+/// Required extra accounts:
+/// - Vault of the token 0
+/// - Vault of the token 1
+pub fn get_price<'a, 'b>(
+    a_to_b: bool,
+    pool: &AccountInfo,
+    clock: &Clock,
+    oracle_prices: &OraclePrices,
+    generic_data: &[u8; 20],
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    // Get extra accounts
+    let token_0_vault_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let token_1_vault_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let data = RaydiumCpmmData::from_generic_data(generic_data)?;
+
+    // Load main account
+    let pool_data = raydium_cp_swap::load_pool_state(pool)?;
+
+    // Check extra accounts pubkeys
+    require_keys_eq!(
+        pool_data.token_0_vault,
+        token_0_vault_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+    require_keys_eq!(
+        pool_data.token_1_vault,
+        token_1_vault_acc.key(),
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    // Reserves exclude the fees not yet collected by the protocol and the fund
+    let reserves_0 = get_vault_amount(token_0_vault_acc)?
+        .checked_sub(pool_data.protocol_fees_token_0)
+        .and_then(|amount| amount.checked_sub(pool_data.fund_fees_token_0))
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?;
+    let reserves_1 = get_vault_amount(token_1_vault_acc)?
+        .checked_sub(pool_data.protocol_fees_token_1)
+        .and_then(|amount| amount.checked_sub(pool_data.fund_fees_token_1))
+        .ok_or_else(|| error!(ScopeError::MathOverflow))?;
+    if reserves_0 == 0 || reserves_1 == 0 {
+        msg!("Raydium CP-Swap pool has empty reserves");
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    // Compute price
+    let (decimals_0, decimals_1) = (pool_data.mint_0_decimals, pool_data.mint_1_decimals);
+    let (reserves_a, decimals_a, reserves_b, decimals_b) = if a_to_b {
+        (reserves_0, decimals_0, reserves_1, decimals_1)
+    } else {
+        (reserves_1, decimals_1, reserves_0, decimals_0)
+    };
+    let price = reserves_to_price(reserves_a, decimals_a, reserves_b, decimals_b)?;
+
+    // Check the price against the reference price
+    let ref_price = oracle_prices
+        .prices
+        .get(usize::from(data.ref_price_entry))
+        .ok_or_else(|| error!(ScopeError::BadTokenNb))?
+        .price;
+    if ref_price.value == 0 {
+        msg!(
+            "Raydium CP-Swap reference entry {} has no price",
+            data.ref_price_entry
+        );
+        return err!(ScopeError::PriceNotValid);
+    }
+    check_price_deviation_bps(price, ref_price, data.max_deviation_bps)?;
+
+    // Return price
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: clock.slot,
+        unix_timestamp: clock_unix_timestamp(clock)?,
+        ..Default::default()
+    })
+}
+
+/// price = (reserves_b / 10^decimals_b) / (reserves_a / 10^decimals_a)
+fn reserves_to_price(
+    reserves_a: u64,
+    decimals_a: u8,
+    reserves_b: u64,
+    decimals_b: u8,
+) -> ScopeResult<Price> {
+    let price_dec = Decimal::from(reserves_b)
+        .try_mul(Decimal::from(ten_pow(decimals_a)?))?
+        .try_div(Decimal::from(reserves_a))?
+        .try_div(Decimal::from(ten_pow(decimals_b)?))?;
+    Price::try_from_decimal(price_dec)
+}
+
+pub fn validate_mapping_cfg(pool: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let Some(pool) = pool else {
+        msg!("No pool account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let _ = raydium_cp_swap::load_pool_state(pool)?;
+
+    let data = RaydiumCpmmData::from_generic_data(generic_data)?;
+    if data.ref_price_entry >= MAX_ENTRIES_U16 {
+        msg!("Raydium CP-Swap reference entry must be lower than {MAX_ENTRIES_U16}");
+        return err!(ScopeError::RaydiumCpmmInvalid);
+    }
+    if data.max_deviation_bps == 0 {
+        msg!("Raydium CP-Swap maximum deviation must be set");
+        return err!(ScopeError::RaydiumCpmmInvalid);
+    }
+
+    Ok(())
+}
+
+/// Minimal layout of the Raydium CP-Swap program accounts
+pub mod raydium_cp_swap {
+    use std::cell::Ref;
+
+    use anchor_lang::Discriminator;
+    use bytemuck::{Pod, Zeroable};
+    use solana_program::pubkey;
+
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C, packed)]
+    pub struct PoolState {
+        pub amm_config: Pubkey,
+        pub pool_creator: Pubkey,
+        pub token_0_vault: Pubkey,
+        pub token_1_vault: Pubkey,
+        pub lp_mint: Pubkey,
+        pub token_0_mint: Pubkey,
+        pub token_1_mint: Pubkey,
+        pub token_0_program: Pubkey,
+        pub token_1_program: Pubkey,
+        pub observation_key: Pubkey,
+        pub auth_bump: u8,
+        pub status: u8,
+        pub lp_mint_decimals: u8,
+        pub mint_0_decimals: u8,
+        pub mint_1_decimals: u8,
+        pub lp_supply: u64,
+        pub protocol_fees_token_0: u64,
+        pub protocol_fees_token_1: u64,
+        pub fund_fees_token_0: u64,
+        pub fund_fees_token_1: u64,
+        pub open_time: u64,
+        // Followed by padding
+    }
+
+    impl Discriminator for PoolState {
+        const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+        fn discriminator() -> [u8; 8] {
+            Self::DISCRIMINATOR
+        }
+    }
+
+    /// The pool state shares its name, hence its discriminator, with the Raydium CLMM one:
+    /// the owner is checked to tell them apart
+    pub fn load_pool_state<'a>(account: &'a AccountInfo) -> Result<Ref<'a, PoolState>> {
+        require_keys_eq!(*account.owner, ID, ScopeError::UnexpectedAccount);
+        Ok(zero_copy_deserialize::<PoolState>(account)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{Discriminator, __private::bytemuck::Zeroable};
+    use anchor_spl::token::spl_token::state::{Account as TokenAccount, AccountState};
+    use solana_program::program_pack::Pack;
+
+    use super::{raydium_cp_swap::*, *};
+
+    const REF_PRICE_ENTRY: u16 = 3;
+
+    /// A SOL (9 decimals) / USDC (6 decimals) pool
+    struct TestPool {
+        pool_pk: Pubkey,
+        pool: Vec<u8>,
+        vault_pks: [Pubkey; 2],
+        vaults: [Vec<u8>; 2],
+        lamports: [u64; 3],
+    }
+
+    impl TestPool {
+        /// Pool holding `vault_amounts` of which `fees` are not yet collected protocol and fund
+        /// fees
+        fn new(vault_amounts: [u64; 2], fees: [u64; 2]) -> Self {
+            let vault_pks = [Pubkey::new_unique(), Pubkey::new_unique()];
+            let mut pool_state: PoolState = bytemuck::Zeroable::zeroed();
+            pool_state.token_0_vault = vault_pks[0];
+            pool_state.token_1_vault = vault_pks[1];
+            pool_state.mint_0_decimals = 9;
+            pool_state.mint_1_decimals = 6;
+            pool_state.protocol_fees_token_0 = fees[0] / 2;
+            pool_state.fund_fees_token_0 = fees[0] - fees[0] / 2;
+            pool_state.protocol_fees_token_1 = fees[1] / 2;
+            pool_state.fund_fees_token_1 = fees[1] - fees[1] / 2;
+            let mut pool = PoolState::DISCRIMINATOR.to_vec();
+            pool.extend_from_slice(bytemuck::bytes_of(&pool_state));
+
+            let vaults = vault_amounts.map(|amount| {
+                let mut vault = vec![0; TokenAccount::LEN];
+                TokenAccount::pack(
+                    TokenAccount {
+                        amount,
+                        state: AccountState::Initialized,
+                        ..Default::default()
+                    },
+                    &mut vault,
+                )
+                .unwrap();
+                vault
+            });
+
+            Self {
+                pool_pk: Pubkey::new_unique(),
+                pool,
+                vault_pks,
+                vaults,
+                lamports: [0; 3],
+            }
+        }
+
+        fn get_price(
+            &mut self,
+            a_to_b: bool,
+            ref_price: Price,
+            max_deviation_bps: u16,
+        ) -> Result<DatedPrice> {
+            let [pool_lamports, vault_0_lamports, vault_1_lamports] = &mut self.lamports;
+            let [vault_0, vault_1] = &mut self.vaults;
+            let pool = AccountInfo::new(
+                &self.pool_pk,
+                false,
+                false,
+                pool_lamports,
+                &mut self.pool,
+                &ID,
+                false,
+                0,
+            );
+            let extra_accounts = [
+                AccountInfo::new(
+                    &self.vault_pks[0],
+                    false,
+                    false,
+                    vault_0_lamports,
+                    vault_0,
+                    &anchor_spl::token::ID,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &self.vault_pks[1],
+                    false,
+                    false,
+                    vault_1_lamports,
+                    vault_1,
+                    &anchor_spl::token::ID,
+                    false,
+                    0,
+                ),
+            ];
+
+            let mut oracle_prices: Box<OraclePrices> = Box::new(Zeroable::zeroed());
+            oracle_prices.prices[usize::from(REF_PRICE_ENTRY)].price = ref_price;
+            let mut generic_data = [0; 20];
+            generic_data[..4].copy_from_slice(
+                &RaydiumCpmmData {
+                    ref_price_entry: REF_PRICE_ENTRY,
+                    max_deviation_bps,
+                }
+                .try_to_vec()
+                .unwrap(),
+            );
+            let clock = Clock {
+                slot: 100,
+                unix_timestamp: 1000,
+                ..Default::default()
+            };
+
+            get_price(
+                a_to_b,
+                &pool,
+                &clock,
+                &oracle_prices,
+                &generic_data,
+                &mut extra_accounts.iter(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_reserves_to_price() {
+        // 2 SOL for 300 USDC
+        let price = reserves_to_price(2_000_000_000, 9, 300_000_000, 6).unwrap();
+        assert_eq!(f64::from(price), 150.0);
+
+        let price = reserves_to_price(300_000_000, 6, 2_000_000_000, 9).unwrap();
+        assert!((f64::from(price) - 1.0 / 150.0).abs() < 1e-15);
+
+        // Same decimals
+        let price = reserves_to_price(4_000, 6, 1_000, 6).unwrap();
+        assert_eq!(f64::from(price), 0.25);
+    }
+
+    #[test]
+    fn test_price() {
+        let mut pool = TestPool::new([2_000_000_000, 300_000_000], [0, 0]);
+
+        let price = pool
+            .get_price(true, Price { value: 150, exp: 0 }, 100)
+            .unwrap();
+        assert_eq!(f64::from(price.price), 150.0);
+        assert_eq!(price.last_updated_slot, 100);
+        assert_eq!(price.unix_timestamp, 1000);
+
+        let price = pool
+            .get_price(false, Price { value: 666, exp: 5 }, 100)
+            .unwrap();
+        assert!((f64::from(price.price) - 1.0 / 150.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_price_excludes_uncollected_fees() {
+        let mut pool = TestPool::new([2_100_000_000, 330_000_000], [100_000_000, 30_000_000]);
+        let price = pool
+            .get_price(true, Price { value: 150, exp: 0 }, 100)
+            .unwrap();
+        assert_eq!(f64::from(price.price), 150.0);
+
+        // More fees than reserves
+        let mut pool = TestPool::new([2_000_000_000, 300_000_000], [2_000_000_001, 0]);
+        assert_eq!(
+            pool.get_price(true, Price { value: 150, exp: 0 }, 100)
+                .unwrap_err(),
+            error!(ScopeError::MathOverflow)
+        );
+    }
+
+    #[test]
+    fn test_price_zero_reserves() {
+        let mut pool = TestPool::new([2_000_000_000, 0], [0, 0]);
+        assert_eq!(
+            pool.get_price(true, Price { value: 150, exp: 0 }, 100)
+                .unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+
+        // Only fees left in the pool
+        let mut pool = TestPool::new([1_000, 300_000_000], [1_000, 0]);
+        assert_eq!(
+            pool.get_price(true, Price { value: 150, exp: 0 }, 100)
+                .unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_price_deviation() {
+        let mut pool = TestPool::new([2_000_000_000, 300_000_000], [0, 0]);
+
+        // 150 is 5% above 142.86
+        pool.get_price(
+            true,
+            Price {
+                value: 14286,
+                exp: 2,
+            },
+            500,
+        )
+        .unwrap();
+        assert_eq!(
+            pool.get_price(true, Price { value: 140, exp: 0 }, 500)
+                .unwrap_err(),
+            error!(ScopeError::PriceDeviationTooLarge)
+        );
+
+        // No reference price
+        assert_eq!(
+            pool.get_price(true, Price { value: 0, exp: 0 }, 500)
+                .unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_price_unexpected_accounts() {
+        let mut pool = TestPool::new([2_000_000_000, 300_000_000], [0, 0]);
+        pool.vault_pks.swap(0, 1);
+        assert_eq!(
+            pool.get_price(true, Price { value: 150, exp: 0 }, 100)
+                .unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+
+        // Pool of another program with the same discriminator
+        let mut pool = TestPool::new([2_000_000_000, 300_000_000], [0, 0]);
+        let mut lamports = 0;
+        let pool_acc = AccountInfo::new(
+            &pool.pool_pk,
+            false,
+            false,
+            &mut lamports,
+            &mut pool.pool,
+            &crate::ID,
+            false,
+            0,
+        );
+        assert_eq!(
+            load_pool_state(&pool_acc).err().unwrap(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+        assert_eq!(
+            validate_mapping_cfg(&Some(pool_acc), &[0; 20]).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+    }
+}
@@ -0,0 +1,654 @@
+use std::ops::Deref;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::state::Account as TokenAccount;
+use decimal_wad::{
+    common::{TryAdd, TryDiv, TryMul},
+    decimal::Decimal,
+};
+use solana_program::{program_pack::Pack, pubkey};
+
+use crate::{
+    scope_chain::get_price_from_chain,
+    utils::{clock_unix_timestamp, unpack_mint},
+    DatedPrice, MintsToScopeChains, OraclePrices, Price, Result, ScopeError,
+};
+
+/// Decimals of INF and of all the LSTs of the pool
+pub const SANCTUM_INF_DECIMALS: u8 = 9;
+
+/// Get the price of 1 INF token in SOL using a scope mapping
+///
+/// The value of the pool is recomputed from the reserves of every LST of the pool and their
+/// price in SOL given by scope.
+/// WARNING: Assumes all the LSTs of the pool have the same decimals as INF (9)
+///
+/// Required extra accounts:
+/// - Mint of the INF token
+/// - The LST state list of the pool
+/// - The scope mint to price mapping (It must be built with the same mints and order than the LST state list)
+/// - The reserves of all LSTs of the pool, in the order of the LST state list
+pub fn get_price<'a, 'b>(
+    entry_id: usize,
+    pool_state_acc: &AccountInfo<'a>,
+    clock: &Clock,
+    oracle_prices_pk: &Pubkey,
+    oracle_prices: &OraclePrices,
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+) -> Result<DatedPrice>
+where
+    'a: 'b,
+{
+    // 1. Get accounts
+    let pool_state_pk = pool_state_acc.key;
+    let pool_state = s_controller::load_pool_state(pool_state_acc)?;
+
+    let mint_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let lst_state_list_acc = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let mint_to_price_map_acc_info = extra_accounts
+        .next()
+        .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+
+    let lst_state_list = s_controller::load_lst_state_list(lst_state_list_acc)?;
+    let num_lsts = lst_state_list.len();
+
+    // Note: we take all the needed accounts before any check to leave the iterator in a consistent state
+    // (otherwise, we could break the next price computation)
+    let reserves_accs = extra_accounts.take(num_lsts).collect::<Vec<_>>();
+    require_eq!(
+        reserves_accs.len(),
+        num_lsts,
+        ScopeError::AccountsAndTokenMismatch
+    );
+
+    let mint_to_price_map_acc =
+        Account::<MintsToScopeChains>::try_from(mint_to_price_map_acc_info)?;
+    let mint_to_price_map = mint_to_price_map_acc.deref();
+
+    // 2. Check accounts
+    require_keys_eq!(
+        pool_state.lp_token_mint,
+        *mint_acc.key,
+        ScopeError::UnexpectedAccount
+    );
+    require_keys_eq!(
+        *oracle_prices_pk,
+        mint_to_price_map.oracle_prices,
+        ScopeError::UnexpectedAccount
+    );
+    require_keys_eq!(
+        *pool_state_pk,
+        mint_to_price_map.seed_pk,
+        ScopeError::UnexpectedAccount
+    );
+    require_eq!(
+        u64::try_from(entry_id).map_err(|_| ScopeError::OutOfRangeIntegralConversion)?,
+        mint_to_price_map.seed_id,
+        ScopeError::UnexpectedAccount
+    );
+    require_gte!(mint_to_price_map.mapping.len(), num_lsts);
+
+    // 3. Get INF supply
+    let inf_supply = {
+        let mint = unpack_mint(mint_acc)?;
+        // This is a sanity check to make sure the mint is configured as expected
+        require_eq!(
+            mint.decimals,
+            SANCTUM_INF_DECIMALS,
+            ScopeError::UnexpectedSanctumInfConfiguration
+        );
+        mint.supply
+    };
+    if inf_supply == 0 {
+        msg!("INF supply is 0");
+        return err!(ScopeError::PriceNotValid);
+    }
+
+    // 4. Compute the value of the pool in SOL
+    let mut oldest_price_ts: u64 = clock_unix_timestamp(clock)?;
+    let mut oldest_price_slot: u64 = clock.slot;
+    let mut pool_value_sol = Decimal::zero();
+
+    for ((lst_state, reserves_acc), mint_to_chain) in lst_state_list
+        .iter()
+        .zip(reserves_accs)
+        .zip(mint_to_price_map.mapping.iter())
+    {
+        require_keys_eq!(
+            lst_state.mint,
+            mint_to_chain.mint,
+            ScopeError::UnexpectedAccount
+        );
+        let reserves_amount =
+            s_controller::get_reserves_amount(pool_state_pk, lst_state, reserves_acc)?;
+        if reserves_amount == 0 {
+            continue;
+        }
+
+        let dated_price =
+            get_price_from_chain(oracle_prices, &mint_to_chain.scope_chain).map_err(|e| {
+                msg!(
+                    "Error while getting price of mint {} from scope chain {:?}: {:?}",
+                    mint_to_chain.mint,
+                    mint_to_chain.scope_chain,
+                    e
+                );
+                ScopeError::from(e)
+            })?;
+        pool_value_sol = Decimal::from(dated_price.price)
+            .try_mul(reserves_amount)
+            .and_then(|value| pool_value_sol.try_add(value))
+            .map_err(|_| ScopeError::MathOverflow)?;
+
+        if dated_price.unix_timestamp < oldest_price_ts {
+            oldest_price_ts = dated_price.unix_timestamp;
+            oldest_price_slot = dated_price.last_updated_slot;
+        }
+    }
+
+    // 5. Compute price
+    let price_dec = pool_value_sol
+        .try_div(inf_supply)
+        .map_err(|_| ScopeError::MathOverflow)?;
+
+    Ok(DatedPrice {
+        price: Price::try_from_decimal(price_dec)?,
+        last_updated_slot: oldest_price_slot,
+        unix_timestamp: oldest_price_ts,
+        ..Default::default()
+    })
+}
+
+pub fn validate_pool_account(account: &Option<AccountInfo>) -> Result<()> {
+    let Some(account) = account else {
+        msg!("No Sanctum INF pool state account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    let (expected_pool_state_pk, _) =
+        Pubkey::find_program_address(&[s_controller::POOL_STATE_SEED], &s_controller::ID);
+    require_keys_eq!(
+        expected_pool_state_pk,
+        *account.key,
+        ScopeError::UnexpectedAccount
+    );
+    let _ = s_controller::load_pool_state(account)?;
+    Ok(())
+}
+
+/// Minimal layout of the Sanctum Infinity (S controller) program accounts
+pub mod s_controller {
+    use anchor_lang::__private::bytemuck::{self, Pod, Zeroable};
+
+    use super::*;
+
+    pub const ID: Pubkey = pubkey!("5ocnV1qiCgaQR8Jb8xWnVbApfaygJ8tNoZfgPwsgx9kx");
+
+    pub const POOL_STATE_SEED: &[u8] = b"state";
+    pub const LST_STATE_LIST_SEED: &[u8] = b"lst-state-list";
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    pub struct PoolState {
+        pub total_sol_value: u64,
+        pub trading_protocol_fee_bps: u16,
+        pub lp_protocol_fee_bps: u16,
+        pub version: u8,
+        pub is_disabled: u8,
+        pub is_rebalancing: u8,
+        pub padding: [u8; 1],
+        pub admin: Pubkey,
+        pub rebalance_authority: Pubkey,
+        pub protocol_fee_beneficiary: Pubkey,
+        pub pricing_program: Pubkey,
+        pub lp_token_mint: Pubkey,
+    }
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    pub struct LstState {
+        pub is_input_disabled: u8,
+        pub pool_reserves_bump: u8,
+        pub protocol_fee_accumulator_bump: u8,
+        pub padding: [u8; 5],
+        pub sol_value: u64,
+        pub mint: Pubkey,
+        pub sol_value_calculator: Pubkey,
+    }
+
+    /// S controller accounts have no discriminator, they are only identified by their owner and size
+    pub fn load_pool_state(account: &AccountInfo) -> Result<PoolState> {
+        require_keys_eq!(*account.owner, ID, ScopeError::UnexpectedAccount);
+        let data = account.data.borrow();
+        let pool_state_data = data
+            .get(..std::mem::size_of::<PoolState>())
+            .ok_or_else(|| {
+                msg!("Invalid Sanctum INF pool state account {}", account.key);
+                error!(ScopeError::UnableToDeserializeAccount)
+            })?;
+        Ok(bytemuck::pod_read_unaligned(pool_state_data))
+    }
+
+    pub fn load_lst_state_list(account: &AccountInfo) -> Result<Vec<LstState>> {
+        let (expected_lst_state_list_pk, _) =
+            Pubkey::find_program_address(&[LST_STATE_LIST_SEED], &ID);
+        require_keys_eq!(
+            expected_lst_state_list_pk,
+            *account.key,
+            ScopeError::UnexpectedAccount
+        );
+        require_keys_eq!(*account.owner, ID, ScopeError::UnexpectedAccount);
+        let data = account.data.borrow();
+        if data.len() % std::mem::size_of::<LstState>() != 0 {
+            msg!("Invalid Sanctum INF LST state list account {}", account.key);
+            return err!(ScopeError::UnableToDeserializeAccount);
+        }
+        Ok(data
+            .chunks_exact(std::mem::size_of::<LstState>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+
+    /// Get the amount held in the reserves of an LST of the pool (the associated token account
+    /// of the pool state for the LST mint)
+    pub fn get_reserves_amount(
+        pool_state_pk: &Pubkey,
+        lst_state: &LstState,
+        reserves_acc: &AccountInfo,
+    ) -> Result<u64> {
+        let token_program = reserves_acc.owner;
+        require!(
+            *token_program == anchor_spl::token::ID || *token_program == anchor_spl::token_2022::ID,
+            ScopeError::UnexpectedAccount
+        );
+        let expected_reserves_pk = Pubkey::create_program_address(
+            &[
+                pool_state_pk.as_ref(),
+                token_program.as_ref(),
+                lst_state.mint.as_ref(),
+                &[lst_state.pool_reserves_bump],
+            ],
+            &anchor_spl::associated_token::ID,
+        )
+        .map_err(|_| error!(ScopeError::UnexpectedAccount))?;
+        require_keys_eq!(
+            expected_reserves_pk,
+            *reserves_acc.key,
+            ScopeError::UnexpectedAccount
+        );
+
+        // Token-2022 accounts share the base layout of the token program accounts
+        let data = reserves_acc.data.borrow();
+        let reserves = data
+            .get(..TokenAccount::LEN)
+            .ok_or_else(|| error!(ScopeError::UnableToDeserializeAccount))
+            .and_then(|data| {
+                TokenAccount::unpack_from_slice(data)
+                    .map_err(|_| error!(ScopeError::UnableToDeserializeAccount))
+            })?;
+        Ok(reserves.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::__private::bytemuck::{self, Zeroable};
+    use anchor_spl::token::spl_token::state::{AccountState, Mint};
+
+    use super::{s_controller::*, *};
+    use crate::{
+        oracles::test_utils::{clock, price, sequential_oracle_prices},
+        MintToScopeChain,
+    };
+
+    const ENTRY_ID: usize = 10;
+
+    /// Accounts of a pool whose LST `n` is priced by the entry `n` of the feed
+    struct TestPool {
+        oracle_prices_pk: Pubkey,
+        pool_state_pk: Pubkey,
+        pool_state: Vec<u8>,
+        mint_pk: Pubkey,
+        mint: Vec<u8>,
+        lst_state_list_pk: Pubkey,
+        lst_state_list: Vec<u8>,
+        mint_to_price_map_pk: Pubkey,
+        mint_to_price_map: Vec<u8>,
+        reserves_pks: Vec<Pubkey>,
+        reserves: Vec<Vec<u8>>,
+        lamports: Vec<u64>,
+    }
+
+    impl TestPool {
+        fn new(reserves_amounts: &[u64], inf_supply: u64) -> Self {
+            let oracle_prices_pk = Pubkey::new_unique();
+            let (pool_state_pk, _) = Pubkey::find_program_address(&[POOL_STATE_SEED], &ID);
+            let (lst_state_list_pk, _) = Pubkey::find_program_address(&[LST_STATE_LIST_SEED], &ID);
+            let mint_pk = Pubkey::new_unique();
+
+            let mut pool_state: PoolState = Zeroable::zeroed();
+            pool_state.lp_token_mint = mint_pk;
+
+            let mut mint = vec![0; Mint::LEN];
+            Mint::pack(
+                Mint {
+                    supply: inf_supply,
+                    decimals: SANCTUM_INF_DECIMALS,
+                    is_initialized: true,
+                    ..Default::default()
+                },
+                &mut mint,
+            )
+            .unwrap();
+
+            let mut lst_state_list = Vec::new();
+            let mut mapping = Vec::new();
+            let mut reserves_pks = Vec::new();
+            let mut reserves = Vec::new();
+            for (lst, amount) in reserves_amounts.iter().enumerate() {
+                let lst_mint = Pubkey::new_unique();
+                let (reserves_pk, pool_reserves_bump) = Pubkey::find_program_address(
+                    &[
+                        pool_state_pk.as_ref(),
+                        anchor_spl::token::ID.as_ref(),
+                        lst_mint.as_ref(),
+                    ],
+                    &anchor_spl::associated_token::ID,
+                );
+                let mut lst_state: LstState = Zeroable::zeroed();
+                lst_state.mint = lst_mint;
+                lst_state.pool_reserves_bump = pool_reserves_bump;
+                lst_state_list.extend_from_slice(bytemuck::bytes_of(&lst_state));
+                mapping.push(MintToScopeChain {
+                    mint: lst_mint,
+                    scope_chain: [lst as u16, u16::MAX, u16::MAX, u16::MAX],
+                });
+
+                let mut reserves_data = vec![0; TokenAccount::LEN];
+                TokenAccount::pack(
+                    TokenAccount {
+                        mint: lst_mint,
+                        owner: pool_state_pk,
+                        amount: *amount,
+                        state: AccountState::Initialized,
+                        ..Default::default()
+                    },
+                    &mut reserves_data,
+                )
+                .unwrap();
+                reserves_pks.push(reserves_pk);
+                reserves.push(reserves_data);
+            }
+
+            let mut mint_to_price_map = Vec::new();
+            MintsToScopeChains {
+                oracle_prices: oracle_prices_pk,
+                seed_pk: pool_state_pk,
+                seed_id: ENTRY_ID as u64,
+                bump: 0,
+                mapping,
+            }
+            .try_serialize(&mut mint_to_price_map)
+            .unwrap();
+
+            Self {
+                oracle_prices_pk,
+                pool_state_pk,
+                pool_state: bytemuck::bytes_of(&pool_state).to_vec(),
+                mint_pk,
+                mint,
+                lst_state_list_pk,
+                lst_state_list,
+                mint_to_price_map_pk: Pubkey::new_unique(),
+                mint_to_price_map,
+                lamports: vec![0; 4 + reserves_pks.len()],
+                reserves_pks,
+                reserves,
+            }
+        }
+
+        /// The pool state account and the extra accounts expected by `get_price`
+        fn account_infos(&mut self) -> (AccountInfo<'_>, Vec<AccountInfo<'_>>) {
+            let mut lamports = self.lamports.iter_mut();
+            let pool_state = AccountInfo::new(
+                &self.pool_state_pk,
+                false,
+                false,
+                lamports.next().unwrap(),
+                &mut self.pool_state,
+                &ID,
+                false,
+                0,
+            );
+            let mut extra_accounts = vec![
+                AccountInfo::new(
+                    &self.mint_pk,
+                    false,
+                    false,
+                    lamports.next().unwrap(),
+                    &mut self.mint,
+                    &anchor_spl::token::ID,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &self.lst_state_list_pk,
+                    false,
+                    false,
+                    lamports.next().unwrap(),
+                    &mut self.lst_state_list,
+                    &ID,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &self.mint_to_price_map_pk,
+                    false,
+                    false,
+                    lamports.next().unwrap(),
+                    &mut self.mint_to_price_map,
+                    &crate::ID,
+                    false,
+                    0,
+                ),
+            ];
+            extra_accounts.extend(
+                self.reserves_pks
+                    .iter()
+                    .zip(self.reserves.iter_mut())
+                    .zip(lamports)
+                    .map(|((key, data), lamports)| {
+                        AccountInfo::new(
+                            key,
+                            false,
+                            false,
+                            lamports,
+                            data,
+                            &anchor_spl::token::ID,
+                            false,
+                            0,
+                        )
+                    }),
+            );
+            (pool_state, extra_accounts)
+        }
+    }
+
+    fn get_test_price(pool: &mut TestPool, oracle_prices: &OraclePrices) -> Result<DatedPrice> {
+        let oracle_prices_pk = pool.oracle_prices_pk;
+        let (pool_state, extra_accounts) = pool.account_infos();
+        get_price(
+            ENTRY_ID,
+            &pool_state,
+            &clock(200, 2000),
+            &oracle_prices_pk,
+            oracle_prices,
+            &mut extra_accounts.iter(),
+        )
+    }
+
+    #[test]
+    fn test_inf_price() {
+        // LST 0 has no reserves, its price is not used
+        let oracle_prices = sequential_oracle_prices(&[price(0, 0), price(110, 2), price(105, 2)]);
+        let mut pool = TestPool::new(&[0, 1_000_000_000_000, 500_000_000_000], 1_300_000_000_000);
+
+        // (1000 * 1.1 + 500 * 1.05) / 1300
+        let price = get_test_price(&mut pool, &oracle_prices).unwrap();
+        assert!((f64::from(price.price) - 1.25).abs() < 1e-12);
+        // Dated by the oldest price used
+        assert_eq!(price.last_updated_slot, 101);
+        assert_eq!(price.unix_timestamp, 1001);
+    }
+
+    #[test]
+    fn test_inf_price_zero_supply() {
+        let oracle_prices = sequential_oracle_prices(&[price(1, 0)]);
+        let mut pool = TestPool::new(&[1_000_000_000], 0);
+        assert_eq!(
+            get_test_price(&mut pool, &oracle_prices).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_inf_price_missing_lst_price() {
+        let oracle_prices = sequential_oracle_prices(&[price(1, 0)]);
+        let mut pool = TestPool::new(&[1_000_000_000, 1_000_000_000], 1_000_000_000);
+        assert!(get_test_price(&mut pool, &oracle_prices).is_err());
+    }
+
+    #[test]
+    fn test_inf_price_unexpected_accounts() {
+        let oracle_prices = sequential_oracle_prices(&[price(1, 0)]);
+
+        let mut pool = TestPool::new(&[1_000_000_000], 1_000_000_000);
+        pool.mint_pk = Pubkey::new_unique();
+        assert_eq!(
+            get_test_price(&mut pool, &oracle_prices).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        // Mapping of another feed
+        let mut pool = TestPool::new(&[1_000_000_000], 1_000_000_000);
+        pool.oracle_prices_pk = Pubkey::new_unique();
+        assert_eq!(
+            get_test_price(&mut pool, &oracle_prices).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        // Mapping of another entry
+        let mut pool = TestPool::new(&[1_000_000_000], 1_000_000_000);
+        let oracle_prices_pk = pool.oracle_prices_pk;
+        let (pool_state, extra_accounts) = pool.account_infos();
+        assert_eq!(
+            get_price(
+                ENTRY_ID + 1,
+                &pool_state,
+                &clock(200, 2000),
+                &oracle_prices_pk,
+                &oracle_prices,
+                &mut extra_accounts.iter(),
+            )
+            .unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        // Reserves that are not the associated token account of the pool
+        let mut pool = TestPool::new(&[1_000_000_000], 1_000_000_000);
+        pool.reserves_pks[0] = Pubkey::new_unique();
+        assert_eq!(
+            get_test_price(&mut pool, &oracle_prices).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        // Missing reserves account
+        let mut pool = TestPool::new(&[1_000_000_000], 1_000_000_000);
+        let oracle_prices_pk = pool.oracle_prices_pk;
+        let (pool_state, mut extra_accounts) = pool.account_infos();
+        extra_accounts.pop();
+        assert_eq!(
+            get_price(
+                ENTRY_ID,
+                &pool_state,
+                &clock(200, 2000),
+                &oracle_prices_pk,
+                &oracle_prices,
+                &mut extra_accounts.iter(),
+            )
+            .unwrap_err(),
+            error!(ScopeError::AccountsAndTokenMismatch)
+        );
+    }
+
+    #[test]
+    fn test_validate_pool_account() {
+        let other_key = Pubkey::new_unique();
+        let mut pool = TestPool::new(&[], 0);
+        let (pool_state, _) = pool.account_infos();
+        validate_pool_account(&Some(pool_state.clone())).unwrap();
+
+        assert_eq!(
+            validate_pool_account(&None).unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+
+        let mut other_pool = pool_state.clone();
+        other_pool.key = &other_key;
+        assert_eq!(
+            validate_pool_account(&Some(other_pool)).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        let mut wrong_owner = pool_state.clone();
+        wrong_owner.owner = &anchor_spl::token::ID;
+        assert_eq!(
+            validate_pool_account(&Some(wrong_owner)).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        let mut truncated_pool = TestPool::new(&[], 0);
+        truncated_pool.pool_state.pop();
+        let (pool_state, _) = truncated_pool.account_infos();
+        assert_eq!(
+            validate_pool_account(&Some(pool_state)).unwrap_err(),
+            error!(ScopeError::UnableToDeserializeAccount)
+        );
+    }
+
+    #[test]
+    fn test_load_lst_state_list() {
+        let other_key = Pubkey::new_unique();
+        let mut pool = TestPool::new(&[1, 2], 0);
+        let lst_mints: Vec<Pubkey> = pool
+            .reserves
+            .iter()
+            .map(|data| TokenAccount::unpack(data).unwrap().mint)
+            .collect();
+        let (_, extra_accounts) = pool.account_infos();
+        let lst_state_list = load_lst_state_list(&extra_accounts[1]).unwrap();
+        assert_eq!(
+            lst_state_list.iter().map(|s| s.mint).collect::<Vec<_>>(),
+            lst_mints
+        );
+
+        // Not the PDA of the pool
+        let mut other_list = extra_accounts[1].clone();
+        other_list.key = &other_key;
+        assert_eq!(
+            load_lst_state_list(&other_list).unwrap_err(),
+            error!(ScopeError::UnexpectedAccount)
+        );
+
+        let mut truncated_pool = TestPool::new(&[1, 2], 0);
+        truncated_pool.lst_state_list.pop();
+        let (_, extra_accounts) = truncated_pool.account_infos();
+        assert_eq!(
+            load_lst_state_list(&extra_accounts[1]).unwrap_err(),
+            error!(ScopeError::UnableToDeserializeAccount)
+        );
+    }
+}
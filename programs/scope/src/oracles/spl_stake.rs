@@ -1,47 +1,63 @@
 use anchor_lang::prelude::*;
-use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::{borsh0_10::try_from_slice_unchecked, pubkey};
 
-use self::spl_stake_pool::StakePool;
-use crate::{utils::SECONDS_PER_HOUR, DatedPrice, Price, Result, ScopeError};
+use self::spl_stake_pool::{AccountType, Fee, StakePool};
+use super::staking_rate::{self, StakingRateSample, DEFAULT_MAX_FEE_BPS};
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS},
+    DatedPrice, Price, Result, ScopeError,
+};
 
 const DECIMALS: u32 = 15u32;
 
-/// 0.5%
-const MAX_ACCEPTABLE_FEE_BPS: spl_stake_pool::Fee = spl_stake_pool::Fee {
-    denominator: 1000,
-    numerator: 5,
-};
+/// Programs known to own stake pools with the SPL stake pool layout
+const STAKE_POOL_PROGRAMS: [Pubkey; 3] = [
+    // SPL stake pool
+    pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
+    // Sanctum single-validator stake pools
+    pubkey!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY"),
+    // Sanctum multi-validator stake pools
+    pubkey!("SPMBzsVUuoHA4Jm6KunbsotaahvVikZs1JyTW6iJvbn"),
+];
+
+/// Configuration of a SPL stake pool entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SplStakeData {
+    /// Maximum withdrawal or deposit fee of the pool, 0 for [`DEFAULT_MAX_FEE_BPS`]
+    pub max_fee_bps: u16,
+}
+
+impl SplStakeData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data).map_err(|_| error!(ScopeError::SplStakeInvalid))
+    }
+
+    fn max_acceptable_fee(&self) -> Fee {
+        let max_fee_bps = if self.max_fee_bps == 0 {
+            DEFAULT_MAX_FEE_BPS
+        } else {
+            self.max_fee_bps
+        };
+        Fee {
+            denominator: FULL_BPS.into(),
+            numerator: max_fee_bps.into(),
+        }
+    }
+}
 
 // Gives the price of 1 staked SOL in SOL
 pub fn get_price(
     stake_pool_account_info: &AccountInfo,
     current_clock: &Clock,
+    generic_data: &[u8; 20],
 ) -> Result<DatedPrice> {
-    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account_info.data.borrow())
-        .map_err(|_| {
-            msg!("Provided pubkey is not a SPL Stake account");
-            ScopeError::UnexpectedAccount
-        })?;
+    let data = SplStakeData::from_generic_data(generic_data)?;
+    let stake_pool = load_stake_pool(stake_pool_account_info)?;
 
-    #[cfg(not(feature = "skip_price_validation"))]
-    {
-        let seconds_since_epoch_started = current_clock
-            .unix_timestamp
-            .saturating_sub(current_clock.epoch_start_timestamp);
-        if (stake_pool.last_update_epoch + 1 == current_clock.epoch
-            && seconds_since_epoch_started >= SECONDS_PER_HOUR)
-            || (stake_pool.last_update_epoch + 1 < current_clock.epoch)
-        {
-            // The price has not been refreshed this epoch and it's been 1 hour
-            // We allow 1 hour of delay because stake account are never refreshed very quickly on a new epoch and we don't want to block the price usage.
-            // This is an accepted tradeoff as this price type is only used as reference and not to compute the value of the token.
-            msg!("SPL Stake account has not been refreshed in current epoch");
-            #[cfg(not(feature = "localnet"))]
-            return Err(ScopeError::PriceNotValid.into());
-        }
-    }
+    staking_rate::check_updated_this_epoch(stake_pool.last_update_epoch, current_clock)?;
 
-    check_fees(&stake_pool).map_err(|e| {
+    check_fees(&stake_pool, data.max_acceptable_fee()).map_err(|e| {
         msg!("Stake pool fees are too high: {}", e);
         e
     })?;
@@ -58,13 +74,97 @@ pub fn get_price(
     let dated_price = DatedPrice {
         price,
         last_updated_slot: current_clock.slot,
-        unix_timestamp: u64::try_from(current_clock.unix_timestamp).unwrap(),
+        unix_timestamp: clock_unix_timestamp(current_clock)?,
         ..Default::default()
     };
 
     Ok(dated_price)
 }
 
+/// Staking rate of a stake pool, see [`staking_rate`]
+pub fn get_staking_rate_sample(stake_pool_account_info: &AccountInfo) -> Result<StakingRateSample> {
+    let stake_pool = load_stake_pool(stake_pool_account_info)?;
+
+    Ok(StakingRateSample {
+        rate: Price {
+            value: scaled_rate(&stake_pool)?,
+            exp: DECIMALS.into(),
+        },
+        withdrawal_fee_bps: stake_pool
+            .sol_withdrawal_fee
+            .to_bps()
+            .max(stake_pool.stake_withdrawal_fee.to_bps()),
+        deposit_fee_bps: stake_pool
+            .sol_deposit_fee
+            .to_bps()
+            .max(stake_pool.stake_deposit_fee.to_bps()),
+        last_update_epoch: Some(stake_pool.last_update_epoch),
+    })
+}
+
+pub fn validate_stake_pool_account(stake_pool_account: &Option<AccountInfo>) -> Result<()> {
+    let Some(stake_pool_account) = stake_pool_account else {
+        msg!("No stake pool account provided");
+        return err!(ScopeError::PriceNotValid);
+    };
+    if !STAKE_POOL_PROGRAMS.contains(stake_pool_account.owner) {
+        msg!(
+            "Stake pool account is owned by an unknown program {}",
+            stake_pool_account.owner
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
+    let _ = load_stake_pool(stake_pool_account)?;
+    Ok(())
+}
+
+pub fn validate_mapping_cfg(
+    stake_pool_account: &Option<AccountInfo>,
+    generic_data: &[u8; 20],
+) -> Result<()> {
+    let data = SplStakeData::from_generic_data(generic_data)?;
+    if data.max_fee_bps > FULL_BPS {
+        msg!("Invalid stake pool max fee {} bps", data.max_fee_bps);
+        return err!(ScopeError::SplStakeInvalid);
+    }
+    validate_stake_pool_account(stake_pool_account)
+}
+
+/// Deserialize a stake pool and check its account type, token program and mint.
+///
+/// Trailing data is ignored: forks of the stake pool program (e.g. Sanctum's) may extend the
+/// layout with extra fields at the end of the account.
+fn load_stake_pool(stake_pool_account_info: &AccountInfo) -> Result<StakePool> {
+    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account_info.data.borrow())
+        .map_err(|_| {
+            msg!("Provided pubkey is not a SPL Stake account");
+            ScopeError::UnexpectedAccount
+        })?;
+
+    if stake_pool.account_type != AccountType::StakePool {
+        msg!(
+            "Provided SPL Stake account is not a stake pool: {:?}",
+            stake_pool.account_type
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
+    if stake_pool.token_program_id != anchor_spl::token::ID
+        && stake_pool.token_program_id != anchor_spl::token_2022::ID
+    {
+        msg!(
+            "Stake pool token program {} is not supported",
+            stake_pool.token_program_id
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
+    if stake_pool.pool_mint == Pubkey::default() {
+        msg!("Stake pool has no pool mint");
+        return err!(ScopeError::UnexpectedAccount);
+    }
+
+    Ok(stake_pool)
+}
+
 fn scaled_rate(stake_pool: &StakePool) -> Result<u64> {
     const FACTOR: u64 = 10u64.pow(DECIMALS);
     stake_pool
@@ -72,37 +172,38 @@ fn scaled_rate(stake_pool: &StakePool) -> Result<u64> {
         .ok_or_else(|| ScopeError::MathOverflow.into())
 }
 
-fn check_fees(stake_pool: &StakePool) -> Result<()> {
+fn check_fees(stake_pool: &StakePool, max_acceptable_fee: Fee) -> Result<()> {
     require_gte!(
-        MAX_ACCEPTABLE_FEE_BPS,
+        max_acceptable_fee,
         stake_pool.sol_withdrawal_fee,
         ScopeError::StakeFeeTooHigh
     );
     require_gte!(
-        MAX_ACCEPTABLE_FEE_BPS,
+        max_acceptable_fee,
         stake_pool.stake_withdrawal_fee,
         ScopeError::StakeFeeTooHigh
     );
     require_gte!(
-        MAX_ACCEPTABLE_FEE_BPS,
+        max_acceptable_fee,
         stake_pool.sol_deposit_fee,
         ScopeError::StakeFeeTooHigh
     );
     require_gte!(
-        MAX_ACCEPTABLE_FEE_BPS,
+        max_acceptable_fee,
         stake_pool.stake_deposit_fee,
         ScopeError::StakeFeeTooHigh
     );
     Ok(())
 }
 
-mod spl_stake_pool {
+pub(crate) mod spl_stake_pool {
     use std::fmt::Display;
 
     use anchor_lang::prelude::borsh::BorshSchema;
     use solana_program::stake::state::Lockup;
 
     use super::*;
+    use crate::utils::consts::FULL_BPS;
 
     /// Wrapper type that "counts down" epochs, which is Borsh-compatible with the
     /// native `Option`
@@ -144,6 +245,19 @@ mod spl_stake_pool {
         pub numerator: u64,
     }
 
+    impl Fee {
+        /// Fee in bps, rounded up
+        pub fn to_bps(&self) -> u16 {
+            if self.denominator == 0 {
+                return 0;
+            }
+            let denominator = u128::from(self.denominator);
+            let bps =
+                (u128::from(self.numerator) * u128::from(FULL_BPS) + denominator - 1) / denominator;
+            u16::try_from(bps).unwrap_or(u16::MAX)
+        }
+    }
+
     impl Display for Fee {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             f.write_fmt(format_args!("{}/{}", self.numerator, self.denominator))
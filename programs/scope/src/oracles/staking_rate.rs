@@ -0,0 +1,435 @@
+//! Staking rate oracle: price of a liquid staking (or restaking) token in its underlying token
+//!
+//! The rate is read by a provider, one per staking program, while the fee, staleness and haircut
+//! policy applied on top of it is shared by all the providers.
+
+use anchor_lang::prelude::*;
+
+use super::{jito_restaking, msol_stake, spl_stake};
+use crate::{
+    utils::{clock_unix_timestamp, consts::FULL_BPS, math, SECONDS_PER_HOUR},
+    DatedPrice, Price, ScopeError,
+};
+
+/// Maximum withdrawal or deposit fee accepted when the entry does not configure one (0.5%)
+pub const DEFAULT_MAX_FEE_BPS: u16 = 50;
+
+/// Staking program the rate is read from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakingRateProvider {
+    /// SPL stake pool (and its Sanctum forks), the price account is the stake pool
+    SplStakePool,
+    /// Marinade (mSOL), the price account is the Marinade state
+    Marinade,
+    /// Jito restaking vault (VRT), the price account is the vault
+    JitoRestaking,
+}
+
+/// Configuration of a staking rate entry, stored (borsh serialized) in the entry generic data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StakingRateData {
+    pub provider: StakingRateProvider,
+    /// Maximum withdrawal or deposit fee of the pool, 0 for [`DEFAULT_MAX_FEE_BPS`]
+    pub max_fee_bps: u16,
+    /// Discount applied to the rate on top of the withdrawal fee
+    pub haircut_bps: u16,
+}
+
+impl StakingRateData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> Result<Self> {
+        let mut data: &[u8] = generic_data;
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| error!(ScopeError::StakingRateInvalid))
+    }
+
+    pub fn max_fee_bps(&self) -> u16 {
+        if self.max_fee_bps == 0 {
+            DEFAULT_MAX_FEE_BPS
+        } else {
+            self.max_fee_bps
+        }
+    }
+}
+
+/// Rate read by a provider, before the shared policy is applied
+#[derive(Clone, Copy, Debug)]
+pub struct StakingRateSample {
+    /// Underlying tokens per staked token, before any fee
+    pub rate: Price,
+    /// Fee taken on withdrawal, deducted from the rate
+    pub withdrawal_fee_bps: u16,
+    /// Fee taken on deposit, only checked against the maximum fee
+    pub deposit_fee_bps: u16,
+    /// Epoch at which the provider last updated the rate, `None` if the provider does not track it
+    pub last_update_epoch: Option<u64>,
+}
+
+/// Get the amount of underlying token a holder gets when withdrawing 1 staked token, net of the
+/// withdrawal fee and of the configured haircut
+pub fn get_price(
+    account: &AccountInfo,
+    clock: &Clock,
+    generic_data: &[u8; 20],
+) -> Result<DatedPrice> {
+    let data = StakingRateData::from_generic_data(generic_data)?;
+    let sample = get_sample(data.provider, account)?;
+
+    if let Some(last_update_epoch) = sample.last_update_epoch {
+        check_updated_this_epoch(last_update_epoch, clock)?;
+    }
+
+    let max_fee_bps = data.max_fee_bps();
+    if sample.withdrawal_fee_bps > max_fee_bps || sample.deposit_fee_bps > max_fee_bps {
+        msg!(
+            "{:?} fees are too high: withdrawal {} bps, deposit {} bps, max {} bps",
+            data.provider,
+            sample.withdrawal_fee_bps,
+            sample.deposit_fee_bps,
+            max_fee_bps
+        );
+        return err!(ScopeError::StakeFeeTooHigh);
+    }
+
+    let kept_bps = FULL_BPS
+        .saturating_sub(sample.withdrawal_fee_bps)
+        .saturating_sub(data.haircut_bps);
+    // Cannot overflow as `kept_bps` is at most `FULL_BPS`
+    let value = u64::try_from(math::mul_bps(sample.rate.value, kept_bps))
+        .map_err(|_| ScopeError::MathOverflow)?;
+
+    Ok(DatedPrice {
+        price: Price {
+            value,
+            exp: sample.rate.exp,
+        },
+        last_updated_slot: clock.slot,
+        unix_timestamp: clock_unix_timestamp(clock)?,
+        ..Default::default()
+    })
+}
+
+fn get_sample(provider: StakingRateProvider, account: &AccountInfo) -> Result<StakingRateSample> {
+    match provider {
+        StakingRateProvider::SplStakePool => spl_stake::get_staking_rate_sample(account),
+        StakingRateProvider::Marinade => {
+            msol_stake::get_staking_rate_sample(account).map_err(Into::into)
+        }
+        StakingRateProvider::JitoRestaking => jito_restaking::get_staking_rate_sample(account),
+    }
+}
+
+/// Reject a rate that has not been updated in the current epoch, once the first hour of the
+/// epoch has passed.
+///
+/// We allow 1 hour of delay because the staking programs are never updated very quickly on a new
+/// epoch and we don't want to block the price usage.
+pub fn check_updated_this_epoch(last_update_epoch: u64, clock: &Clock) -> Result<()> {
+    #[cfg(not(feature = "skip_price_validation"))]
+    {
+        let seconds_since_epoch_started = clock
+            .unix_timestamp
+            .saturating_sub(clock.epoch_start_timestamp);
+        if (last_update_epoch + 1 == clock.epoch && seconds_since_epoch_started >= SECONDS_PER_HOUR)
+            || (last_update_epoch + 1 < clock.epoch)
+        {
+            msg!(
+                "Staking rate has not been updated in current epoch {} (last update {})",
+                clock.epoch,
+                last_update_epoch
+            );
+            #[cfg(not(feature = "localnet"))]
+            return Err(ScopeError::PriceNotValid.into());
+        }
+    }
+    #[cfg(feature = "skip_price_validation")]
+    let _ = (last_update_epoch, clock);
+    Ok(())
+}
+
+pub fn validate_mapping_cfg(account: &Option<AccountInfo>, generic_data: &[u8; 20]) -> Result<()> {
+    let data = StakingRateData::from_generic_data(generic_data)?;
+
+    if data.max_fee_bps > FULL_BPS || data.haircut_bps >= FULL_BPS {
+        msg!(
+            "Invalid staking rate fees: max fee {} bps, haircut {} bps",
+            data.max_fee_bps,
+            data.haircut_bps
+        );
+        return err!(ScopeError::StakingRateInvalid);
+    }
+
+    match data.provider {
+        StakingRateProvider::SplStakePool => spl_stake::validate_stake_pool_account(account),
+        StakingRateProvider::Marinade => msol_stake::validate_state_account(account),
+        StakingRateProvider::JitoRestaking => jito_restaking::validate_account(account),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+
+    use super::*;
+    use crate::oracles::{
+        jito_restaking::jito_vault_core::Vault as JitoVault,
+        msol_stake::msol_stake_pool::State as MarinadeState,
+        spl_stake::spl_stake_pool::{AccountType, Fee, StakePool},
+    };
+
+    const EPOCH: u64 = 500;
+
+    fn generic_data(provider: StakingRateProvider, max_fee_bps: u16, haircut_bps: u16) -> [u8; 20] {
+        let mut generic_data = [0; 20];
+        let serialized = StakingRateData {
+            provider,
+            max_fee_bps,
+            haircut_bps,
+        }
+        .try_to_vec()
+        .unwrap();
+        generic_data[..serialized.len()].copy_from_slice(&serialized);
+        generic_data
+    }
+
+    fn clock() -> Clock {
+        Clock {
+            slot: 100,
+            epoch_start_timestamp: 1000,
+            epoch: EPOCH,
+            unix_timestamp: 1000 + SECONDS_PER_HOUR + 1,
+            ..Default::default()
+        }
+    }
+
+    fn get_test_price(data: &mut [u8], generic_data: &[u8; 20]) -> Result<DatedPrice> {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account = AccountInfo::new(&key, false, false, &mut lamports, data, &owner, false, 0);
+        get_price(&account, &clock(), generic_data)
+    }
+
+    /// Stake pool with 1.1 SOL per pool token and a 0.1% SOL withdrawal fee
+    fn stake_pool() -> StakePool {
+        StakePool {
+            account_type: AccountType::StakePool,
+            pool_mint: Pubkey::new_unique(),
+            token_program_id: anchor_spl::token::ID,
+            total_lamports: 1_100_000_000_000,
+            pool_token_supply: 1_000_000_000_000,
+            last_update_epoch: EPOCH,
+            sol_withdrawal_fee: Fee {
+                denominator: 1000,
+                numerator: 1,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Marinade state with 1.2 SOL per mSOL
+    fn marinade_state() -> Vec<u8> {
+        let mut state = MarinadeState::default();
+        state.validator_system.total_active_balance = 1_200_000_000_000;
+        state.msol_supply = 1_000_000_000_000;
+        let mut data = vec![0; 8];
+        data.extend(state.try_to_vec().unwrap());
+        data
+    }
+
+    /// Jito vault with 1.05 supported token per VRT, 0.2% of withdrawal fee (0.3% next epoch) and a
+    /// 0.1% program fee
+    fn jito_vault() -> Vec<u8> {
+        let mut vault = JitoVault::default();
+        vault.vrt_supply.set(1_000_000_000_000);
+        vault.tokens_deposited.set(1_050_000_000_000);
+        vault.withdrawal_fee_bps.set(20);
+        vault.next_withdrawal_fee_bps.set(30);
+        vault.program_fee_bps.set(10);
+        vault.deposit_fee_bps.set(5);
+        let mut data = JitoVault::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&vault));
+        data
+    }
+
+    #[test]
+    fn test_spl_stake_pool_rate() {
+        let mut data = stake_pool().try_to_vec().unwrap();
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::SplStakePool, 0, 0),
+        )
+        .unwrap();
+        // 1.1 minus the 0.1% withdrawal fee
+        assert_eq!(
+            price.price,
+            Price {
+                value: 1_098_900_000_000_000,
+                exp: 15
+            }
+        );
+        assert_eq!(price.last_updated_slot, 100);
+
+        // The haircut is taken on top of the withdrawal fee
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::SplStakePool, 0, 40),
+        )
+        .unwrap();
+        assert_eq!(price.price.value, 1_094_500_000_000_000);
+    }
+
+    #[test]
+    #[cfg(not(any(feature = "skip_price_validation", feature = "localnet")))]
+    fn test_spl_stake_pool_stale_rate() {
+        let mut stake_pool = stake_pool();
+        stake_pool.last_update_epoch = EPOCH - 1;
+        let mut data = stake_pool.try_to_vec().unwrap();
+        assert_eq!(
+            get_test_price(
+                &mut data,
+                &generic_data(StakingRateProvider::SplStakePool, 0, 0)
+            )
+            .unwrap_err(),
+            error!(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_spl_stake_pool_fees() {
+        let mut stake_pool = stake_pool();
+        stake_pool.stake_deposit_fee = Fee {
+            denominator: 10_000,
+            numerator: 60,
+        };
+        let mut data = stake_pool.try_to_vec().unwrap();
+        assert_eq!(
+            get_test_price(
+                &mut data,
+                &generic_data(StakingRateProvider::SplStakePool, 0, 0)
+            )
+            .unwrap_err(),
+            error!(ScopeError::StakeFeeTooHigh)
+        );
+
+        // The deposit fee is only checked, it does not change the rate
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::SplStakePool, 100, 0),
+        )
+        .unwrap();
+        assert_eq!(price.price.value, 1_098_900_000_000_000);
+    }
+
+    #[test]
+    fn test_marinade_rate() {
+        let mut data = marinade_state();
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::Marinade, 0, 0),
+        )
+        .unwrap();
+        assert_eq!(
+            price.price,
+            Price {
+                value: 1_200_000_000_000_000,
+                exp: 15
+            }
+        );
+
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::Marinade, 0, 25),
+        )
+        .unwrap();
+        assert_eq!(price.price.value, 1_197_000_000_000_000);
+    }
+
+    #[test]
+    fn test_jito_restaking_rate() {
+        let mut data = jito_vault();
+        // 1.05 minus the 0.4% withdrawal fee (program fee and next epoch vault fee)
+        let price = get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::JitoRestaking, 0, 0),
+        )
+        .unwrap();
+        assert!((f64::from(price.price) - 1.0458).abs() < 1e-12);
+
+        // 0.4% is above the maximum fee
+        assert_eq!(
+            get_test_price(
+                &mut data,
+                &generic_data(StakingRateProvider::JitoRestaking, 30, 0)
+            )
+            .unwrap_err(),
+            error!(ScopeError::StakeFeeTooHigh)
+        );
+    }
+
+    #[test]
+    fn test_provider_account_mismatch() {
+        let mut data = jito_vault();
+        assert!(get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::SplStakePool, 0, 0)
+        )
+        .is_err());
+
+        let mut data = stake_pool().try_to_vec().unwrap();
+        assert!(get_test_price(
+            &mut data,
+            &generic_data(StakingRateProvider::JitoRestaking, 0, 0)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_invalid_generic_data() {
+        let mut data = stake_pool().try_to_vec().unwrap();
+
+        // Unknown provider
+        let mut unknown_provider = generic_data(StakingRateProvider::SplStakePool, 0, 0);
+        unknown_provider[0] = 3;
+        assert_eq!(
+            get_test_price(&mut data, &unknown_provider).unwrap_err(),
+            error!(ScopeError::StakingRateInvalid)
+        );
+        assert_eq!(
+            validate_mapping_cfg(&None, &unknown_provider).unwrap_err(),
+            error!(ScopeError::StakingRateInvalid)
+        );
+
+        assert_eq!(
+            validate_mapping_cfg(
+                &None,
+                &generic_data(StakingRateProvider::SplStakePool, FULL_BPS + 1, 0)
+            )
+            .unwrap_err(),
+            error!(ScopeError::StakingRateInvalid)
+        );
+        assert_eq!(
+            validate_mapping_cfg(
+                &None,
+                &generic_data(StakingRateProvider::SplStakePool, 0, FULL_BPS)
+            )
+            .unwrap_err(),
+            error!(ScopeError::StakingRateInvalid)
+        );
+    }
+
+    #[test]
+    fn test_default_max_fee() {
+        let data =
+            StakingRateData::from_generic_data(&generic_data(StakingRateProvider::Marinade, 0, 0))
+                .unwrap();
+        assert_eq!(data.max_fee_bps(), DEFAULT_MAX_FEE_BPS);
+
+        let data = StakingRateData::from_generic_data(&generic_data(
+            StakingRateProvider::Marinade,
+            120,
+            0,
+        ))
+        .unwrap();
+        assert_eq!(data.max_fee_bps(), 120);
+    }
+}
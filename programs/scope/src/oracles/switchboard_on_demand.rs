@@ -1,19 +1,125 @@
 use std::convert::TryInto;
 
 use anchor_lang::{prelude::*, solana_program::clock::DEFAULT_MS_PER_SLOT};
-use sbod_itf::accounts::PullFeedAccountData;
+use sbod_itf::accounts::{CurrentResult, PullFeedAccountData};
 
 use super::switchboard_v2::validate_confidence;
-use crate::{utils::zero_copy_deserialize, DatedPrice, Price, ScopeError};
+use crate::{
+    utils::{consts::FULL_BPS, zero_copy_deserialize},
+    ConfidencePolicy, DatedPrice, Price, ScopeError, ScopeResult,
+};
 
 const MAX_EXPONENT: u32 = 15;
 
+/// Number of leading bytes of the feed hash pinned in [`SwitchboardOnDemandData`]
+const PINNED_FEED_HASH_LEN: usize = 12;
+
+/// Configuration of a Switchboard On-Demand entry, stored (borsh serialized) in the entry generic
+/// data. The checks set to 0 are disabled.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwitchboardOnDemandData {
+    /// First bytes of the expected feed hash (the job definition of the feed), not checked if zero
+    pub feed_hash_prefix: [u8; PINNED_FEED_HASH_LEN],
+    /// Minimum number of oracle samples the feed result is computed from
+    pub min_samples: u8,
+    /// Maximum number of slots elapsed since the feed result was signed
+    pub max_staleness_slots: u32,
+    /// Maximum range of the samples (max - min) of the feed result, in bps of its value
+    pub max_variance_bps: u16,
+}
+
+impl SwitchboardOnDemandData {
+    const SERIALIZED_LEN: usize = PINNED_FEED_HASH_LEN + 1 + 4 + 2;
+
+    /// Read the configuration of an entry reading `feed`, checking the pinned feed hash
+    fn from_generic_data(feed: &PullFeedAccountData, generic_data: &[u8; 20]) -> ScopeResult<Self> {
+        if generic_data.iter().all(|b| *b == 0) {
+            return Ok(Self::default());
+        }
+        let mut data: &[u8] = generic_data;
+        let config: Self = AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| ScopeError::SwitchboardOnDemandInvalidConfig)?;
+        if config.feed_hash_prefix != [0; PINNED_FEED_HASH_LEN]
+            && feed.feed_hash[..PINNED_FEED_HASH_LEN] != config.feed_hash_prefix
+        {
+            return Err(ScopeError::SwitchboardOnDemandFeedHashMismatch);
+        }
+        Ok(config)
+    }
+
+    /// Check the feed result against the configured strictness
+    fn check_result(&self, result: &CurrentResult, current_slot: u64) -> ScopeResult<()> {
+        if result.num_samples < self.min_samples {
+            msg!(
+                "SB On-Demand result has {} samples, at least {} are required",
+                result.num_samples,
+                self.min_samples
+            );
+            return Err(ScopeError::SwitchboardOnDemandError);
+        }
+        let age_slots = current_slot.saturating_sub(result.slot);
+        if self.max_staleness_slots != 0 && age_slots > u64::from(self.max_staleness_slots) {
+            msg!(
+                "SB On-Demand result is {age_slots} slots old, max staleness is {} slots",
+                self.max_staleness_slots
+            );
+            return Err(ScopeError::SwitchboardOnDemandError);
+        }
+        if self.max_variance_bps != 0 {
+            // We return an error if value * max_variance_bps < range * FULL_BPS
+            let value_tolerance = result
+                .value
+                .checked_mul(self.max_variance_bps.into())
+                .ok_or(ScopeError::MathOverflow)?;
+            let range_bps = result
+                .range
+                .checked_mul(FULL_BPS.into())
+                .ok_or(ScopeError::MathOverflow)?;
+            if value_tolerance < range_bps {
+                msg!(
+                    "SB On-Demand result range {} is above {} bps of its value {}",
+                    result.range,
+                    self.max_variance_bps,
+                    result.value
+                );
+                return Err(ScopeError::SwitchboardOnDemandError);
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self, generic_data: &[u8; 20]) -> ScopeResult<()> {
+        if generic_data[Self::SERIALIZED_LEN..].iter().any(|b| *b != 0) {
+            msg!("SB On-Demand generic data has non-zero bytes after the configuration");
+            return Err(ScopeError::SwitchboardOnDemandInvalidConfig);
+        }
+        if self.max_variance_bps > FULL_BPS {
+            msg!(
+                "SB On-Demand max variance {} bps is above 100%",
+                self.max_variance_bps
+            );
+            return Err(ScopeError::SwitchboardOnDemandInvalidConfig);
+        }
+        Ok(())
+    }
+}
+
 pub fn get_price(
     switchboard_feed_info: &AccountInfo,
     clock: &Clock,
+    generic_data: &[u8; 20],
+    confidence_policy: &ConfidencePolicy,
 ) -> std::result::Result<DatedPrice, ScopeError> {
     let feed = zero_copy_deserialize::<PullFeedAccountData>(switchboard_feed_info)?;
 
+    let config = SwitchboardOnDemandData::from_generic_data(&feed, generic_data).map_err(|e| {
+        msg!(
+            "SB On-Demand feed {} does not match the entry configuration",
+            switchboard_feed_info.key()
+        );
+        e
+    })?;
+
     let price_switchboard_desc = feed
         .result
         .value()
@@ -21,11 +127,14 @@ pub fn get_price(
     let price: Price = price_switchboard_desc.try_into()?;
 
     if !cfg!(feature = "skip_price_validation") {
+        config.check_result(&feed.result, clock.slot)?;
+
         let std_dev = feed
             .result
             .std_dev()
             .ok_or(ScopeError::SwitchboardOnDemandError)?;
         if validate_confidence(
+            confidence_policy,
             price_switchboard_desc.mantissa(),
             price_switchboard_desc.scale(),
             std_dev.mantissa(),
@@ -62,7 +171,10 @@ pub fn get_price(
     })
 }
 
-pub fn validate_price_account(switchboard_feed_info: &Option<AccountInfo>) -> Result<()> {
+pub fn validate_price_account(
+    switchboard_feed_info: &Option<AccountInfo>,
+    generic_data: &[u8; 20],
+) -> Result<()> {
     if cfg!(feature = "skip_price_validation") {
         return Ok(());
     }
@@ -70,7 +182,18 @@ pub fn validate_price_account(switchboard_feed_info: &Option<AccountInfo>) -> Re
         msg!("No pyth pull price account provided");
         return err!(ScopeError::PriceNotValid);
     };
-    zero_copy_deserialize::<PullFeedAccountData>(switchboard_feed_info)?;
+    let feed = zero_copy_deserialize::<PullFeedAccountData>(switchboard_feed_info)?;
+    let config = SwitchboardOnDemandData::from_generic_data(&feed, generic_data).map_err(|e| {
+        msg!(
+            "SB On-Demand feed {} hash {:?} does not match the entry configuration {:?}",
+            switchboard_feed_info.key(),
+            feed.feed_hash,
+            generic_data
+        );
+        e
+    })?;
+    config.validate(generic_data)?;
+    msg!("SB On-Demand entry configuration: {config:?}");
     Ok(())
 }
 
@@ -102,3 +225,107 @@ impl TryFrom<rust_decimal::Decimal> for Price {
         Ok(Price { value, exp })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed() -> PullFeedAccountData {
+        let mut feed: PullFeedAccountData = bytemuck::Zeroable::zeroed();
+        feed.feed_hash = core::array::from_fn(|i| i as u8 + 1);
+        feed
+    }
+
+    fn generic_data(config: &SwitchboardOnDemandData) -> [u8; 20] {
+        let serialized = config.try_to_vec().unwrap();
+        assert_eq!(serialized.len(), SwitchboardOnDemandData::SERIALIZED_LEN);
+        let mut generic_data = [0; 20];
+        generic_data[..serialized.len()].copy_from_slice(&serialized);
+        generic_data
+    }
+
+    #[test]
+    fn test_config_from_generic_data() {
+        let feed = feed();
+        assert_eq!(
+            SwitchboardOnDemandData::from_generic_data(&feed, &[0; 20]).unwrap(),
+            SwitchboardOnDemandData::default()
+        );
+
+        let config = SwitchboardOnDemandData {
+            feed_hash_prefix: feed.feed_hash[..PINNED_FEED_HASH_LEN].try_into().unwrap(),
+            min_samples: 3,
+            max_staleness_slots: 50,
+            max_variance_bps: 100,
+        };
+        assert_eq!(
+            SwitchboardOnDemandData::from_generic_data(&feed, &generic_data(&config)).unwrap(),
+            config
+        );
+        let unpinned = SwitchboardOnDemandData {
+            feed_hash_prefix: [0; PINNED_FEED_HASH_LEN],
+            ..config
+        };
+        assert_eq!(
+            SwitchboardOnDemandData::from_generic_data(&feed, &generic_data(&unpinned)).unwrap(),
+            unpinned
+        );
+        let mismatch = SwitchboardOnDemandData {
+            feed_hash_prefix: [1; PINNED_FEED_HASH_LEN],
+            ..config
+        };
+        assert_eq!(
+            SwitchboardOnDemandData::from_generic_data(&feed, &generic_data(&mismatch)),
+            Err(ScopeError::SwitchboardOnDemandFeedHashMismatch)
+        );
+    }
+
+    #[test]
+    fn test_check_result() {
+        let mut result: CurrentResult = bytemuck::Zeroable::zeroed();
+        result.value = 100_000;
+        result.range = 1_000;
+        result.num_samples = 3;
+        result.slot = 1_000;
+
+        assert!(SwitchboardOnDemandData::default()
+            .check_result(&result, 100_000)
+            .is_ok());
+
+        let config = SwitchboardOnDemandData {
+            min_samples: 3,
+            max_staleness_slots: 50,
+            max_variance_bps: 100,
+            ..Default::default()
+        };
+        assert!(config.check_result(&result, 1_050).is_ok());
+        // Too old
+        assert!(config.check_result(&result, 1_051).is_err());
+        // Not enough samples
+        result.num_samples = 2;
+        assert!(config.check_result(&result, 1_000).is_err());
+        result.num_samples = 3;
+        // Range above 1% of the value
+        result.range = 1_001;
+        assert!(config.check_result(&result, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = SwitchboardOnDemandData {
+            max_variance_bps: FULL_BPS,
+            ..Default::default()
+        };
+        assert!(config.validate(&generic_data(&config)).is_ok());
+
+        let mut trailing = generic_data(&config);
+        trailing[19] = 1;
+        assert!(config.validate(&trailing).is_err());
+
+        let config = SwitchboardOnDemandData {
+            max_variance_bps: FULL_BPS + 1,
+            ..Default::default()
+        };
+        assert!(config.validate(&generic_data(&config)).is_err());
+    }
+}
@@ -4,14 +4,15 @@ use anchor_lang::prelude::*;
 
 use self::switchboard::*;
 use crate::{
-    utils::{consts::ORACLE_CONFIDENCE_FACTOR, math::check_confidence_interval},
-    DatedPrice, Price, Result, ScopeError,
+    utils::consts::ORACLE_CONFIDENCE_FACTOR, ConfidencePolicy, DatedPrice, Price, Result,
+    ScopeError,
 };
 
 const MAX_EXPONENT: u32 = 10;
 
 pub fn get_price(
     switchboard_feed_info: &AccountInfo,
+    confidence_policy: &ConfidencePolicy,
 ) -> std::result::Result<DatedPrice, ScopeError> {
     let feed = AggregatorAccountData::new(switchboard_feed_info)
         .map_err(|_| ScopeError::SwitchboardV2Error)?;
@@ -30,6 +31,7 @@ pub fn get_price(
         let stdev_mantissa = feed.latest_confirmed_round.std_deviation.mantissa;
         let stdev_scale = feed.latest_confirmed_round.std_deviation.scale;
         if validate_confidence(
+            confidence_policy,
             price_switchboard_desc.mantissa,
             price_switchboard_desc.scale,
             stdev_mantissa,
@@ -53,7 +55,7 @@ pub fn get_price(
         .latest_confirmed_round
         .round_open_timestamp
         .try_into()
-        .unwrap();
+        .map_err(|_| ScopeError::BadTimestamp)?;
 
     Ok(DatedPrice {
         price,
@@ -63,17 +65,20 @@ pub fn get_price(
     })
 }
 
+/// Check the standard deviation of a switchboard price against the confidence policy of the entry
 #[inline(always)]
 pub(super) fn validate_confidence(
+    confidence_policy: &ConfidencePolicy,
     price_mantissa: i128,
     price_scale: u32,
     stdev_mantissa: i128,
     stdev_scale: u32,
 ) -> std::result::Result<(), ScopeError> {
-    check_confidence_interval(
-        price_mantissa.try_into().unwrap(),
+    // A negative price or deviation is rejected
+    confidence_policy.check(
+        price_mantissa.try_into()?,
         price_scale,
-        stdev_mantissa.try_into().unwrap(),
+        stdev_mantissa.try_into()?,
         stdev_scale,
         ORACLE_CONFIDENCE_FACTOR,
     )
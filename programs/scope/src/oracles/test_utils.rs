@@ -0,0 +1,105 @@
+//! Fixtures shared by the tests of the oracles
+
+use anchor_lang::{__private::bytemuck::Zeroable, prelude::*};
+
+use crate::{DatedPrice, OraclePrices, Price, ScopeError, MAX_ENTRIES_U16};
+
+/// Entry generic data holding the borsh serialization of `config`, zero padded
+pub(crate) fn generic_data(config: &impl AnchorSerialize) -> [u8; 20] {
+    let serialized = config.try_to_vec().unwrap();
+    let mut generic_data = [0; 20];
+    generic_data[..serialized.len()].copy_from_slice(&serialized);
+    generic_data
+}
+
+pub(crate) fn price(value: u64, exp: u64) -> Price {
+    Price { value, exp }
+}
+
+pub(crate) fn dated_price(price: Price, last_updated_slot: u64, unix_timestamp: u64) -> DatedPrice {
+    DatedPrice {
+        price,
+        last_updated_slot,
+        unix_timestamp,
+        ..Default::default()
+    }
+}
+
+/// Prices of a feed where only the given entries have a price
+pub(crate) fn oracle_prices(
+    dated_prices: impl IntoIterator<Item = (u16, DatedPrice)>,
+) -> Box<OraclePrices> {
+    let mut oracle_prices: Box<OraclePrices> = Box::new(Zeroable::zeroed());
+    for (entry, dated_price) in dated_prices {
+        oracle_prices.prices[usize::from(entry)] = dated_price;
+    }
+    oracle_prices
+}
+
+/// Prices of the entries 0, 1, 2... with the given prices, entry `n` being updated at slot
+/// `100 + n` and time `1000 + n`
+pub(crate) fn sequential_oracle_prices(prices: &[Price]) -> Box<OraclePrices> {
+    oracle_prices((0..).zip(prices).map(|(entry, price)| {
+        (
+            entry,
+            dated_price(*price, 100 + u64::from(entry), 1000 + u64::from(entry)),
+        )
+    }))
+}
+
+pub(crate) fn clock(slot: u64, unix_timestamp: i64) -> Clock {
+    Clock {
+        slot,
+        unix_timestamp,
+        ..Default::default()
+    }
+}
+
+/// Check that the price of an oracle derived from a `source` entry of `oracle_prices` fails if the
+/// source has no price or is out of the feed.
+///
+/// `get_price` computes the price of the oracle configured with the given source entry.
+pub(crate) fn check_missing_source(
+    oracle_prices: &OraclePrices,
+    source: u16,
+    get_price: impl Fn(&OraclePrices, u16) -> Result<DatedPrice>,
+) {
+    let mut without_source: Box<OraclePrices> = Box::new(*oracle_prices);
+    without_source.prices[usize::from(source)] = DatedPrice::default();
+    assert_eq!(
+        get_price(&without_source, source).unwrap_err(),
+        error!(ScopeError::PriceNotValid)
+    );
+    assert_eq!(
+        get_price(oracle_prices, MAX_ENTRIES_U16).unwrap_err(),
+        error!(ScopeError::BadTokenNb)
+    );
+}
+
+/// Check the mapping validation of an oracle computed from other entries of the feed: the `valid`
+/// generic data are accepted, unless a price account is provided, and the `invalid` ones are
+/// rejected with `invalid_error`.
+pub(crate) fn check_validate_mapping_cfg(
+    validate_mapping_cfg: impl Fn(&Option<AccountInfo>, &[u8; 20]) -> Result<()>,
+    valid: &[[u8; 20]],
+    invalid: &[[u8; 20]],
+    invalid_error: ScopeError,
+) {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data: [u8; 0] = [];
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+    for generic_data in valid {
+        validate_mapping_cfg(&None, generic_data).unwrap();
+        assert_eq!(
+            validate_mapping_cfg(&Some(account.clone()), generic_data).unwrap_err(),
+            error!(ScopeError::PriceAccountNotExpected)
+        );
+    }
+    for generic_data in invalid {
+        assert_eq!(
+            validate_mapping_cfg(&None, generic_data).unwrap_err(),
+            error!(invalid_error)
+        );
+    }
+}
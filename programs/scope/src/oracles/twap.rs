@@ -5,15 +5,19 @@ use intbits::Bits;
 
 use self::utils::{reset_ema_twap, update_ema_twap};
 use crate::{
-    DatedPrice, OracleMappings, OracleTwaps, Price, ScopeError, ScopeResult, MAX_ENTRIES_U16,
+    utils::clock_unix_timestamp, DatedPrice, EmaTwap, EmaType, OracleMappings, OracleTwaps, Price,
+    ScopeError, ScopeResult, MAX_ENTRIES_U16,
 };
 
-const EMA_1H_DURATION_SECONDS: u64 = 60 * 60;
 const MIN_SAMPLES_IN_PERIOD: u32 = 10;
 const NUM_SUB_PERIODS: usize = 3;
 const MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD: u32 = 1;
 
-pub fn validate_price_account(account: &Option<AccountInfo>, twap_source: u16) -> Result<()> {
+pub fn validate_price_account(
+    account: &Option<AccountInfo>,
+    twap_source: u16,
+    generic_data: &[u8; 20],
+) -> Result<()> {
     if account.is_some() {
         return err!(ScopeError::PriceAccountNotExpected);
     }
@@ -24,9 +28,17 @@ pub fn validate_price_account(account: &Option<AccountInfo>, twap_source: u16) -
         ScopeError::TwapSourceIndexOutOfRange
     );
 
+    let ema_type = get_ema_type(generic_data)?;
+    msg!("Twap entry reads the {ema_type:?}");
+
     Ok(())
 }
 
+/// The EMA type read by a twap entry is selected by the first byte of the entry generic data
+fn get_ema_type(generic_data: &[u8; 20]) -> ScopeResult<EmaType> {
+    EmaType::try_from(usize::from(generic_data[0])).map_err(|_| ScopeError::InvalidEmaType)
+}
+
 pub fn update_twap(
     oracle_twaps: &mut OracleTwaps,
     entry_id: usize,
@@ -37,6 +49,7 @@ pub fn update_twap(
         .get_mut(entry_id)
         .ok_or(ScopeError::TwapSourceIndexOutOfRange)?;
 
+    let previous_update_slot = twap.last_update_slot;
     // if there is no previous twap, store the existent
     update_ema_twap(
         twap,
@@ -44,6 +57,13 @@ pub fn update_twap(
         price.unix_timestamp,
         price.last_updated_slot,
     )?;
+    // Only the refreshes adding a sample count towards the end of a bootstrap
+    if twap.last_update_slot > previous_update_slot && twap.bootstrap_remaining_refreshes > 0 {
+        twap.bootstrap_remaining_refreshes -= 1;
+        if twap.bootstrap_remaining_refreshes == 0 {
+            msg!("Twap bootstrap of token {entry_id} expired");
+        }
+    }
     Ok(())
 }
 
@@ -58,10 +78,71 @@ pub fn reset_twap(
         .twaps
         .get_mut(entry_id)
         .ok_or(ScopeError::TwapSourceIndexOutOfRange)?;
-    reset_ema_twap(twap, price, price_ts, price_slot);
+    reset_ema_twap(twap, price, price_ts, price_slot)?;
     Ok(())
 }
 
+/// Seed the EMA of an entry that has never been sampled with an admin provided price.
+///
+/// The EMA is served without the usual samples validation (and flagged with
+/// [`DatedPrice::STATUS_BOOTSTRAP_SEED`]) until `num_refreshes` refreshes added a sample.
+pub fn bootstrap_twap(
+    oracle_twaps: &mut OracleTwaps,
+    entry_id: usize,
+    seed_price: Price,
+    price_ts: u64,
+    price_slot: u64,
+    num_refreshes: u64,
+) -> Result<()> {
+    let twap = oracle_twaps
+        .twaps
+        .get_mut(entry_id)
+        .ok_or(ScopeError::TwapSourceIndexOutOfRange)?;
+
+    if twap.last_update_slot != 0 {
+        msg!(
+            "Twap of token {entry_id} already has samples (last update slot {})",
+            twap.last_update_slot
+        );
+        return err!(ScopeError::TwapBootstrapNotAllowed);
+    }
+
+    reset_ema_twap(twap, seed_price, price_ts, price_slot)?;
+    twap.bootstrap_remaining_refreshes = num_refreshes;
+    Ok(())
+}
+
+/// Add a historical sample to the EMAs of the given entry.
+///
+/// The sample is added to every EMA period, the slower EMAs skipping it if it is too close to
+/// their previous sample (as on refresh). Unlike [`update_twap`], samples that are not strictly
+/// more recent than the last update are rejected instead of being silently skipped. Returns the
+/// updated EMAs.
+pub fn inject_twap_sample(
+    oracle_twaps: &mut OracleTwaps,
+    entry_id: usize,
+    price: Price,
+    price_ts: u64,
+    price_slot: u64,
+) -> Result<EmaTwap> {
+    let twap = oracle_twaps
+        .twaps
+        .get_mut(entry_id)
+        .ok_or(ScopeError::TwapSourceIndexOutOfRange)?;
+
+    if price_ts <= twap.last_update_unix_timestamp || price_slot <= twap.last_update_slot {
+        msg!(
+            "Injected sample (ts {price_ts}, slot {price_slot}) is not after the last update (ts {}, slot {})",
+            twap.last_update_unix_timestamp,
+            twap.last_update_slot
+        );
+        return err!(ScopeError::TwapInjectedSampleOutOfOrder);
+    }
+
+    update_ema_twap(twap, price, price_ts, price_slot)?;
+    Ok(*twap)
+}
+
 pub fn get_price(
     oracle_mappings: &OracleMappings,
     oracle_twaps: &OracleTwaps,
@@ -76,17 +157,28 @@ pub fn get_price(
         .get(source_index)
         .ok_or(ScopeError::TwapSourceIndexOutOfRange)?;
 
-    let current_ts = clock.unix_timestamp.try_into().unwrap();
-    utils::validate_ema(twap, current_ts)?;
+    let ema_type = get_ema_type(&oracle_mappings.generic[entry_id])?;
 
-    Ok(twap.as_dated_price(source_index.try_into().unwrap()))
+    if twap.bootstrap_remaining_refreshes > 0 {
+        msg!(
+            "Twap of token {source_index} is bootstrapped ({} refreshes left)",
+            twap.bootstrap_remaining_refreshes
+        );
+    } else {
+        let current_ts = clock_unix_timestamp(clock)?;
+        utils::validate_ema(twap, ema_type, current_ts)?;
+    }
+
+    let source_index =
+        u16::try_from(source_index).map_err(|_| ScopeError::OutOfRangeIntegralConversion)?;
+    Ok(twap.as_dated_price(source_index, ema_type))
 }
 
 mod utils {
     use decimal_wad::decimal::Decimal;
 
     use super::*;
-    use crate::{EmaTwap, Price, ScopeResult};
+    use crate::{Price, ScopeResult};
 
     /// Get the adjusted smoothing factor (alpha) based on the time between the last two samples.
     ///
@@ -121,6 +213,9 @@ mod utils {
 
     /// update the EMA  time weighted on how recent the last price is. EMA is calculated as:
     /// EMA = (price * smoothing_factor) + (1 - smoothing_factor) * previous_EMA. The smoothing factor is calculated as: (last_sample_delta / sampling_rate_in_seconds) * (2 / (1 + samples_number_per_period)).
+    ///
+    /// A sample too close to the previous one is rejected by the 1h EMA, the slower EMAs only
+    /// skip the samples too close to their own previous sample.
     pub(super) fn update_ema_twap(
         twap: &mut EmaTwap,
         price: Price,
@@ -129,61 +224,125 @@ mod utils {
     ) -> ScopeResult<()> {
         // Skip update if the price is the same as the last one
         if price_slot > twap.last_update_slot {
-            if twap.last_update_slot == 0 {
-                twap.current_ema_1h = Decimal::from(price).to_scaled_val().unwrap();
-            } else {
-                let ema_decimal = Decimal::from_scaled_val(twap.current_ema_1h);
-                let price_decimal = Decimal::from(price);
-
-                let smoothing_factor = get_adjusted_smoothing_factor(
-                    twap.last_update_unix_timestamp,
+            update_ema(
+                &mut twap.current_ema_1h,
+                &mut twap.updates_tracker_1h,
+                twap.last_update_unix_timestamp,
+                twap.last_update_slot == 0,
+                price,
+                price_ts,
+                EmaType::Ema1h,
+            )?;
+            for (ema, tracker, last_sample_ts, ema_type) in [
+                (
+                    &mut twap.current_ema_24h,
+                    &mut twap.updates_tracker_24h,
+                    &mut twap.last_sample_unix_timestamp_24h,
+                    EmaType::Ema24h,
+                ),
+                (
+                    &mut twap.current_ema_7d,
+                    &mut twap.updates_tracker_7d,
+                    &mut twap.last_sample_unix_timestamp_7d,
+                    EmaType::Ema7d,
+                ),
+            ] {
+                let is_first_sample = *last_sample_ts == 0;
+                match update_ema(
+                    ema,
+                    tracker,
+                    *last_sample_ts,
+                    is_first_sample,
+                    price,
                     price_ts,
-                    EMA_1H_DURATION_SECONDS,
-                )?;
-                let new_ema = price_decimal * smoothing_factor
-                    + (Decimal::one() - smoothing_factor) * ema_decimal;
-
-                twap.current_ema_1h = new_ema
-                    .to_scaled_val()
-                    .map_err(|_| ScopeError::IntegerOverflow)?;
+                    ema_type,
+                ) {
+                    Ok(()) => *last_sample_ts = price_ts,
+                    Err(ScopeError::TwapSampleTooFrequent) => {}
+                    Err(e) => return Err(e),
+                }
             }
-            let mut tracker: EmaTracker = twap.updates_tracker_1h.into();
-            tracker.update_tracker(
-                EMA_1H_DURATION_SECONDS,
-                price_ts,
-                twap.last_update_unix_timestamp,
-            );
-            twap.updates_tracker_1h = tracker.into();
             twap.last_update_slot = price_slot;
             twap.last_update_unix_timestamp = price_ts;
         }
         Ok(())
     }
 
-    pub(super) fn reset_ema_twap(twap: &mut EmaTwap, price: Price, price_ts: u64, price_slot: u64) {
-        twap.current_ema_1h = Decimal::from(price).to_scaled_val().unwrap();
+    /// Add a sample to one EMA and its samples tracker
+    fn update_ema(
+        ema: &mut u128,
+        updates_tracker: &mut u64,
+        last_sample_ts: u64,
+        is_first_sample: bool,
+        price: Price,
+        price_ts: u64,
+        ema_type: EmaType,
+    ) -> ScopeResult<()> {
+        let ema_period_s = ema_type.period_seconds();
+        if is_first_sample {
+            *ema = price
+                .try_to_decimal()?
+                .to_scaled_val()
+                .map_err(|_| ScopeError::IntegerOverflow)?;
+        } else {
+            let ema_decimal = Decimal::from_scaled_val(*ema);
+            let price_decimal = price.try_to_decimal()?;
+
+            let smoothing_factor =
+                get_adjusted_smoothing_factor(last_sample_ts, price_ts, ema_period_s)?;
+            let new_ema = price_decimal * smoothing_factor
+                + (Decimal::one() - smoothing_factor) * ema_decimal;
+
+            *ema = new_ema
+                .to_scaled_val()
+                .map_err(|_| ScopeError::IntegerOverflow)?;
+        }
+        let mut tracker: EmaTracker = (*updates_tracker).into();
+        tracker.update_tracker(ema_period_s, price_ts, last_sample_ts);
+        *updates_tracker = tracker.into();
+        Ok(())
+    }
+
+    pub(super) fn reset_ema_twap(
+        twap: &mut EmaTwap,
+        price: Price,
+        price_ts: u64,
+        price_slot: u64,
+    ) -> ScopeResult<()> {
+        let price_scaled = price
+            .try_to_decimal()?
+            .to_scaled_val()
+            .map_err(|_| ScopeError::IntegerOverflow)?;
+        twap.current_ema_1h = price_scaled;
+        twap.current_ema_24h = price_scaled;
+        twap.current_ema_7d = price_scaled;
         twap.last_update_slot = price_slot;
         twap.last_update_unix_timestamp = price_ts;
+        twap.last_sample_unix_timestamp_24h = price_ts;
+        twap.last_sample_unix_timestamp_7d = price_ts;
         twap.updates_tracker_1h = 0;
+        twap.updates_tracker_24h = 0;
+        twap.updates_tracker_7d = 0;
+        twap.bootstrap_remaining_refreshes = 0;
+        Ok(())
     }
 
-    pub(super) fn validate_ema(twap: &EmaTwap, current_ts: u64) -> ScopeResult<()> {
-        let mut tracker: EmaTracker = twap.updates_tracker_1h.into();
-        tracker.erase_old_samples(
-            EMA_1H_DURATION_SECONDS,
-            current_ts,
-            twap.last_update_unix_timestamp,
-        );
+    pub(super) fn validate_ema(
+        twap: &EmaTwap,
+        ema_type: EmaType,
+        current_ts: u64,
+    ) -> ScopeResult<()> {
+        let ema_period_s = ema_type.period_seconds();
+        let (updates_tracker, last_sample_ts) = twap.get_tracker(ema_type);
+        let mut tracker: EmaTracker = updates_tracker.into();
+        tracker.erase_old_samples(ema_period_s, current_ts, last_sample_ts);
 
         if tracker.get_samples_count() < MIN_SAMPLES_IN_PERIOD {
             return Err(ScopeError::TwapNotEnoughSamplesInPeriod);
         }
 
         let samples_count_per_subperiods = tracker
-            .get_samples_count_per_subperiods::<NUM_SUB_PERIODS>(
-                EMA_1H_DURATION_SECONDS,
-                twap.last_update_unix_timestamp,
-            );
+            .get_samples_count_per_subperiods::<NUM_SUB_PERIODS>(ema_period_s, last_sample_ts);
 
         if samples_count_per_subperiods[0] < MIN_SAMPLES_IN_FIRST_AND_LAST_PERIOD
             || samples_count_per_subperiods[NUM_SUB_PERIODS - 1]
@@ -230,23 +389,28 @@ impl EmaTracker {
     }
 
     /// Erase the sample tracker points that are older than the ema_period.
+    ///
+    /// A `current_update_ts` before `last_update_ts` (the cluster clock going backwards, or a
+    /// sample timestamped ahead of it) is clamped to `last_update_ts`: nothing is erased.
     pub(super) fn erase_old_samples(
         &mut self,
         ema_period: u64,
         current_update_ts: u64,
         last_update_ts: u64,
     ) {
-        assert!(
-            current_update_ts >= last_update_ts,
-            "current_update_ts must be bigger than last_update_ts"
-        );
+        let current_update_ts = current_update_ts.max(last_update_ts);
         let sample_tracker = &mut self.0;
 
         let ts_to_point = |ts| Self::ts_to_point(ts, ema_period);
 
         let current_point = ts_to_point(current_update_ts);
+        // Points elapsed since the last update, not wrapped around the tracker.
+        // Note: `last_update_ts + ema_period > current_update_ts` does not guarantee that the
+        // tracker did not wrap around, when the ema_period is not a multiple of 64.
+        let points_since_last_update = current_update_ts * Self::NB_POINTS / ema_period
+            - last_update_ts * Self::NB_POINTS / ema_period;
         // 1. Reset all points up to the current one if needed.
-        if last_update_ts + ema_period <= current_update_ts {
+        if points_since_last_update >= Self::NB_POINTS {
             // Reset all points
             *sample_tracker = 0;
         } else {
@@ -260,11 +424,8 @@ impl EmaTracker {
             let last_point_to_clean = current_point;
 
             match first_point_to_clean.cmp(&last_point_to_clean) {
-                Ordering::Equal => {
-                    // Nothing to reset
-                }
-                Ordering::Less => {
-                    // Reset all points between the first and the last one
+                Ordering::Less | Ordering::Equal => {
+                    // Reset all points between the first and the last one (included)
                     sample_tracker.set_bits(first_point_to_clean..=last_point_to_clean, 0);
                 }
                 Ordering::Greater => {
@@ -276,12 +437,16 @@ impl EmaTracker {
     }
 
     /// Track updates to the EMA
+    ///
+    /// As in [`Self::erase_old_samples`], an update before `last_update_ts` is tracked at
+    /// `last_update_ts`.
     pub(super) fn update_tracker(
         &mut self,
         ema_period: u64,
         current_update_ts: u64,
         last_update_ts: u64,
     ) {
+        let current_update_ts = current_update_ts.max(last_update_ts);
         // 1. Reset all points up to the current one if needed.
         self.erase_old_samples(ema_period, current_update_ts, last_update_ts);
 
@@ -333,3 +498,363 @@ impl EmaTracker {
         counts
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use decimal_wad::decimal::Decimal;
+
+    use super::*;
+
+    /// Small deterministic PRNG (splitmix64) so that failures are reproducible
+    struct SplitMix64(u64);
+
+    impl SplitMix64 {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        /// Random number in `0..=max`
+        fn up_to(&mut self, max: u64) -> u64 {
+            self.next_u64() % (max + 1)
+        }
+    }
+
+    const SEEDS: u64 = 64;
+    const STEPS_PER_SEED: usize = 500;
+    const EMA_PERIODS: [u64; 6] = [64, 100, 1_000, 3_600, 24 * 60 * 60, 7 * 24 * 60 * 60];
+
+    /// Index of the tracker point of `ts`, not wrapped around the tracker
+    fn absolute_point(ts: u64, ema_period: u64) -> u64 {
+        ts * EmaTracker::NB_POINTS / ema_period
+    }
+
+    /// Reference model: the points of the samples in the last 64 points up to `current_ts`
+    fn model_points(samples: &[u64], ema_period: u64, current_ts: u64) -> Vec<u64> {
+        let current_point = absolute_point(current_ts, ema_period);
+        let mut points: Vec<u64> = samples
+            .iter()
+            .map(|&ts| absolute_point(ts, ema_period))
+            .filter(|&point| {
+                point <= current_point && point + EmaTracker::NB_POINTS > current_point
+            })
+            .collect();
+        points.dedup();
+        points
+    }
+
+    fn model_tracker(samples: &[u64], ema_period: u64, current_ts: u64) -> EmaTracker {
+        let bits = model_points(samples, ema_period, current_ts)
+            .into_iter()
+            .fold(0_u64, |bits, point| {
+                bits | (1 << (point % EmaTracker::NB_POINTS))
+            });
+        EmaTracker(bits)
+    }
+
+    fn model_samples_count_per_subperiods<const N: usize>(
+        samples: &[u64],
+        ema_period: u64,
+        current_ts: u64,
+    ) -> [u32; N] {
+        let current_point = absolute_point(current_ts, ema_period);
+        let sub_period_size = EmaTracker::NB_POINTS / N as u64;
+        let mut counts = [0; N];
+        for point in model_points(samples, ema_period, current_ts) {
+            // Oldest point of the window is at index 0, the current one at index 63
+            let index = point + EmaTracker::NB_POINTS - 1 - current_point;
+            let sub_period = usize::try_from(index / sub_period_size).unwrap().min(N - 1);
+            counts[sub_period] += 1;
+        }
+        counts
+    }
+
+    /// Gap between two updates, mixing frequent updates, long pauses and period boundaries
+    fn random_gap(rng: &mut SplitMix64, ema_period: u64) -> u64 {
+        let point_size = ema_period / EmaTracker::NB_POINTS;
+        match rng.up_to(7) {
+            0 => 0,
+            1 | 2 => rng.up_to(point_size.max(1) * 2),
+            3 => rng.up_to(ema_period / 4),
+            4 => rng.up_to(ema_period * 2),
+            5 => ema_period - 1 + rng.up_to(2),
+            6 => point_size * rng.up_to(EmaTracker::NB_POINTS + 1),
+            _ => ema_period * (EmaTracker::NB_POINTS - 1) / EmaTracker::NB_POINTS + rng.up_to(2),
+        }
+    }
+
+    fn check_tracker(
+        tracker: EmaTracker,
+        samples: &[u64],
+        ema_period: u64,
+        current_ts: u64,
+        context: &str,
+    ) {
+        let expected = model_tracker(samples, ema_period, current_ts);
+        assert_eq!(
+            tracker, expected,
+            "{context}: tracker mismatch (period {ema_period}, ts {current_ts})"
+        );
+        assert_eq!(
+            tracker.get_samples_count(),
+            u32::try_from(model_points(samples, ema_period, current_ts).len()).unwrap(),
+            "{context}: samples count mismatch (period {ema_period}, ts {current_ts})"
+        );
+        macro_rules! check_subperiods {
+            ($n:literal) => {
+                assert_eq!(
+                    tracker.get_samples_count_per_subperiods::<$n>(ema_period, current_ts),
+                    model_samples_count_per_subperiods::<$n>(samples, ema_period, current_ts),
+                    "{context}: {} subperiods count mismatch (period {ema_period}, ts {current_ts})",
+                    $n
+                );
+            };
+        }
+        check_subperiods!(1);
+        check_subperiods!(2);
+        check_subperiods!(3);
+        check_subperiods!(5);
+        check_subperiods!(64);
+    }
+
+    #[test]
+    fn test_ema_tracker_randomized_against_model() {
+        for seed in 0..SEEDS {
+            for ema_period in EMA_PERIODS {
+                let mut rng = SplitMix64(seed);
+                let mut tracker = EmaTracker::default();
+                let mut last_update_ts = 1_700_000_000 + rng.up_to(ema_period);
+                let mut samples = vec![];
+
+                for step in 0..STEPS_PER_SEED {
+                    let current_ts = last_update_ts + random_gap(&mut rng, ema_period);
+                    let context = format!("seed {seed}, step {step}");
+
+                    // Reading the tracker later than the last update only erases old samples
+                    let mut erased = tracker;
+                    erased.erase_old_samples(ema_period, current_ts, last_update_ts);
+                    check_tracker(erased, &samples, ema_period, current_ts, &context);
+
+                    tracker.update_tracker(ema_period, current_ts, last_update_ts);
+                    samples.push(current_ts);
+                    check_tracker(tracker, &samples, ema_period, current_ts, &context);
+
+                    last_update_ts = current_ts;
+                    samples.retain(|&ts| ts + 2 * ema_period > current_ts);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ema_tracker_every_gap_around_wrap_around() {
+        // Exhaustively check updates on both sides of every point boundary of a full period
+        for ema_period in EMA_PERIODS {
+            let start_ts = 1_700_000_000;
+            for offset in 0..EmaTracker::NB_POINTS {
+                let point_start_ts = start_ts + offset * ema_period / EmaTracker::NB_POINTS;
+                for last_update_ts in [point_start_ts, point_start_ts + 1] {
+                    // Fill the tracker with one sample per point up to the last update
+                    let mut tracker = EmaTracker::default();
+                    let mut samples = vec![];
+                    let mut ts = last_update_ts - ema_period + 1;
+                    let mut prev_ts = ts;
+                    while ts <= last_update_ts {
+                        tracker.update_tracker(ema_period, ts, prev_ts);
+                        samples.push(ts);
+                        prev_ts = ts;
+                        ts += ema_period / EmaTracker::NB_POINTS;
+                    }
+                    if prev_ts != last_update_ts {
+                        tracker.update_tracker(ema_period, last_update_ts, prev_ts);
+                        samples.push(last_update_ts);
+                    }
+
+                    for gap in [
+                        ema_period - 2,
+                        ema_period - 1,
+                        ema_period,
+                        ema_period + 1,
+                        2 * ema_period,
+                    ] {
+                        let current_ts = last_update_ts + gap;
+                        let context = format!("offset {offset}, gap {gap}");
+
+                        let mut erased = tracker;
+                        erased.erase_old_samples(ema_period, current_ts, last_update_ts);
+                        check_tracker(erased, &samples, ema_period, current_ts, &context);
+
+                        let mut updated = tracker;
+                        updated.update_tracker(ema_period, current_ts, last_update_ts);
+                        let mut updated_samples = samples.clone();
+                        updated_samples.push(current_ts);
+                        check_tracker(updated, &updated_samples, ema_period, current_ts, &context);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ema_tracker_same_point_updates() {
+        let ema_period = 3_600;
+        let mut tracker = EmaTracker::default();
+        let start_ts = 1_700_000_000;
+        // Several updates within the same point are counted once
+        tracker.update_tracker(ema_period, start_ts, start_ts);
+        tracker.update_tracker(ema_period, start_ts + 1, start_ts);
+        tracker.update_tracker(ema_period, start_ts + 2, start_ts + 1);
+        assert_eq!(tracker.get_samples_count(), 1);
+        // A full period later the single previous sample is out of the window
+        tracker.update_tracker(ema_period, start_ts + 2 + ema_period, start_ts + 2);
+        assert_eq!(tracker.get_samples_count(), 1);
+    }
+
+    #[test]
+    fn test_ema_tracker_backwards_time() {
+        for ema_period in EMA_PERIODS {
+            let point_size = ema_period / EmaTracker::NB_POINTS;
+            let start_ts = 1_700_000_000;
+            let mut tracker = EmaTracker::default();
+            let mut samples = vec![];
+            let mut last_update_ts = start_ts;
+            for i in 0..EmaTracker::NB_POINTS / 2 {
+                let ts = start_ts + i * point_size;
+                tracker.update_tracker(ema_period, ts, last_update_ts);
+                samples.push(ts);
+                last_update_ts = ts;
+            }
+            // One second (or a whole point) back, the window is left untouched
+            for back in [1, point_size] {
+                let current_ts = last_update_ts - back;
+                let context = format!("ema period {ema_period}, back {back}");
+
+                let mut erased = tracker;
+                erased.erase_old_samples(ema_period, current_ts, last_update_ts);
+                assert_eq!(erased, tracker, "{context}");
+
+                let mut updated = tracker;
+                updated.update_tracker(ema_period, current_ts, last_update_ts);
+                assert_eq!(updated, tracker, "{context}");
+                check_tracker(updated, &samples, ema_period, last_update_ts, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ema_twap_backwards_sample_ts() {
+        let start_ts = 1_700_000_000;
+        let price = Price { value: 100, exp: 0 };
+        let mut twap: EmaTwap = bytemuck::Zeroable::zeroed();
+        utils::reset_ema_twap(&mut twap, price, start_ts, 1).unwrap();
+        utils::update_ema_twap(&mut twap, price, start_ts + 60, 2).unwrap();
+        let expected = twap;
+
+        // A newer slot timestamped a second before the last sample is skipped, not recorded in
+        // the past
+        assert_eq!(
+            utils::update_ema_twap(&mut twap, price, start_ts + 59, 3),
+            Err(ScopeError::TwapSampleTooFrequent)
+        );
+        assert_eq!(twap, expected);
+
+        // Validating against a clock behind the last sample does not underflow
+        for ema_type in [EmaType::Ema1h, EmaType::Ema24h, EmaType::Ema7d] {
+            assert_eq!(
+                utils::validate_ema(&twap, ema_type, start_ts + 59),
+                Err(ScopeError::TwapNotEnoughSamplesInPeriod)
+            );
+        }
+    }
+
+    #[test]
+    fn test_inject_twap_sample_updates_every_ema() {
+        let start_ts = 1_700_000_000;
+        let mut oracle_twaps: Box<OracleTwaps> = Box::new(bytemuck::Zeroable::zeroed());
+        reset_twap(
+            &mut oracle_twaps,
+            1,
+            Price { value: 100, exp: 0 },
+            start_ts,
+            1,
+        )
+        .unwrap();
+        let seeded = oracle_twaps.twaps[1];
+
+        // An hour later: the 1h EMA takes the new price, the slower ones move towards it
+        let twap = inject_twap_sample(
+            &mut oracle_twaps,
+            1,
+            Price { value: 200, exp: 0 },
+            start_ts + 3_600,
+            2,
+        )
+        .unwrap();
+        assert_eq!(twap, oracle_twaps.twaps[1]);
+        assert_eq!(
+            twap.current_ema_1h,
+            Decimal::from(200_u64).to_scaled_val().unwrap()
+        );
+        for (ema, seeded_ema) in [
+            (twap.current_ema_24h, seeded.current_ema_24h),
+            (twap.current_ema_7d, seeded.current_ema_7d),
+        ] {
+            assert!(ema > seeded_ema && ema < twap.current_ema_1h);
+        }
+        assert_eq!(twap.last_sample_unix_timestamp_24h, start_ts + 3_600);
+        assert_eq!(twap.last_sample_unix_timestamp_7d, start_ts + 3_600);
+        for tracker in [
+            twap.updates_tracker_1h,
+            twap.updates_tracker_24h,
+            twap.updates_tracker_7d,
+        ] {
+            assert_ne!(tracker, 0);
+        }
+
+        // A minute later the sample is too close for the slower EMAs, which skip it
+        let twap = inject_twap_sample(
+            &mut oracle_twaps,
+            1,
+            Price { value: 300, exp: 0 },
+            start_ts + 3_660,
+            3,
+        )
+        .unwrap();
+        assert_eq!(twap.last_update_unix_timestamp, start_ts + 3_660);
+        assert_eq!(twap.last_sample_unix_timestamp_24h, start_ts + 3_600);
+        assert_eq!(twap.last_sample_unix_timestamp_7d, start_ts + 3_600);
+    }
+
+    #[test]
+    fn test_inject_twap_sample_rejected() {
+        let start_ts = 1_700_000_000;
+        let price = Price { value: 100, exp: 0 };
+        let mut oracle_twaps: Box<OracleTwaps> = Box::new(bytemuck::Zeroable::zeroed());
+        reset_twap(&mut oracle_twaps, 1, price, start_ts, 10).unwrap();
+        let expected = oracle_twaps.twaps[1];
+
+        // Samples must be strictly after the last update, both in time and slot
+        for (ts, slot) in [(start_ts, 11), (start_ts + 3_600, 10), (start_ts - 1, 9)] {
+            assert_eq!(
+                inject_twap_sample(&mut oracle_twaps, 1, price, ts, slot).unwrap_err(),
+                error!(ScopeError::TwapInjectedSampleOutOfOrder)
+            );
+        }
+        assert_eq!(oracle_twaps.twaps[1], expected);
+
+        assert_eq!(
+            inject_twap_sample(
+                &mut oracle_twaps,
+                crate::MAX_ENTRIES,
+                price,
+                start_ts + 3_600,
+                11
+            )
+            .unwrap_err(),
+            error!(ScopeError::TwapSourceIndexOutOfRange)
+        );
+    }
+}
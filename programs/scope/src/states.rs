@@ -6,7 +6,11 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{utils::consts::*, MAX_ENTRIES, MAX_ENTRIES_U16};
+use crate::{
+    oracles::OracleType,
+    utils::{consts::*, price_impl::DEFAULT_REF_PRICE_TOLERANCE_BPS},
+    MAX_ENTRIES, MAX_ENTRIES_U16,
+};
 
 #[zero_copy]
 #[derive(Debug, Default, AnchorDeserialize, AnchorSerialize)]
@@ -26,25 +30,44 @@ pub struct Price {
 }
 
 #[zero_copy]
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, AnchorSerialize, AnchorDeserialize)]
 pub struct DatedPrice {
     pub price: Price,
     pub last_updated_slot: u64,
     pub unix_timestamp: u64,
-    pub _reserved: [u64; 2],
-    pub _reserved2: [u16; 3],
+    /// 1h EMA of the entry at the time of the refresh, zero if the entry has no TWAP enabled
+    pub ema_1h: Price,
+    /// Confidence interval reported by the oracle, in bps of the price (saturated at `u16::MAX`),
+    /// 0 if the oracle does not report one
+    pub confidence_bps: u16,
+    /// Flags qualifying the price, see the `DatedPrice::STATUS_*` constants
+    pub status: u16,
+    /// Source that produced the price: 0 for the entry own oracle, `n` for its n-th fallback
+    pub price_source: u16,
     // Current index of the dated price.
     pub index: u16,
 }
 
+impl DatedPrice {
+    /// The price (or its TWAP) comes from an admin provided seed and not from an oracle
+    pub const STATUS_BOOTSTRAP_SEED: u16 = 1 << 0;
+    /// The entry own oracle failed too many consecutive refreshes, see [`OracleEntryStats`].
+    /// Kept across refreshes until the admin acknowledges it.
+    pub const STATUS_DEGRADED: u16 = 1 << 1;
+    /// The entry is suspended by the admin, the price is frozen until the entry is resumed
+    pub const STATUS_SUSPENDED: u16 = 1 << 2;
+}
+
 impl Default for DatedPrice {
     fn default() -> Self {
         Self {
             price: Default::default(),
             last_updated_slot: Default::default(),
             unix_timestamp: Default::default(),
-            _reserved: Default::default(),
-            _reserved2: Default::default(),
+            ema_1h: Default::default(),
+            confidence_bps: 0,
+            status: 0,
+            price_source: 0,
             index: MAX_ENTRIES_U16,
         }
     }
@@ -54,6 +77,18 @@ impl Default for DatedPrice {
 #[repr(usize)]
 pub enum EmaType {
     Ema1h,
+    Ema24h,
+    Ema7d,
+}
+
+impl EmaType {
+    pub const fn period_seconds(&self) -> u64 {
+        match self {
+            EmaType::Ema1h => 60 * 60,
+            EmaType::Ema24h => 24 * 60 * 60,
+            EmaType::Ema7d => 7 * 24 * 60 * 60,
+        }
+    }
 }
 
 #[zero_copy]
@@ -67,7 +102,24 @@ pub struct EmaTwap {
     pub updates_tracker_1h: u64,
     pub padding_0: u64,
 
-    pub padding_1: [u128; 39],
+    pub current_ema_24h: u128,
+    pub current_ema_7d: u128,
+    pub updates_tracker_24h: u64,
+    pub updates_tracker_7d: u64,
+    /// Slower EMAs skip the samples too close to their previous one, so they track their own last sample
+    pub last_sample_unix_timestamp_24h: u64,
+    pub last_sample_unix_timestamp_7d: u64,
+
+    /// Number of refreshes left before the EMA seeded by a bootstrap is validated as usual
+    pub bootstrap_remaining_refreshes: u64,
+    pub padding_2: u64,
+
+    /// Last three raw prices of the entry oracle (most recent first), recorded when the entry
+    /// has a write-time smoothing (see [`SmoothingMode`])
+    pub smoothing_samples: [Price; 3],
+    pub smoothing_samples_slots: [u64; 3],
+    pub padding_3: u64,
+    pub padding_1: [u128; 29],
 }
 
 impl Default for EmaTwap {
@@ -78,19 +130,86 @@ impl Default for EmaTwap {
             last_update_unix_timestamp: 0,
             updates_tracker_1h: 0,
             padding_0: 0,
-            padding_1: [0_u128; 39],
+            current_ema_24h: 0,
+            current_ema_7d: 0,
+            updates_tracker_24h: 0,
+            updates_tracker_7d: 0,
+            last_sample_unix_timestamp_24h: 0,
+            last_sample_unix_timestamp_7d: 0,
+            bootstrap_remaining_refreshes: 0,
+            padding_2: 0,
+            smoothing_samples: Default::default(),
+            smoothing_samples_slots: [0; 3],
+            padding_3: 0,
+            padding_1: [0_u128; 29],
         }
     }
 }
 
 impl EmaTwap {
-    pub fn as_dated_price(&self, index: u16) -> DatedPrice {
+    /// Record `price` as the latest raw price of the entry (if it is newer than the previous one)
+    /// and return the median of the last three raw prices, or `price` until three are recorded
+    pub fn record_and_median_of_3(&mut self, price: &DatedPrice) -> Price {
+        if price.last_updated_slot > self.smoothing_samples_slots[0] {
+            self.smoothing_samples.copy_within(0..2, 1);
+            self.smoothing_samples_slots.copy_within(0..2, 1);
+            self.smoothing_samples[0] = price.price;
+            self.smoothing_samples_slots[0] = price.last_updated_slot;
+        }
+        if self
+            .smoothing_samples
+            .iter()
+            .any(|sample| sample.value == 0)
+        {
+            return price.price;
+        }
+        let mut samples = self
+            .smoothing_samples
+            .map(|sample| (Decimal::from(sample), sample));
+        samples.sort_by(|(a, _), (b, _)| a.cmp(b));
+        samples[1].1
+    }
+
+    /// Forget the recorded raw prices, e.g. when the smoothing of the entry is reconfigured
+    pub fn clear_smoothing_samples(&mut self) {
+        self.smoothing_samples = Default::default();
+        self.smoothing_samples_slots = [0; 3];
+    }
+
+    /// Get the scaled EMA value of the given type
+    pub fn get_ema(&self, ema_type: EmaType) -> u128 {
+        match ema_type {
+            EmaType::Ema1h => self.current_ema_1h,
+            EmaType::Ema24h => self.current_ema_24h,
+            EmaType::Ema7d => self.current_ema_7d,
+        }
+    }
+
+    /// Get the samples tracker and the last sample timestamp of the given EMA type
+    pub fn get_tracker(&self, ema_type: EmaType) -> (u64, u64) {
+        match ema_type {
+            EmaType::Ema1h => (self.updates_tracker_1h, self.last_update_unix_timestamp),
+            EmaType::Ema24h => (
+                self.updates_tracker_24h,
+                self.last_sample_unix_timestamp_24h,
+            ),
+            EmaType::Ema7d => (self.updates_tracker_7d, self.last_sample_unix_timestamp_7d),
+        }
+    }
+
+    pub fn as_dated_price(&self, index: u16, ema_type: EmaType) -> DatedPrice {
         DatedPrice {
-            price: Decimal::from_scaled_val(self.current_ema_1h).into(),
+            price: Decimal::from_scaled_val(self.get_ema(ema_type)).into(),
             last_updated_slot: self.last_update_slot,
             unix_timestamp: self.last_update_unix_timestamp,
-            _reserved: [0; 2],
-            _reserved2: [0; 3],
+            ema_1h: Default::default(),
+            confidence_bps: 0,
+            status: if self.bootstrap_remaining_refreshes > 0 {
+                DatedPrice::STATUS_BOOTSTRAP_SEED
+            } else {
+                0
+            },
+            price_source: 0,
             index,
         }
     }
@@ -115,6 +234,16 @@ pub struct OraclePrices {
     pub prices: [DatedPrice; MAX_ENTRIES],
 }
 
+/// Copy of the prices of a feed taken by the `snapshot_prices` instruction, never written after its
+/// creation so that consumers can pin the prices of a given slot (e.g. during an incident)
+#[account(zero_copy)]
+pub struct OraclePricesSnapshot {
+    pub oracle_prices: Pubkey,
+    pub snapshot_slot: u64,
+    pub snapshot_unix_timestamp: u64,
+    pub prices: [DatedPrice; MAX_ENTRIES],
+}
+
 static_assertions::const_assert_eq!(ORACLE_MAPPING_SIZE, std::mem::size_of::<OracleMappings>());
 static_assertions::const_assert_eq!(0, std::mem::size_of::<OracleMappings>() % 8);
 #[account(zero_copy)]
@@ -123,19 +252,268 @@ pub struct OracleMappings {
     pub price_info_accounts: [Pubkey; MAX_ENTRIES],
     pub price_types: [u8; MAX_ENTRIES],
     pub twap_source: [u16; MAX_ENTRIES], // meaningful only if type == TWAP; the index of where we find the TWAP
-    pub twap_enabled: [u8; MAX_ENTRIES], // true or false
-    pub ref_price: [u16; MAX_ENTRIES], // reference price against which we check confidence within 5%
+    pub twap_enabled: [u8; MAX_ENTRIES], // 0: no twap, TWAP_ENABLED_CRANK_ONLY: sampled by update_twap_only only, other: sampled on refresh
+    pub ref_price: [u16; MAX_ENTRIES], // reference price against which we check the refreshed price is within the tolerance
     pub generic: [[u8; 20]; MAX_ENTRIES], // generic data parsed depending on oracle type
+    pub fallback_sources: [FallbackSources; MAX_ENTRIES], // entries used when the entry own oracle fails
+    pub deviation_checks: [PriceDeviationCheck; MAX_ENTRIES], // max deviation versus the previous stored price
+    pub quote_entries: [QuoteEntry; MAX_ENTRIES], // entry by which the oracle output is multiplied
+    pub ref_price_tolerance_bps: [u16; MAX_ENTRIES], // max deviation versus the ref price, 0 for the default
+    pub cu_budgets: [u32; MAX_ENTRIES], // refresh compute budget estimated on validation, 0 for the type default
+    pub smoothing_modes: [u8; MAX_ENTRIES], // write-time smoothing of the refreshed prices, see SmoothingMode
+    pub unit_scales: [i8; MAX_ENTRIES], // the oracle quotes 10^unit_scale tokens, scaled back to 1 token on refresh
+    pub suspended: [u8; MAX_ENTRIES], // non-zero: the refreshes of the entry are rejected until it is resumed
+    pub confidence_policies: [ConfidencePolicy; MAX_ENTRIES], // confidence interval check of the oracle price
+}
+
+/// Entry whose stored price multiplies the output of the oracle of an entry before it is stored,
+/// e.g. to publish X/USD from an oracle giving X/SOL
+///
+/// This also converts the prices of the pool oracles (CLMM, DLMM...), expressed in the other token
+/// of the pool: a pool quoting in USDT publishes a USD price with the USDT/USD entry as quote.
+#[zero_copy]
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct QuoteEntry {
+    pub is_set: u8,
+    pub _padding: u8,
+    pub entry: u16,
+}
+
+impl QuoteEntry {
+    pub fn get(&self) -> Option<usize> {
+        (self.is_set != 0).then_some(usize::from(self.entry))
+    }
+}
+
+/// Maximum deviation accepted between a new price and the previous stored price of an entry,
+/// if the previous price is not older than `window_slots` compared to the new one.
+#[zero_copy]
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct PriceDeviationCheck {
+    /// 0 means no deviation check
+    pub max_deviation_bps: u16,
+    pub window_slots: u16,
+}
+
+/// Check of the confidence interval (or standard deviation) reported by the oracle of an entry,
+/// applied the same way by all the providers reporting one. See
+/// [`crate::oracles::confidence`] for the check itself.
+///
+/// A zeroed policy applies the default confidence factor of the provider and rejects the prices
+/// failing the check.
+#[zero_copy]
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct ConfidencePolicy {
+    /// Confidence intervals not above `abs_floor_value / 10^abs_floor_exp` are always accepted,
+    /// 0 disables the floor
+    pub abs_floor_value: u64,
+    /// The price must be greater than `factor` times the confidence interval,
+    /// 0 for the default factor of the provider
+    pub factor: u32,
+    /// Maximum confidence interval in bps of the price, replaces the factor when non-zero
+    pub max_bps: u16,
+    pub abs_floor_exp: u8,
+    /// What to do with a price failing the check, see [`ConfidenceFailureMode`]
+    pub failure_mode: u8,
+}
+
+/// Behavior of a [`ConfidencePolicy`] when a price fails the check
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ConfidenceFailureMode {
+    /// The price is rejected (and the fallback sources of the entry are used, if any)
+    #[default]
+    Reject = 0,
+    /// The failure is logged but the price is accepted
+    Warn = 1,
+}
+
+/// Write-time smoothing of the prices refreshed from the oracle of an entry
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum SmoothingMode {
+    /// The raw price of the oracle is stored
+    #[default]
+    None = 0,
+    /// The median of the last three raw prices of the oracle is stored, to filter out one-off bad
+    /// ticks of the source. Only the prices passing the ref price and deviation checks are recorded
+    MedianOf3 = 1,
+}
+
+/// Entries whose prices are used, in order, when the oracle of an entry fails to provide a price
+///
+/// The secondary sources of an entry are other entries of the feed rather than extra oracle
+/// configurations of the entry itself. Each source thus keeps its own oracle type, account, generic
+/// data and checks (reference price, deviation, confidence...) without duplicating them per entry,
+/// which would grow the mappings by more than 50KiB. A fallback entry must be refreshed on its own
+/// and its price is only used when it is more recent than the current price of the entry. The
+/// source of the stored price is recorded in [`DatedPrice::price_source`].
+#[zero_copy]
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct FallbackSources {
+    pub count: u16,
+    pub entries: [u16; 2],
+}
+
+impl FallbackSources {
+    pub const MAX_FALLBACKS: usize = 2;
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.entries
+            .iter()
+            .take(usize::from(self.count))
+            .map(|entry| usize::from(*entry))
+    }
 }
 
 impl OracleMappings {
+    /// `twap_enabled` value of the entries whose TWAP is only sampled by `update_twap_only`
+    pub const TWAP_ENABLED_CRANK_ONLY: u8 = 2;
+
     pub fn is_twap_enabled(&self, entry_id: usize) -> bool {
         self.twap_enabled[entry_id] > 0
     }
 
+    /// Whether the refreshes of the spot price of `entry_id` add a sample to its TWAP
+    pub fn is_twap_sampled_on_refresh(&self, entry_id: usize) -> bool {
+        self.is_twap_enabled(entry_id)
+            && self.twap_enabled[entry_id] != Self::TWAP_ENABLED_CRANK_ONLY
+    }
+
     pub fn get_twap_source(&self, entry_id: usize) -> usize {
         usize::from(self.twap_source[entry_id])
     }
+
+    /// Largest power of ten (either way) between the unit quoted by an oracle and the whole token
+    pub const MAX_UNIT_SCALE: i8 = 9;
+
+    pub fn is_suspended(&self, entry_id: usize) -> bool {
+        self.suspended[entry_id] != 0
+    }
+
+    /// Write-time smoothing of `entry_id`, unknown modes are treated as no smoothing
+    pub fn get_smoothing_mode(&self, entry_id: usize) -> SmoothingMode {
+        SmoothingMode::try_from(self.smoothing_modes[entry_id]).unwrap_or_default()
+    }
+
+    /// Compute budget needed to refresh `entry_id`: the one estimated when the entry was configured
+    /// or, for entries configured before the estimation existed, the default of its type
+    pub fn get_update_cu_budget(&self, entry_id: usize, price_type: OracleType) -> u32 {
+        match self.cu_budgets[entry_id] {
+            0 => price_type.get_update_cu_budget(),
+            cu_budget => cu_budget,
+        }
+    }
+
+    /// Entries whose price is read by the configuration of `entry_id`, with the kind of each
+    /// reference (TWAP source, ref price, fallback, quote or generic data)
+    pub fn get_dependencies(&self, entry_id: usize) -> Vec<(usize, &'static str)> {
+        if self.price_info_accounts[entry_id] == Pubkey::default() {
+            return Vec::new();
+        }
+        let Ok(price_type) = OracleType::try_from(self.price_types[entry_id]) else {
+            return Vec::new();
+        };
+        let mut dependencies = Vec::new();
+        if price_type.is_twap() {
+            dependencies.push((self.get_twap_source(entry_id), "twap source"));
+        }
+        if let Some(ref_price) = self.get_ref_price(entry_id) {
+            dependencies.push((ref_price, "ref price"));
+        }
+        for fallback in self.fallback_sources[entry_id].iter() {
+            dependencies.push((fallback, "fallback source"));
+        }
+        if let Some(quote_entry) = self.quote_entries[entry_id].get() {
+            dependencies.push((quote_entry, "quote entry"));
+        }
+        for referenced in
+            crate::oracles::get_generic_data_references(price_type, &self.generic[entry_id])
+        {
+            dependencies.push((usize::from(referenced), "generic data"));
+        }
+        dependencies
+    }
+
+    /// Entries whose price is read, directly or through other entries, by the configuration of
+    /// `entry_id`, in increasing order.
+    ///
+    /// `entry_id` itself is not listed, even if it belongs to a cycle of references.
+    pub fn get_transitive_dependencies(&self, entry_id: usize) -> Vec<usize> {
+        let mut visited = [false; MAX_ENTRIES];
+        visited[entry_id] = true;
+        let mut to_visit = vec![entry_id];
+        let mut dependencies = Vec::new();
+        while let Some(visited_id) = to_visit.pop() {
+            for (dependency, _) in self.get_dependencies(visited_id) {
+                if dependency < MAX_ENTRIES && !visited[dependency] {
+                    visited[dependency] = true;
+                    dependencies.push(dependency);
+                    to_visit.push(dependency);
+                }
+            }
+        }
+        dependencies.sort_unstable();
+        dependencies
+    }
+
+    /// Entries whose configuration reads the price of `entry_id` (see [`Self::get_dependencies`]),
+    /// each reference is logged
+    pub fn find_references(&self, entry_id: usize) -> Vec<usize> {
+        let mut referencing_entries = Vec::new();
+        for other_id in 0..MAX_ENTRIES {
+            if other_id == entry_id {
+                continue;
+            }
+            let Ok(price_type) = OracleType::try_from(self.price_types[other_id]) else {
+                continue;
+            };
+            let references: Vec<&str> = self
+                .get_dependencies(other_id)
+                .into_iter()
+                .filter(|(dependency, _)| *dependency == entry_id)
+                .map(|(_, kind)| kind)
+                .collect();
+            if !references.is_empty() {
+                msg!("Entry {entry_id} is referenced by entry {other_id} ({price_type:?}) as {references:?}");
+                referencing_entries.push(other_id);
+            }
+        }
+        referencing_entries
+    }
+
+    /// Copy the whole configuration of `entry_id` from the mappings of another feed
+    pub fn copy_entry_from(&mut self, src: &OracleMappings, entry_id: usize) {
+        self.price_info_accounts[entry_id] = src.price_info_accounts[entry_id];
+        self.price_types[entry_id] = src.price_types[entry_id];
+        self.twap_source[entry_id] = src.twap_source[entry_id];
+        self.twap_enabled[entry_id] = src.twap_enabled[entry_id];
+        self.ref_price[entry_id] = src.ref_price[entry_id];
+        self.generic[entry_id] = src.generic[entry_id];
+        self.fallback_sources[entry_id] = src.fallback_sources[entry_id];
+        self.deviation_checks[entry_id] = src.deviation_checks[entry_id];
+        self.quote_entries[entry_id] = src.quote_entries[entry_id];
+        self.ref_price_tolerance_bps[entry_id] = src.ref_price_tolerance_bps[entry_id];
+        self.cu_budgets[entry_id] = src.cu_budgets[entry_id];
+        self.smoothing_modes[entry_id] = src.smoothing_modes[entry_id];
+        self.unit_scales[entry_id] = src.unit_scales[entry_id];
+        self.suspended[entry_id] = src.suspended[entry_id];
+        self.confidence_policies[entry_id] = src.confidence_policies[entry_id];
+    }
+
+    /// Entry against which the refreshed prices of `entry_id` are cross-checked, if any
+    pub fn get_ref_price(&self, entry_id: usize) -> Option<usize> {
+        let ref_price = self.ref_price[entry_id];
+        (ref_price != u16::MAX).then_some(usize::from(ref_price))
+    }
+
+    /// Maximum deviation of the refreshed prices of `entry_id` versus its ref price
+    pub fn get_ref_price_tolerance_bps(&self, entry_id: usize) -> u16 {
+        match self.ref_price_tolerance_bps[entry_id] {
+            0 => DEFAULT_REF_PRICE_TOLERANCE_BPS,
+            tolerance_bps => tolerance_bps,
+        }
+    }
 }
 
 static_assertions::const_assert_eq!(TOKEN_METADATA_SIZE, std::mem::size_of::<TokenMetadatas>());
@@ -154,6 +532,30 @@ pub struct TokenMetadata {
     pub _reserved: [u64; 15],
 }
 
+/// Max age of the prices of the feed, see [`Configuration::max_age_slots`]
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq, Eq, Default)]
+pub struct StalenessPolicy {
+    /// Non-zero: the refreshed prices older than the max age of their entry are rejected (and the
+    /// fallback sources of the entry are used, if any)
+    pub enforce_on_refresh: u8,
+    pub _padding: [u8; 7],
+}
+
+impl StalenessPolicy {
+    pub fn is_enforced_on_refresh(&self) -> bool {
+        self.enforce_on_refresh != 0
+    }
+}
+
+/// Default token metadata applied to an entry when it is configured with a given oracle type
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq, Eq, Default)]
+pub struct OracleTypeMetadataDefaults {
+    pub max_age_price_slots: u64,
+    pub group_ids_bitset: u64,
+}
+
 static_assertions::const_assert_eq!(CONFIGURATION_SIZE, std::mem::size_of::<Configuration>());
 static_assertions::const_assert_eq!(0, std::mem::size_of::<Configuration>() % 8);
 // Configuration account of the program
@@ -165,7 +567,396 @@ pub struct Configuration {
     pub tokens_metadata: Pubkey,
     pub oracle_twaps: Pubkey,
     pub admin_cached: Pubkey,
-    _padding: [u64; 1255],
+    /// Metadata defaults indexed by oracle type
+    pub oracle_type_metadata_defaults: [OracleTypeMetadataDefaults; 64],
+    /// When set, all the refresh instructions of the feed fail
+    pub paused: u8,
+    _padding_0: [u8; 7],
+    /// Entry giving the SOL price in USD, used to re-express the USD entries in SOL
+    pub sol_usd_entry: u16,
+    pub sol_quote_publications_count: u16,
+    _padding_1: [u8; 4],
+    /// USD entries re-expressed in SOL into their companion entry on refresh
+    pub sol_quote_publications: [SolQuotePublication; MAX_SOL_QUOTE_PUBLICATIONS],
+    /// Unix timestamp of the pending proposal to close the feed, 0 if none
+    pub close_proposed_at: u64,
+    /// Account receiving the rent of the feed accounts when the pending close is executed
+    pub close_destination: Pubkey,
+    /// Payouts of the cranks refreshing stale entries, from the feed reward vault
+    pub refresh_reward: RefreshRewardConfig,
+    /// Programs allowed to call the list refresh through a direct CPI, unset slots are default
+    /// pubkeys
+    pub refresh_cpi_callers: [Pubkey; MAX_REFRESH_CPI_CALLERS],
+    /// Can also update the oracle mappings, unset if default pubkey
+    pub mapping_admin: Pubkey,
+    /// Can also update the tokens metadata and pause the feed, unset if default pubkey
+    pub risk_admin: Pubkey,
+    /// Degraded mode forced by the admin, see [`Configuration::is_degraded`]
+    pub degraded_mode_forced: u8,
+    /// Degraded mode entered by `update_degraded_mode` because the slot production lags the wall
+    /// clock by more than `degraded_slot_lag_bps`
+    pub degraded_mode_auto: u8,
+    /// Factor applied to the max age of the prices while the feed is degraded
+    pub degraded_max_age_factor: u16,
+    /// Slot production lag, in bps of the slots expected over the observed period, above which the
+    /// feed automatically enters the degraded mode, 0 to disable the automatic mode
+    pub degraded_slot_lag_bps: u16,
+    _padding_2: [u8; 2],
+    /// Slot and unix timestamp of the last observation of the slot production, 0 if none
+    pub slot_observation_slot: u64,
+    pub slot_observation_ts: u64,
+    /// Bitmask of the [`InstructionFlag`]s disabled on the feed
+    pub disabled_instructions: u64,
+    pub staleness_policy: StalenessPolicy,
+    /// Minimum number of slots between two prices of each entry, the prices refreshed earlier are
+    /// skipped by all the refresh instructions, 0 for no minimum
+    pub min_refresh_intervals_slots: [u32; MAX_ENTRIES],
+    _padding: [u64; 816],
+}
+
+/// Groups of instructions that the admin can disable on a feed, so that deployments with narrower
+/// trust models can switch off surface area
+#[derive(Debug, PartialEq, Eq, Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum InstructionFlag {
+    /// `create_mint_map` and `update_mint_map`
+    MintMaps = 0,
+    /// Manual TWAP overrides: `reset_twap`, `bootstrap_twap` and `inject_twap_samples`
+    TwapOverrides = 1,
+    /// `update_mapping`, `remove_entry`, `clone_mappings`, `set_entry_suspended` and the single
+    /// entry setting updates (`update_unit_scale`, `update_quote_entry`...)
+    MappingUpdates = 2,
+    /// `set_refresh_cpi_callers`
+    RefreshCpiCallers = 3,
+}
+
+impl InstructionFlag {
+    /// Mask of all the known flags
+    pub const ALL: u64 = (1 << 4) - 1;
+
+    pub const fn mask(self) -> u64 {
+        1 << self as u8
+    }
+}
+
+impl Configuration {
+    pub fn is_paused(&self) -> bool {
+        self.paused != 0
+    }
+
+    pub fn check_instruction_enabled(&self, flag: InstructionFlag) -> crate::ScopeResult<()> {
+        if self.disabled_instructions & flag.mask() != 0 {
+            msg!("Instructions {flag:?} are disabled on this feed");
+            return Err(crate::ScopeError::InstructionDisabled);
+        }
+        Ok(())
+    }
+
+    pub fn is_close_proposed(&self) -> bool {
+        self.close_proposed_at != 0
+    }
+
+    pub fn sol_quote_publications(&self) -> &[SolQuotePublication] {
+        &self.sol_quote_publications[..usize::from(self.sol_quote_publications_count)]
+    }
+
+    /// Whether `signer` is the admin or the mapping admin
+    pub fn is_mapping_admin(&self, signer: &Pubkey) -> bool {
+        *signer == self.admin
+            || (self.mapping_admin != Pubkey::default() && *signer == self.mapping_admin)
+    }
+
+    /// Whether `signer` is the admin or the risk admin
+    pub fn is_risk_admin(&self, signer: &Pubkey) -> bool {
+        *signer == self.admin
+            || (self.risk_admin != Pubkey::default() && *signer == self.risk_admin)
+    }
+
+    /// Whether the max age of the prices is currently scaled because of a cluster slowdown
+    pub fn is_degraded(&self) -> bool {
+        self.degraded_mode_forced != 0 || self.degraded_mode_auto != 0
+    }
+
+    /// Max age of the prices of an entry of type `price_type`, not scaled by the degraded mode:
+    /// - the max age of the entry metadata (`entry_max_age_slots`) if set,
+    /// - otherwise the max age of the metadata defaults of the oracle type if set,
+    /// - otherwise the built-in default of the oracle type (see
+    ///   [`OracleType::default_max_age_slots`]), `None` meaning no max age.
+    pub fn max_age_slots(&self, price_type: OracleType, entry_max_age_slots: u64) -> Option<u64> {
+        if entry_max_age_slots != 0 {
+            return Some(entry_max_age_slots);
+        }
+        match self.oracle_type_metadata_defaults[usize::from(u8::from(price_type))]
+            .max_age_price_slots
+        {
+            0 => price_type.default_max_age_slots(),
+            type_max_age_slots => Some(type_max_age_slots),
+        }
+    }
+
+    /// Max age of a price given the max age configured for its entry
+    pub fn effective_max_age_slots(&self, max_age_slots: u64) -> u64 {
+        if self.is_degraded() {
+            max_age_slots.saturating_mul(self.degraded_max_age_factor.max(1).into())
+        } else {
+            max_age_slots
+        }
+    }
+}
+
+/// Reward paid from the feed reward vault (PDA holding lamports, funded by anyone) to the cranks
+/// of `refresh_price_list_rewarded`
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq, Eq, Default)]
+pub struct RefreshRewardConfig {
+    /// Lamports paid for each refreshed entry, 0 disables the rewards
+    pub lamports_per_entry: u64,
+    /// An entry is only rewarded if its previous price is at least this number of slots old
+    pub min_stale_slots: u64,
+    /// Maximum lamports paid for one refresh instruction
+    pub max_lamports_per_refresh: u64,
+}
+
+impl RefreshRewardConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.lamports_per_entry != 0
+    }
+}
+
+pub const MAX_SOL_QUOTE_PUBLICATIONS: usize = 32;
+
+pub const MAX_REFRESH_CPI_CALLERS: usize = 4;
+
+/// A USD entry whose price is published in SOL in a companion entry
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq, Eq, Default)]
+pub struct SolQuotePublication {
+    pub usd_entry: u16,
+    /// Entry reserved to the SOL quote, it must not have an oracle mapping
+    pub sol_entry: u16,
+}
+
+/// Compact digest of the prices of a token group
+///
+/// Small account that external transactions can read to assert over a group of prices without
+/// loading the whole [`OraclePrices`] account.
+#[account(zero_copy)]
+pub struct PriceGroupDigest {
+    pub oracle_prices: Pubkey,
+    pub group_id: u8,
+    _padding0: [u8; 7],
+    /// Slot at which the digest was last written
+    pub last_update_slot: u64,
+    /// Oldest price update slot among the entries of the group
+    pub oldest_price_slot: u64,
+    /// Number of entries in the group when the digest was written
+    pub entries_count: u64,
+    /// XOR of the entries prices, each value being rotated left by its exponent
+    pub prices_digest: u64,
+}
+
+impl PriceGroupDigest {
+    pub fn update(&mut self, prices: &OraclePrices, tokens_metadata: &TokenMetadatas, slot: u64) {
+        let mut oldest_price_slot = u64::MAX;
+        let mut entries_count = 0;
+        let mut prices_digest = 0;
+        for (dated_price, metadata) in prices
+            .prices
+            .iter()
+            .zip(tokens_metadata.metadatas_array.iter())
+        {
+            if metadata.group_ids_bitset & (1 << self.group_id) == 0 {
+                continue;
+            }
+            let Price { value, exp } = dated_price.price;
+            prices_digest ^= value.rotate_left((exp % 64) as u32);
+            oldest_price_slot = oldest_price_slot.min(dated_price.last_updated_slot);
+            entries_count += 1;
+        }
+        self.last_update_slot = slot;
+        self.oldest_price_slot = if entries_count == 0 {
+            0
+        } else {
+            oldest_price_slot
+        };
+        self.entries_count = entries_count;
+        self.prices_digest = prices_digest;
+    }
+}
+
+/// Last prices stored for an entry, in a ring buffer
+///
+/// Written by the refreshes it is passed to (after the price accounts), so that the prices served
+/// at a given slot can be reconstructed without an archival RPC.
+#[account(zero_copy)]
+pub struct OraclePriceHistory {
+    pub oracle_prices: Pubkey,
+    pub entry_id: u16,
+    _padding0: [u8; 6],
+    /// Index in `prices` of the next price to be written
+    pub next_index: u64,
+    /// Number of prices written since the creation of the account
+    pub total_count: u64,
+    pub prices: [DatedPrice; PRICE_HISTORY_DEPTH],
+}
+
+impl OraclePriceHistory {
+    /// Record `price` if it is newer than the last recorded one
+    pub fn record(&mut self, price: &DatedPrice) -> bool {
+        if price.price.value == 0
+            || self
+                .latest()
+                .is_some_and(|latest| price.last_updated_slot <= latest.last_updated_slot)
+        {
+            return false;
+        }
+        let next_index = self.next_index as usize % PRICE_HISTORY_DEPTH;
+        self.prices[next_index] = *price;
+        self.next_index = ((next_index + 1) % PRICE_HISTORY_DEPTH) as u64;
+        self.total_count = self.total_count.saturating_add(1);
+        true
+    }
+
+    pub fn latest(&self) -> Option<&DatedPrice> {
+        if self.total_count == 0 {
+            return None;
+        }
+        let latest_index =
+            (self.next_index as usize + PRICE_HISTORY_DEPTH - 1) % PRICE_HISTORY_DEPTH;
+        Some(&self.prices[latest_index])
+    }
+
+    /// Recorded prices, from the oldest to the latest
+    pub fn iter(&self) -> impl Iterator<Item = &DatedPrice> {
+        let len = self.total_count.min(PRICE_HISTORY_DEPTH as u64) as usize;
+        let first_index =
+            (self.next_index as usize + PRICE_HISTORY_DEPTH - len) % PRICE_HISTORY_DEPTH;
+        (0..len).map(move |i| &self.prices[(first_index + i) % PRICE_HISTORY_DEPTH])
+    }
+
+    /// Price that was served at `slot`: the latest recorded price updated at or before `slot`
+    pub fn get_price_at_slot(&self, slot: u64) -> Option<&DatedPrice> {
+        self.iter()
+            .take_while(|price| price.last_updated_slot <= slot)
+            .last()
+    }
+
+    /// Latest recorded price published at or before `unix_timestamp`
+    pub fn get_price_at_timestamp(&self, unix_timestamp: u64) -> Option<&DatedPrice> {
+        self.iter()
+            .take_while(|price| price.unix_timestamp <= unix_timestamp)
+            .last()
+    }
+}
+
+/// Refresh health of the entries of a feed, updated by the refreshes it is provided to
+#[account(zero_copy)]
+pub struct OracleEntryStats {
+    pub oracle_prices: Pubkey,
+    /// Consecutive failed refreshes after which an entry is marked as degraded, 0 to disable
+    pub max_consecutive_failures: u16,
+    _padding0: [u8; 6],
+    pub entries: [EntryStats; MAX_ENTRIES],
+}
+
+#[zero_copy]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EntryStats {
+    /// Refreshes of the entry whose own oracle failed validation since its last successful one
+    pub consecutive_failures: u16,
+    /// See [`EntryStatus`]
+    pub status: u8,
+    _padding0: [u8; 5],
+    /// Slot at which the entry was marked as degraded, 0 if it is not
+    pub degraded_since_slot: u64,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum EntryStatus {
+    #[default]
+    Healthy = 0,
+    /// Too many consecutive refreshes failed, stays so until acknowledged by the admin
+    Degraded = 1,
+}
+
+impl OracleEntryStats {
+    /// Record the outcome of a refresh of `entry_id`, returns true if the entry just became
+    /// degraded
+    pub fn record_refresh(&mut self, entry_id: usize, failed: bool, slot: u64) -> bool {
+        let max_consecutive_failures = self.max_consecutive_failures;
+        let stats = &mut self.entries[entry_id];
+        if !failed {
+            stats.consecutive_failures = 0;
+            return false;
+        }
+        stats.consecutive_failures = stats.consecutive_failures.saturating_add(1);
+        if max_consecutive_failures == 0
+            || stats.consecutive_failures < max_consecutive_failures
+            || stats.status == u8::from(EntryStatus::Degraded)
+        {
+            return false;
+        }
+        stats.status = EntryStatus::Degraded.into();
+        stats.degraded_since_slot = slot;
+        true
+    }
+
+    pub fn is_degraded(&self, entry_id: usize) -> bool {
+        self.entries[entry_id].status == u8::from(EntryStatus::Degraded)
+    }
+
+    /// Clear the degraded status and the failure count of `entry_id`
+    pub fn acknowledge(&mut self, entry_id: usize) {
+        self.entries[entry_id] = EntryStats::default();
+    }
+}
+
+/// Listing audit trail of an entry, maintained by the admin for the governance tooling
+#[account(zero_copy)]
+pub struct EntryProvenance {
+    pub oracle_prices: Pubkey,
+    pub entry_id: u16,
+    _padding0: [u8; 6],
+    pub info: EntryProvenanceInfo,
+    /// Slot of the last update of `info`
+    pub last_update_slot: u64,
+    pub _reserved: [u64; 32],
+}
+
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq, Eq, Default)]
+pub struct EntryProvenanceInfo {
+    /// Name of the price provider, zero padded
+    pub provider_name: [u8; 32],
+    /// sha256 of the URL of the provider feed
+    pub feed_url_hash: [u8; 32],
+    /// Slot at which the entry was listed, the creation slot of the account if 0 at creation
+    pub onboarding_slot: u64,
+    /// Reference of the risk review approving the listing, zero padded
+    pub risk_review_ref: [u8; 64],
+}
+
+/// Result of the last audit of the oracle mappings accounts, one bit per entry
+#[account(zero_copy)]
+pub struct MappingsAuditReport {
+    pub oracle_mappings: Pubkey,
+    pub last_audit_slot: u64,
+    /// Entries checked by the last audit that covered them, the entries without external account
+    /// or whose oracle type cannot validate its account are left unset
+    pub audited_entries: [u64; 8],
+    /// Entries whose mapped account failed the last audit that covered them
+    pub failed_entries: [u64; 8],
+}
+
+impl MappingsAuditReport {
+    pub fn set_entry_result(&mut self, entry_id: usize, audited: bool, failed: bool) {
+        let (word, bit) = (entry_id / 64, entry_id % 64);
+        self.audited_entries[word] &= !(1 << bit);
+        self.audited_entries[word] |= u64::from(audited) << bit;
+        self.failed_entries[word] &= !(1 << bit);
+        self.failed_entries[word] |= u64::from(failed) << bit;
+    }
 }
 
 /// Map of mints to scope chain only valid for a given price feed
@@ -184,6 +975,8 @@ pub struct MintsToScopeChains {
 pub struct MintToScopeChain {
     #[cfg_attr(feature = "serde", serde(with = "serde_string"))] // Use bs58 for serialization
     pub mint: Pubkey,
+    /// Entries whose prices are multiplied (or divided, see
+    /// [`crate::scope_chain::DIVIDE_HOP_FLAG`]) to price the mint
     pub scope_chain: [u16; 4],
 }
 
@@ -199,6 +992,68 @@ impl MintsToScopeChains {
             + size_of::<u32>() // Vec length
             + len * MINT_TO_SCOPE_CHAIN_SERIALIZED_SIZE // Vec data
     }
+
+    /// Number of mappings once the given `(index, scope_chain)` updates are applied, an update
+    /// at the current length appends a new mapping
+    pub fn len_after_updates(&self, updates: &[(u8, [u16; 4])]) -> usize {
+        updates
+            .iter()
+            .map(|(index, _)| usize::from(*index) + 1)
+            .fold(self.mapping.len(), usize::max)
+    }
+}
+
+/// Declarative token group membership, used to recompute all the entries group IDs bitsets at once
+#[derive(Default)]
+#[account]
+pub struct GroupDefinitions {
+    pub configuration: Pubkey,
+    pub bump: u8,
+    pub groups: Vec<GroupDefinition>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GroupDefinition {
+    /// Group ID in range [0, 64)
+    pub group_id: u8,
+    /// Indices of the entries belonging to the group
+    pub entries: Vec<u16>,
+}
+
+impl GroupDefinitions {
+    pub fn size_from_groups(groups: &[GroupDefinition]) -> usize {
+        let groups_size: usize = groups
+            .iter()
+            .map(|group| {
+                size_of::<u8>() // group_id
+                    + size_of::<u32>() // Vec length
+                    + group.entries.len() * size_of::<u16>() // Vec data
+            })
+            .sum();
+
+        size_of::<Pubkey>() // configuration
+            + size_of::<u8>() // bump
+            + size_of::<u32>() // Vec length
+            + groups_size // Vec data
+    }
+
+    /// Compute the group IDs bitset of every entry from the group definitions
+    pub fn compute_group_ids_bitsets(&self) -> crate::ScopeResult<[u64; MAX_ENTRIES]> {
+        let mut bitsets = [0_u64; MAX_ENTRIES];
+        for group in self.groups.iter() {
+            if u32::from(group.group_id) >= u64::BITS {
+                return Err(crate::ScopeError::InvalidGroupId);
+            }
+            for entry in group.entries.iter() {
+                let bitset = bitsets
+                    .get_mut(usize::from(*entry))
+                    .ok_or(crate::ScopeError::BadTokenNb)?;
+                *bitset |= 1 << group.group_id;
+            }
+        }
+        Ok(bitsets)
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -0,0 +1,438 @@
+//! Versioned header of the feed accounts whose layout is shared with consumers (prices, mappings
+//! and TWAPs).
+//!
+//! These accounts have no reserved space and their data is read at fixed offsets by consumers, so
+//! the 8 bytes header is stored after the zero-copy data, at the end of the account. The accounts
+//! created before the header existed have none and are considered to be at version 0.
+//!
+//! Upgrade path of a layout:
+//! 1. Change the layout, only appending fields at the end, and bump [`VersionedAccount::VERSION`].
+//! 2. Deploy the program: the accounts too small for the new layout can no longer be loaded.
+//! 3. Call `migrate_account` for each account of each feed, until it reports the migration as
+//!    complete. Each call grows the account by at most [`MAX_PERMITTED_DATA_INCREASE`] bytes (the
+//!    limit of the runtime per instruction), the new fields are zeroed and the header is rewritten
+//!    at the new end of the account. The header keeps the previous version until the account
+//!    reaches the new layout, so a partially migrated account is never loaded.
+//!
+//! A program never loads an account whose header has a version more recent than its own layout,
+//! nor an account whose header is not at the end of the current layout (i.e. not migrated yet).
+//! The accounts are thus always loaded through [`load_versioned`] (or [`LoadVersioned`] for the
+//! typed account loaders), which check the version first.
+
+use std::cell::{Ref, RefMut};
+
+use anchor_lang::{
+    __private::bytemuck, prelude::*, solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    system_program, Discriminator, Owner, ZeroCopy,
+};
+
+use crate::{
+    utils::{zero_copy_deserialize, zero_copy_deserialize_mut},
+    OracleMappings, OraclePrices, OracleTwaps, ScopeError, ScopeResult,
+};
+
+/// Identifies a header at the end of an account, absent from the accounts created before it
+const HEADER_MAGIC: [u8; 4] = *b"SCPH";
+
+pub const ACCOUNT_HEADER_LEN: usize = std::mem::size_of::<AccountHeader>();
+
+#[zero_copy]
+#[derive(Debug, PartialEq, Eq)]
+pub struct AccountHeader {
+    pub magic: [u8; 4],
+    /// Layout version of the account data
+    pub version: u16,
+    /// Reserved for the migrations needing to track a state, 0 for now
+    pub flags: u16,
+}
+
+/// Zero-copy account carrying an [`AccountHeader`]
+pub trait VersionedAccount: bytemuck::Pod + Discriminator {
+    /// Current layout version of the account, bumped on each layout change
+    const VERSION: u16;
+
+    /// Size of the account with the current layout and its header
+    fn versioned_len() -> usize {
+        8 + std::mem::size_of::<Self>() + ACCOUNT_HEADER_LEN
+    }
+}
+
+impl VersionedAccount for OraclePrices {
+    const VERSION: u16 = 1;
+}
+
+impl VersionedAccount for OracleMappings {
+    /// 2: quote entries, ref price tolerances, CU budgets, smoothing modes, unit scales, suspended
+    /// flags and confidence policies appended
+    const VERSION: u16 = 2;
+}
+
+impl VersionedAccount for OracleTwaps {
+    const VERSION: u16 = 1;
+}
+
+/// Header at the end of `data`, if any
+fn read_header(data: &[u8]) -> Option<AccountHeader> {
+    let start = data.len().checked_sub(ACCOUNT_HEADER_LEN)?;
+    let header: AccountHeader = bytemuck::pod_read_unaligned(&data[start..]);
+    (header.magic == HEADER_MAGIC).then_some(header)
+}
+
+/// Layout version of `account` (0 if it has no header), failing if it cannot be read with the
+/// current layout of `T`
+pub fn check_account_version<T: VersionedAccount>(account: &AccountInfo) -> ScopeResult<u16> {
+    let data = account.try_borrow_data().map_err(|_| {
+        msg!("Account {} is already mutably borrowed", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
+    let Some(header) = read_header(&data) else {
+        // Created before the header: only readable if it already has room for the current layout
+        let min_len = 8 + std::mem::size_of::<T>();
+        if data.len() < min_len {
+            msg!(
+                "Account {} (no header, {} bytes) must be migrated to version {} ({} bytes)",
+                account.key(),
+                data.len(),
+                T::VERSION,
+                T::versioned_len()
+            );
+            return Err(ScopeError::AccountMigrationRequired);
+        }
+        return Ok(0);
+    };
+    if header.version > T::VERSION {
+        msg!(
+            "Account {} is at version {}, this program supports up to version {}",
+            account.key(),
+            header.version,
+            T::VERSION
+        );
+        return Err(ScopeError::UnsupportedAccountVersion);
+    }
+    if data.len() != T::versioned_len() {
+        msg!(
+            "Account {} (version {}, {} bytes) must be migrated to version {} ({} bytes)",
+            account.key(),
+            header.version,
+            data.len(),
+            T::VERSION,
+            T::versioned_len()
+        );
+        return Err(ScopeError::AccountMigrationRequired);
+    }
+    Ok(header.version)
+}
+
+/// Load a versioned account, once checked that it can be read with the current layout of `T`
+pub fn load_versioned<'info, T: VersionedAccount>(
+    account: &'info AccountInfo,
+) -> ScopeResult<Ref<'info, T>> {
+    check_account_version::<T>(account)?;
+    zero_copy_deserialize::<T>(account)
+}
+
+/// Mutable variant of [`load_versioned`]
+pub fn load_versioned_mut<'info, T: VersionedAccount>(
+    account: &'info AccountInfo,
+) -> ScopeResult<RefMut<'info, T>> {
+    check_account_version::<T>(account)?;
+    zero_copy_deserialize_mut::<T>(account)
+}
+
+/// Checked loads of the versioned accounts passed as typed [`AccountLoader`]s, see
+/// [`load_versioned`]
+pub trait LoadVersioned<T> {
+    fn load_versioned(&self) -> Result<Ref<'_, T>>;
+    fn load_versioned_mut(&self) -> Result<RefMut<'_, T>>;
+}
+
+impl<T: VersionedAccount + ZeroCopy + Owner> LoadVersioned<T> for AccountLoader<'_, T> {
+    fn load_versioned(&self) -> Result<Ref<'_, T>> {
+        check_account_version::<T>(self.as_ref())?;
+        self.load()
+    }
+
+    fn load_versioned_mut(&self) -> Result<RefMut<'_, T>> {
+        check_account_version::<T>(self.as_ref())?;
+        self.load_mut()
+    }
+}
+
+/// Write the header of the current version at the end of a newly created account, if it was
+/// allocated with room for it
+pub fn init_account_header<T: VersionedAccount>(account: &AccountInfo) -> ScopeResult<()> {
+    let mut data = account.try_borrow_mut_data().map_err(|_| {
+        msg!("Account {} is already borrowed", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
+    if data.len() == T::versioned_len() {
+        write_header(&mut data, T::VERSION, 0);
+    }
+    Ok(())
+}
+
+fn write_header(data: &mut [u8], version: u16, flags: u16) {
+    let start = data.len() - ACCOUNT_HEADER_LEN;
+    data[start..].copy_from_slice(bytemuck::bytes_of(&AccountHeader {
+        magic: HEADER_MAGIC,
+        version,
+        flags,
+    }));
+}
+
+/// One call of [`migrate_account`]: growth of the account and header written at its new end
+#[derive(Debug, PartialEq, Eq)]
+struct MigrationStep {
+    /// Whether the account currently ends with a header, which must be zeroed as it becomes part
+    /// of the data of the new layout
+    has_header: bool,
+    from_version: u16,
+    new_len: usize,
+    /// Version written in the header at the new end, the previous one until the migration is
+    /// complete
+    version: u16,
+    flags: u16,
+}
+
+impl MigrationStep {
+    /// Plan the next step of the migration of `data` to the current layout of `T`
+    fn next<T: VersionedAccount>(key: &Pubkey, data: &[u8]) -> ScopeResult<MigrationStep> {
+        let current_len = data.len();
+        let target_len = T::versioned_len();
+        let header = read_header(data);
+        let (from_version, flags) = header
+            .map(|header| (header.version, header.flags))
+            .unwrap_or_default();
+
+        if from_version > T::VERSION {
+            msg!(
+                "Account {key} is at version {from_version}, more recent than {}",
+                T::VERSION
+            );
+            return Err(ScopeError::UnsupportedAccountVersion);
+        }
+        if current_len >= target_len {
+            if current_len == target_len && header.is_some() {
+                msg!("Account {key} is already at version {from_version} ({current_len} bytes)");
+            } else {
+                msg!(
+                    "Account {key} is {current_len} bytes, larger than the versioned layout ({target_len} bytes)"
+                );
+            }
+            return Err(ScopeError::CannotResizeAccount);
+        }
+
+        let new_len = target_len.min(current_len + MAX_PERMITTED_DATA_INCREASE);
+        Ok(MigrationStep {
+            has_header: header.is_some(),
+            from_version,
+            new_len,
+            version: if new_len == target_len {
+                T::VERSION
+            } else {
+                from_version
+            },
+            flags,
+        })
+    }
+
+    /// Zero the current header, before the account is grown
+    fn clear_header(&self, data: &mut [u8]) {
+        if self.has_header {
+            let start = data.len() - ACCOUNT_HEADER_LEN;
+            data[start..].fill(0);
+        }
+    }
+
+    /// Write the header at the end of the grown account
+    fn write_header(&self, data: &mut [u8]) {
+        write_header(data, self.version, self.flags);
+    }
+}
+
+/// Grow `account` towards the current layout of `T`, the rent of the extra space is paid by
+/// `payer`. Returns whether the migration is complete.
+///
+/// The account grows by at most [`MAX_PERMITTED_DATA_INCREASE`] bytes per call, the migration has to
+/// be resumed by new calls until it is complete (see the module documentation).
+pub fn migrate_account<'info, T: VersionedAccount>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<bool> {
+    let current_len = account.data_len();
+    let step = MigrationStep::next::<T>(account.key, &account.try_borrow_data()?)?;
+
+    // The previous header is part of the data of the new layout, which must be zeroed
+    step.clear_header(&mut account.try_borrow_mut_data()?);
+
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(step.new_len);
+    let missing_lamports = rent_exempt_lamports.saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            missing_lamports,
+        )?;
+    }
+
+    account.realloc(step.new_len, true)?;
+    step.write_header(&mut account.try_borrow_mut_data()?);
+
+    let complete = step.new_len == T::versioned_len();
+    msg!(
+        "Migrating account {} from version {} to version {}: {current_len} -> {} of {} bytes{}",
+        account.key(),
+        step.from_version,
+        T::VERSION,
+        step.new_len,
+        T::versioned_len(),
+        if complete {
+            ", complete"
+        } else {
+            ", to be resumed"
+        }
+    );
+
+    Ok(complete)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_header() {
+        assert_eq!(ACCOUNT_HEADER_LEN, 8);
+        assert!(read_header(&[]).is_none());
+        assert!(read_header(&[0; 64]).is_none());
+
+        let mut data = vec![1_u8; 64];
+        write_header(&mut data, 3, 1);
+        assert_eq!(
+            read_header(&data),
+            Some(AccountHeader {
+                magic: HEADER_MAGIC,
+                version: 3,
+                flags: 1,
+            })
+        );
+        assert_eq!(data[..56], [1; 56]);
+    }
+
+    /// Apply the migration steps to `data` as [`migrate_account`] does until it is complete,
+    /// returns the length of the account after each step
+    fn migrate<T: VersionedAccount>(data: &mut Vec<u8>) -> Vec<usize> {
+        let mut lens = Vec::new();
+        while data.len() < T::versioned_len() {
+            let step = MigrationStep::next::<T>(&Pubkey::default(), data).unwrap();
+            step.clear_header(data);
+            data.resize(step.new_len, 0);
+            step.write_header(data);
+            lens.push(step.new_len);
+        }
+        lens
+    }
+
+    #[test]
+    fn test_migrate_baseline_oracle_mappings() {
+        // Mappings created before the header, with the layout of the first release
+        const BASELINE_MAPPINGS_SIZE: usize = 29696;
+        let baseline_len = 8 + BASELINE_MAPPINGS_SIZE;
+        let mut data = vec![0xab_u8; baseline_len];
+        let target_len = OracleMappings::versioned_len();
+
+        let lens = migrate::<OracleMappings>(&mut data);
+
+        assert_eq!(lens.last(), Some(&target_len));
+        let mut previous_len = baseline_len;
+        for len in lens {
+            assert!(len - previous_len <= MAX_PERMITTED_DATA_INCREASE);
+            previous_len = len;
+        }
+        assert_eq!(data[..baseline_len], vec![0xab_u8; baseline_len]);
+        assert!(data[baseline_len..target_len - ACCOUNT_HEADER_LEN]
+            .iter()
+            .all(|&byte| byte == 0));
+        assert_eq!(
+            read_header(&data),
+            Some(AccountHeader {
+                magic: HEADER_MAGIC,
+                version: OracleMappings::VERSION,
+                flags: 0,
+            })
+        );
+        assert!(matches!(
+            MigrationStep::next::<OracleMappings>(&Pubkey::default(), &data),
+            Err(ScopeError::CannotResizeAccount)
+        ));
+    }
+
+    #[test]
+    fn test_migration_resumed() {
+        let target_len = OracleMappings::versioned_len();
+        let initial_len = target_len - 2 * MAX_PERMITTED_DATA_INCREASE - 100;
+        let mut data = vec![0xab_u8; initial_len];
+
+        let step = MigrationStep::next::<OracleMappings>(&Pubkey::default(), &data).unwrap();
+        assert!(!step.has_header);
+        assert_eq!(step.new_len, initial_len + MAX_PERMITTED_DATA_INCREASE);
+        step.clear_header(&mut data);
+        data.resize(step.new_len, 0);
+        step.write_header(&mut data);
+
+        // The partially migrated account keeps its previous version
+        assert_eq!(read_header(&data).map(|header| header.version), Some(0));
+        let step = MigrationStep::next::<OracleMappings>(&Pubkey::default(), &data).unwrap();
+        assert!(step.has_header);
+        assert_eq!(step.version, 0);
+
+        assert_eq!(migrate::<OracleMappings>(&mut data).len(), 2);
+        assert_eq!(data.len(), target_len);
+        // The intermediate headers are cleared
+        assert!(data[initial_len..target_len - ACCOUNT_HEADER_LEN]
+            .iter()
+            .all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_check_headerless_account() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        // Mappings created before the header, with the layout of the first release
+        let mut data = vec![0_u8; 8 + 29696];
+        let account =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+        assert_eq!(
+            check_account_version::<OracleMappings>(&account),
+            Err(ScopeError::AccountMigrationRequired)
+        );
+
+        let mut lamports = 0;
+        let mut data = vec![0_u8; 8 + std::mem::size_of::<OracleMappings>()];
+        let account =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &key, false, 0);
+        assert_eq!(check_account_version::<OracleMappings>(&account), Ok(0));
+    }
+
+    #[test]
+    fn test_migrate_unsupported_version() {
+        let mut data = vec![0_u8; 64];
+        write_header(&mut data, OracleMappings::VERSION + 1, 0);
+        assert!(matches!(
+            MigrationStep::next::<OracleMappings>(&Pubkey::default(), &data),
+            Err(ScopeError::UnsupportedAccountVersion)
+        ));
+
+        let data = vec![0_u8; OracleMappings::versioned_len() + 8];
+        assert!(matches!(
+            MigrationStep::next::<OracleMappings>(&Pubkey::default(), &data),
+            Err(ScopeError::CannotResizeAccount)
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 pub const CONFIGURATION_SIZE: usize = 10232;
-pub const ORACLE_MAPPING_SIZE: usize = 29696;
+pub const ORACLE_MAPPING_SIZE: usize = 49664;
 pub const ORACLE_PRICES_SIZE: usize = 28704;
 pub const ORACLE_TWAPS_SIZE: usize = 344128;
 pub const TOKEN_METADATA_SIZE: usize = 86016;
@@ -10,3 +10,25 @@ pub const TOKEN_METADATA_SIZE: usize = 86016;
 pub const ORACLE_CONFIDENCE_FACTOR: u32 = super::math::confidence_bps_to_factor(200); // 2%
 
 pub const FULL_BPS: u16 = 10_000;
+
+/// Maximum number of compute units a transaction can consume
+pub const MAX_TX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Maximum number of prices returned by `get_validated_prices` (fits in the 1024 bytes of the
+/// instruction return data)
+pub const MAX_VALIDATED_PRICES: usize = 18;
+
+/// Number of prices kept by an [`crate::OraclePriceHistory`] account (fits in the 10KiB limit of
+/// the accounts created by CPI)
+pub const PRICE_HISTORY_DEPTH: usize = 128;
+
+/// Delay between the proposal to close a feed and its execution
+pub const FEED_CLOSE_TIMELOCK_SECONDS: u64 = 3 * 24 * 60 * 60; // 3 days
+
+/// Expected duration of a slot, used to measure the slot production lag of the cluster
+pub const TARGET_SLOT_DURATION_MS: u64 = 400;
+
+/// Bounds of the period over which the slot production is observed to enter or leave the
+/// degraded mode, a longer period only restarts the observation
+pub const DEGRADED_MODE_MIN_OBSERVATION_SECONDS: u64 = 60;
+pub const DEGRADED_MODE_MAX_OBSERVATION_SECONDS: u64 = 60 * 60;
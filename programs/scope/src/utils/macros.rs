@@ -1,3 +1,18 @@
+/// Log either the verbose message or, with the `compact-logs` feature, the given code and integer
+/// parameters, see [`crate::utils::logs`]
+#[macro_export]
+macro_rules! scope_log {
+    ($code:expr, [$($param:expr),* $(,)?], $($verbose:tt)+) => {{
+        #[cfg(all(feature = "compact-logs", not(feature = "devnet")))]
+        $crate::utils::logs::log_compact(
+            $code,
+            &[$(u64::try_from($param).unwrap_or(u64::MAX)),*],
+        );
+        #[cfg(not(all(feature = "compact-logs", not(feature = "devnet"))))]
+        ::anchor_lang::prelude::msg!($($verbose)+);
+    }};
+}
+
 #[macro_export]
 macro_rules! assert_fuzzy_eq {
     ($actual:expr, $expected:expr, $epsilon:expr) => {
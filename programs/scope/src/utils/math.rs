@@ -1,20 +1,31 @@
-use decimal_wad::{decimal::U192, rate::U128};
+use std::cmp::Ordering;
+
+use anchor_lang::prelude::msg;
+use decimal_wad::{
+    common::{TryDiv, TryMul},
+    decimal::{Decimal, U192},
+    rate::U128,
+};
 use raydium_amm_v3::libraries::U256;
 use yvaults::utils::FULL_BPS;
 
 use crate::{Price, ScopeError, ScopeResult};
 
 /// Transform sqrt price to normal price scaled by 2^64
-fn sqrt_price_to_x64_price(sqrt_price: u128, decimals_a: u8, decimals_b: u8) -> U192 {
+fn sqrt_price_to_x64_price(sqrt_price: u128, decimals_a: u8, decimals_b: u8) -> ScopeResult<U192> {
     let sqrt_price = U256::from(sqrt_price);
     let price = (sqrt_price * sqrt_price) >> U256::from(64);
     let price_u256 = if decimals_a >= decimals_b {
-        price * U256::from(ten_pow(decimals_a - decimals_b))
+        price
+            .checked_mul(U256::from(ten_pow(decimals_a - decimals_b)?))
+            .ok_or(ScopeError::MathOverflow)?
     } else {
-        price / U256::from(ten_pow(decimals_b - decimals_a))
+        price / U256::from(ten_pow(decimals_b - decimals_a)?)
     };
-    debug_assert_eq!(price_u256.0[3], 0, "price overflow: {:?}", price_u256); // should not overflow because of the shift
-    U192([price_u256.0[0], price_u256.0[1], price_u256.0[2]])
+    if price_u256.0[3] != 0 {
+        return Err(ScopeError::MathOverflow);
+    }
+    Ok(U192([price_u256.0[0], price_u256.0[1], price_u256.0[2]]))
 }
 
 pub fn sqrt_price_to_price(
@@ -28,16 +39,39 @@ pub fn sqrt_price_to_price(
     }
 
     let x64_price = if a_to_b {
-        sqrt_price_to_x64_price(sqrt_price, decimals_a, decimals_b)
+        sqrt_price_to_x64_price(sqrt_price, decimals_a, decimals_b)?
     } else {
         // invert the sqrt price
         let inverted_sqrt_price = (U192::one() << 128) / sqrt_price;
-        sqrt_price_to_x64_price(inverted_sqrt_price.as_u128(), decimals_b, decimals_a)
+        if inverted_sqrt_price > U192::from(u128::MAX) {
+            return Err(ScopeError::MathOverflow);
+        }
+        sqrt_price_to_x64_price(inverted_sqrt_price.as_u128(), decimals_b, decimals_a)?
     };
 
     q64x64_price_to_price(x64_price)
 }
 
+/// Transform a price of token A in token B into the sqrt price (Q64.64) of a pool A/B.
+///
+/// Inverse of [`sqrt_price_to_price`] with `a_to_b`.
+pub fn price_to_sqrt_price(price: Price, decimals_a: u8, decimals_b: u8) -> ScopeResult<u128> {
+    // Price of 1 lamport of A in lamports of B, scaled by 10^18
+    let lamport_price: u128 = (price
+        .try_to_decimal()?
+        .try_mul(Decimal::from(ten_pow(decimals_b)?))?
+        .try_div(Decimal::from(ten_pow(decimals_a)?))?)
+    .to_scaled_val()
+    .map_err(|_| ScopeError::MathOverflow)?;
+    // sqrt(lamport_price * 2^128 / 10^18) = sqrt(lamport_price * 2^128) / 10^9
+    let lamport_price_x128 = U256::from(lamport_price) << U256::from(128);
+    let sqrt_price = lamport_price_x128.integer_sqrt() / U256::from(ten_pow(9_u32)?);
+    if sqrt_price > U256::from(u128::MAX) {
+        return Err(ScopeError::MathOverflow);
+    }
+    Ok(sqrt_price.as_u128())
+}
+
 pub fn q64x64_price_to_price(x64_price: U192) -> ScopeResult<Price> {
     const MAX_INTEGER_PART: u128 = u64::MAX as u128;
 
@@ -76,7 +110,7 @@ pub fn price_of_lamports_to_price_of_tokens(
     lamport_price: Price,
     token_a_decimals: u64,
     token_b_decimals: u64,
-) -> Price {
+) -> ScopeResult<Price> {
     // lamport_price = number_of_token_b_lamport / number_of_token_a_lamport
     // price = number_of_token_b / number_of_token_a
     // price = (number_of_token_b_lamport / 10^token_b_decimals) / (number_of_token_a_lamport / 10^token_a_decimals)
@@ -89,25 +123,30 @@ pub fn price_of_lamports_to_price_of_tokens(
         exp: lamport_exp,
     } = lamport_price;
 
-    if lamport_exp + token_b_decimals >= token_a_decimals {
-        let exp = lamport_exp + token_b_decimals - token_a_decimals;
-        Price {
+    let lamport_exp_b = lamport_exp
+        .checked_add(token_b_decimals)
+        .ok_or(ScopeError::MathOverflow)?;
+    if lamport_exp_b >= token_a_decimals {
+        Ok(Price {
             value: lamport_value,
-            exp,
-        }
+            exp: lamport_exp_b - token_a_decimals,
+        })
     } else {
-        let adjust_exp = token_a_decimals - (lamport_exp + token_b_decimals);
-        let value = lamport_value * 10_u64.pow(adjust_exp.try_into().unwrap());
-        Price { value, exp: 0 }
+        let adjust_exp = u32::try_from(token_a_decimals - lamport_exp_b)?;
+        let value = 10_u64
+            .checked_pow(adjust_exp)
+            .and_then(|factor| lamport_value.checked_mul(factor))
+            .ok_or(ScopeError::MathOverflow)?;
+        Ok(Price { value, exp: 0 })
     }
 }
 
-pub fn u64_div_to_price(numerator: u64, denominator: u64) -> Price {
+pub fn u64_div_to_price(numerator: u64, denominator: u64) -> ScopeResult<Price> {
     // this implementation aims to keep as much precision as possible
     // choose exp to be the nearest power of 10 to the denominator
     // so that the result is in the range [0, 10^18]
     let (exp, ten_pow_exp) = match denominator {
-        0 => panic!("Creating a price by dividing by 0"),
+        0 => return Err(ScopeError::MathOverflow),
         1..=10 => (0, 1_u64),
         11..=100 => (1, 10),
         101..=1000 => (2, 100),
@@ -130,13 +169,16 @@ pub fn u64_div_to_price(numerator: u64, denominator: u64) -> Price {
     };
     let numerator_scaled = U128::from(numerator) * U128::from(ten_pow_exp);
     let price_value = numerator_scaled / U128::from(denominator);
-    Price {
+    if price_value > U128::from(u64::MAX) {
+        return Err(ScopeError::IntegerOverflow);
+    }
+    Ok(Price {
         value: price_value.as_u64(),
         exp,
-    }
+    })
 }
 
-pub fn ten_pow(exponent: impl Into<u32>) -> u128 {
+pub fn ten_pow(exponent: impl Into<u32>) -> ScopeResult<u128> {
     let expo = exponent.into();
     let value: u128 = match expo {
         30 => 1_000_000_000_000_000_000_000_000_000_000,
@@ -170,10 +212,13 @@ pub fn ten_pow(exponent: impl Into<u32>) -> u128 {
         2 => 100,
         1 => 10,
         0 => 1,
-        _ => panic!("no support for exponent: {expo}"),
+        _ => {
+            msg!("No support for exponent: {expo}");
+            return Err(ScopeError::MathOverflow);
+        }
     };
 
-    value
+    Ok(value)
 }
 
 /// Convert a confidence in bps to a confidence factor
@@ -201,25 +246,94 @@ pub fn check_confidence_interval(
     tolerance_factor: u32,
 ) -> ScopeResult<()> {
     // We return an error if price <= deviation * tolerance
-    // price_value / 10^price_exp <= deviation_value * tolerance / 10^deviation_exp
-    // price * 10^deviation_exp <= deviation * tolerance * 10^price_exp
-
-    // avoid useless overflows simplify the exponents
-    let common_exp = u32::min(price_exp, deviation_exp);
-
-    let price_scaled = price_value * ten_pow(deviation_exp - common_exp);
-    let deviation_scaled =
-        deviation * u128::from(tolerance_factor) * ten_pow(price_exp - common_exp);
+    let deviation_scaled = deviation
+        .checked_mul(u128::from(tolerance_factor))
+        .ok_or(ScopeError::MathOverflow)?;
 
-    if price_scaled <= deviation_scaled {
+    if cmp_scaled(price_value, price_exp, deviation_scaled, deviation_exp)?.is_le() {
         return Err(ScopeError::ConfidenceIntervalCheckFailed);
     }
 
     Ok(())
 }
 
+/// Compare `a / 10^a_exp` with `b / 10^b_exp`
+pub fn cmp_scaled(a: u128, a_exp: u32, b: u128, b_exp: u32) -> ScopeResult<Ordering> {
+    // a / 10^a_exp <=> b / 10^b_exp
+    // a * 10^b_exp <=> b * 10^a_exp
+
+    // avoid useless overflows simplify the exponents
+    let common_exp = u32::min(a_exp, b_exp);
+
+    let a_scaled = a
+        .checked_mul(ten_pow(b_exp - common_exp)?)
+        .ok_or(ScopeError::MathOverflow)?;
+    let b_scaled = b
+        .checked_mul(ten_pow(a_exp - common_exp)?)
+        .ok_or(ScopeError::MathOverflow)?;
+
+    Ok(a_scaled.cmp(&b_scaled))
+}
+
 pub fn mul_bps(amount: impl Into<u128>, bps: impl Into<u128>) -> u128 {
     let a = amount.into();
     let b = bps.into();
     a * b / u128::from(FULL_BPS)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ten_pow_out_of_range_is_an_error() {
+        assert_eq!(ten_pow(30_u32), Ok(10_u128.pow(30)));
+        assert_eq!(ten_pow(31_u32), Err(ScopeError::MathOverflow));
+        assert_eq!(ten_pow(u32::MAX), Err(ScopeError::MathOverflow));
+    }
+
+    #[test]
+    fn u64_div_to_price_rejects_zero_denominator() {
+        assert!(u64_div_to_price(1, 0).is_err());
+        assert!(u64_div_to_price(u64::MAX, 1).is_ok());
+    }
+
+    #[test]
+    fn adversarial_decimals_do_not_panic() {
+        let price = Price {
+            value: u64::MAX,
+            exp: 0,
+        };
+        assert!(price_of_lamports_to_price_of_tokens(price, u64::MAX, 0).is_err());
+        let price_with_exp = Price { value: 1, exp: 1 };
+        assert!(price_of_lamports_to_price_of_tokens(price_with_exp, 0, u64::MAX).is_err());
+        assert!(price_to_sqrt_price(price, u8::MAX, 0).is_err());
+        for (decimals_a, decimals_b) in [(u8::MAX, 0), (0, u8::MAX), (u8::MAX, u8::MAX)] {
+            for sqrt_price in [1, u64::MAX.into(), u128::MAX] {
+                for a_to_b in [true, false] {
+                    // Only checks that no panic happens, the result may be valid or not
+                    let _ = sqrt_price_to_price(a_to_b, sqrt_price, decimals_a, decimals_b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn adversarial_prices_do_not_panic() {
+        let huge_exp = Price {
+            value: u64::MAX,
+            exp: u64::MAX,
+        };
+        assert!(huge_exp.to_scaled_value(6).is_err());
+        assert!(huge_exp.try_to_decimal().is_err());
+        assert!(check_confidence_interval(u128::MAX, 0, u128::MAX, 0, u32::MAX).is_err());
+
+        let too_big = Decimal::from(u64::MAX)
+            .try_mul(Decimal::from(10_u64))
+            .unwrap();
+        assert!(matches!(
+            Price::try_from_decimal(too_big),
+            Err(ScopeError::IntegerOverflow)
+        ));
+    }
+}
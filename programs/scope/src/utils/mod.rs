@@ -1,4 +1,6 @@
+pub mod account_header;
 pub mod consts;
+pub mod logs;
 pub mod macros;
 pub mod math;
 pub mod pdas;
@@ -9,14 +11,36 @@ use std::cell::{Ref, RefMut};
 
 use anchor_lang::{
     __private::bytemuck,
-    prelude::{msg, AccountDeserialize, AccountInfo},
+    prelude::{msg, AccountDeserialize, AccountInfo, Clock},
     Discriminator, Key,
 };
+use anchor_spl::token_2022::spl_token_2022::{extension::StateWithExtensions, state::Mint};
 pub use decimal_wad;
 
 use crate::{ScopeError, ScopeResult};
 
 pub const SECONDS_PER_HOUR: i64 = 60 * 60;
+pub const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+pub const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+
+/// Unix timestamp of `clock`, failing instead of wrapping if it is negative
+pub fn clock_unix_timestamp(clock: &Clock) -> ScopeResult<u64> {
+    u64::try_from(clock.unix_timestamp).map_err(|_| ScopeError::BadTimestamp)
+}
+
+/// Unpack a mint owned by either the SPL token or the Token-2022 program, ignoring the extensions
+/// of the latter
+pub fn unpack_mint(account: &AccountInfo) -> ScopeResult<Mint> {
+    let data = account.try_borrow_data().map_err(|_| {
+        msg!("Mint account {} is already borrowed", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data).map_err(|_| {
+        msg!("Account {} is not a valid mint", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
+    Ok(mint.base)
+}
 
 pub fn account_deserialize<T: AccountDeserialize + Discriminator>(
     account: &AccountInfo<'_>,
@@ -51,7 +75,10 @@ pub fn account_deserialize<T: AccountDeserialize + Discriminator>(
 pub fn zero_copy_deserialize<'info, T: bytemuck::AnyBitPattern + Discriminator>(
     account: &'info AccountInfo,
 ) -> ScopeResult<Ref<'info, T>> {
-    let data = account.data.try_borrow().unwrap();
+    let data = account.data.try_borrow().map_err(|_| {
+        msg!("Account {:?} is already mutably borrowed", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
 
     let disc_bytes = data.get(..8).ok_or_else(|| {
         msg!(
@@ -70,13 +97,25 @@ pub fn zero_copy_deserialize<'info, T: bytemuck::AnyBitPattern + Discriminator>(
         return Err(ScopeError::InvalidAccountDiscriminator);
     }
     let end = std::mem::size_of::<T>() + 8;
+    if data.len() < end {
+        msg!(
+            "Account {:?} is too small to be deserialized ({} < {})",
+            account.key(),
+            data.len(),
+            end
+        );
+        return Err(ScopeError::UnableToDeserializeAccount);
+    }
     Ok(Ref::map(data, |data| bytemuck::from_bytes(&data[8..end])))
 }
 
 pub fn zero_copy_deserialize_mut<'info, T: bytemuck::Pod + Discriminator>(
     account: &'info AccountInfo,
 ) -> ScopeResult<RefMut<'info, T>> {
-    let data = account.data.try_borrow_mut().unwrap();
+    let data = account.data.try_borrow_mut().map_err(|_| {
+        msg!("Account {:?} is already borrowed", account.key());
+        ScopeError::UnableToDeserializeAccount
+    })?;
 
     let disc_bytes = data.get(..8).ok_or_else(|| {
         msg!(
@@ -95,6 +134,15 @@ pub fn zero_copy_deserialize_mut<'info, T: bytemuck::Pod + Discriminator>(
         return Err(ScopeError::InvalidAccountDiscriminator);
     }
     let end = std::mem::size_of::<T>() + 8;
+    if data.len() < end {
+        msg!(
+            "Account {:?} is too small to be deserialized ({} < {})",
+            account.key(),
+            data.len(),
+            end
+        );
+        return Err(ScopeError::UnableToDeserializeAccount);
+    }
     Ok(RefMut::map(data, |data| {
         bytemuck::from_bytes_mut(&mut data[8..end])
     }))
@@ -3,6 +3,13 @@ use anchor_lang::prelude::*;
 pub mod seeds {
     pub const CONFIG: &[u8] = b"conf";
     pub const MINTS_TO_SCOPE_CHAINS: &[u8] = b"mints_to_scope_chains";
+    pub const GROUP_DEFINITIONS: &[u8] = b"group_definitions";
+    pub const MAPPINGS_AUDIT_REPORT: &[u8] = b"mappings_audit_report";
+    pub const PRICE_GROUP_DIGEST: &[u8] = b"price_group_digest";
+    pub const REFRESH_REWARD_VAULT: &[u8] = b"refresh_reward_vault";
+    pub const PRICE_HISTORY: &[u8] = b"price_history";
+    pub const ENTRY_STATS: &[u8] = b"entry_stats";
+    pub const ENTRY_PROVENANCE: &[u8] = b"entry_provenance";
 }
 
 pub fn config_pubkey(price_feed: &str) -> (Pubkey, u8) {
@@ -25,3 +32,72 @@ pub fn mints_to_scope_chains_pubkey(
         program_id,
     )
 }
+
+pub fn group_definitions_pubkey(configuration_pk: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::GROUP_DEFINITIONS, configuration_pk.as_ref()],
+        program_id,
+    )
+}
+
+pub fn mappings_audit_report_pubkey(
+    oracle_mappings_pk: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::MAPPINGS_AUDIT_REPORT, oracle_mappings_pk.as_ref()],
+        program_id,
+    )
+}
+
+pub fn refresh_reward_vault_pubkey(configuration_pk: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::REFRESH_REWARD_VAULT, configuration_pk.as_ref()],
+        program_id,
+    )
+}
+
+pub fn price_group_digest_pubkey(
+    prices_pk: &Pubkey,
+    group_id: u8,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[seeds::PRICE_GROUP_DIGEST, prices_pk.as_ref(), &[group_id]],
+        program_id,
+    )
+}
+
+pub fn price_history_pubkey(
+    prices_pk: &Pubkey,
+    entry_id: u16,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            seeds::PRICE_HISTORY,
+            prices_pk.as_ref(),
+            &entry_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+pub fn entry_stats_pubkey(prices_pk: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seeds::ENTRY_STATS, prices_pk.as_ref()], program_id)
+}
+
+pub fn entry_provenance_pubkey(
+    prices_pk: &Pubkey,
+    entry_id: u16,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            seeds::ENTRY_PROVENANCE,
+            prices_pk.as_ref(),
+            &entry_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
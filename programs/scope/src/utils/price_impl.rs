@@ -1,12 +1,17 @@
 use anchor_lang::prelude::*;
-use decimal_wad::{common::PERCENT_SCALER, decimal::Decimal};
+use decimal_wad::{
+    common::{TryDiv, TryMul, PERCENT_SCALER},
+    decimal::Decimal,
+};
 use solana_program::msg;
 
-use super::math::ten_pow;
-use crate::{Price, ScopeError};
+use super::{consts::FULL_BPS, math::ten_pow};
+use crate::{Price, ScopeError, ScopeResult};
 
 pub const MAX_REF_RATIO_TOLERANCE_PCT: u64 = 5;
 pub const MAX_REF_RATIO_TOLERANCE_SCALED: u64 = MAX_REF_RATIO_TOLERANCE_PCT * PERCENT_SCALER;
+/// Tolerance versus the ref price of the entries without a configured one
+pub const DEFAULT_REF_PRICE_TOLERANCE_BPS: u16 = MAX_REF_RATIO_TOLERANCE_PCT as u16 * 100;
 
 #[cfg(not(target_os = "solana"))]
 impl From<Price> for f64 {
@@ -16,20 +21,216 @@ impl From<Price> for f64 {
 }
 
 impl Price {
-    pub fn to_scaled_value(&self, decimals: u8) -> u128 {
-        let exp = u8::try_from(self.exp).expect("Price exp is too big");
+    pub fn to_scaled_value(&self, decimals: u8) -> ScopeResult<u128> {
+        let exp = u8::try_from(self.exp)?;
         let value: u128 = self.value.into();
         if exp > decimals {
             let diff = exp - decimals;
-            value / ten_pow(diff)
+            Ok(value / ten_pow(diff)?)
         } else {
             let diff = decimals - exp;
-            value * ten_pow(diff)
+            value
+                .checked_mul(ten_pow(diff)?)
+                .ok_or(ScopeError::MathOverflow)
         }
     }
+
+    /// Convert a decimal into a price keeping as many decimals as possible, fails if the integer
+    /// part of the decimal does not fit in a `u64`
+    pub fn try_from_decimal(decimal: Decimal) -> ScopeResult<Price> {
+        decimal_to_price(decimal)
+    }
+
+    /// Convert the price into a decimal, fails if the exponent is out of the supported range
+    pub fn try_to_decimal(&self) -> ScopeResult<Decimal> {
+        let exp = u32::try_from(self.exp)?;
+        Decimal::from(self.value)
+            .try_div(Decimal::from(ten_pow(exp)?))
+            .map_err(|_| ScopeError::MathOverflow)
+    }
+
+    /// Convert the price of `10^unit_scale` tokens into the price of 1 token, e.g. a price quoted
+    /// per 1000 tokens has a unit scale of 3 and a price quoted per milli-token one of -3.
+    ///
+    /// At most 18 decimals are kept.
+    pub fn scale_units(self, unit_scale: i8) -> ScopeResult<Price> {
+        let scale = u64::from(unit_scale.unsigned_abs());
+        let scaled = if unit_scale >= 0 {
+            PriceU128 {
+                value: self.value.into(),
+                exp: self.exp.saturating_add(scale),
+            }
+            .reduce_exp(18)
+        } else if self.exp >= scale {
+            PriceU128 {
+                value: self.value.into(),
+                exp: self.exp - scale,
+            }
+        } else {
+            PriceU128 {
+                value: u128::from(self.value)
+                    .checked_mul(ten_pow(u32::try_from(scale - self.exp)?)?)
+                    .ok_or(ScopeError::IntegerOverflow)?,
+                exp: 0,
+            }
+        };
+        scaled.try_into()
+    }
+
+    /// Inverse of the price (1/price), with up to 18 decimals of precision. Fails on a zero price.
+    pub fn inverse(self) -> ScopeResult<Price> {
+        let one = ten_pow(u32::try_from(self.exp)?)?;
+        PriceU128::from_ratio(one, self.value)?.try_into()
+    }
+}
+
+/// Price with a `u128` value used for intermediate computations.
+///
+/// Composite prices (scope chains, multipliers, ratios of token amounts...) can temporarily exceed
+/// the range of a `u64` value. They are computed with this type and only converted to the public
+/// [`Price`] when written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriceU128 {
+    pub value: u128,
+    pub exp: u64,
 }
 
-pub fn check_ref_price_difference(curr_price: Price, ref_price: Price) -> Result<()> {
+impl From<Price> for PriceU128 {
+    fn from(price: Price) -> Self {
+        Self {
+            value: price.value.into(),
+            exp: price.exp,
+        }
+    }
+}
+
+impl PriceU128 {
+    pub const ONE: PriceU128 = PriceU128 { value: 1, exp: 0 };
+
+    /// Ratio of two integer amounts (e.g. of lamports), with up to 18 decimals of precision
+    pub fn from_ratio(numerator: u128, denominator: u64) -> ScopeResult<PriceU128> {
+        const MAX_EXP: u32 = 18;
+        if denominator == 0 {
+            return Err(ScopeError::MathOverflow);
+        }
+        // Keep as many decimals as the numerator allows without overflowing
+        let (scaled_numerator, exp) = (0..=MAX_EXP)
+            .rev()
+            .find_map(|exp| {
+                numerator
+                    .checked_mul(10_u128.pow(exp))
+                    .map(|scaled| (scaled, exp))
+            })
+            .ok_or(ScopeError::MathOverflow)?;
+        Ok(PriceU128 {
+            value: scaled_numerator / u128::from(denominator),
+            exp: exp.into(),
+        })
+    }
+
+    /// Multiply by a [`Price`].
+    ///
+    /// The least significant decimals of `self` are dropped first if needed so that the value fits
+    /// in a `u64`, which guarantees that the product cannot overflow.
+    pub fn mul_price(self, other: Price) -> ScopeResult<PriceU128> {
+        let lhs = self.trim_to_u64_range()?;
+        let value = lhs.value * u128::from(other.value);
+        let exp = lhs
+            .exp
+            .checked_add(other.exp)
+            .ok_or(ScopeError::MathOverflow)?;
+        Ok(PriceU128 { value, exp })
+    }
+
+    /// Divide by a non-zero [`Price`].
+    ///
+    /// `self` is first trimmed to the `u64` range then scaled by 10^18, so that the quotient keeps
+    /// at least 18 significant decimals of precision without overflowing.
+    pub fn div_price(self, other: Price) -> ScopeResult<PriceU128> {
+        const SCALE_EXP: u64 = 18;
+        if other.value == 0 {
+            return Err(ScopeError::MathOverflow);
+        }
+        let lhs = self.trim_to_u64_range()?;
+        let value = lhs.value * 10_u128.pow(SCALE_EXP as u32) / u128::from(other.value);
+        let exp = lhs
+            .exp
+            .checked_add(SCALE_EXP)
+            .and_then(|exp| exp.checked_sub(other.exp))
+            .ok_or(ScopeError::MathOverflow)?;
+        Ok(PriceU128 { value, exp })
+    }
+
+    /// Convert the price of a lamport of token A in lamports of token B into the price of a token A
+    /// in tokens B, i.e. multiply it by 10^(token_a_decimals - token_b_decimals).
+    pub fn lamports_to_tokens(
+        self,
+        token_a_decimals: u64,
+        token_b_decimals: u64,
+    ) -> ScopeResult<PriceU128> {
+        let exp_b = self
+            .exp
+            .checked_add(token_b_decimals)
+            .ok_or(ScopeError::MathOverflow)?;
+        if exp_b >= token_a_decimals {
+            return Ok(PriceU128 {
+                value: self.value,
+                exp: exp_b - token_a_decimals,
+            });
+        }
+        let value = self
+            .value
+            .checked_mul(ten_pow(u32::try_from(token_a_decimals - exp_b)?)?)
+            .ok_or(ScopeError::MathOverflow)?;
+        Ok(PriceU128 { value, exp: 0 })
+    }
+
+    /// Reduce the number of decimals to at most `max_exp`.
+    pub fn reduce_exp(self, max_exp: u64) -> PriceU128 {
+        if self.exp <= max_exp {
+            return self;
+        }
+        let value = u32::try_from(self.exp - max_exp)
+            .ok()
+            .and_then(|diff| 10_u128.checked_pow(diff))
+            .map_or(0, |factor| self.value / factor);
+        PriceU128 {
+            value,
+            exp: max_exp,
+        }
+    }
+
+    /// Drop the least significant decimals until the value fits in a `u64`.
+    fn trim_to_u64_range(self) -> ScopeResult<PriceU128> {
+        let PriceU128 { mut value, mut exp } = self;
+        while value > u128::from(u64::MAX) {
+            if exp == 0 {
+                return Err(ScopeError::IntegerOverflow);
+            }
+            value /= 10;
+            exp -= 1;
+        }
+        Ok(PriceU128 { value, exp })
+    }
+}
+
+impl TryFrom<PriceU128> for Price {
+    type Error = ScopeError;
+
+    fn try_from(price: PriceU128) -> ScopeResult<Price> {
+        let PriceU128 { value, exp } = price.trim_to_u64_range()?;
+        Ok(Price {
+            value: value.try_into()?,
+            exp,
+        })
+    }
+}
+
+pub fn check_ref_price_difference(
+    curr_price: Price,
+    ref_price: Price,
+    tolerance_bps: u16,
+) -> Result<()> {
     let ref_price_decimal = Decimal::from(ref_price);
     let curr_price_decimal = Decimal::from(curr_price);
     let absolute_diff = if ref_price_decimal > curr_price_decimal {
@@ -38,11 +239,11 @@ pub fn check_ref_price_difference(curr_price: Price, ref_price: Price) -> Result
         curr_price_decimal - ref_price_decimal
     };
 
-    if absolute_diff * 100 > ref_price_decimal * MAX_REF_RATIO_TOLERANCE_PCT {
+    if absolute_diff * u64::from(FULL_BPS) > ref_price_decimal * u64::from(tolerance_bps) {
         msg!(
             "Price diff is too high: absolute_diff {}, tolerance = {}",
             absolute_diff,
-            ref_price_decimal * Decimal::from_percent(MAX_REF_RATIO_TOLERANCE_PCT)
+            ref_price_decimal * u64::from(tolerance_bps) / u64::from(FULL_BPS)
         );
         return Err(ScopeError::PriceNotValid.into());
     }
@@ -50,14 +251,40 @@ pub fn check_ref_price_difference(curr_price: Price, ref_price: Price) -> Result
     Ok(())
 }
 
-fn decimal_to_price(decimal: Decimal) -> Price {
+pub fn check_price_deviation_bps(
+    new_price: Price,
+    previous_price: Price,
+    max_deviation_bps: u16,
+) -> Result<()> {
+    let previous_price_decimal = Decimal::from(previous_price);
+    let new_price_decimal = Decimal::from(new_price);
+    let absolute_diff = if previous_price_decimal > new_price_decimal {
+        previous_price_decimal - new_price_decimal
+    } else {
+        new_price_decimal - previous_price_decimal
+    };
+
+    if absolute_diff * u64::from(FULL_BPS) > previous_price_decimal * u64::from(max_deviation_bps) {
+        msg!(
+            "Price deviation is too high: previous {}, new {}, max deviation {} bps",
+            previous_price_decimal,
+            new_price_decimal,
+            max_deviation_bps
+        );
+        return err!(ScopeError::PriceDeviationTooLarge);
+    }
+
+    Ok(())
+}
+
+fn decimal_to_price(decimal: Decimal) -> ScopeResult<Price> {
     // this implementation aims to keep as much precision as possible
     // choose exp to be as big as possible (minimize what is needed for the integer part)
 
     // Use a match instead of log10 to save some CUs
     let (exp, ten_pow_exp) = match decimal
         .try_round::<u64>()
-        .expect("Decimal integer part is too big")
+        .map_err(|_| ScopeError::IntegerOverflow)?
     {
         0_u64 => (18, 10_u64.pow(18)),
         1..=9 => (17, 10_u64.pow(17)),
@@ -79,17 +306,21 @@ fn decimal_to_price(decimal: Decimal) -> Price {
         10000000000000000..=99999999999999999 => (1, 10_u64.pow(1)),
         100000000000000000..=u64::MAX => (0, 1),
     };
-    let value = (decimal * ten_pow_exp)
-        .try_round::<u64>()
-        .unwrap_or_else(|e| {
-            panic!("Decimal {decimal} conversion to price failed (exp:{exp}): {e:?}");
-        });
-    Price { value, exp }
+    let value = decimal
+        .try_mul(ten_pow_exp)
+        .and_then(|scaled| scaled.try_round::<u64>())
+        .map_err(|e| {
+            msg!("Decimal {decimal} conversion to price failed (exp:{exp}): {e:?}");
+            ScopeError::IntegerOverflow
+        })?;
+    Ok(Price { value, exp })
 }
 
+/// Panics if the integer part of the decimal does not fit in a `u64`, only to be used with bounded
+/// decimals. Prices computed from account data use [`Price::try_from_decimal`].
 impl From<Decimal> for Price {
     fn from(val: Decimal) -> Self {
-        decimal_to_price(val)
+        decimal_to_price(val).expect("Decimal integer part is too big")
     }
 }
 
@@ -135,3 +366,78 @@ impl PartialEq for Price {
 }
 
 impl Eq for Price {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_u128_from_ratio() {
+        // 1.5 tokens per share, with 18 decimals
+        let ratio = PriceU128::from_ratio(3_000_000, 2_000_000).unwrap();
+        assert_eq!(
+            ratio,
+            PriceU128 {
+                value: 1_500_000_000_000_000_000,
+                exp: 18
+            }
+        );
+        assert_eq!(Price::try_from(ratio).unwrap(), Price { value: 15, exp: 1 });
+        // Amounts above the u64 range keep their most significant digits
+        let large = PriceU128::from_ratio(u128::from(u64::MAX) * 1_000, 1_000).unwrap();
+        assert_eq!(
+            Price::try_from(large).unwrap(),
+            Price {
+                value: u64::MAX,
+                exp: 0
+            }
+        );
+        assert!(Price::try_from(PriceU128::from_ratio(u128::MAX, 1).unwrap()).is_err());
+        assert!(PriceU128::from_ratio(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_price_u128_lamports_to_tokens() {
+        let lamport_price = PriceU128 { value: 15, exp: 1 };
+        // 1.5 lamports B (9 decimals) per lamport A (6 decimals): 0.0015 B per A
+        assert_eq!(
+            lamport_price.lamports_to_tokens(6, 9).unwrap(),
+            PriceU128 { value: 15, exp: 4 }
+        );
+        // 1.5 lamports B (6 decimals) per lamport A (9 decimals): 1500 B per A
+        assert_eq!(
+            lamport_price.lamports_to_tokens(9, 6).unwrap(),
+            PriceU128 {
+                value: 1_500,
+                exp: 0
+            }
+        );
+        assert!(lamport_price.lamports_to_tokens(u64::MAX, 0).is_err());
+        assert!(lamport_price.lamports_to_tokens(0, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_price_inverse() {
+        let usdt_per_usdc = Price {
+            value: 99_950_000,
+            exp: 8,
+        };
+        let inverse = usdt_per_usdc.inverse().unwrap();
+        assert_eq!(inverse.exp, 18);
+        assert_eq!(inverse.value, 1_000_500_250_125_062_531);
+
+        assert_eq!(
+            Price { value: 4, exp: 0 }.inverse().unwrap(),
+            Price { value: 25, exp: 2 }
+        );
+        // Small prices give large inverses, the least significant decimals are dropped
+        assert_eq!(
+            Price { value: 1, exp: 10 }.inverse().unwrap(),
+            Price {
+                value: 10_000_000_000,
+                exp: 0
+            }
+        );
+        assert!(Price { value: 0, exp: 6 }.inverse().is_err());
+    }
+}
@@ -8,6 +8,10 @@
 //! An account can store up to `crate::MAX_ENTRIES` chains.
 //! One chain is composed of at most 4 prices.
 //!
+//! Each hop multiplies the price computed so far by the price of its entry, or divides it when
+//! the high bit of its id is set (see [`DIVIDE_HOP_FLAG`]). For example the chain
+//! `[USDC/USD, divide_hop(EUR/USD)]` gives the price of USDC in EUR.
+//!
 //! ## Example
 //!
 //! ### Scenario
@@ -84,14 +88,36 @@ use std::fmt::Debug;
 
 use anchor_lang::Discriminator;
 use bytemuck;
-use decimal_wad::rate::U128;
 pub use strum::IntoEnumIterator;
 
-use crate::{DatedPrice, OraclePrices, Price, ScopeError, MAX_ENTRIES};
+use crate::{
+    utils::price_impl::PriceU128, DatedPrice, OracleMappings, OraclePrices, Price, ScopeError,
+    MAX_ENTRIES,
+};
 
 /// Maximum length of a chain (4 so the size of one chain is the same as `u64`)
 pub const MAX_CHAIN_LENGTH: usize = 4;
 
+/// High bit of a chain hop id: the price is divided by the hop entry price instead of multiplied
+pub const DIVIDE_HOP_FLAG: u16 = 1 << 15;
+
+/// Decimals kept by a chain whose last hop is a division
+const DIVIDED_CHAIN_EXP: u64 = 18;
+
+/// Encode a hop dividing by the price of `entry`
+pub const fn divide_hop(entry: u16) -> u16 {
+    entry | DIVIDE_HOP_FLAG
+}
+
+/// Entry id of a raw chain hop, without its direction
+pub const fn hop_entry(hop: u16) -> u16 {
+    hop & !DIVIDE_HOP_FLAG
+}
+
+pub const fn is_divide_hop(hop: u16) -> bool {
+    hop & DIVIDE_HOP_FLAG != 0
+}
+
 type RawChain = [u16; MAX_CHAIN_LENGTH];
 
 #[derive(Clone, Debug)]
@@ -238,62 +264,106 @@ impl ScopeChainAccount {
     }
 }
 
-// TODO not working with latest prices that have a lot of decimals. Backport yvault version here.
+/// Number of hops of a chain
+///
+/// The chain ends at its first out of range entry id (ignoring the direction of the hop), any
+/// valid id after it is a missing hop.
+pub fn chain_len(chain: &[u16; MAX_CHAIN_LENGTH]) -> Result<usize, ScopeChainError> {
+    let is_valid_hop = |&id: &u16| usize::from(hop_entry(id)) < MAX_ENTRIES;
+    let chain_len = chain
+        .iter()
+        .position(|id| !is_valid_hop(id))
+        .unwrap_or(MAX_CHAIN_LENGTH);
+    if chain_len == 0 {
+        return Err(ScopeChainError::NoChainForToken);
+    }
+    if let Some(hop) = chain[chain_len..].iter().position(is_valid_hop) {
+        // Report the hole in the chain, not the valid entry following it
+        let hop = chain_len + hop - 1;
+        return Err(ScopeChainError::HopFailure {
+            hop,
+            entry: chain[hop],
+            cause: ScopeChainHopFailure::MissingEntry,
+        });
+    }
+    Ok(chain_len)
+}
+
+/// Check a chain against the mappings of its feed, returns its length.
+///
+/// On top of the checks of [`chain_len`], every hop must refer to an entry mapped in the feed and
+/// an entry cannot be used by several hops, so that a chain that can never be priced is rejected
+/// when it is stored rather than failing at price time.
+pub fn validate_chain(
+    oracle_mappings: &OracleMappings,
+    chain: &[u16; MAX_CHAIN_LENGTH],
+) -> Result<usize, ScopeChainError> {
+    let chain_len = chain_len(chain)?;
+    for (hop, &entry) in chain[..chain_len].iter().enumerate() {
+        let hop_failure = |cause| ScopeChainError::HopFailure { hop, entry, cause };
+        let entry_id = hop_entry(entry);
+        if oracle_mappings.price_info_accounts[usize::from(entry_id)] == Default::default() {
+            return Err(hop_failure(ScopeChainHopFailure::UnmappedEntry));
+        }
+        if chain[..hop]
+            .iter()
+            .any(|&previous| hop_entry(previous) == entry_id)
+        {
+            return Err(hop_failure(ScopeChainHopFailure::DuplicateEntry));
+        }
+    }
+    Ok(chain_len)
+}
+
+/// Compute the price given by a chain of scope entries
+///
+/// See [`chain_len`] for the end of the chain. Failures on a given hop are reported with its index
+/// and cause in [`ScopeChainError::HopFailure`].
 pub fn get_price_from_chain(
     prices: &OraclePrices,
     chain: &[u16; MAX_CHAIN_LENGTH],
 ) -> Result<DatedPrice, ScopeChainError> {
-    let price_chain = chain.map(usize::from).map(|id| prices.prices.get(id));
+    let chain_len = chain_len(chain)?;
 
-    let last_updated_slot = price_chain
-        .iter()
-        .filter_map(|&opt| opt.map(|price| price.last_updated_slot))
-        .reduce(|acc, val| acc.min(val))
-        .ok_or(ScopeChainError::NoChainForToken)?;
-
-    let unix_timestamp = price_chain
-        .iter()
-        .filter_map(|&opt| opt.map(|price| price.unix_timestamp))
-        .reduce(|acc, val| acc.min(val))
-        .ok_or(ScopeChainError::NoChainForToken)?;
-
-    let total_decimals: u64 = price_chain
-        .iter()
-        .filter_map(|&opt| opt.map(|price| price.price.exp))
-        .try_fold(0u64, |acc, exp| acc.checked_add(exp))
-        .ok_or(ScopeChainError::MathOverflow)?;
+    let mut last_updated_slot = u64::MAX;
+    let mut unix_timestamp = u64::MAX;
+    // Intermediate values are kept on 128 bits and only converted at the end.
+    let mut product = PriceU128::ONE;
+    for (hop, &entry) in chain[..chain_len].iter().enumerate() {
+        let hop_failure = |cause| ScopeChainError::HopFailure { hop, entry, cause };
+        let dated_price = &prices.prices[usize::from(hop_entry(entry))];
+        if dated_price.price.value == 0 {
+            return Err(hop_failure(ScopeChainHopFailure::ZeroPrice));
+        }
+        last_updated_slot = last_updated_slot.min(dated_price.last_updated_slot);
+        unix_timestamp = unix_timestamp.min(dated_price.unix_timestamp);
+        product = if is_divide_hop(entry) {
+            product.div_price(dated_price.price)
+        } else {
+            product.mul_price(dated_price.price)
+        }
+        .map_err(|_| hop_failure(ScopeChainHopFailure::MathOverflow))?;
+    }
 
     // Final number of decimals is the last element one's which should be the quotation price.
-    let exp = price_chain
-        .iter()
-        .filter_map(|&opt| opt.map(|price| price.price.exp))
-        .last()
-        .unwrap(); // chain is never empty here by construction
-
-    // Compute token value by multiplying all value of the chain
-    let product = price_chain
-        .iter()
-        .filter_map(|&opt| opt.map(|price| price.price.value))
-        .try_fold(U128::from(1u128), |acc, value| {
-            acc.checked_mul(value.into())
-        })
-        .ok_or(ScopeChainError::MathOverflow)?;
+    // An inverted last hop gives no such reference, the decimals of the division are kept.
+    let last_hop = chain[chain_len - 1];
+    let exp = if is_divide_hop(last_hop) {
+        DIVIDED_CHAIN_EXP
+    } else {
+        prices.prices[usize::from(last_hop)].price.exp
+    };
 
     // Compute final value by removing extra decimals
-    let scale_down_decimals: u32 = total_decimals.checked_sub(exp).unwrap().try_into().unwrap(); // Cannot fail by construction of `total_decimals`
-    let scale_down_factor = U128::from(10u128)
-        .checked_pow(U128::from(scale_down_decimals))
-        .unwrap();
-    let value: u64 = product
-        .checked_div(scale_down_factor)
-        .unwrap() // Cannot fail thanks to the early return
+    let price: Price = product
+        .reduce_exp(exp)
         .try_into()
         .map_err(|_| ScopeChainError::IntegerConversionOverflow)?;
 
     Ok(DatedPrice {
         last_updated_slot,
         unix_timestamp,
-        price: Price { value, exp },
+        price,
         ..Default::default()
     })
 }
@@ -311,6 +381,27 @@ pub enum ScopeChainError {
     InvalidPricesInChain,
     MathOverflow,
     IntegerConversionOverflow,
+    /// Computation failed on the hop `hop` (index in the chain) using the scope entry `entry`
+    HopFailure {
+        hop: usize,
+        entry: u16,
+        cause: ScopeChainHopFailure,
+    },
+}
+
+/// Reason of the failure of a scope chain hop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeChainHopFailure {
+    /// The hop entry is out of range while the chain continues after it
+    MissingEntry,
+    /// The price of the hop entry is zero
+    ZeroPrice,
+    /// Multiplying or dividing by the price of the hop entry overflowed
+    MathOverflow,
+    /// The hop entry is not mapped in the feed
+    UnmappedEntry,
+    /// The hop entry is already used by a previous hop of the chain
+    DuplicateEntry,
 }
 
 impl From<ScopeChainError> for ScopeError {
@@ -322,6 +413,13 @@ impl From<ScopeChainError> for ScopeError {
             ScopeChainError::InvalidPricesInChain => ScopeError::BadScopeChainOrPrices,
             ScopeChainError::MathOverflow => ScopeError::MathOverflow,
             ScopeChainError::IntegerConversionOverflow => ScopeError::IntegerOverflow,
+            ScopeChainError::HopFailure { cause, .. } => match cause {
+                ScopeChainHopFailure::MissingEntry => ScopeError::ScopeChainMissingEntry,
+                ScopeChainHopFailure::ZeroPrice => ScopeError::ScopeChainZeroPrice,
+                ScopeChainHopFailure::MathOverflow => ScopeError::MathOverflow,
+                ScopeChainHopFailure::UnmappedEntry => ScopeError::ScopeChainUnmappedEntry,
+                ScopeChainHopFailure::DuplicateEntry => ScopeError::ScopeChainDuplicateEntry,
+            },
         }
     }
 }